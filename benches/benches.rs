@@ -12,11 +12,10 @@ pub fn pcap(c: &mut Criterion) {
     group.throughput(criterion::Throughput::Bytes(pcap.len() as u64));
 
     group.bench_function("Parser", |b| {
-        let rt = Runtime::new().unwrap();
-        b.to_async(rt).iter(|| async {
-            let (mut src, parser) = PcapParser::new(&pcap).await.unwrap();
+        b.iter(|| {
+            let (mut src, parser) = PcapParser::new(&pcap).unwrap();
             loop {
-                match parser.next_packet(src).await {
+                match parser.next_packet(src) {
                     Ok((rem, _)) => src = rem,
                     Err(PcapError::IncompleteBuffer) => break,
                     Err(_) => panic!(),
@@ -26,11 +25,10 @@ pub fn pcap(c: &mut Criterion) {
     });
 
     group.bench_function("ParserRaw", |b| {
-        let rt = Runtime::new().unwrap();
-        b.to_async(rt).iter(|| async {
-            let (mut src, parser) = PcapParser::new(&pcap).await.unwrap();
+        b.iter(|| {
+            let (mut src, parser) = PcapParser::new(&pcap).unwrap();
             loop {
-                match parser.next_raw_packet(src).await {
+                match parser.next_raw_packet(src) {
                     Ok((rem, _)) => src = rem,
                     Err(PcapError::IncompleteBuffer) => break,
                     Err(_) => panic!(),
@@ -70,11 +68,10 @@ pub fn pcapng(c: &mut Criterion) {
     group.throughput(criterion::Throughput::Bytes(pcapng.len() as u64));
 
     group.bench_function("Parser", |b| {
-        let rt = Runtime::new().unwrap();
-        b.to_async(rt).iter(|| async {
-            let (mut src, mut parser) = PcapNgParser::new(&pcapng).await.unwrap();
+        b.iter(|| {
+            let (mut src, mut parser) = PcapNgParser::new(&pcapng).unwrap();
             loop {
-                match parser.next_block(src).await {
+                match parser.next_block(src) {
                     Ok((rem, _)) => src = rem,
                     Err(PcapError::IncompleteBuffer) => break,
                     Err(_) => panic!(),
@@ -84,11 +81,10 @@ pub fn pcapng(c: &mut Criterion) {
     });
 
     group.bench_function("ParserRaw", |b| {
-        let rt = Runtime::new().unwrap();
-        b.to_async(rt).iter(|| async {
-            let (mut src, mut parser) = PcapNgParser::new(&pcapng).await.unwrap();
+        b.iter(|| {
+            let (mut src, mut parser) = PcapNgParser::new(&pcapng).unwrap();
             loop {
-                match parser.next_raw_block(src).await {
+                match parser.next_raw_block(src) {
                     Ok((rem, _)) => src = rem,
                     Err(PcapError::IncompleteBuffer) => break,
                     Err(_) => panic!(),
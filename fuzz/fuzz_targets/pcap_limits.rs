@@ -0,0 +1,30 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use pcap_file_tokio::pcap::{PcapParser, PcapReader};
+use pcap_file_tokio::{ParseLimits, ReaderConfig};
+
+fuzz_target!(|data: &[u8]| {
+    // Reads a small maximum packet length from the first byte of the input
+    let (limits, data) = match data.split_first() {
+        Some((max_len, data)) => (ParseLimits::default().with_max_block_len(*max_len as usize * 8), data),
+        None => return,
+    };
+
+    if let Ok((rem, pcap_parser)) = PcapParser::with_limits(data, limits) {
+        let mut src = rem;
+
+        while let Ok((rem, _packet)) = pcap_parser.next_raw_packet(src) {
+            assert!(src.len() - rem.len() <= limits.max_block_len);
+            src = rem;
+        }
+    }
+
+    tokio_test::block_on(async {
+        let config = ReaderConfig::default().with_limits(limits);
+        if let Ok(mut pcap_reader) = PcapReader::with_config(data, config).await {
+            while let Some(Ok(packet)) = pcap_reader.next_packet().await {
+                assert!(16 + packet.data.len() <= limits.max_block_len);
+            }
+        }
+    });
+});
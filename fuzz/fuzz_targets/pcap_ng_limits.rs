@@ -0,0 +1,155 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use pcap_file_tokio::pcapng::blocks::enhanced_packet::EnhancedPacketOption;
+use pcap_file_tokio::pcapng::blocks::interface_description::InterfaceDescriptionOption;
+use pcap_file_tokio::pcapng::blocks::interface_statistics::InterfaceStatisticsOption;
+use pcap_file_tokio::pcapng::blocks::name_resolution::{NameResolutionOption, Record};
+use pcap_file_tokio::pcapng::blocks::packet::PacketOption;
+use pcap_file_tokio::pcapng::blocks::section_header::SectionHeaderOption;
+use pcap_file_tokio::pcapng::{Block, PcapNgParser, PcapNgReader};
+use pcap_file_tokio::{ParseLimits, ReaderConfig};
+
+/// Reads small limits from the first bytes of the input, so that each one of them is exceeded
+fn split_limits(data: &[u8]) -> Option<(ParseLimits, &[u8])> {
+    if data.len() < 7 {
+        return None;
+    }
+
+    let limits = ParseLimits::default()
+        .with_max_block_len(data[0] as usize * 16)
+        .with_max_options(data[1] as usize % 16)
+        .with_max_nrb_records(data[2] as usize % 16)
+        .with_max_nrb_names(data[3] as usize % 32)
+        .with_max_interfaces(data[4] as usize % 8)
+        .with_max_string_len(data[5] as usize)
+        .with_max_sections(data[6] as usize % 8);
+
+    Some((limits, &data[7..]))
+}
+
+/// Checks the number of options of a block and the length of its string options
+fn check_options<T>(options: &[T], string_len: impl Fn(&T) -> Option<usize>, limits: &ParseLimits) {
+    assert!(options.len() <= limits.max_options);
+    for len in options.iter().filter_map(string_len) {
+        assert!(len <= limits.max_string_len);
+    }
+}
+
+/// Checks that a parsed block respects the limits
+fn check_block(block: &Block, limits: &ParseLimits) {
+    match block {
+        Block::SectionHeader(block) => check_options(
+            &block.options,
+            |opt| match opt {
+                SectionHeaderOption::Comment(s)
+                | SectionHeaderOption::Hardware(s)
+                | SectionHeaderOption::OS(s)
+                | SectionHeaderOption::UserApplication(s) => Some(s.len()),
+                SectionHeaderOption::CustomUtf8(opt) => Some(opt.value.len()),
+                _ => None,
+            },
+            limits,
+        ),
+        Block::InterfaceDescription(block) => check_options(
+            &block.options,
+            |opt| match opt {
+                InterfaceDescriptionOption::Comment(s)
+                | InterfaceDescriptionOption::IfName(s)
+                | InterfaceDescriptionOption::IfDescription(s)
+                | InterfaceDescriptionOption::IfOs(s)
+                | InterfaceDescriptionOption::IfHardware(s) => Some(s.len()),
+                InterfaceDescriptionOption::CustomUtf8(opt) => Some(opt.value.len()),
+                _ => None,
+            },
+            limits,
+        ),
+        Block::Packet(block) => check_options(
+            &block.options,
+            |opt| match opt {
+                PacketOption::Comment(s) => Some(s.len()),
+                PacketOption::CustomUtf8(opt) => Some(opt.value.len()),
+                _ => None,
+            },
+            limits,
+        ),
+        Block::NameResolution(block) => check_options(
+            &block.options,
+            |opt| match opt {
+                NameResolutionOption::Comment(s) | NameResolutionOption::NsDnsName(s) => Some(s.len()),
+                NameResolutionOption::CustomUtf8(opt) => Some(opt.value.len()),
+                _ => None,
+            },
+            limits,
+        ),
+        Block::InterfaceStatistics(block) => check_options(
+            &block.options,
+            |opt| match opt {
+                InterfaceStatisticsOption::Comment(s) => Some(s.len()),
+                InterfaceStatisticsOption::CustomUtf8(opt) => Some(opt.value.len()),
+                _ => None,
+            },
+            limits,
+        ),
+        Block::EnhancedPacket(block) => check_options(
+            &block.options,
+            |opt| match opt {
+                EnhancedPacketOption::Comment(s) => Some(s.len()),
+                EnhancedPacketOption::CustomUtf8(opt) => Some(opt.value.len()),
+                _ => None,
+            },
+            limits,
+        ),
+        Block::SimplePacket(_) | Block::SystemdJournalExport(_) | Block::Unknown(_) => {},
+    }
+
+    if let Block::NameResolution(block) = block {
+        assert!(block.records.len() <= limits.max_nrb_records);
+
+        let names = block.records.iter().flat_map(|record| match record {
+            Record::Ipv4(record) => &record.names[..],
+            Record::Ipv6(record) => &record.names[..],
+            Record::End | Record::Unknown(_) => &[],
+        });
+        let mut nb_names = 0;
+        for name in names {
+            assert!(name.len() <= limits.max_string_len);
+            nb_names += 1;
+        }
+        assert!(nb_names <= limits.max_nrb_names);
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let (limits, data) = match split_limits(data) {
+        Some(split) => split,
+        None => return,
+    };
+
+    if let Ok((rem, mut pcapng_parser)) = PcapNgParser::with_limits(data, limits) {
+        let mut src = rem;
+        let mut sections = 1;
+
+        while let Ok((rem, block)) = pcapng_parser.next_block(src) {
+            assert!(src.len() - rem.len() <= limits.max_block_len);
+            check_block(&block, &limits);
+
+            if let Block::SectionHeader(_) = block {
+                sections += 1;
+            }
+            assert!(sections <= limits.max_sections);
+            assert!(pcapng_parser.interfaces().len() <= limits.max_interfaces);
+
+            src = rem;
+        }
+    }
+
+    tokio_test::block_on(async {
+        let config = ReaderConfig::default().with_limits(limits);
+        if let Ok(mut pcapng_reader) = PcapNgReader::with_config(data, config).await {
+            while let Some(Ok(block)) = pcapng_reader.next_block().await {
+                check_block(&block, &limits);
+                assert!(pcapng_reader.interfaces().len() <= limits.max_interfaces);
+            }
+        }
+    });
+});
@@ -0,0 +1,307 @@
+//! Writers running in a background task, shared by several producers.
+//!
+//! [`spawn_writer`] moves a [`PcapWriter`] or a [`PcapNgWriter`] into a dedicated tokio task fed by a bounded channel,
+//! and returns a cloneable [`WriterHandle`] that can be used from any task.
+//!
+//! # Example
+//! ```rust,no_run
+//! # tokio_test::block_on(async {
+//! use std::borrow::Cow;
+//! use std::time::Duration;
+//!
+//! use pcap_file_tokio::background::{spawn_writer, BackgroundConfig};
+//! use pcap_file_tokio::pcap::{PcapPacket, PcapWriter};
+//!
+//! let file = tokio::fs::File::create("out.pcap").await.unwrap();
+//! let handle = spawn_writer(PcapWriter::new(file).await.unwrap(), BackgroundConfig::default());
+//!
+//! let mut tasks = vec![];
+//! for i in 0..4 {
+//!     let handle = handle.clone();
+//!     tasks.push(tokio::spawn(async move {
+//!         let packet = PcapPacket { timestamp: Duration::from_secs(i), orig_len: 4, data: Cow::Owned(vec![0; 4]) };
+//!         handle.write(packet).await.unwrap();
+//!     }));
+//! }
+//! for task in tasks {
+//!     task.await.unwrap();
+//! }
+//!
+//! let summary = handle.close().await.unwrap();
+//! println!("{} packets written", summary.summary.packets);
+//! # });
+//! ```
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use tokio::io::AsyncWrite;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinHandle;
+
+use crate::pcap::{PcapPacket, PcapWriter};
+use crate::pcapng::{Block, PcapNgWriter};
+use crate::{PcapError, PcapResult, WriteSummary};
+
+
+/// A writer that can be moved into a background task by [`spawn_writer`].
+#[async_trait::async_trait]
+pub trait BackgroundWrite: Send + Sized + 'static {
+    /// Record written by the writer
+    type Record: Send + 'static;
+    /// Wrapped writer, returned when the writer is finished
+    type Output: Send + 'static;
+
+    /// Writes a record.
+    async fn write_record(&mut self, record: &Self::Record) -> PcapResult<()>;
+
+    /// Flushes the writer.
+    async fn flush(&mut self) -> PcapResult<()>;
+
+    /// Finishes the writer, see [`PcapWriter::finish`] and [`PcapNgWriter::finish`].
+    async fn finish(self) -> PcapResult<(Self::Output, WriteSummary)>;
+}
+
+#[async_trait::async_trait]
+impl<W: AsyncWrite + Unpin + Send + 'static> BackgroundWrite for PcapWriter<W> {
+    type Record = PcapPacket<'static>;
+    type Output = W;
+
+    async fn write_record(&mut self, record: &Self::Record) -> PcapResult<()> {
+        self.write_packet(record).await.map(|_| ())
+    }
+
+    async fn flush(&mut self) -> PcapResult<()> {
+        PcapWriter::flush(self).await
+    }
+
+    async fn finish(self) -> PcapResult<(W, WriteSummary)> {
+        PcapWriter::finish(self).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<W: AsyncWrite + Unpin + Send + 'static> BackgroundWrite for PcapNgWriter<W> {
+    type Record = Block<'static>;
+    type Output = W;
+
+    async fn write_record(&mut self, record: &Self::Record) -> PcapResult<()> {
+        self.write_block(record).await.map(|_| ())
+    }
+
+    async fn flush(&mut self) -> PcapResult<()> {
+        PcapNgWriter::flush(self).await
+    }
+
+    async fn finish(self) -> PcapResult<(W, WriteSummary)> {
+        PcapNgWriter::finish(self).await
+    }
+}
+
+
+/// Behavior of [`WriterHandle::write`] when the queue of the writer is full.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Backpressure {
+    /// Waits for a free slot in the queue
+    #[default]
+    Wait,
+    /// Returns [`PcapError::QueueFull`]
+    Fail,
+    /// Drops the record and counts it in [`BackgroundSummary::dropped`]
+    Drop,
+}
+
+/// Configuration of a background writer.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct BackgroundConfig {
+    /// Maximum number of records waiting in the queue
+    pub capacity: usize,
+    /// Maximum number of records written between two flushes of the writer
+    pub batch_size: usize,
+    /// Behavior when the queue is full
+    pub backpressure: Backpressure,
+}
+
+impl Default for BackgroundConfig {
+    fn default() -> Self {
+        BackgroundConfig { capacity: 1024, batch_size: 64, backpressure: Backpressure::Wait }
+    }
+}
+
+impl BackgroundConfig {
+    /// Sets the maximum number of records waiting in the queue, at least 1.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Sets the maximum number of records written between two flushes of the writer, at least 1.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Sets the behavior when the queue is full.
+    pub fn with_backpressure(mut self, backpressure: Backpressure) -> Self {
+        self.backpressure = backpressure;
+        self
+    }
+}
+
+/// Summary of a background writer, returned by [`WriterHandle::close`].
+#[derive(Debug)]
+pub struct BackgroundSummary<O> {
+    /// Wrapped writer, flushed and shut down
+    pub writer: O,
+    /// Summary of the records written
+    pub summary: WriteSummary,
+    /// Number of batches written, i.e. number of flushes of the writer
+    pub batches: u64,
+    /// Number of records dropped with [`Backpressure::Drop`]
+    pub dropped: u64,
+}
+
+
+enum Command<R> {
+    Write(R),
+    Close,
+}
+
+type Task<W> = JoinHandle<PcapResult<(<W as BackgroundWrite>::Output, WriteSummary, u64)>>;
+
+/// Cloneable handle to a writer running in a background task, created by [`spawn_writer`].
+///
+/// The writer task ends when [`WriterHandle::close`] is called or when all the handles are dropped.
+pub struct WriterHandle<W: BackgroundWrite> {
+    sender: mpsc::Sender<Command<W::Record>>,
+    backpressure: Backpressure,
+    dropped: Arc<AtomicU64>,
+    task: Arc<Mutex<Option<Task<W>>>>,
+}
+
+impl<W: BackgroundWrite> Clone for WriterHandle<W> {
+    fn clone(&self) -> Self {
+        WriterHandle { sender: self.sender.clone(), backpressure: self.backpressure, dropped: self.dropped.clone(), task: self.task.clone() }
+    }
+}
+
+/// Moves `writer` into a new tokio task and returns a handle to write to it.
+///
+/// The records are written in the order they are queued. The writer is flushed after each batch of at most
+/// [`BackgroundConfig::batch_size`] records, or earlier when the queue is empty.
+///
+/// # Panics
+/// Must be called from a tokio runtime.
+pub fn spawn_writer<W: BackgroundWrite>(writer: W, config: BackgroundConfig) -> WriterHandle<W> {
+    let (sender, receiver) = mpsc::channel(config.capacity.max(1));
+    let task = tokio::spawn(run(writer, receiver, config.batch_size.max(1)));
+
+    WriterHandle { sender, backpressure: config.backpressure, dropped: Arc::new(AtomicU64::new(0)), task: Arc::new(Mutex::new(Some(task))) }
+}
+
+impl<W: BackgroundWrite> WriterHandle<W> {
+    /// Queues a record, following the [`Backpressure`] of the writer when the queue is full.
+    ///
+    /// # Errors
+    /// The writer is closed, e.g. after a write error, or the queue is full with [`Backpressure::Fail`].
+    pub async fn write(&self, record: W::Record) -> PcapResult<()> {
+        let command = Command::Write(record);
+
+        match self.backpressure {
+            Backpressure::Wait => self.sender.send(command).await.map_err(|_| PcapError::WriterClosed),
+            Backpressure::Fail => match self.sender.try_send(command) {
+                Ok(()) => Ok(()),
+                Err(TrySendError::Full(_)) => Err(PcapError::QueueFull),
+                Err(TrySendError::Closed(_)) => Err(PcapError::WriterClosed),
+            },
+            Backpressure::Drop => match self.sender.try_send(command) {
+                Ok(()) => Ok(()),
+                Err(TrySendError::Full(_)) => {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                    Ok(())
+                },
+                Err(TrySendError::Closed(_)) => Err(PcapError::WriterClosed),
+            },
+        }
+    }
+
+    /// Returns the number of records dropped with [`Backpressure::Drop`] so far.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Closes the writer, waiting for the records already queued to be written, and finishes it.
+    ///
+    /// The writes of the other handles fail once the writer is closed.
+    ///
+    /// # Errors
+    /// The first error of the writer, which is still finished, or [`PcapError::WriterClosed`] if the writer was already closed
+    /// by another handle.
+    pub async fn close(self) -> PcapResult<BackgroundSummary<W::Output>> {
+        // The writer may have stopped after an error, its result is still in the task
+        let _ = self.sender.send(Command::Close).await;
+
+        let task = match self.task.lock().await.take() {
+            Some(task) => task,
+            None => return Err(PcapError::WriterClosed),
+        };
+
+        let (writer, summary, batches) = task.await.map_err(|e| PcapError::IoError(std::io::Error::other(e)))??;
+
+        Ok(BackgroundSummary { writer, summary, batches, dropped: self.dropped() })
+    }
+}
+
+/// Writes the queued records until the writer is closed or all the handles are dropped.
+async fn run<W: BackgroundWrite>(
+    mut writer: W,
+    mut receiver: mpsc::Receiver<Command<W::Record>>,
+    batch_size: usize,
+) -> PcapResult<(W::Output, WriteSummary, u64)> {
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut batches = 0;
+    let mut error = None;
+
+    while let Some(command) = receiver.recv().await {
+        batch.push(command);
+        while batch.len() < batch_size {
+            match receiver.try_recv() {
+                Ok(command) => batch.push(command),
+                Err(_) => break,
+            }
+        }
+
+        for command in batch.drain(..) {
+            match command {
+                Command::Write(record) => {
+                    if error.is_none() {
+                        if let Err(e) = writer.write_record(&record).await {
+                            error = Some(e);
+                            receiver.close();
+                        }
+                    }
+                },
+                // The records already queued are still received
+                Command::Close => receiver.close(),
+            }
+        }
+
+        if error.is_none() {
+            if let Err(e) = writer.flush().await {
+                error = Some(e);
+                receiver.close();
+            }
+            batches += 1;
+        }
+    }
+
+    match error {
+        Some(e) => {
+            // The output is still shut down, the first error is the one reported
+            let _ = writer.finish().await;
+            Err(e)
+        },
+        None =>writer.finish().await.map(|(output, summary)| (output, summary, batches)),
+    }
+}
@@ -0,0 +1,333 @@
+//! Contains [`PcapCodec`] and [`PcapNgCodec`], implementations of [`Decoder`] and [`Encoder`] for [`FramedRead`] and
+//! [`FramedWrite`].
+//!
+//! The decoded packets are [`BytesPcapPacket`] and [`BytesEnhancedPacketBlock`], whose payload shares the buffer of
+//! the [`FramedRead`] instead of being copied.
+//!
+//! [`FramedRead`]: tokio_util::codec::FramedRead
+//! [`FramedWrite`]: tokio_util::codec::FramedWrite
+//!
+//! # Example
+//! ```rust,no_run
+//! # tokio_test::block_on(async {
+//! use futures::StreamExt;
+//! use tokio_util::codec::FramedRead;
+//!
+//! use pcap_file_tokio::codec::PcapCodec;
+//!
+//! let file_in = tokio::fs::File::open("test.pcap").await.expect("Error opening file");
+//! let mut frames = FramedRead::new(file_in, PcapCodec::new());
+//!
+//! while let Some(packet) = frames.next().await {
+//!     let packet = packet.unwrap();
+//!     println!("{:?}: {} bytes", packet.timestamp, packet.data.len());
+//! }
+//! # });
+//! ```
+
+use std::future::Future;
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+use byteorder::{BigEndian, LittleEndian};
+use bytes::BytesMut;
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::pcap::{decode_header, decode_packet, BytesPcapPacket, PcapHeader, PcapPacket, PcapParser};
+use crate::pcapng::blocks::enhanced_packet::BytesEnhancedPacketBlock;
+use crate::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use crate::pcapng::blocks::section_header::SectionHeaderBlock;
+use crate::pcapng::{decode_first_section, decode_frame, Block, PcapNgBlock, PcapNgParser};
+pub use crate::pcapng::PcapNgFrame;
+use crate::read_buffer::decode_bounded;
+use crate::{Endianness, ParseLimits, ParseMode, ParseWarning, PcapError, PcapResult, ReaderConfig};
+
+
+/// Codec of a pcap stream.
+///
+/// The decoder parses the header of the stream then yields its packets, the encoder writes its header before the
+/// first packet.
+///
+/// The decoder fails with [`PcapError::RecordTooLarge`] on a packet larger than its maximum record size, see
+/// [`PcapCodec::with_max_record_size`], and applies its [`ParseLimits`], see [`PcapCodec::with_limits`].
+#[derive(Clone, Debug)]
+pub struct PcapCodec {
+    /// Parser of the decoded stream, once its header is parsed
+    parser: Option<PcapParser>,
+    /// Maximum size of a decoded packet
+    max_record_size: usize,
+    /// Resource limits of the decoded stream
+    limits: ParseLimits,
+    /// Header of the encoded stream
+    header: PcapHeader,
+    header_written: bool,
+}
+
+impl Default for PcapCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PcapCodec {
+    /// Creates a new [`PcapCodec`], encoding with a default native endian [`PcapHeader`].
+    pub fn new() -> Self {
+        Self::with_header(PcapHeader { endianness: Endianness::native(), ..Default::default() })
+    }
+
+    /// Creates a new [`PcapCodec`], encoding with the given header.
+    pub fn with_header(header: PcapHeader) -> Self {
+        PcapCodec {
+            parser: None,
+            max_record_size: ReaderConfig::default().max_record_size,
+            limits: ParseLimits::default(),
+            header,
+            header_written: false,
+        }
+    }
+
+    /// Sets the maximum size of a decoded packet, 8_000_000 bytes by default.
+    pub fn with_max_record_size(mut self, max_record_size: usize) -> Self {
+        self.max_record_size = max_record_size.max(1);
+        self
+    }
+
+    /// Sets the resource limits of the decoded stream, none by default.
+    ///
+    /// Only the maximum block length applies to a pcap, see [`PcapParser::with_limits`].
+    pub fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Returns the header of the decoded stream, once parsed.
+    pub fn decoded_header(&self) -> Option<PcapHeader> {
+        self.parser.as_ref().map(|parser| parser.header())
+    }
+
+    /// Returns and clears the warnings collected on the decoded stream, see [`ParseMode`].
+    pub fn take_decoded_warnings(&mut self) -> Vec<ParseWarning> {
+        self.parser.as_mut().map(|parser| parser.take_warnings()).unwrap_or_default()
+    }
+
+    /// Returns the header of the encoded stream.
+    pub fn header(&self) -> PcapHeader {
+        self.header
+    }
+}
+
+impl Decoder for PcapCodec {
+    type Item = BytesPcapPacket;
+    type Error = PcapError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> PcapResult<Option<BytesPcapPacket>> {
+        let parser = match &mut self.parser {
+            Some(parser) => parser,
+            None => match decode_header(src, self.limits, ParseMode::default())? {
+                Some(parser) => self.parser.insert(parser),
+                None => return Ok(None),
+            },
+        };
+
+        decode_bounded(src, self.max_record_size, |src| decode_packet(src, parser))
+    }
+}
+
+impl<'a> Encoder<PcapPacket<'a>> for PcapCodec {
+    type Error = PcapError;
+
+    fn encode(&mut self, packet: PcapPacket<'a>, dst: &mut BytesMut) -> PcapResult<()> {
+        let mut buffer = Vec::new();
+
+        if !self.header_written {
+            write_sync(self.header.write_to(&mut buffer))?;
+        }
+
+        let header = self.header;
+        match header.endianness {
+            Endianness::Big => write_sync(packet.write_to::<_, BigEndian>(&mut buffer, header.ts_resolution, header.snaplen))?,
+            Endianness::Little => write_sync(packet.write_to::<_, LittleEndian>(&mut buffer, header.ts_resolution, header.snaplen))?,
+        };
+
+        dst.extend_from_slice(&buffer);
+        self.header_written = true;
+
+        Ok(())
+    }
+}
+
+impl Encoder<BytesPcapPacket> for PcapCodec {
+    type Error = PcapError;
+
+    fn encode(&mut self, packet: BytesPcapPacket, dst: &mut BytesMut) -> PcapResult<()> {
+        self.encode(packet.as_pcap_packet(), dst)
+    }
+}
+
+
+/// Codec of a pcapng stream.
+///
+/// The decoder yields every block of the stream, including the first Section Header Block, and keeps track of the
+/// current section and interfaces. The encoder writes its Section Header Block before the first block, unless this
+/// block is a Section Header Block itself.
+///
+/// The decoder fails with [`PcapError::RecordTooLarge`] on a block larger than its maximum record size, see
+/// [`PcapNgCodec::with_max_record_size`], and applies its [`ParseLimits`] to every block, see [`PcapNgCodec::with_limits`].
+#[derive(Clone, Debug)]
+pub struct PcapNgCodec {
+    /// Parser of the decoded stream, once its first section is parsed
+    parser: Option<PcapNgParser>,
+    /// Maximum size of a decoded block
+    max_record_size: usize,
+    /// Resource limits of the decoded stream
+    limits: ParseLimits,
+    /// Current section of the encoded stream
+    section: SectionHeaderBlock<'static>,
+    section_written: bool,
+}
+
+impl Default for PcapNgCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PcapNgCodec {
+    /// Creates a new [`PcapNgCodec`], encoding with a default native endian [`SectionHeaderBlock`].
+    pub fn new() -> Self {
+        Self::with_section_header(SectionHeaderBlock { endianness: Endianness::native(), ..Default::default() })
+    }
+
+    /// Creates a new [`PcapNgCodec`], encoding with the given section header.
+    pub fn with_section_header(section: SectionHeaderBlock<'static>) -> Self {
+        PcapNgCodec {
+            parser: None,
+            max_record_size: ReaderConfig::default().max_record_size,
+            limits: ParseLimits::default(),
+            section,
+            section_written: false,
+        }
+    }
+
+    /// Sets the maximum size of a decoded block, 8_000_000 bytes by default.
+    pub fn with_max_record_size(mut self, max_record_size: usize) -> Self {
+        self.max_record_size = max_record_size.max(1);
+        self
+    }
+
+    /// Sets the resource limits of the decoded stream, none by default.
+    pub fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Returns the current section of the decoded stream, once parsed.
+    pub fn decoded_section(&self) -> Option<&SectionHeaderBlock<'static>> {
+        self.parser.as_ref().map(|parser| parser.section())
+    }
+
+    /// Returns the interfaces of the current section of the decoded stream.
+    pub fn decoded_interfaces(&self) -> &[InterfaceDescriptionBlock<'static>] {
+        match &self.parser {
+            Some(parser) => parser.interfaces(),
+            None => &[],
+        }
+    }
+
+    /// Returns and clears the warnings collected on the decoded stream, see [`ParseMode`].
+    pub fn take_decoded_warnings(&mut self) -> Vec<ParseWarning> {
+        self.parser.as_mut().map(|parser| parser.take_warnings()).unwrap_or_default()
+    }
+
+    /// Returns the current section of the encoded stream.
+    pub fn section(&self) -> &SectionHeaderBlock<'static> {
+        &self.section
+    }
+}
+
+impl Decoder for PcapNgCodec {
+    type Item = PcapNgFrame;
+    type Error = PcapError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> PcapResult<Option<PcapNgFrame>> {
+        let (parser, limits) = (&mut self.parser, self.limits);
+        decode_bounded(src, self.max_record_size, |src| match parser {
+            Some(parser) => decode_frame(src, parser),
+            None => match decode_first_section(src, limits, ParseMode::default())? {
+                Some(first) => {
+                    let section = first.section().clone();
+                    *parser = Some(first);
+                    Ok(Some(PcapNgFrame::Block(section.into_block())))
+                },
+                None => Ok(None),
+            },
+        })
+    }
+}
+
+impl<'a> Encoder<Block<'a>> for PcapNgCodec {
+    type Error = PcapError;
+
+    fn encode(&mut self, block: Block<'a>, dst: &mut BytesMut) -> PcapResult<()> {
+        let mut buffer = Vec::new();
+
+        match &block {
+            Block::SectionHeader(section) => self.section = section.clone().into_owned(),
+            _ if !self.section_written => {
+                let section = self.section.clone().into_block();
+                match self.section.endianness {
+                    Endianness::Big => write_sync(section.write_to::<BigEndian, _>(&mut buffer)),
+                    Endianness::Little => write_sync(section.write_to::<LittleEndian, _>(&mut buffer)),
+                }
+                .map_err(PcapError::IoError)?;
+            },
+            _ => {},
+        }
+
+        match self.section.endianness {
+            Endianness::Big => write_sync(block.write_to::<BigEndian, _>(&mut buffer)),
+            Endianness::Little => write_sync(block.write_to::<LittleEndian, _>(&mut buffer)),
+        }
+        .map_err(PcapError::IoError)?;
+
+        dst.extend_from_slice(&buffer);
+        self.section_written = true;
+
+        Ok(())
+    }
+}
+
+impl Encoder<BytesEnhancedPacketBlock> for PcapNgCodec {
+    type Error = PcapError;
+
+    fn encode(&mut self, packet: BytesEnhancedPacketBlock, dst: &mut BytesMut) -> PcapResult<()> {
+        self.encode(packet.as_block().into_block(), dst)
+    }
+}
+
+impl Encoder<PcapNgFrame> for PcapNgCodec {
+    type Error = PcapError;
+
+    fn encode(&mut self, frame: PcapNgFrame, dst: &mut BytesMut) -> PcapResult<()> {
+        match frame {
+            PcapNgFrame::Packet(packet) => self.encode(packet, dst),
+            PcapNgFrame::Block(block) => self.encode(block, dst),
+        }
+    }
+}
+
+
+/// Waker that does nothing, the futures polled by [`write_sync`] are never pending.
+const NOOP_WAKER: RawWaker = RawWaker::new(std::ptr::null(), &NOOP_WAKER_VTABLE);
+const NOOP_WAKER_VTABLE: RawWakerVTable = RawWakerVTable::new(|_| NOOP_WAKER, |_| {}, |_| {}, |_| {});
+
+/// Runs a write into a `Vec<u8>`, which is always ready.
+fn write_sync<T>(future: impl Future<Output = T>) -> T {
+    let mut future = std::pin::pin!(future);
+
+    // Sound because the functions of the vtable ignore the data pointer
+    let waker = unsafe { Waker::from_raw(NOOP_WAKER) };
+    match future.as_mut().poll(&mut Context::from_waker(&waker)) {
+        Poll::Ready(output) => output,
+        Poll::Pending => unreachable!("Writes into a Vec<u8> are always ready"),
+    }
+}
@@ -1,5 +1,7 @@
 use byteorder::{ByteOrder, BigEndian, LittleEndian};
 
+use crate::{ParseLimits, ParseMode};
+
 /// Timestamp resolution of the pcap
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum TsResolution {
@@ -55,6 +57,151 @@ impl Endianness {
     }
 }
 
+/// A file that can be truncated, required to append to an existing capture.
+///
+/// Appending truncates the partial record left at the end of a capture, e.g. by a killed writer.
+/// The implementation for `tokio::fs::File` requires the `fs` feature.
+#[async_trait::async_trait]
+pub trait Truncate {
+    /// Truncates the file to `len` bytes, without changing its position.
+    async fn truncate(&mut self, len: u64) -> std::io::Result<()>;
+}
+
+#[cfg(feature = "fs")]
+#[async_trait::async_trait]
+impl Truncate for tokio::fs::File {
+    async fn truncate(&mut self, len: u64) -> std::io::Result<()> {
+        self.set_len(len).await
+    }
+}
+
+#[async_trait::async_trait]
+impl Truncate for std::io::Cursor<Vec<u8>> {
+    async fn truncate(&mut self, len: u64) -> std::io::Result<()> {
+        self.get_mut().truncate(len as usize);
+        Ok(())
+    }
+}
+
+/// Summary of what a writer wrote, returned by the `finish` methods of the writers.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct WriteSummary {
+    /// Number of bytes written, headers included
+    pub bytes: u64,
+    /// Number of packets written
+    pub packets: u64,
+    /// Number of pcapng blocks written, section headers included, always 0 for a pcap
+    pub blocks: u64,
+}
+
+/// Configuration of the buffer of a [`PcapReader`](crate::pcap::PcapReader) or a [`PcapNgReader`](crate::pcapng::PcapNgReader).
+///
+/// The buffer starts with `initial_capacity` bytes and grows, by doubling, when a record (a packet or a block) doesn't
+/// fit in it, up to `max_record_size` bytes. A larger record fails with [`PcapError::RecordTooLarge`](crate::PcapError).
+///
+/// The `limits` and the `mode` are applied to the parser of the reader, see [`ParseLimits`] and [`ParseMode`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ReaderConfig {
+    /// Initial capacity of the buffer, in bytes
+    pub initial_capacity: usize,
+    /// Maximum size of a record, i.e. maximum capacity of the buffer, in bytes
+    pub max_record_size: usize,
+    /// Shrinks the buffer back to its initial capacity once the large records are consumed
+    pub shrink: bool,
+    /// Resource limits of the parser
+    pub limits: ParseLimits,
+    /// Checks enforced by the parser
+    pub mode: ParseMode,
+}
+
+/// Creates a new [`ReaderConfig`] with these parameters:
+///
+/// ```rust,ignore
+/// ReaderConfig {
+///     initial_capacity: 65_536,
+///     max_record_size: 8_000_000,
+///     shrink: true,
+///     limits: ParseLimits::default(),
+///     mode: ParseMode::default(),
+/// };
+/// ```
+impl Default for ReaderConfig {
+    fn default() -> Self {
+        ReaderConfig {
+            initial_capacity: 65_536,
+            max_record_size: 8_000_000,
+            shrink: true,
+            limits: ParseLimits::default(),
+            mode: ParseMode::default(),
+        }
+    }
+}
+
+impl ReaderConfig {
+    /// Sets the initial capacity of the buffer, at least 1 byte.
+    pub fn with_initial_capacity(mut self, initial_capacity: usize) -> Self {
+        self.initial_capacity = initial_capacity.max(1);
+        self
+    }
+
+    /// Sets the maximum size of a record.
+    pub fn with_max_record_size(mut self, max_record_size: usize) -> Self {
+        self.max_record_size = max_record_size;
+        self
+    }
+
+    /// Sets whether the buffer shrinks back to its initial capacity.
+    pub fn with_shrink(mut self, shrink: bool) -> Self {
+        self.shrink = shrink;
+        self
+    }
+
+    /// Sets the resource limits of the parser.
+    pub fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets the checks enforced by the parser.
+    pub fn with_mode(mut self, mode: ParseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+}
+
+/// Warns, in debug builds, when a writer is dropped without being finished.
+#[derive(Debug)]
+pub(crate) struct FinishGuard {
+    #[cfg(debug_assertions)]
+    name: &'static str,
+    armed: bool,
+}
+
+impl FinishGuard {
+    #[cfg_attr(not(debug_assertions), allow(unused_variables))]
+    pub(crate) fn new(name: &'static str) -> Self {
+        FinishGuard {
+            #[cfg(debug_assertions)]
+            name,
+            armed: true,
+        }
+    }
+
+    /// Disarms the guard, the writer was finished or given back to the user.
+    pub(crate) fn disarm(&mut self) {
+        self.armed = false;
+    }
+}
+
+#[cfg(debug_assertions)]
+impl Drop for FinishGuard {
+    fn drop(&mut self) {
+        if self.armed && !std::thread::panicking() {
+            eprintln!("warning: {} dropped without calling finish(), the end of the output may be lost", self.name);
+        }
+    }
+}
+
 pub(crate) trait RuntimeByteorder: ByteOrder {
     fn endianness() -> Endianness;
 }
@@ -72,3 +219,17 @@ impl RuntimeByteorder for LittleEndian {
 }
 
 pub use pcap_file::DataLink;
+
+/// Returns the part of `buffer` holding `data`, without copying, or a copy of `data` if it isn't part of `buffer`.
+#[cfg(feature = "bytes")]
+pub(crate) fn share_bytes(buffer: &bytes::Bytes, data: &[u8]) -> bytes::Bytes {
+    let start = buffer.as_ptr() as usize;
+    let data_start = data.as_ptr() as usize;
+
+    if data_start >= start && data_start + data.len() <= start + buffer.len() {
+        buffer.slice_ref(data)
+    }
+    else {
+        bytes::Bytes::copy_from_slice(data)
+    }
+}
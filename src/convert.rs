@@ -0,0 +1,424 @@
+//! Conversions between pcap and pcapng.
+//!
+//! The packet level conversions are [`pcap_header_to_interface`], [`pcap_packet_to_enhanced_packet`] and
+//! [`enhanced_packet_to_pcap_packet`].
+//!
+//! The file level converters are [`pcap_to_pcapng`], [`pcapng_to_pcap`] and [`pcapng_to_pcap_split`].
+//! Pcap can't hold everything a pcapng can, what is lost during the conversion is reported in the returned [`ConversionReport`].
+
+use std::borrow::Cow;
+use std::future::Future;
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::pcap::{PcapHeader, PcapPacket, PcapReader, PcapWriter};
+use crate::pcapng::blocks::enhanced_packet::{EnhancedPacketBlock, EnhancedPacketOption};
+use crate::pcapng::blocks::interface_description::{InterfaceDescriptionBlock, InterfaceDescriptionOption};
+use crate::pcapng::blocks::packet::PacketOption;
+use crate::pcapng::blocks::section_header::{SectionHeaderBlock, SectionHeaderOption};
+use crate::pcapng::{Block, PcapNgReader, PcapNgWriter};
+use crate::{DataLink, Endianness, PcapError, PcapResult, TsResolution};
+
+
+/// Snaplen used in the pcap header when the pcapng interface doesn't limit the packet length (snaplen of 0).
+///
+/// Same value as the `MAXIMUM_SNAPLEN` of libpcap.
+pub const MAX_SNAPLEN: u32 = 262144;
+
+/// Summary of a conversion.
+///
+/// All the `dropped_*` and `truncated_*` fields count something that couldn't be represented in the output file.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ConversionReport {
+    /// Number of packets written
+    pub packets: u64,
+
+    /// Number of comments dropped (section, interface and packet comments)
+    pub dropped_comments: u64,
+
+    /// Number of other options dropped (section, interface and packet options)
+    pub dropped_options: u64,
+
+    /// Number of Name Resolution Blocks dropped
+    pub dropped_name_resolutions: u64,
+
+    /// Number of Interface Statistics Blocks dropped
+    pub dropped_interface_statistics: u64,
+
+    /// Number of Simple Packet Blocks dropped, they don't have a timestamp
+    pub dropped_simple_packets: u64,
+
+    /// Number of other blocks dropped (Systemd Journal Export, unknown blocks...)
+    pub dropped_other_blocks: u64,
+
+    /// Number of packets whose timestamp lost precision because the output only has a microsecond resolution
+    pub truncated_timestamps: u64,
+}
+
+impl ConversionReport {
+    /// Returns true if nothing was lost during the conversion.
+    pub fn is_lossless(&self) -> bool {
+        *self == ConversionReport { packets: self.packets, ..Default::default() }
+    }
+}
+
+
+/// Creates the [`InterfaceDescriptionBlock`] equivalent to a [`PcapHeader`].
+///
+/// The link type and the snaplen are preserved, the timestamp resolution is mapped into the `if_tsresol` option.
+pub fn pcap_header_to_interface(header: &PcapHeader) -> InterfaceDescriptionBlock<'static> {
+    let ts_resol = match header.ts_resolution {
+        TsResolution::MicroSecond => 6,
+        TsResolution::NanoSecond => 9,
+    };
+
+    InterfaceDescriptionBlock {
+        linktype: header.datalink,
+        snaplen: header.snaplen,
+        options: vec![InterfaceDescriptionOption::IfTsResol(ts_resol)],
+    }
+}
+
+/// Creates a borrowed [`EnhancedPacketBlock`] from a [`PcapPacket`].
+///
+/// The `interface` is used to convert the timestamp into its resolution.
+pub fn pcap_packet_to_enhanced_packet<'a>(
+    packet: &'a PcapPacket,
+    interface_id: u32,
+    interface: &InterfaceDescriptionBlock,
+) -> PcapResult<EnhancedPacketBlock<'a>> {
+    let mut block = EnhancedPacketBlock {
+        interface_id,
+        timestamp: Duration::ZERO,
+        original_len: packet.orig_len,
+        data: Cow::Borrowed(&packet.data),
+        options: vec![],
+    };
+    block.set_absolute_timestamp(interface, packet.timestamp)?;
+
+    Ok(block)
+}
+
+/// Creates a borrowed [`PcapPacket`] from an [`EnhancedPacketBlock`].
+///
+/// The `interface` is used to compute the absolute timestamp. The options of the packet are dropped.
+pub fn enhanced_packet_to_pcap_packet<'a>(packet: &'a EnhancedPacketBlock, interface: &InterfaceDescriptionBlock) -> PcapPacket<'a> {
+    PcapPacket::new(packet.absolute_timestamp(interface), packet.original_len, &packet.data)
+}
+
+
+/// Converts a pcap into a pcapng.
+///
+/// The pcapng has the same endianness as the pcap and a single interface created by [`pcap_header_to_interface`].
+/// Returns the wrapped writer and the [`ConversionReport`].
+///
+/// # Example
+/// ```rust,no_run
+/// # tokio_test::block_on(async {
+/// use tokio::fs::File;
+///
+/// use pcap_file_tokio::convert::pcap_to_pcapng;
+///
+/// let file_in = File::open("test.pcap").await.expect("Error opening file");
+/// let file_out = File::create("out.pcapng").await.expect("Error creating file");
+///
+/// let (_file_out, report) = pcap_to_pcapng(file_in, file_out).await.unwrap();
+/// println!("{} packets converted", report.packets);
+/// # });
+/// ```
+pub async fn pcap_to_pcapng<R, W>(reader: R, writer: W) -> PcapResult<(W, ConversionReport)>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin + Send,
+{
+    let mut reader = PcapReader::new(reader).await?;
+    let header = reader.header();
+
+    let interface = pcap_header_to_interface(&header);
+    let mut writer = PcapNgWriter::with_endianness(writer, header.endianness).await?;
+    writer.write_block(&Block::InterfaceDescription(interface.clone())).await?;
+
+    let mut report = ConversionReport::default();
+    while let Some(packet) = reader.next_packet().await {
+        let packet = packet?;
+        let block = pcap_packet_to_enhanced_packet(&packet, 0, &interface)?;
+        writer.write_block(&Block::EnhancedPacket(block)).await?;
+        report.packets += 1;
+    }
+
+    Ok((writer.into_inner(), report))
+}
+
+/// Converts a pcapng into a pcap.
+///
+/// The pcap has the endianness of the first section of the pcapng. Its link type, snaplen and timestamp resolution
+/// come from the interface of the first packet. An interface snaplen of 0 is converted to [`MAX_SNAPLEN`] and a
+/// `if_tsresol` other than microseconds gives a nanosecond pcap.
+///
+/// # Errors
+/// Returns [`PcapError::MixedLinkTypes`] if the packets don't all have the same link type,
+/// use [`pcapng_to_pcap_split`] for these files.
+///
+/// # Example
+/// ```rust,no_run
+/// # tokio_test::block_on(async {
+/// use tokio::fs::File;
+///
+/// use pcap_file_tokio::convert::pcapng_to_pcap;
+///
+/// let file_in = File::open("test.pcapng").await.expect("Error opening file");
+/// let file_out = File::create("out.pcap").await.expect("Error creating file");
+///
+/// let (_file_out, report) = pcapng_to_pcap(file_in, file_out).await.unwrap();
+/// if !report.is_lossless() {
+///     println!("Some information was lost: {report:?}");
+/// }
+/// # });
+/// ```
+pub async fn pcapng_to_pcap<R, W>(reader: R, writer: W) -> PcapResult<(W, ConversionReport)>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut reader = PcapNgReader::new(reader).await?;
+    let mut extractor = Extractor::new(reader.section());
+
+    let mut pending = Some(writer);
+    let mut output: Option<(DataLink, PcapWriter<W>)> = None;
+
+    while let Some(block) = reader.next_block().await {
+        let extracted = match extractor.extract(block?)? {
+            Some(extracted) => extracted,
+            None => continue,
+        };
+
+        let pcap_writer = match &mut output {
+            Some((linktype, pcap_writer)) => {
+                if *linktype != extracted.linktype {
+                    return Err(PcapError::MixedLinkTypes(*linktype, extracted.linktype));
+                }
+                pcap_writer
+            },
+            None => {
+                let header = extractor.pcap_header(extracted.linktype, extracted.ts_resolution);
+                let pcap_writer = PcapWriter::with_header(pending.take().unwrap(), header).await?;
+                &mut output.insert((extracted.linktype, pcap_writer)).1
+            },
+        };
+
+        extractor.write(pcap_writer, &extracted.packet).await?;
+    }
+
+    let writer = match (output, pending) {
+        (Some((_, pcap_writer)), _) => pcap_writer.into_writer(),
+        (None, Some(writer)) => {
+            // No packet, writes the header of the first interface if any
+            let header = extractor.empty_pcap_header();
+            PcapWriter::with_header(writer, header).await?.into_writer()
+        },
+        (None, None) => unreachable!(),
+    };
+
+    Ok((writer, extractor.report))
+}
+
+/// Converts a pcapng into one pcap per link type.
+///
+/// `make_writer` is called once per link type, the first time a packet of this link type is found.
+/// The pcaps are created as in [`pcapng_to_pcap`] and returned with their link type, in order of creation.
+///
+/// # Example
+/// ```rust,no_run
+/// # tokio_test::block_on(async {
+/// use tokio::fs::File;
+///
+/// use pcap_file_tokio::convert::pcapng_to_pcap_split;
+///
+/// let file_in = File::open("test.pcapng").await.expect("Error opening file");
+///
+/// let (outputs, _report) = pcapng_to_pcap_split(file_in, |linktype| async move {
+///     let path = format!("out_{}.pcap", u32::from(linktype));
+///     File::create(path).await.map_err(pcap_file_tokio::PcapError::IoError)
+/// })
+/// .await
+/// .unwrap();
+///
+/// for (linktype, _file) in outputs {
+///     println!("Created a pcap for {linktype:?}");
+/// }
+/// # });
+/// ```
+pub async fn pcapng_to_pcap_split<R, W, F, Fut>(reader: R, mut make_writer: F) -> PcapResult<(Vec<(DataLink, W)>, ConversionReport)>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+    F: FnMut(DataLink) -> Fut,
+    Fut: Future<Output = PcapResult<W>>,
+{
+    let mut reader = PcapNgReader::new(reader).await?;
+    let mut extractor = Extractor::new(reader.section());
+
+    // DataLink isn't hashable and there are only a few link types in a file
+    let mut outputs: Vec<(DataLink, PcapWriter<W>)> = vec![];
+
+    while let Some(block) = reader.next_block().await {
+        let extracted = match extractor.extract(block?)? {
+            Some(extracted) => extracted,
+            None => continue,
+        };
+
+        let idx = match outputs.iter().position(|(linktype, _)| *linktype == extracted.linktype) {
+            Some(idx) => idx,
+            None => {
+                let writer = make_writer(extracted.linktype).await?;
+                let header = extractor.pcap_header(extracted.linktype, extracted.ts_resolution);
+                outputs.push((extracted.linktype, PcapWriter::with_header(writer, header).await?));
+                outputs.len() - 1
+            },
+        };
+
+        extractor.write(&mut outputs[idx].1, &extracted.packet).await?;
+    }
+
+    let outputs = outputs.into_iter().map(|(linktype, writer)| (linktype, writer.into_writer())).collect();
+
+    Ok((outputs, extractor.report))
+}
+
+
+/// Packet extracted from a pcapng block, with the interface information needed to write it in a pcap.
+struct Extracted<'a> {
+    linktype: DataLink,
+    ts_resolution: TsResolution,
+    packet: PcapPacket<'a>,
+}
+
+/// Keeps track of the pcapng interfaces and of the lost information.
+struct Extractor {
+    interfaces: Vec<InterfaceDescriptionBlock<'static>>,
+    endianness: Endianness,
+    report: ConversionReport,
+}
+
+impl Extractor {
+    fn new(section: &SectionHeaderBlock) -> Self {
+        let mut extractor = Extractor { interfaces: vec![], endianness: section.endianness, report: ConversionReport::default() };
+        extractor.count_section(section);
+
+        extractor
+    }
+
+    fn count_section(&mut self, section: &SectionHeaderBlock) {
+        for opt in &section.options {
+            match opt {
+                SectionHeaderOption::Comment(_) => self.report.dropped_comments += 1,
+                _ => self.report.dropped_options += 1,
+            }
+        }
+    }
+
+    /// Converts a packet block into a pcap packet, updating the interfaces and the report for the other blocks.
+    fn extract<'a>(&mut self, block: Block<'a>) -> PcapResult<Option<Extracted<'a>>> {
+        let (interface_id, units, original_len, data) = match block {
+            Block::SectionHeader(section) => {
+                self.count_section(&section);
+                self.interfaces.clear();
+                return Ok(None);
+            },
+            Block::InterfaceDescription(interface) => {
+                for opt in &interface.options {
+                    match opt {
+                        InterfaceDescriptionOption::Comment(_) => self.report.dropped_comments += 1,
+                        // Applied to the timestamps
+                        InterfaceDescriptionOption::IfTsResol(_) | InterfaceDescriptionOption::IfTsOffset(_) => {},
+                        _ => self.report.dropped_options += 1,
+                    }
+                }
+                self.interfaces.push(interface.into_owned());
+                return Ok(None);
+            },
+            Block::EnhancedPacket(packet) => {
+                for opt in &packet.options {
+                    match opt {
+                        EnhancedPacketOption::Comment(_) => self.report.dropped_comments += 1,
+                        _ => self.report.dropped_options += 1,
+                    }
+                }
+                (packet.interface_id, packet.timestamp.as_nanos() as u64, packet.original_len, packet.data)
+            },
+            Block::Packet(packet) => {
+                for opt in &packet.options {
+                    match opt {
+                        PacketOption::Comment(_) => self.report.dropped_comments += 1,
+                        _ => self.report.dropped_options += 1,
+                    }
+                }
+                (packet.interface_id as u32, packet.timestamp, packet.original_len, packet.data)
+            },
+            Block::SimplePacket(_) => {
+                self.report.dropped_simple_packets += 1;
+                return Ok(None);
+            },
+            Block::NameResolution(_) => {
+                self.report.dropped_name_resolutions += 1;
+                return Ok(None);
+            },
+            Block::InterfaceStatistics(_) => {
+                self.report.dropped_interface_statistics += 1;
+                return Ok(None);
+            },
+            Block::SystemdJournalExport(_) | Block::Unknown(_) => {
+                self.report.dropped_other_blocks += 1;
+                return Ok(None);
+            },
+        };
+
+        let interface = self.interfaces.get(interface_id as usize).ok_or(PcapError::InvalidInterfaceId(interface_id))?;
+        let packet = PcapPacket { timestamp: interface.timestamp_to_duration(units), orig_len: original_len, data };
+
+        Ok(Some(Extracted { linktype: interface.linktype, ts_resolution: pcap_ts_resolution(interface), packet }))
+    }
+
+    /// Returns the pcap header for a link type.
+    ///
+    /// The snaplen is the biggest one of the current interfaces with this link type.
+    fn pcap_header(&self, linktype: DataLink, ts_resolution: TsResolution) -> PcapHeader {
+        let snaplen = self
+            .interfaces
+            .iter()
+            .filter(|interface| interface.linktype == linktype)
+            .map(|interface| if interface.snaplen == 0 { MAX_SNAPLEN } else { interface.snaplen })
+            .max()
+            .unwrap_or(MAX_SNAPLEN);
+
+        PcapHeader { snaplen, datalink: linktype, ts_resolution, endianness: self.endianness, ..Default::default() }
+    }
+
+    /// Returns the pcap header of a pcapng without packets, using its first interface if any.
+    fn empty_pcap_header(&self) -> PcapHeader {
+        match self.interfaces.first() {
+            Some(interface) => self.pcap_header(interface.linktype, pcap_ts_resolution(interface)),
+            None => PcapHeader { endianness: self.endianness, ..Default::default() },
+        }
+    }
+
+    async fn write<W: AsyncWrite + Unpin>(&mut self, writer: &mut PcapWriter<W>, packet: &PcapPacket<'_>) -> PcapResult<()> {
+        if writer.ts_resolution() == TsResolution::MicroSecond && !packet.timestamp.subsec_nanos().is_multiple_of(1000) {
+            self.report.truncated_timestamps += 1;
+        }
+
+        writer.write_packet(packet).await?;
+        self.report.packets += 1;
+
+        Ok(())
+    }
+}
+
+/// Returns the pcap timestamp resolution able to hold the timestamps of an interface.
+fn pcap_ts_resolution(interface: &InterfaceDescriptionBlock) -> TsResolution {
+    match interface.ts_resolution() {
+        6 => TsResolution::MicroSecond,
+        _ => TsResolution::NanoSecond,
+    }
+}
@@ -0,0 +1,179 @@
+//! Contains [`Decoder`], a push-based decoder of pcap and pcapng that doesn't depend on any IO.
+//!
+//! The decoder is fed with chunks of bytes of any size, e.g. from a ring buffer or from the reader of another async
+//! runtime, and yields [`DecoderEvent`]s as soon as enough bytes are buffered.
+
+use crate::info::CaptureFormat;
+use crate::pcap::{PcapHeader, PcapPacket, PcapParser};
+use crate::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use crate::pcapng::blocks::section_header::SectionHeaderBlock;
+use crate::pcapng::blocks::SECTION_HEADER_BLOCK;
+use crate::pcapng::{Block, PcapNgParser};
+use crate::{ParseLimits, PcapError, PcapResult};
+
+
+/// Event yielded by [`Decoder::next_event`].
+#[derive(Clone, Debug)]
+pub enum DecoderEvent<'a> {
+    /// Header of a pcap
+    Header(PcapHeader),
+    /// Header of a new pcapng section, including the first one
+    Section(SectionHeaderBlock<'a>),
+    /// New interface of the current pcapng section
+    Interface(InterfaceDescriptionBlock<'a>),
+    /// Packet of a pcap
+    Packet(PcapPacket<'a>),
+    /// Any other pcapng block, including the packet blocks
+    Block(Block<'a>),
+    /// The next header, packet or block isn't complete, more bytes must be fed
+    NeedMore,
+}
+
+enum State {
+    /// The format isn't known yet
+    Start,
+    Pcap(PcapParser),
+    PcapNg(PcapNgParser),
+}
+
+/// Push-based decoder of pcap and pcapng, detected from their magic number.
+///
+/// Unlike [`PcapReader`](crate::pcap::PcapReader) and [`PcapNgReader`](crate::pcapng::PcapNgReader), the decoder
+/// doesn't read from an [`AsyncRead`](tokio::io::AsyncRead): the bytes are pushed with [`Decoder::feed`] and the
+/// events are pulled with [`Decoder::next_event`] until it returns [`DecoderEvent::NeedMore`].
+///
+/// [`PcapError::IncompleteBuffer`] is never returned, it is reported as [`DecoderEvent::NeedMore`].
+/// The decoder can't recover from the other errors.
+///
+/// The decoder buffers the bytes of a record until it is complete. To decode untrusted captures, create it with
+/// [`Decoder::with_limits`]: a record longer than the maximum block length fails as soon as its header is fed, which
+/// bounds the bytes buffered while [`Decoder::next_event`] returns [`DecoderEvent::NeedMore`].
+///
+/// # Example
+/// ```rust,no_run
+/// use pcap_file_tokio::decoder::{Decoder, DecoderEvent};
+///
+/// let chunks: Vec<Vec<u8>> = vec![];
+/// let mut decoder = Decoder::new();
+///
+/// for chunk in chunks {
+///     decoder.feed(&chunk);
+///
+///     loop {
+///         match decoder.next_event().unwrap() {
+///             DecoderEvent::Packet(packet) => println!("{:?}", packet.timestamp),
+///             DecoderEvent::Block(block) => println!("{block:?}"),
+///             DecoderEvent::NeedMore => break,
+///             _ => {},
+///         }
+///     }
+/// }
+///
+/// if decoder.buffered() > 0 {
+///     println!("Truncated capture");
+/// }
+/// ```
+pub struct Decoder {
+    /// Bytes fed and not decoded yet, from `pos`
+    buffer: Vec<u8>,
+    /// Start of the bytes not decoded yet
+    pos: usize,
+    state: State,
+    limits: ParseLimits,
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder {
+    /// Creates a new [`Decoder`].
+    pub fn new() -> Self {
+        Self::with_limits(ParseLimits::default())
+    }
+
+    /// Creates a new [`Decoder`] with the given [`ParseLimits`].
+    ///
+    /// A record exceeding the limits returns [`PcapError::LimitExceeded`], every pcapng block is checked like by
+    /// [`PcapNgParser::next_block`].
+    pub fn with_limits(limits: ParseLimits) -> Self {
+        Decoder { buffer: Vec::new(), pos: 0, state: State::Start, limits }
+    }
+
+    /// Appends a chunk of bytes to decode.
+    pub fn feed(&mut self, chunk: &[u8]) {
+        // Discard the decoded bytes before growing the buffer
+        if self.pos > 0 {
+            self.buffer.drain(..self.pos);
+            self.pos = 0;
+        }
+
+        self.buffer.extend_from_slice(chunk);
+    }
+
+    /// Returns the next event, or [`DecoderEvent::NeedMore`] if the bytes fed so far aren't enough to decode it.
+    ///
+    /// The first event is a [`DecoderEvent::Header`] for a pcap and a [`DecoderEvent::Section`] for a pcapng.
+    pub fn next_event(&mut self) -> PcapResult<DecoderEvent<'_>> {
+        let src = &self.buffer[self.pos..];
+
+        let result = match &mut self.state {
+            State::Start => {
+                if src.len() < 4 {
+                    return Ok(DecoderEvent::NeedMore);
+                }
+
+                if src[..4] == SECTION_HEADER_BLOCK.to_be_bytes() {
+                    PcapNgParser::with_limits(src, self.limits).map(|(rem, parser)| {
+                        let section = parser.section().clone();
+                        self.state = State::PcapNg(parser);
+                        (rem, DecoderEvent::Section(section))
+                    })
+                }
+                else {
+                    PcapParser::with_limits(src, self.limits).map(|(rem, parser)| {
+                        let header = parser.header();
+                        self.state = State::Pcap(parser);
+                        (rem, DecoderEvent::Header(header))
+                    })
+                }
+            },
+
+            State::Pcap(parser) => parser.next_packet_with_warnings(src).map(|(rem, packet)| (rem, DecoderEvent::Packet(packet))),
+
+            State::PcapNg(parser) => parser.next_block(src).map(|(rem, block)| {
+                let event = match block {
+                    Block::SectionHeader(section) => DecoderEvent::Section(section),
+                    Block::InterfaceDescription(interface) => DecoderEvent::Interface(interface),
+                    block => DecoderEvent::Block(block),
+                };
+                (rem, event)
+            }),
+        };
+
+        match result {
+            Ok((rem, event)) => {
+                self.pos += src.len() - rem.len();
+                Ok(event)
+            },
+            Err(PcapError::IncompleteBuffer) => Ok(DecoderEvent::NeedMore),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the format of the capture, once its header is decoded.
+    pub fn format(&self) -> Option<CaptureFormat> {
+        match self.state {
+            State::Start => None,
+            State::Pcap(_) => Some(CaptureFormat::Pcap),
+            State::PcapNg(_) => Some(CaptureFormat::PcapNg),
+        }
+    }
+
+    /// Returns the number of bytes fed and not decoded yet.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len() - self.pos
+    }
+}
@@ -29,6 +29,38 @@ pub enum PcapError {
     /// Invalid interface ID (only for Pcap NG)
     #[error("No corresponding interface id: {0}")]
     InvalidInterfaceId(u32),
+
+    /// Packets with different link types can't be written in the same pcap
+    #[error("Mixed link types: {0:?} and {1:?}")]
+    MixedLinkTypes(crate::DataLink, crate::DataLink),
+
+    /// The first link type can't be converted to the second one
+    #[error("Unsupported link type conversion: {0:?} to {1:?}")]
+    UnsupportedLinkTypeConversion(crate::DataLink, crate::DataLink),
+
+    /// A packet can't be converted from the first link type to the second one
+    #[error("Packet can't be converted from {0:?} to {1:?}: {2}")]
+    InvalidPacketForLinkType(crate::DataLink, crate::DataLink, &'static str),
+
+    /// VLAN tags can't be added or removed with this link type
+    #[error("VLAN tags are not supported by the link type {0:?}")]
+    VlanUnsupported(crate::DataLink),
+
+    /// A record doesn't fit in the maximum record size of the reader, see [`ReaderConfig`](crate::ReaderConfig)
+    #[error("Record larger than the maximum record size of {0} bytes")]
+    RecordTooLarge(usize),
+
+    /// A capture exceeds a limit of its [`ParseLimits`](crate::ParseLimits), the maximum value is given
+    #[error("{0:?} limit of {1} exceeded")]
+    LimitExceeded(crate::Limit, usize),
+
+    /// The queue of a background writer is full
+    #[error("The queue of the writer is full")]
+    QueueFull,
+
+    /// The background writer is closed
+    #[error("The writer is closed")]
+    WriterClosed,
 }
 
 impl From<std::str::Utf8Error> for PcapError {
@@ -42,3 +74,9 @@ impl From<std::string::FromUtf8Error> for PcapError {
         PcapError::FromUtf8Error(err)
     }
 }
+
+impl From<std::io::Error> for PcapError {
+    fn from(err: std::io::Error) -> Self {
+        PcapError::IoError(err)
+    }
+}
@@ -0,0 +1,594 @@
+//! Contains [`CaptureInfo`], a summary of a capture like the one printed by `capinfos`.
+
+use std::fmt::{self, Write};
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::pcap::PcapReader;
+use crate::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use crate::pcapng::blocks::interface_statistics::{InterfaceStatisticsBlock, InterfaceStatisticsOption};
+use crate::pcapng::blocks::section_header::{SectionHeaderBlock, SectionHeaderOption};
+use crate::pcapng::{Block, PcapNgReader};
+use crate::{DataLink, Endianness, PcapError, PcapResult, TsResolution};
+
+
+/// Magic number of a pcapng, i.e. the type of its Section Header Block
+const PCAPNG_MAGIC: [u8; 4] = [0x0A, 0x0D, 0x0D, 0x0A];
+
+/// Format of a capture.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CaptureFormat {
+    /// Pcap
+    Pcap,
+    /// PcapNg
+    PcapNg,
+}
+
+/// Statistics of an interface, merged from all its Interface Statistics Blocks.
+///
+/// The counters of the blocks are cumulative, so the counters of the latest block providing them are kept.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct InterfaceStatistics {
+    /// Earliest `isb_starttime`, since the epoch
+    pub start_time: Option<Duration>,
+    /// Latest `isb_endtime`, since the epoch
+    pub end_time: Option<Duration>,
+    /// Packets received from the physical interface (`isb_ifrecv`)
+    pub received: Option<u64>,
+    /// Packets dropped by the interface (`isb_ifdrop`)
+    pub dropped: Option<u64>,
+    /// Packets accepted by the filter (`isb_filteraccept`)
+    pub filter_accepted: Option<u64>,
+    /// Packets dropped by the operating system (`isb_osdrop`)
+    pub os_dropped: Option<u64>,
+    /// Packets delivered to the user (`isb_usrdeliv`)
+    pub delivered: Option<u64>,
+}
+
+/// Summary of an interface of a capture.
+///
+/// A pcap has a single interface described by its header.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InterfaceInfo {
+    /// Link type
+    pub linktype: DataLink,
+    /// Snaplen, 0 for no limit
+    pub snaplen: u32,
+    /// Timestamp resolution, encoded like the `if_tsresol` option
+    pub ts_resolution: u8,
+    /// Number of packets captured on the interface
+    pub packets: u64,
+    /// Statistics merged from the Interface Statistics Blocks of the interface, if any
+    pub statistics: Option<InterfaceStatistics>,
+}
+
+/// Summary of a capture, computed in a single pass by [`CaptureInfo::compute`].
+///
+/// The summary can be rendered as text with [`Display`](fmt::Display) or as JSON with [`CaptureInfo::to_json`].
+/// Both renderings are stable: fields are only ever added.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CaptureInfo {
+    /// Format of the capture
+    pub format: CaptureFormat,
+    /// Endianness of the header of the pcap or of the first section of the pcapng
+    pub endianness: Endianness,
+    /// Major version of the pcap or of the first section of the pcapng
+    pub version_major: u16,
+    /// Minor version of the pcap or of the first section of the pcapng
+    pub version_minor: u16,
+    /// Number of sections, 1 for a pcap
+    pub sections: u64,
+    /// `shb_hardware` of the first section
+    pub hardware: Option<String>,
+    /// `shb_os` of the first section
+    pub os: Option<String>,
+    /// `shb_userappl` of the first section
+    pub application: Option<String>,
+    /// Interfaces of all the sections, in order
+    pub interfaces: Vec<InterfaceInfo>,
+    /// Number of packets
+    pub packets: u64,
+    /// Sum of the original lengths of the packets
+    pub total_bytes: u64,
+    /// Sum of the captured lengths of the packets
+    pub captured_bytes: u64,
+    /// Earliest packet timestamp, since the epoch
+    pub first_timestamp: Option<Duration>,
+    /// Latest packet timestamp, since the epoch
+    pub last_timestamp: Option<Duration>,
+    /// Smallest captured length
+    pub min_packet_size: Option<u32>,
+    /// Largest captured length
+    pub max_packet_size: Option<u32>,
+    /// True if every packet timestamp is greater than or equal to the previous one
+    pub strict_time_order: bool,
+}
+
+impl CaptureInfo {
+    /// Computes the summary of a pcap or pcapng, detected from its magic number.
+    ///
+    /// The Simple Packet Blocks don't have a timestamp and are ignored by the timestamp statistics.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # tokio_test::block_on(async {
+    /// use tokio::fs::File;
+    ///
+    /// use pcap_file_tokio::info::CaptureInfo;
+    ///
+    /// let file_in = File::open("test.pcapng").await.expect("Error opening file");
+    /// let info = CaptureInfo::compute(file_in).await.unwrap();
+    ///
+    /// println!("{info}");
+    /// println!("{}", info.to_json());
+    /// # });
+    /// ```
+    pub async fn compute<R: AsyncRead + Unpin>(mut reader: R) -> PcapResult<CaptureInfo> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic).await.map_err(PcapError::IoError)?;
+        let reader = (&magic[..]).chain(reader);
+
+        if magic == PCAPNG_MAGIC {
+            Self::compute_pcapng(reader).await
+        }
+        else {
+            Self::compute_pcap(reader).await
+        }
+    }
+
+    async fn compute_pcap<R: AsyncRead + Unpin>(reader: R) -> PcapResult<CaptureInfo> {
+        let mut reader = PcapReader::new(reader).await?;
+        let header = reader.header();
+
+        let ts_resolution = match header.ts_resolution {
+            TsResolution::MicroSecond => 6,
+            TsResolution::NanoSecond => 9,
+        };
+        let interface = InterfaceInfo { linktype: header.datalink, snaplen: header.snaplen, ts_resolution, packets: 0, statistics: None };
+
+        let mut info = CaptureInfo::new(CaptureFormat::Pcap, header.endianness, header.version_major, header.version_minor);
+        info.interfaces.push(interface);
+
+        while let Some(packet) = reader.next_packet().await {
+            let packet = packet?;
+            info.interfaces[0].packets += 1;
+            info.add_packet(Some(packet.timestamp), packet.orig_len, packet.data.len() as u32);
+        }
+
+        Ok(info)
+    }
+
+    async fn compute_pcapng<R: AsyncRead + Unpin>(reader: R) -> PcapResult<CaptureInfo> {
+        let mut reader = PcapNgReader::new(reader).await?;
+        let section = reader.section();
+
+        let mut info = CaptureInfo::new(CaptureFormat::PcapNg, section.endianness, section.major_version, section.minor_version);
+        info.add_section(section);
+
+        // Interfaces of the current section and index of its first interface in `info.interfaces`
+        let mut interfaces: Vec<InterfaceDescriptionBlock<'static>> = vec![];
+        let mut first_interface = 0;
+
+        while let Some(block) = reader.next_block().await {
+            let (interface_id, timestamp, original_len, captured_len) = match block? {
+                Block::SectionHeader(section) => {
+                    info.add_section(&section);
+                    interfaces.clear();
+                    first_interface = info.interfaces.len();
+                    continue;
+                },
+                Block::InterfaceDescription(interface) => {
+                    info.interfaces.push(InterfaceInfo {
+                        linktype: interface.linktype,
+                        snaplen: interface.snaplen,
+                        ts_resolution: interface.ts_resolution(),
+                        packets: 0,
+                        statistics: None,
+                    });
+                    interfaces.push(interface.into_owned());
+                    continue;
+                },
+                Block::InterfaceStatistics(stats) => {
+                    let interface = interfaces.get(stats.interface_id as usize).ok_or(PcapError::InvalidInterfaceId(stats.interface_id))?;
+                    let info = &mut info.interfaces[first_interface + stats.interface_id as usize];
+                    info.statistics.get_or_insert_with(Default::default).merge(&stats, interface);
+                    continue;
+                },
+                Block::EnhancedPacket(packet) => {
+                    let interface = interfaces.get(packet.interface_id as usize).ok_or(PcapError::InvalidInterfaceId(packet.interface_id))?;
+                    (packet.interface_id, Some(packet.absolute_timestamp(interface)), packet.original_len, packet.data.len() as u32)
+                },
+                Block::Packet(packet) => {
+                    let interface_id = packet.interface_id as u32;
+                    let interface = interfaces.get(interface_id as usize).ok_or(PcapError::InvalidInterfaceId(interface_id))?;
+                    (interface_id, Some(interface.timestamp_to_duration(packet.timestamp)), packet.original_len, packet.data.len() as u32)
+                },
+                Block::SimplePacket(packet) => {
+                    if interfaces.is_empty() {
+                        return Err(PcapError::InvalidInterfaceId(0));
+                    }
+                    (0, None, packet.original_len, packet.data.len() as u32)
+                },
+                _ => continue,
+            };
+
+            info.interfaces[first_interface + interface_id as usize].packets += 1;
+            info.add_packet(timestamp, original_len, captured_len);
+        }
+
+        Ok(info)
+    }
+
+    fn new(format: CaptureFormat, endianness: Endianness, version_major: u16, version_minor: u16) -> Self {
+        CaptureInfo {
+            format,
+            endianness,
+            version_major,
+            version_minor,
+            sections: 0,
+            hardware: None,
+            os: None,
+            application: None,
+            interfaces: vec![],
+            packets: 0,
+            total_bytes: 0,
+            captured_bytes: 0,
+            first_timestamp: None,
+            last_timestamp: None,
+            min_packet_size: None,
+            max_packet_size: None,
+            strict_time_order: true,
+        }
+    }
+
+    fn add_section(&mut self, section: &SectionHeaderBlock) {
+        if self.sections == 0 {
+            for option in &section.options {
+                match option {
+                    SectionHeaderOption::Hardware(hardware) => self.hardware = Some(hardware.to_string()),
+                    SectionHeaderOption::OS(os) => self.os = Some(os.to_string()),
+                    SectionHeaderOption::UserApplication(application) => self.application = Some(application.to_string()),
+                    _ => {},
+                }
+            }
+        }
+
+        self.sections += 1;
+    }
+
+    fn add_packet(&mut self, timestamp: Option<Duration>, original_len: u32, captured_len: u32) {
+        if let Some(timestamp) = timestamp {
+            // The latest timestamp is the previous one as long as the packets are ordered
+            if self.last_timestamp.is_some_and(|last| timestamp < last) {
+                self.strict_time_order = false;
+            }
+
+            self.first_timestamp = Some(self.first_timestamp.map_or(timestamp, |first| first.min(timestamp)));
+            self.last_timestamp = Some(self.last_timestamp.map_or(timestamp, |last| last.max(timestamp)));
+        }
+
+        self.packets += 1;
+        self.total_bytes += original_len as u64;
+        self.captured_bytes += captured_len as u64;
+        self.min_packet_size = Some(self.min_packet_size.map_or(captured_len, |min| min.min(captured_len)));
+        self.max_packet_size = Some(self.max_packet_size.map_or(captured_len, |max| max.max(captured_len)));
+    }
+
+    /// Returns the time elapsed between the earliest and the latest packet.
+    pub fn duration(&self) -> Option<Duration> {
+        Some(self.last_timestamp? - self.first_timestamp?)
+    }
+
+    /// Returns the average captured length of the packets.
+    pub fn average_packet_size(&self) -> Option<f64> {
+        (self.packets != 0).then(|| self.captured_bytes as f64 / self.packets as f64)
+    }
+
+    /// Returns the average number of packets per second, `None` if the duration is zero.
+    pub fn packet_rate(&self) -> Option<f64> {
+        self.per_second(self.packets)
+    }
+
+    /// Returns the average number of captured bytes per second, `None` if the duration is zero.
+    pub fn data_byte_rate(&self) -> Option<f64> {
+        self.per_second(self.captured_bytes)
+    }
+
+    /// Returns the average number of captured bits per second, `None` if the duration is zero.
+    pub fn data_bit_rate(&self) -> Option<f64> {
+        self.data_byte_rate().map(|rate| rate * 8.0)
+    }
+
+    fn per_second(&self, count: u64) -> Option<f64> {
+        let secs = self.duration()?.as_secs_f64();
+        (secs > 0.0).then(|| count as f64 / secs)
+    }
+
+    /// Renders the summary as a JSON object.
+    ///
+    /// Timestamps and durations are in nanoseconds, unknown values are `null`.
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{");
+
+        let format = match self.format {
+            CaptureFormat::Pcap => "pcap",
+            CaptureFormat::PcapNg => "pcapng",
+        };
+        let _ = write!(json, "\"format\":\"{format}\",\"endianness\":\"{}\"", endianness_name(self.endianness));
+        let _ = write!(json, ",\"version\":\"{}.{}\",\"sections\":{}", self.version_major, self.version_minor, self.sections);
+        let _ = write!(json, ",\"hardware\":{},\"os\":{}", json_string(&self.hardware), json_string(&self.os));
+        let _ = write!(json, ",\"application\":{}", json_string(&self.application));
+
+        json.push_str(",\"interfaces\":[");
+        for (i, interface) in self.interfaces.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+
+            let _ = write!(
+                json,
+                "{{\"link_type\":{},\"link_type_name\":\"{:?}\",\"snaplen\":{},\"ts_resolution\":{},\"packets\":{},\"statistics\":",
+                u32::from(interface.linktype),
+                interface.linktype,
+                interface.snaplen,
+                interface.ts_resolution,
+                interface.packets
+            );
+
+            match &interface.statistics {
+                Some(stats) => {
+                    let _ = write!(
+                        json,
+                        "{{\"start_time\":{},\"end_time\":{},\"received\":{},\"dropped\":{},\"filter_accepted\":{},\"os_dropped\":{},\"delivered\":{}}}",
+                        json_value(stats.start_time.map(|time| time.as_nanos())),
+                        json_value(stats.end_time.map(|time| time.as_nanos())),
+                        json_value(stats.received),
+                        json_value(stats.dropped),
+                        json_value(stats.filter_accepted),
+                        json_value(stats.os_dropped),
+                        json_value(stats.delivered)
+                    );
+                },
+                None => json.push_str("null"),
+            }
+            json.push('}');
+        }
+        json.push(']');
+
+        let _ = write!(json, ",\"packets\":{},\"total_bytes\":{},\"captured_bytes\":{}", self.packets, self.total_bytes, self.captured_bytes);
+        let _ = write!(
+            json,
+            ",\"first_timestamp\":{},\"last_timestamp\":{},\"duration\":{}",
+            json_value(self.first_timestamp.map(|ts| ts.as_nanos())),
+            json_value(self.last_timestamp.map(|ts| ts.as_nanos())),
+            json_value(self.duration().map(|duration| duration.as_nanos()))
+        );
+        let _ = write!(
+            json,
+            ",\"packet_rate\":{},\"data_byte_rate\":{},\"data_bit_rate\":{}",
+            json_value(self.packet_rate()),
+            json_value(self.data_byte_rate()),
+            json_value(self.data_bit_rate())
+        );
+        let _ = write!(
+            json,
+            ",\"min_packet_size\":{},\"max_packet_size\":{},\"average_packet_size\":{}",
+            json_value(self.min_packet_size),
+            json_value(self.max_packet_size),
+            json_value(self.average_packet_size())
+        );
+        let _ = write!(json, ",\"strict_time_order\":{}}}", self.strict_time_order);
+
+        json
+    }
+}
+
+impl InterfaceStatistics {
+    /// Merges an Interface Statistics Block of `interface` into the statistics.
+    ///
+    /// The blocks must be merged in the order of the file.
+    pub fn merge(&mut self, block: &InterfaceStatisticsBlock, interface: &InterfaceDescriptionBlock) {
+        for option in &block.options {
+            match option {
+                InterfaceStatisticsOption::IsbStartTime(units) => {
+                    let start = interface.timestamp_to_duration(*units);
+                    self.start_time = Some(self.start_time.map_or(start, |time| time.min(start)));
+                },
+                InterfaceStatisticsOption::IsbEndTime(units) => {
+                    let end = interface.timestamp_to_duration(*units);
+                    self.end_time = Some(self.end_time.map_or(end, |time| time.max(end)));
+                },
+                InterfaceStatisticsOption::IsbIfRecv(count) => self.received = Some(*count),
+                InterfaceStatisticsOption::IsbIfDrop(count) => self.dropped = Some(*count),
+                InterfaceStatisticsOption::IsbFilterAccept(count) => self.filter_accepted = Some(*count),
+                InterfaceStatisticsOption::IsbOsDrop(count) => self.os_dropped = Some(*count),
+                InterfaceStatisticsOption::IsbUsrDeliv(count) => self.delivered = Some(*count),
+                _ => {},
+            }
+        }
+    }
+}
+
+
+/// Merges the Interface Statistics Blocks of a pcapng into one [`InterfaceStatistics`] per interface.
+///
+/// # Example
+/// ```rust,no_run
+/// # tokio_test::block_on(async {
+/// use tokio::fs::File;
+///
+/// use pcap_file_tokio::info::StatisticsAggregator;
+/// use pcap_file_tokio::pcapng::PcapNgReader;
+///
+/// let file_in = File::open("test.pcapng").await.expect("Error opening file");
+/// let mut reader = PcapNgReader::new(file_in).await.unwrap();
+///
+/// let mut aggregator = StatisticsAggregator::default();
+/// while let Some(block) = reader.next_block().await {
+///     aggregator.push(&block.unwrap()).unwrap();
+/// }
+///
+/// for (i, statistics) in aggregator.statistics().iter().enumerate() {
+///     println!("Interface #{i}: {statistics:?}");
+/// }
+/// # });
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct StatisticsAggregator {
+    /// Interfaces of the current section
+    interfaces: Vec<InterfaceDescriptionBlock<'static>>,
+    /// Index of the first interface of the current section in `statistics`
+    first_interface: usize,
+    statistics: Vec<Option<InterfaceStatistics>>,
+}
+
+impl StatisticsAggregator {
+    /// Processes a block of the pcapng.
+    ///
+    /// All the blocks following the first Section Header Block must be pushed, in order, as the interfaces are tracked.
+    ///
+    /// # Errors
+    /// Returns [`PcapError::InvalidInterfaceId`] if a statistics block references an unknown interface.
+    pub fn push(&mut self, block: &Block) -> PcapResult<()> {
+        match block {
+            Block::SectionHeader(_) => {
+                self.interfaces.clear();
+                self.first_interface = self.statistics.len();
+            },
+            Block::InterfaceDescription(interface) => {
+                self.interfaces.push(interface.clone().into_owned());
+                self.statistics.push(None);
+            },
+            Block::InterfaceStatistics(stats) => {
+                let interface = self.interfaces.get(stats.interface_id as usize).ok_or(PcapError::InvalidInterfaceId(stats.interface_id))?;
+                let statistics = &mut self.statistics[self.first_interface + stats.interface_id as usize];
+                statistics.get_or_insert_with(Default::default).merge(stats, interface);
+            },
+            _ => {},
+        }
+
+        Ok(())
+    }
+
+    /// Returns the statistics of all the interfaces of all the sections, in order.
+    ///
+    /// The interfaces without Interface Statistics Block have no statistics.
+    pub fn statistics(&self) -> &[Option<InterfaceStatistics>] {
+        &self.statistics
+    }
+}
+
+/// Renders the summary as text, one `name: value` line per field.
+impl fmt::Display for CaptureInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let format = match self.format {
+            CaptureFormat::Pcap => "pcap",
+            CaptureFormat::PcapNg => "pcapng",
+        };
+        let or_na = |value: &Option<String>| value.clone().unwrap_or_else(|| "n/a".to_string());
+
+        writeln!(f, "File format:         {format}")?;
+        writeln!(f, "Endianness:          {}", endianness_name(self.endianness))?;
+        writeln!(f, "Version:             {}.{}", self.version_major, self.version_minor)?;
+        writeln!(f, "Sections:            {}", self.sections)?;
+        writeln!(f, "Hardware:            {}", or_na(&self.hardware))?;
+        writeln!(f, "OS:                  {}", or_na(&self.os))?;
+        writeln!(f, "Application:         {}", or_na(&self.application))?;
+        writeln!(f, "Interfaces:          {}", self.interfaces.len())?;
+
+        for (i, interface) in self.interfaces.iter().enumerate() {
+            writeln!(
+                f,
+                "Interface #{i}:        {:?}, snaplen {}, timestamp resolution {}, {} packets",
+                interface.linktype,
+                interface.snaplen,
+                resolution_name(interface.ts_resolution),
+                interface.packets
+            )?;
+
+            if let Some(stats) = &interface.statistics {
+                let or_na = |value: Option<u64>| value.map_or("n/a".to_string(), |value| value.to_string());
+                writeln!(f, "  Statistics times:  {} to {}", format_timestamp(stats.start_time), format_timestamp(stats.end_time))?;
+                writeln!(
+                    f,
+                    "  Statistics:        received {}, dropped {}, filter accepted {}, OS dropped {}, delivered {}",
+                    or_na(stats.received),
+                    or_na(stats.dropped),
+                    or_na(stats.filter_accepted),
+                    or_na(stats.os_dropped),
+                    or_na(stats.delivered)
+                )?;
+            }
+        }
+
+        let timestamp = format_timestamp;
+        let rate = |rate: Option<f64>, unit| rate.map_or("n/a".to_string(), |rate| format!("{rate:.2} {unit}"));
+        let size = |size: Option<u32>| size.map_or("n/a".to_string(), |size| format!("{size} bytes"));
+
+        writeln!(f, "Packets:             {}", self.packets)?;
+        writeln!(f, "Captured bytes:      {}", self.captured_bytes)?;
+        writeln!(f, "Total bytes:         {}", self.total_bytes)?;
+        writeln!(f, "First packet time:   {}", timestamp(self.first_timestamp))?;
+        writeln!(f, "Last packet time:    {}", timestamp(self.last_timestamp))?;
+        writeln!(f, "Duration:            {}", timestamp(self.duration()))?;
+        writeln!(f, "Packet rate:         {}", rate(self.packet_rate(), "packets/s"))?;
+        writeln!(f, "Data byte rate:      {}", rate(self.data_byte_rate(), "bytes/s"))?;
+        writeln!(f, "Data bit rate:       {}", rate(self.data_bit_rate(), "bits/s"))?;
+        writeln!(f, "Min packet size:     {}", size(self.min_packet_size))?;
+        writeln!(f, "Max packet size:     {}", size(self.max_packet_size))?;
+        writeln!(f, "Average packet size: {}", rate(self.average_packet_size(), "bytes"))?;
+        writeln!(f, "Strict time order:   {}", self.strict_time_order)
+    }
+}
+
+fn format_timestamp(timestamp: Option<Duration>) -> String {
+    timestamp.map_or("n/a".to_string(), |ts| format!("{}.{:09}", ts.as_secs(), ts.subsec_nanos()))
+}
+
+fn endianness_name(endianness: Endianness) -> &'static str {
+    match endianness {
+        Endianness::Big => "big",
+        Endianness::Little => "little",
+    }
+}
+
+/// Formats an `if_tsresol` value, e.g. "10^-6 s".
+fn resolution_name(resolution: u8) -> String {
+    if resolution & 0x80 == 0 {
+        format!("10^-{resolution} s")
+    }
+    else {
+        format!("2^-{} s", resolution & 0x7F)
+    }
+}
+
+fn json_value<T: fmt::Display>(value: Option<T>) -> String {
+    value.map_or("null".to_string(), |value| value.to_string())
+}
+
+fn json_string(value: &Option<String>) -> String {
+    let value = match value {
+        Some(value) => value,
+        None => return "null".to_string(),
+    };
+
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            },
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+
+    json
+}
@@ -8,17 +8,46 @@
 //!
 //! For PcapNg files see the [`pcapng`] module, especially [`PcapNgParser`](pcapng::PcapNgParser),
 //! [`PcapNgReader<R>`](pcapng::PcapNgReader) and [`PcapNgWriter<W>`](pcapng::PcapNgWriter)
+//!
+//! PcapNg files can be checked against the spec with [`pcapng::lint`].
+//!
+//! Captures from untrusted sources can be parsed with resource limits, see [`ParseLimits`] and [`ReaderConfig`].
+//!
+//! Conversions between Pcap and PcapNg files are in the [`convert`] module.
+//!
+//! Capture summaries, like the one printed by `capinfos`, are in the [`info`] module.
+//!
+//! Packet transforms, such as the IP defragmentation, are in the [`transform`] module.
+//!
+//! A push-based decoder, independent of any IO or async runtime, is in the [`decoder`] module.
+//!
+//! Writers shared by several tasks are in the `background` module, behind the `background` feature.
+//!
+//! Readers of packets backed by a `bytes::Bytes`, sent to other tasks without copying their payload, are behind the
+//! `bytes` feature. Codecs for `tokio_util::codec` are in the `codec` module, behind the `codec` feature.
 
 
 pub use common::*;
 pub use errors::*;
+pub use limits::*;
+pub use validation::*;
 
 pub(crate) mod common;
 pub(crate) mod errors;
+pub(crate) mod limits;
 pub(crate) mod read_buffer;
+pub(crate) mod validation;
 
+#[cfg(feature = "background")]
+pub mod background;
+#[cfg(feature = "codec")]
+pub mod codec;
+pub mod convert;
+pub mod decoder;
+pub mod info;
 pub mod pcap;
 pub mod pcapng;
+pub mod transform;
 
 
 #[allow(dead_code)]
@@ -0,0 +1,262 @@
+use crate::errors::PcapError;
+use crate::pcapng::blocks::enhanced_packet::EnhancedPacketOption;
+use crate::pcapng::blocks::interface_description::InterfaceDescriptionOption;
+use crate::pcapng::blocks::interface_statistics::InterfaceStatisticsOption;
+use crate::pcapng::blocks::name_resolution::{NameResolutionBlock, NameResolutionOption, Record};
+use crate::pcapng::blocks::packet::PacketOption;
+use crate::pcapng::blocks::section_header::SectionHeaderOption;
+use crate::pcapng::Block;
+
+
+/// Limit of a [`ParseLimits`], returned by [`PcapError::LimitExceeded`] when a capture exceeds it
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Limit {
+    /// Length of a pcapng block, or of a pcap packet with its header
+    BlockLen,
+    /// Number of options of a block
+    Options,
+    /// Number of records of a Name Resolution Block
+    NrbRecords,
+    /// Number of names of a Name Resolution Block
+    NrbNames,
+    /// Number of interfaces of a section
+    Interfaces,
+    /// Length of a string option, or of a name of a Name Resolution Block
+    StringLen,
+    /// Number of sections of a capture
+    Sections,
+}
+
+/// Resource limits of the parsers, to read captures from untrusted sources.
+///
+/// The length of a block is checked from its header, before the block is buffered or parsed.
+/// The other limits are checked on the parsed blocks, whose size is bounded by the maximum block length.
+///
+/// Used by [`PcapParser`](crate::pcap::PcapParser), [`PcapNgParser`](crate::pcapng::PcapNgParser), the readers
+/// through their [`ReaderConfig`](crate::ReaderConfig), [`Decoder::with_limits`](crate::decoder::Decoder::with_limits)
+/// and the `with_limits` of the codecs. Only the maximum block length applies to a pcap.
+///
+/// Only [`PcapNgParser::next_block`](crate::pcapng::PcapNgParser::next_block) checks the content of all the blocks,
+/// [`PcapNgParser::next_raw_block`](crate::pcapng::PcapNgParser::next_raw_block) only checks the content of the
+/// Section Header and Interface Description blocks.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ParseLimits {
+    /// Maximum length of a pcapng block, or of a pcap packet with its 16 bytes header
+    pub max_block_len: usize,
+    /// Maximum number of options of a block
+    pub max_options: usize,
+    /// Maximum number of records of a Name Resolution Block
+    pub max_nrb_records: usize,
+    /// Maximum number of names, across all the records, of a Name Resolution Block
+    pub max_nrb_names: usize,
+    /// Maximum number of interfaces of a section
+    pub max_interfaces: usize,
+    /// Maximum length of a string option, or of a name of a Name Resolution Block, in bytes
+    pub max_string_len: usize,
+    /// Maximum number of sections of a capture, the first one included
+    pub max_sections: usize,
+}
+
+/// Creates new [`ParseLimits`] without any limit:
+///
+/// ```rust,ignore
+/// ParseLimits {
+///     max_block_len: usize::MAX,
+///     max_options: usize::MAX,
+///     max_nrb_records: usize::MAX,
+///     max_nrb_names: usize::MAX,
+///     max_interfaces: usize::MAX,
+///     max_string_len: usize::MAX,
+///     max_sections: usize::MAX,
+/// };
+/// ```
+impl Default for ParseLimits {
+    fn default() -> Self {
+        ParseLimits {
+            max_block_len: usize::MAX,
+            max_options: usize::MAX,
+            max_nrb_records: usize::MAX,
+            max_nrb_names: usize::MAX,
+            max_interfaces: usize::MAX,
+            max_string_len: usize::MAX,
+            max_sections: usize::MAX,
+        }
+    }
+}
+
+impl ParseLimits {
+    /// Creates new [`ParseLimits`] suited to captures from untrusted sources, with these parameters:
+    ///
+    /// ```rust,ignore
+    /// ParseLimits {
+    ///     max_block_len: 1_048_576,
+    ///     max_options: 256,
+    ///     max_nrb_records: 4096,
+    ///     max_nrb_names: 16_384,
+    ///     max_interfaces: 1024,
+    ///     max_string_len: 4096,
+    ///     max_sections: 1024,
+    /// };
+    /// ```
+    pub fn untrusted() -> Self {
+        ParseLimits {
+            max_block_len: 1_048_576,
+            max_options: 256,
+            max_nrb_records: 4096,
+            max_nrb_names: 16_384,
+            max_interfaces: 1024,
+            max_string_len: 4096,
+            max_sections: 1024,
+        }
+    }
+
+    /// Sets the maximum length of a block.
+    pub fn with_max_block_len(mut self, max_block_len: usize) -> Self {
+        self.max_block_len = max_block_len;
+        self
+    }
+
+    /// Sets the maximum number of options of a block.
+    pub fn with_max_options(mut self, max_options: usize) -> Self {
+        self.max_options = max_options;
+        self
+    }
+
+    /// Sets the maximum number of records of a Name Resolution Block.
+    pub fn with_max_nrb_records(mut self, max_nrb_records: usize) -> Self {
+        self.max_nrb_records = max_nrb_records;
+        self
+    }
+
+    /// Sets the maximum number of names of a Name Resolution Block.
+    pub fn with_max_nrb_names(mut self, max_nrb_names: usize) -> Self {
+        self.max_nrb_names = max_nrb_names;
+        self
+    }
+
+    /// Sets the maximum number of interfaces of a section.
+    pub fn with_max_interfaces(mut self, max_interfaces: usize) -> Self {
+        self.max_interfaces = max_interfaces;
+        self
+    }
+
+    /// Sets the maximum length of a string option.
+    pub fn with_max_string_len(mut self, max_string_len: usize) -> Self {
+        self.max_string_len = max_string_len;
+        self
+    }
+
+    /// Sets the maximum number of sections.
+    pub fn with_max_sections(mut self, max_sections: usize) -> Self {
+        self.max_sections = max_sections;
+        self
+    }
+
+    /// Checks the length of a block.
+    pub(crate) fn check_block_len(&self, len: usize) -> Result<(), PcapError> {
+        check(Limit::BlockLen, self.max_block_len, len)
+    }
+
+    /// Checks the number of interfaces of a section.
+    pub(crate) fn check_interfaces(&self, interfaces: usize) -> Result<(), PcapError> {
+        check(Limit::Interfaces, self.max_interfaces, interfaces)
+    }
+
+    /// Checks the number of sections.
+    pub(crate) fn check_sections(&self, sections: usize) -> Result<(), PcapError> {
+        check(Limit::Sections, self.max_sections, sections)
+    }
+
+    /// Checks the content of a parsed block: its options, its strings and its records.
+    pub(crate) fn check_block(&self, block: &Block) -> Result<(), PcapError> {
+        match block {
+            Block::SectionHeader(block) => self.check_options(block.options.iter().map(|opt| match opt {
+                SectionHeaderOption::Comment(s)
+                | SectionHeaderOption::Hardware(s)
+                | SectionHeaderOption::OS(s)
+                | SectionHeaderOption::UserApplication(s) => Some(s.len()),
+                SectionHeaderOption::CustomUtf8(opt) => Some(opt.value.len()),
+                _ => None,
+            })),
+
+            Block::InterfaceDescription(block) => self.check_options(block.options.iter().map(|opt| match opt {
+                InterfaceDescriptionOption::Comment(s)
+                | InterfaceDescriptionOption::IfName(s)
+                | InterfaceDescriptionOption::IfDescription(s)
+                | InterfaceDescriptionOption::IfOs(s)
+                | InterfaceDescriptionOption::IfHardware(s) => Some(s.len()),
+                InterfaceDescriptionOption::CustomUtf8(opt) => Some(opt.value.len()),
+                _ => None,
+            })),
+
+            Block::Packet(block) => self.check_options(block.options.iter().map(|opt| match opt {
+                PacketOption::Comment(s) => Some(s.len()),
+                PacketOption::CustomUtf8(opt) => Some(opt.value.len()),
+                _ => None,
+            })),
+
+            Block::NameResolution(block) => self.check_name_resolution(block),
+
+            Block::InterfaceStatistics(block) => self.check_options(block.options.iter().map(|opt| match opt {
+                InterfaceStatisticsOption::Comment(s) => Some(s.len()),
+                InterfaceStatisticsOption::CustomUtf8(opt) => Some(opt.value.len()),
+                _ => None,
+            })),
+
+            Block::EnhancedPacket(block) => self.check_options(block.options.iter().map(|opt| match opt {
+                EnhancedPacketOption::Comment(s) => Some(s.len()),
+                EnhancedPacketOption::CustomUtf8(opt) => Some(opt.value.len()),
+                _ => None,
+            })),
+
+            Block::SimplePacket(_) | Block::SystemdJournalExport(_) | Block::Unknown(_) => Ok(()),
+        }
+    }
+
+    /// Checks the records, the names and the options of a Name Resolution Block.
+    fn check_name_resolution(&self, block: &NameResolutionBlock) -> Result<(), PcapError> {
+        check(Limit::NrbRecords, self.max_nrb_records, block.records.len())?;
+
+        let mut names = 0;
+        for record in &block.records {
+            let record_names = match record {
+                Record::Ipv4(record) => &record.names,
+                Record::Ipv6(record) => &record.names,
+                Record::End | Record::Unknown(_) => continue,
+            };
+
+            names += record_names.len();
+            check(Limit::NrbNames, self.max_nrb_names, names)?;
+
+            for name in record_names {
+                check(Limit::StringLen, self.max_string_len, name.len())?;
+            }
+        }
+
+        self.check_options(block.options.iter().map(|opt| match opt {
+            NameResolutionOption::Comment(s) | NameResolutionOption::NsDnsName(s) => Some(s.len()),
+            NameResolutionOption::CustomUtf8(opt) => Some(opt.value.len()),
+            _ => None,
+        }))
+    }
+
+    /// Checks the number of options, given the length of each one if it is a string.
+    fn check_options(&self, options: impl ExactSizeIterator<Item = Option<usize>>) -> Result<(), PcapError> {
+        check(Limit::Options, self.max_options, options.len())?;
+
+        for len in options.flatten() {
+            check(Limit::StringLen, self.max_string_len, len)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns [`PcapError::LimitExceeded`] if `value` is greater than `max`.
+fn check(limit: Limit, max: usize, value: usize) -> Result<(), PcapError> {
+    if value > max {
+        return Err(PcapError::LimitExceeded(limit, max));
+    }
+
+    Ok(())
+}
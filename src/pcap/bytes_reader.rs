@@ -0,0 +1,121 @@
+use bytes::{Buf, BytesMut};
+use tokio::io::AsyncRead;
+
+use super::{BytesPcapPacket, PcapHeader, PcapParser};
+use crate::errors::*;
+use crate::read_buffer::BytesReadBuffer;
+use crate::{ParseLimits, ParseMode, ParseWarning, ReaderConfig};
+
+
+/// Reads a pcap from a reader, into packets whose payload is a [`Bytes`](bytes::Bytes).
+///
+/// The reader fills shared chunks of memory and the payload of each packet is a slice of a chunk: the packets are
+/// `'static`, cheap to clone and can be sent to other tasks without copying their payload.
+/// A chunk is freed once all its packets are dropped.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # tokio_test::block_on(async {
+/// use tokio::fs::File;
+///
+/// use pcap_file_tokio::pcap::BytesPcapReader;
+///
+/// let file_in = File::open("test.pcap").await.expect("Error opening file");
+/// let mut pcap_reader = BytesPcapReader::new(file_in).await.unwrap();
+///
+/// while let Some(pkt) = pcap_reader.next_packet().await {
+///     let pkt = pkt.unwrap();
+///     tokio::spawn(async move { println!("{} bytes", pkt.data.len()) });
+/// }
+/// # });
+/// ```
+#[derive(Debug)]
+pub struct BytesPcapReader<R: AsyncRead + Unpin> {
+    parser: PcapParser,
+    reader: BytesReadBuffer<R>,
+}
+
+impl<R: AsyncRead + Unpin> BytesPcapReader<R> {
+    /// Creates a new [`BytesPcapReader`] from an existing reader.
+    ///
+    /// This function reads the global pcap header of the file to verify its integrity.
+    ///
+    /// # Errors
+    /// The data stream is not in a valid pcap file format.
+    ///
+    /// The underlying data are not readable.
+    pub async fn new(reader: R) -> PcapResult<BytesPcapReader<R>> {
+        Self::with_config(reader, ReaderConfig::default()).await
+    }
+
+    /// Creates a new [`BytesPcapReader`] from an existing reader, with the given [`ReaderConfig`].
+    ///
+    /// The chunks have the initial capacity of the config.
+    /// A packet larger than [`ReaderConfig::with_max_record_size`] returns [`PcapError::RecordTooLarge`].
+    /// The [`ParseLimits`] and the [`ParseMode`] of the config are applied like by a [`PcapReader`](super::PcapReader).
+    pub async fn with_config(reader: R, config: ReaderConfig) -> PcapResult<BytesPcapReader<R>> {
+        let mut reader = BytesReadBuffer::with_config(reader, config);
+        let parser = match reader.decode_with(|src| decode_header(src, config.limits, config.mode)).await? {
+            Some(parser) => parser,
+            None => return Err(PcapError::IoError(std::io::ErrorKind::UnexpectedEof.into())),
+        };
+
+        Ok(BytesPcapReader { parser, reader })
+    }
+
+    /// Returns the next [`BytesPcapPacket`].
+    pub async fn next_packet(&mut self) -> Option<PcapResult<BytesPcapPacket>> {
+        let parser = &mut self.parser;
+        self.reader.decode_with(|src| decode_packet(src, parser)).await.transpose()
+    }
+
+    /// Returns the global header of the pcap.
+    pub fn header(&self) -> PcapHeader {
+        self.parser.header()
+    }
+
+    /// Returns the warnings collected, see [`ParseMode`].
+    pub fn warnings(&self) -> &[ParseWarning] {
+        self.parser.warnings()
+    }
+
+    /// Returns and clears the warnings collected, see [`ParseMode`].
+    pub fn take_warnings(&mut self) -> Vec<ParseWarning> {
+        self.parser.take_warnings()
+    }
+
+    /// Consumes [`Self`], returning the wrapped reader.
+    pub fn into_reader(self) -> R {
+        self.reader.into_inner()
+    }
+}
+
+
+/// Decodes a [`PcapHeader`] from the start of `src` into a [`PcapParser`], or returns `None` if `src` is too short.
+pub(crate) fn decode_header(src: &mut BytesMut, limits: ParseLimits, mode: ParseMode) -> PcapResult<Option<PcapParser>> {
+    let (len, parser) = match PcapParser::with_limits_and_mode(src, limits, mode) {
+        Ok((rem, parser)) => (src.len() - rem.len(), parser),
+        Err(PcapError::IncompleteBuffer) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    src.advance(len);
+
+    Ok(Some(parser))
+}
+
+/// Decodes a [`BytesPcapPacket`] sharing the memory of `src`, or returns `None` if `src` is too short.
+pub(crate) fn decode_packet(src: &mut BytesMut, parser: &mut PcapParser) -> PcapResult<Option<BytesPcapPacket>> {
+    // The length is checked against the limits before the packet is buffered
+    let len = match parser.next_raw_packet(src) {
+        Ok((rem, _)) => src.len() - rem.len(),
+        Err(PcapError::IncompleteBuffer) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    let buffer = src.split_to(len).freeze();
+    let (_, packet) = parser.next_packet_with_warnings(&buffer)?;
+
+    Ok(Some(BytesPcapPacket::from_packet(&packet, &buffer)))
+}
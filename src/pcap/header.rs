@@ -1,7 +1,7 @@
 use tokio::io::AsyncWrite;
 
-use byteorder::{BigEndian, LittleEndian, ByteOrder};
-use tokio_byteorder::{AsyncReadBytesExt, AsyncWriteBytesExt};
+use byteorder::{BigEndian, LittleEndian, ByteOrder, ReadBytesExt};
+use tokio_byteorder::AsyncWriteBytesExt;
 
 use crate::{errors::*};
 use crate::{DataLink, Endianness, TsResolution};
@@ -42,36 +42,36 @@ impl PcapHeader {
     /// or if there is a reading error.
     ///
     /// [`PcapError::IncompleteBuffer`] indicates that there is not enough data in the buffer.
-    pub async fn from_slice(mut slice: &[u8]) -> PcapResult<(&[u8], PcapHeader)> {
+    pub fn from_slice(mut slice: &[u8]) -> PcapResult<(&[u8], PcapHeader)> {
         // Check that slice.len() > PcapHeader length
         if slice.len() < 24 {
             return Err(PcapError::IncompleteBuffer);
         }
 
-        let magic_number = slice.read_u32::<BigEndian>().await.unwrap();
+        let magic_number = slice.read_u32::<BigEndian>().unwrap();
 
         match magic_number {
-            0xA1B2C3D4 => return init_pcap_header::<BigEndian>(slice, TsResolution::MicroSecond, Endianness::Big).await,
-            0xA1B23C4D => return init_pcap_header::<BigEndian>(slice, TsResolution::NanoSecond, Endianness::Big).await,
-            0xD4C3B2A1 => return init_pcap_header::<LittleEndian>(slice, TsResolution::MicroSecond, Endianness::Little).await,
-            0x4D3CB2A1 => return init_pcap_header::<LittleEndian>(slice, TsResolution::NanoSecond, Endianness::Little).await,
+            0xA1B2C3D4 => return init_pcap_header::<BigEndian>(slice, TsResolution::MicroSecond, Endianness::Big),
+            0xA1B23C4D => return init_pcap_header::<BigEndian>(slice, TsResolution::NanoSecond, Endianness::Big),
+            0xD4C3B2A1 => return init_pcap_header::<LittleEndian>(slice, TsResolution::MicroSecond, Endianness::Little),
+            0x4D3CB2A1 => return init_pcap_header::<LittleEndian>(slice, TsResolution::NanoSecond, Endianness::Little),
             _ => return Err(PcapError::InvalidField("PcapHeader: wrong magic number")),
         };
 
         // Inner function used for the initialisation of the PcapHeader.
         // Must check the srcclength before calling it.
-        async fn init_pcap_header<B: ByteOrder>(
+        fn init_pcap_header<B: ByteOrder>(
             mut src: &[u8],
             ts_resolution: TsResolution,
             endianness: Endianness,
         ) -> PcapResult<(&[u8], PcapHeader)> {
             let header = PcapHeader {
-                version_major: src.read_u16::<B>().await.unwrap(),
-                version_minor: src.read_u16::<B>().await.unwrap(),
-                ts_correction: src.read_i32::<B>().await.unwrap(),
-                ts_accuracy: src.read_u32::<B>().await.unwrap(),
-                snaplen: src.read_u32::<B>().await.unwrap(),
-                datalink: DataLink::from(src.read_u32::<B>().await.unwrap()),
+                version_major: src.read_u16::<B>().unwrap(),
+                version_minor: src.read_u16::<B>().unwrap(),
+                ts_correction: src.read_i32::<B>().unwrap(),
+                ts_accuracy: src.read_u32::<B>().unwrap(),
+                snaplen: src.read_u32::<B>().unwrap(),
+                datalink: DataLink::from(src.read_u32::<B>().unwrap()),
                 ts_resolution,
                 endianness,
             };
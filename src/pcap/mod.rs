@@ -1,11 +1,15 @@
 //! Contains the Pcap parser, reader and writer
 
+#[cfg(feature = "bytes")]
+mod bytes_reader;
 mod header;
 mod packet;
 mod parser;
 mod reader;
 mod writer;
 
+#[cfg(feature = "bytes")]
+pub use bytes_reader::*;
 pub use header::*;
 pub use packet::*;
 pub use parser::*;
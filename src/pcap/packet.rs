@@ -1,14 +1,14 @@
 use std::borrow::Cow;
 use tokio::io::AsyncWrite;
-use tokio_byteorder::AsyncReadBytesExt;
 use tokio_byteorder::AsyncWriteBytesExt;
 use std::time::Duration;
 
-use byteorder::ByteOrder;
+use byteorder::{ByteOrder, ReadBytesExt};
 use derive_into_owned::IntoOwned;
 
 use crate::errors::*;
-use crate::TsResolution;
+use crate::validation::Validator;
+use crate::{Check, ParseMode, TsResolution};
 
 /// Pcap packet.
 ///
@@ -35,8 +35,8 @@ impl<'a> PcapPacket<'a> {
     }
 
     /// Parses a new borrowed [`PcapPacket`] from a slice.
-    pub async fn from_slice<B: ByteOrder>(slice: &'a [u8], ts_resolution: TsResolution, snap_len: u32) -> PcapResult<(&'a [u8], PcapPacket<'a>)> {
-        let (rem, raw_packet) = RawPcapPacket::from_slice::<B>(slice).await?;
+    pub fn from_slice<B: ByteOrder>(slice: &'a [u8], ts_resolution: TsResolution, snap_len: u32) -> PcapResult<(&'a [u8], PcapPacket<'a>)> {
+        let (rem, raw_packet) = RawPcapPacket::from_slice::<B>(slice)?;
         let s = Self::try_from_raw_packet(raw_packet, ts_resolution, snap_len)?;
 
         Ok((rem, s))
@@ -75,14 +75,30 @@ impl<'a> PcapPacket<'a> {
 
     /// Tries to create a [`PcapPacket`] from a [`RawPcapPacket`].
     pub fn try_from_raw_packet(raw: RawPcapPacket<'a>, ts_resolution: TsResolution, snap_len: u32) -> PcapResult<Self> {
+        Self::try_from_raw_packet_validated(raw, ts_resolution, snap_len, &mut Validator::new(ParseMode::Strict))
+    }
+
+    /// Tries to create a [`PcapPacket`] from a [`RawPcapPacket`], with the checks of the given [`Validator`].
+    pub(crate) fn try_from_raw_packet_validated(
+        raw: RawPcapPacket<'a>,
+        ts_resolution: TsResolution,
+        snap_len: u32,
+        validator: &mut Validator,
+    ) -> PcapResult<Self> {
         // Validate timestamps //
-        let ts_sec = raw.ts_sec;
-        let mut ts_nsec = raw.ts_frac;
+        let ts_sec = raw.ts_sec as u64;
+        let mut ts_nsec = raw.ts_frac as u64;
         if ts_resolution == TsResolution::MicroSecond {
-            ts_nsec = ts_nsec.checked_mul(1000).ok_or(PcapError::InvalidField("PacketHeader ts_nanosecond is invalid"))?;
+            ts_nsec *= 1000;
         }
-        if ts_nsec >= 1_000_000_000 {
-            return Err(PcapError::InvalidField("PacketHeader ts_nanosecond >= 1_000_000_000"));
+
+        if ts_resolution == TsResolution::MicroSecond && ts_nsec > u32::MAX as u64 {
+            let reason = "PacketHeader ts_nanosecond is invalid";
+            validator.violation(Check::Timestamp, reason, PcapError::InvalidField(reason))?;
+        }
+        else if ts_nsec >= 1_000_000_000 {
+            let reason = "PacketHeader ts_nanosecond >= 1_000_000_000";
+            validator.violation(Check::Timestamp, reason, PcapError::InvalidField(reason))?;
         }
 
         // Validate lengths //
@@ -90,18 +106,23 @@ impl<'a> PcapPacket<'a> {
         let orig_len = raw.orig_len;
 
         if incl_len > snap_len {
-            return Err(PcapError::InvalidField("PacketHeader incl_len > snap_len"));
+            let reason = "PacketHeader incl_len > snap_len";
+            validator.violation(Check::Snaplen, reason, PcapError::InvalidField(reason))?;
         }
 
         if orig_len > snap_len {
-            return Err(PcapError::InvalidField("PacketHeader orig_len > snap_len"));
+            let reason = "PacketHeader orig_len > snap_len";
+            validator.violation(Check::Snaplen, reason, PcapError::InvalidField(reason))?;
         }
 
         if incl_len > orig_len {
             return Err(PcapError::InvalidField("PacketHeader incl_len > orig_len"));
         }
 
-        Ok(PcapPacket { timestamp: Duration::new(ts_sec as u64, ts_nsec), orig_len, data: raw.data })
+        // The nanoseconds overflowing, if allowed, are carried into the seconds
+        let timestamp = Duration::from_secs(ts_sec) + Duration::from_nanos(ts_nsec);
+
+        Ok(PcapPacket { timestamp, orig_len, data: raw.data })
     }
 }
 
@@ -125,7 +146,7 @@ pub struct RawPcapPacket<'a> {
 
 impl<'a> RawPcapPacket<'a> {
     /// Parses a new borrowed [`RawPcapPacket`] from a slice.
-    pub async fn from_slice<B: ByteOrder>(mut slice: &'a [u8]) -> PcapResult<(&'a [u8], RawPcapPacket<'a>)> {
+    pub fn from_slice<B: ByteOrder>(mut slice: &'a [u8]) -> PcapResult<(&'a [u8], RawPcapPacket<'a>)> {
         // Check header length
         if slice.len() < 16 {
             return Err(PcapError::IncompleteBuffer);
@@ -133,10 +154,10 @@ impl<'a> RawPcapPacket<'a> {
 
         // Read packet header  //
         // Can unwrap because the length check is done before
-        let ts_sec = slice.read_u32::<B>().await.unwrap();
-        let ts_frac = slice.read_u32::<B>().await.unwrap();
-        let incl_len = slice.read_u32::<B>().await.unwrap();
-        let orig_len = slice.read_u32::<B>().await.unwrap();
+        let ts_sec = slice.read_u32::<B>().unwrap();
+        let ts_frac = slice.read_u32::<B>().unwrap();
+        let incl_len = slice.read_u32::<B>().unwrap();
+        let orig_len = slice.read_u32::<B>().unwrap();
 
         let pkt_len = incl_len as usize;
         if slice.len() < pkt_len {
@@ -166,3 +187,33 @@ impl<'a> RawPcapPacket<'a> {
         PcapPacket::try_from_raw_packet(self, ts_resolution, snap_len)
     }
 }
+
+
+/// Pcap packet whose payload is a [`Bytes`](bytes::Bytes).
+///
+/// The payload shares the buffer the packet was parsed from, so the packet is `'static` and cheap to clone.
+#[cfg(feature = "bytes")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BytesPcapPacket {
+    /// Timestamp EPOCH of the packet with a nanosecond resolution
+    pub timestamp: Duration,
+    /// Original length of the packet when captured on the wire
+    pub orig_len: u32,
+    /// Payload of the packet
+    pub data: bytes::Bytes,
+}
+
+#[cfg(feature = "bytes")]
+impl BytesPcapPacket {
+    /// Creates a [`BytesPcapPacket`] from a [`PcapPacket`] parsed from `buffer`, without copying its payload.
+    ///
+    /// The payload is copied if it isn't part of `buffer`.
+    pub fn from_packet(packet: &PcapPacket, buffer: &bytes::Bytes) -> Self {
+        BytesPcapPacket { timestamp: packet.timestamp, orig_len: packet.orig_len, data: crate::common::share_bytes(buffer, &packet.data) }
+    }
+
+    /// Returns a [`PcapPacket`] borrowing the payload of this packet.
+    pub fn as_pcap_packet(&self) -> PcapPacket<'_> {
+        PcapPacket::new(self.timestamp, self.orig_len, &self.data)
+    }
+}
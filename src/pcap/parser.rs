@@ -1,18 +1,20 @@
-use byteorder::{BigEndian, LittleEndian};
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
 
 use super::RawPcapPacket;
 use crate::errors::*;
 use crate::pcap::{PcapHeader, PcapPacket};
-use crate::Endianness;
+use crate::validation::Validator;
+use crate::{Endianness, ParseLimits, ParseMode, ParseWarning};
 
 
 /// Parses a Pcap from a slice of bytes.
 ///
+/// The parser is synchronous, [`PcapReader`](super::PcapReader) is built on top of it to read from an [`AsyncRead`](tokio::io::AsyncRead).
+///
 /// You can match on [`PcapError::IncompleteBuffer`](crate::errors::PcapError) to known if the parser need more data.
 ///
 /// # Example
 /// ```no_run
-/// # tokio_test::block_on(async {
 /// use pcap_file_tokio::pcap::PcapParser;
 /// use pcap_file_tokio::PcapError;
 ///
@@ -20,11 +22,11 @@ use crate::Endianness;
 /// let mut src = &pcap[..];
 ///
 /// // Creates a new parser and parse the pcap header
-/// let (rem, pcap_parser) = PcapParser::new(&pcap[..]).await.unwrap();
+/// let (rem, mut pcap_parser) = PcapParser::new(&pcap[..]).unwrap();
 /// src = rem;
 ///
 /// loop {
-///     match pcap_parser.next_packet(src).await {
+///     match pcap_parser.next_packet(src) {
 ///         Ok((rem, packet)) => {
 ///             // Do something
 ///
@@ -40,38 +42,75 @@ use crate::Endianness;
 ///         Err(_) => {},                           // Parsing error
 ///     }
 /// }
-/// # });
 /// ```
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct PcapParser {
     header: PcapHeader,
+    limits: ParseLimits,
+    validator: Validator,
 }
 
 impl PcapParser {
     /// Creates a new [`PcapParser`].
     ///
     /// Returns the remainder and the parser.
-    pub async fn new(slice: &[u8]) -> PcapResult<(&[u8], PcapParser)> {
-        let (slice, header) = PcapHeader::from_slice(slice).await?;
+    pub fn new(slice: &[u8]) -> PcapResult<(&[u8], PcapParser)> {
+        Self::with_limits(slice, ParseLimits::default())
+    }
+
+    /// Creates a new [`PcapParser`] with the given [`ParseLimits`].
+    ///
+    /// Only the maximum block length applies to a pcap: a packet longer than it, with its 16 bytes header, returns
+    /// [`PcapError::LimitExceeded`] as soon as its header is parsed.
+    pub fn with_limits(slice: &[u8], limits: ParseLimits) -> PcapResult<(&[u8], PcapParser)> {
+        Self::with_limits_and_mode(slice, limits, ParseMode::default())
+    }
+
+    /// Creates a new [`PcapParser`] with the given [`ParseLimits`] and [`ParseMode`].
+    ///
+    /// The mode controls the [`Check::Snaplen`](crate::Check::Snaplen) and [`Check::Timestamp`](crate::Check::Timestamp)
+    /// of the packets returned by [`PcapParser::next_packet`], the raw packets aren't checked.
+    pub fn with_limits_and_mode(slice: &[u8], limits: ParseLimits, mode: ParseMode) -> PcapResult<(&[u8], PcapParser)> {
+        let (slice, header) = PcapHeader::from_slice(slice)?;
 
-        let parser = PcapParser { header };
+        let parser = PcapParser { header, limits, validator: Validator::new(mode) };
 
         Ok((slice, parser))
     }
 
     /// Returns the remainder and the next [`PcapPacket`].
-    pub async fn next_packet<'a>(&self, slice: &'a [u8]) -> PcapResult<(&'a [u8], PcapPacket<'a>)> {
-        match self.header.endianness {
-            Endianness::Big => PcapPacket::from_slice::<BigEndian>(slice, self.header.ts_resolution, self.header.snaplen).await,
-            Endianness::Little => PcapPacket::from_slice::<LittleEndian>(slice, self.header.ts_resolution, self.header.snaplen).await,
-        }
+    ///
+    /// The violations of the checks which aren't enforced by the [`ParseMode`] are accepted without being collected,
+    /// see [`PcapParser::next_packet_with_warnings`].
+    pub fn next_packet<'a>(&self, slice: &'a [u8]) -> PcapResult<(&'a [u8], PcapPacket<'a>)> {
+        let (rem, raw_packet) = self.next_raw_packet(slice)?;
+
+        let header = self.header;
+        let mut validator = Validator::new(self.validator.mode());
+        let packet = PcapPacket::try_from_raw_packet_validated(raw_packet, header.ts_resolution, header.snaplen, &mut validator)?;
+
+        Ok((rem, packet))
+    }
+
+    /// Returns the remainder and the next [`PcapPacket`], collecting the warnings, see [`PcapParser::warnings`].
+    pub fn next_packet_with_warnings<'a>(&mut self, slice: &'a [u8]) -> PcapResult<(&'a [u8], PcapPacket<'a>)> {
+        let (rem, raw_packet) = self.next_raw_packet(slice)?;
+
+        let header = self.header;
+        let mark = self.validator.mark();
+        let res = PcapPacket::try_from_raw_packet_validated(raw_packet, header.ts_resolution, header.snaplen, &mut self.validator);
+        let packet = self.validator.commit(mark, res)?;
+
+        Ok((rem, packet))
     }
 
     /// Returns the remainder and the next [`RawPcapPacket`].
-    pub async fn next_raw_packet<'a>(&self, slice: &'a [u8]) -> PcapResult<(&'a [u8], RawPcapPacket<'a>)> {
+    pub fn next_raw_packet<'a>(&self, slice: &'a [u8]) -> PcapResult<(&'a [u8], RawPcapPacket<'a>)> {
+        self.check_packet_len(slice)?;
+
         match self.header.endianness {
-            Endianness::Big => RawPcapPacket::from_slice::<BigEndian>(slice).await,
-            Endianness::Little => RawPcapPacket::from_slice::<LittleEndian>(slice).await,
+            Endianness::Big => RawPcapPacket::from_slice::<BigEndian>(slice),
+            Endianness::Little => RawPcapPacket::from_slice::<LittleEndian>(slice),
         }
     }
 
@@ -79,4 +118,28 @@ impl PcapParser {
     pub fn header(&self) -> PcapHeader {
         self.header
     }
+
+    /// Returns the warnings collected, see [`ParseMode`].
+    pub fn warnings(&self) -> &[ParseWarning] {
+        self.validator.warnings()
+    }
+
+    /// Returns and clears the warnings collected, see [`ParseMode`].
+    pub fn take_warnings(&mut self) -> Vec<ParseWarning> {
+        self.validator.take_warnings()
+    }
+
+    /// Checks the length of the next packet against the limits, before it is buffered.
+    fn check_packet_len(&self, slice: &[u8]) -> PcapResult<()> {
+        if slice.len() < 16 {
+            return Ok(());
+        }
+
+        let incl_len = match self.header.endianness {
+            Endianness::Big => BigEndian::read_u32(&slice[8..12]),
+            Endianness::Little => LittleEndian::read_u32(&slice[8..12]),
+        };
+
+        self.limits.check_block_len(16 + incl_len as usize)
+    }
 }
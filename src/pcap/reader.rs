@@ -4,6 +4,7 @@ use super::{PcapParser, RawPcapPacket};
 use crate::errors::*;
 use crate::pcap::{PcapHeader, PcapPacket};
 use crate::read_buffer::ReadBuffer;
+use crate::{ParseWarning, ReaderConfig};
 
 
 /// Reads a pcap from a reader.
@@ -46,8 +47,21 @@ impl<R: AsyncRead + Unpin> PcapReader<R> {
     ///
     /// The underlying data are not readable.
     pub async fn new(reader: R) -> Result<PcapReader<R>, PcapError> {
-        let mut reader = ReadBuffer::new(reader);
-        let parser = reader.parse_with(PcapParser::new).await?;
+        Self::with_config(reader, ReaderConfig::default()).await
+    }
+
+    /// Creates a new [`PcapReader`] from an existing reader, with the given [`ReaderConfig`].
+    ///
+    /// # Errors
+    /// Same as [`PcapReader::new`].
+    ///
+    /// A packet larger than [`ReaderConfig::with_max_record_size`] returns [`PcapError::RecordTooLarge`].
+    /// A packet exceeding the [`ParseLimits`](crate::ParseLimits) of the config returns [`PcapError::LimitExceeded`].
+    /// A packet violating a check enforced by the [`ParseMode`](crate::ParseMode) of the config returns an error, the
+    /// violations of the other checks are collected as warnings, see [`Self::warnings`].
+    pub async fn with_config(reader: R, config: ReaderConfig) -> Result<PcapReader<R>, PcapError> {
+        let mut reader = ReadBuffer::with_config(reader, config);
+        let parser = reader.parse_with(|src| PcapParser::with_limits_and_mode(src, config.limits, config.mode)).await?;
 
         Ok(PcapReader { parser, reader })
     }
@@ -62,7 +76,7 @@ impl<R: AsyncRead + Unpin> PcapReader<R> {
         match self.reader.has_data_left().await {
             Ok(has_data) => {
                 if has_data {
-                    Some(self.reader.parse_with(|src|  self.parser.next_packet(src)).await)
+                    Some(self.reader.parse_with(|src|  self.parser.next_packet_with_warnings(src)).await)
                 }
                 else {
                     None
@@ -87,8 +101,25 @@ impl<R: AsyncRead + Unpin> PcapReader<R> {
         }
     }
 
+    /// Returns the number of bytes of the pcap consumed by the reader.
+    pub(crate) fn position(&self) -> u64 {
+        self.reader.consumed()
+    }
+
     /// Returns the global header of the pcap.
     pub fn header(&self) -> PcapHeader {
         self.parser.header()
     }
+
+    /// Returns the warnings collected by the parser, see [`ParseMode`](crate::ParseMode).
+    pub fn warnings(&self) -> &[ParseWarning] {
+        self.parser.warnings()
+    }
+
+    /// Returns and clears the warnings collected by the parser, see [`ParseMode`](crate::ParseMode).
+    ///
+    /// The warnings are kept until they are taken, this should be called regularly on long captures read leniently.
+    pub fn take_warnings(&mut self) -> Vec<ParseWarning> {
+        self.parser.take_warnings()
+    }
 }
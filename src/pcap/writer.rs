@@ -1,11 +1,14 @@
-use tokio::io::AsyncWrite;
+use std::io::{ErrorKind, SeekFrom};
+
+use tokio::io::{AsyncRead, AsyncSeek, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
 
 use byteorder::{BigEndian, LittleEndian};
 
 use super::RawPcapPacket;
 use crate::errors::*;
-use crate::pcap::{PcapHeader, PcapPacket};
-use crate::{Endianness, TsResolution};
+use crate::pcap::{PcapHeader, PcapPacket, PcapReader};
+use crate::common::FinishGuard;
+use crate::{Endianness, Truncate, TsResolution, WriteSummary};
 
 
 /// Writes a pcap to a writer.
@@ -31,13 +34,22 @@ use crate::{Endianness, TsResolution};
 ///     //Write each packet of test.pcap in out.pcap
 ///     pcap_writer.write_packet(&pkt).await.unwrap();
 /// }
+///
+/// // Flush and shut down out.pcap
+/// let (_file_out, summary) = pcap_writer.finish().await.unwrap();
+/// println!("{} packets written", summary.packets);
 /// # });
 /// ```
+///
+/// In debug builds, a warning is printed if the writer is dropped without calling [`PcapWriter::finish`]
+/// or [`PcapWriter::into_writer`].
 #[derive(Debug)]
 pub struct PcapWriter<W: AsyncWrite> {
     endianness: Endianness,
     snaplen: u32,
     ts_resolution: TsResolution,
+    summary: WriteSummary,
+    guard: FinishGuard,
     writer: W,
 }
 
@@ -75,35 +87,73 @@ impl<W: AsyncWrite + Unpin> PcapWriter<W> {
     /// # Errors
     /// The writer can't be written to.
     pub async fn with_header(mut writer: W, header: PcapHeader) -> PcapResult<PcapWriter<W>> {
-        header.write_to(&mut writer).await?;
+        let bytes = header.write_to(&mut writer).await?;
 
         Ok(PcapWriter {
             endianness: header.endianness,
             snaplen: header.snaplen,
             ts_resolution: header.ts_resolution,
+            summary: WriteSummary { bytes: bytes as u64, ..Default::default() },
+            guard: FinishGuard::new("PcapWriter"),
             writer,
         })
     }
 
     /// Consumes [`Self`], returning the wrapped writer.
-    pub fn into_writer(self) -> W {
+    ///
+    /// The writer is neither flushed nor shut down, see [`PcapWriter::finish`].
+    pub fn into_writer(mut self) -> W {
+        self.guard.disarm();
         self.writer
     }
 
+    /// Flushes the wrapped writer.
+    pub async fn flush(&mut self) -> PcapResult<()> {
+        self.writer.flush().await.map_err(PcapError::IoError)
+    }
+
+    /// Flushes and shuts down the wrapped writer, returning it with a summary of what was written.
+    ///
+    /// Must be called to make sure that the end of the pcap reaches its destination,
+    /// e.g. with a `BufWriter` or a compressing writer.
+    pub async fn finish(mut self) -> PcapResult<(W, WriteSummary)> {
+        // An error below is reported to the caller, the guard has nothing to add
+        self.guard.disarm();
+
+        self.writer.flush().await.map_err(PcapError::IoError)?;
+        self.writer.shutdown().await.map_err(PcapError::IoError)?;
+
+        let summary = self.summary;
+        Ok((self.into_writer(), summary))
+    }
+
+    /// Returns a summary of what was written so far.
+    pub fn summary(&self) -> WriteSummary {
+        self.summary
+    }
+
     /// Writes a [`PcapPacket`].
     pub async fn write_packet(&mut self, packet: &PcapPacket<'_>) -> PcapResult<usize> {
-        match self.endianness {
+        let bytes = match self.endianness {
             Endianness::Big => packet.write_to::<_, BigEndian>(&mut self.writer, self.ts_resolution, self.snaplen).await,
             Endianness::Little => packet.write_to::<_, LittleEndian>(&mut self.writer, self.ts_resolution, self.snaplen).await,
-        }
+        }?;
+
+        self.summary.bytes += bytes as u64;
+        self.summary.packets += 1;
+        Ok(bytes)
     }
 
     /// Writes a [`RawPcapPacket`].
     pub async fn write_raw_packet(&mut self, packet: &RawPcapPacket<'_>) -> PcapResult<usize> {
-        match self.endianness {
+        let bytes = match self.endianness {
             Endianness::Big => packet.write_to::<_, BigEndian>(&mut self.writer).await,
             Endianness::Little => packet.write_to::<_, LittleEndian>(&mut self.writer).await,
-        }
+        }?;
+
+        self.summary.bytes += bytes as u64;
+        self.summary.packets += 1;
+        Ok(bytes)
     }
 
     /// Returns the endianess used by the writer.
@@ -122,3 +172,46 @@ impl<W: AsyncWrite + Unpin> PcapWriter<W> {
         self.ts_resolution
     }
 }
+
+impl<W: AsyncRead + AsyncWrite + AsyncSeek + Truncate + Unpin> PcapWriter<W> {
+    /// Creates a new [`PcapWriter`] appending to an existing pcap file.
+    ///
+    /// The pcap header of the file is validated and adopted by the writer, and a partial packet at the end of the file
+    /// is truncated. If the file is empty, a default pcap header is written like [`PcapWriter::new`].
+    ///
+    /// The [`WriteSummary`] of the writer only counts the packets appended.
+    ///
+    /// # Errors
+    /// The file is not a valid pcap or can't be read, truncated or written to.
+    pub async fn append(mut file: W) -> PcapResult<PcapWriter<W>> {
+        if file.seek(SeekFrom::End(0)).await.map_err(PcapError::IoError)? == 0 {
+            return PcapWriter::new(file).await;
+        }
+        file.seek(SeekFrom::Start(0)).await.map_err(PcapError::IoError)?;
+
+        let mut reader = PcapReader::new(&mut file).await?;
+        let header = reader.header();
+        let mut end = reader.position();
+        loop {
+            match reader.next_raw_packet().await {
+                Some(Ok(_)) => end = reader.position(),
+                Some(Err(PcapError::IoError(e))) if e.kind() == ErrorKind::UnexpectedEof => break,
+                Some(Err(e)) => return Err(e),
+                None => break,
+            }
+        }
+        drop(reader);
+
+        file.truncate(end).await.map_err(PcapError::IoError)?;
+        file.seek(SeekFrom::Start(end)).await.map_err(PcapError::IoError)?;
+
+        Ok(PcapWriter {
+            endianness: header.endianness,
+            snaplen: header.snaplen,
+            ts_resolution: header.ts_resolution,
+            summary: WriteSummary::default(),
+            guard: FinishGuard::new("PcapWriter"),
+            writer: file,
+        })
+    }
+}
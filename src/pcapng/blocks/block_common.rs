@@ -3,10 +3,10 @@
 use std::borrow::Cow;
 use std::io::{Result as IoResult};
 
-use byteorder::{ByteOrder, BigEndian, LittleEndian};
+use byteorder::{ByteOrder, BigEndian, LittleEndian, ReadBytesExt};
 use derive_into_owned::IntoOwned;
 use tokio::io::AsyncWrite;
-use tokio_byteorder::{AsyncReadBytesExt, AsyncWriteBytesExt};
+use tokio_byteorder::AsyncWriteBytesExt;
 
 use super::enhanced_packet::EnhancedPacketBlock;
 use super::interface_description::InterfaceDescriptionBlock;
@@ -65,35 +65,35 @@ pub struct RawBlock<'a> {
 
 impl<'a> RawBlock<'a> {
     /// Parses a borrowed [`RawBlock`] from a slice.
-    pub async fn from_slice<B: ByteOrder>(mut slice: &'a [u8]) -> Result<(&'a [u8], RawBlock<'a>), PcapError> {
+    pub fn from_slice<B: ByteOrder>(mut slice: &'a [u8]) -> Result<(&'a [u8], RawBlock<'a>), PcapError> {
         if slice.len() < 12 {
             return Err(PcapError::IncompleteBuffer);
         }
 
-        let type_ = slice.read_u32::<B>().await.unwrap();
+        let type_ = slice.read_u32::<B>().unwrap();
 
         // Special case for the section header because we don't know the endianness yet
         if type_ == SECTION_HEADER_BLOCK {
-            let initial_len = slice.read_u32::<BigEndian>().await.unwrap();
+            let initial_len = slice.read_u32::<BigEndian>().unwrap();
 
             // Check the first field of the Section header to find the endianness
             let mut tmp_slice = slice;
-            let magic = tmp_slice.read_u32::<BigEndian>().await.unwrap();
+            let magic = tmp_slice.read_u32::<BigEndian>().unwrap();
             let res = match magic {
-                0x1A2B3C4D => inner_parse::<BigEndian>(slice, type_, initial_len).await,
-                0x4D3C2B1A => inner_parse::<LittleEndian>(slice, type_, initial_len.swap_bytes()).await,
+                0x1A2B3C4D => inner_parse::<BigEndian>(slice, type_, initial_len),
+                0x4D3C2B1A => inner_parse::<LittleEndian>(slice, type_, initial_len.swap_bytes()),
                 _ => Err(PcapError::InvalidField("SectionHeaderBlock: invalid magic number")),
             };
 
             return res;
         }
         else {
-            let initial_len = slice.read_u32::<B>().await.map_err(|_| PcapError::IncompleteBuffer)?;
-            return inner_parse::<B>(slice, type_, initial_len).await;
+            let initial_len = slice.read_u32::<B>().map_err(|_| PcapError::IncompleteBuffer)?;
+            return inner_parse::<B>(slice, type_, initial_len);
         };
 
         // Section Header parsing
-        async fn inner_parse<B: ByteOrder>(slice: &[u8], type_: u32, initial_len: u32) -> Result<(&[u8], RawBlock<'_>), PcapError> {
+        fn inner_parse<B: ByteOrder>(slice: &[u8], type_: u32, initial_len: u32) -> Result<(&[u8], RawBlock<'_>), PcapError> {
             if (initial_len % 4) != 0 {
                 return Err(PcapError::InvalidField("Block: (initial_len % 4) != 0"));
             }
@@ -112,7 +112,7 @@ impl<'a> RawBlock<'a> {
 
             let mut rem = &slice[body_len as usize..];
 
-            let trailer_len = rem.read_u32::<B>().await.unwrap();
+            let trailer_len = rem.read_u32::<B>().unwrap();
 
             if initial_len != trailer_len {
                 return Err(PcapError::InvalidField("Block: initial_length != trailer_length"));
@@ -133,12 +133,17 @@ impl<'a> RawBlock<'a> {
         tokio::io::AsyncWriteExt::write_all(writer, &self.body[..]).await?;
         writer.write_u32::<B>(self.trailer_len).await?;
 
-        Ok(self.body.len() + 6)
+        Ok(self.body.len() + 12)
+    }
+
+    /// Converts a borrowed [`RawBlock`] into an owned one.
+    pub fn into_owned(self) -> RawBlock<'static> {
+        RawBlock { type_: self.type_, initial_len: self.initial_len, body: Cow::Owned(self.body.into_owned()), trailer_len: self.trailer_len }
     }
 
     /// Tries to convert a [`RawBlock`] into a [`Block`]
-    pub async fn try_into_block<B: ByteOrder + Send>(self) -> PcapResult<Block<'a>> {
-        Block::try_from_raw_block::<B>(self).await
+    pub fn try_into_block<B: ByteOrder + Send>(self) -> PcapResult<Block<'a>> {
+        Block::try_from_raw_block::<B>(self)
     }
 }
 
@@ -167,9 +172,9 @@ pub enum Block<'a> {
 
 impl<'a> Block<'a> {
     /// Parses a [`Block`] from a slice
-    pub async fn from_slice<B: ByteOrder + Send>(slice: &'a [u8]) -> Result<(&'a [u8], Block<'a>), PcapError> {
-        let (rem, raw_block) = RawBlock::from_slice::<B>(slice).await?;
-        let block = Self::try_from_raw_block::<B>(raw_block).await?;
+    pub fn from_slice<B: ByteOrder + Send>(slice: &'a [u8]) -> Result<(&'a [u8], Block<'a>), PcapError> {
+        let (rem, raw_block) = RawBlock::from_slice::<B>(slice)?;
+        let block = Self::try_from_raw_block::<B>(raw_block)?;
 
         Ok((rem, block))
     }
@@ -208,7 +213,12 @@ impl<'a> Block<'a> {
     /// Tries to create a [`Block`] from a [`RawBlock`].
     ///
     /// The RawBlock must be Borrowed.
-    pub async fn try_from_raw_block<B: ByteOrder + Send>(raw_block: RawBlock<'a>) -> Result<Block<'a>, PcapError> {
+    pub fn try_from_raw_block<B: ByteOrder + Send>(raw_block: RawBlock<'a>) -> Result<Block<'a>, PcapError> {
+        Self::try_from_raw_block_with_rem::<B>(raw_block).map(|(_, block)| block)
+    }
+
+    /// Same as [`Block::try_from_raw_block`], also returns the bytes of the body after the end of the options.
+    pub(crate) fn try_from_raw_block_with_rem<B: ByteOrder + Send>(raw_block: RawBlock<'a>) -> Result<(&'a [u8], Block<'a>), PcapError> {
         let body = match raw_block.body {
             Cow::Borrowed(b) => b,
             _ => panic!("The raw block is not borrowed"),
@@ -216,38 +226,38 @@ impl<'a> Block<'a> {
 
         match raw_block.type_ {
             SECTION_HEADER_BLOCK => {
-                let (_, block) = SectionHeaderBlock::from_slice::<BigEndian>(body).await?;
-                Ok(Block::SectionHeader(block))
+                let (rem, block) = SectionHeaderBlock::from_slice::<BigEndian>(body)?;
+                Ok((rem, Block::SectionHeader(block)))
             },
             INTERFACE_DESCRIPTION_BLOCK => {
-                let (_, block) = InterfaceDescriptionBlock::from_slice::<B>(body).await?;
-                Ok(Block::InterfaceDescription(block))
+                let (rem, block) = InterfaceDescriptionBlock::from_slice::<B>(body)?;
+                Ok((rem, Block::InterfaceDescription(block)))
             },
             PACKET_BLOCK => {
-                let (_, block) = PacketBlock::from_slice::<B>(body).await?;
-                Ok(Block::Packet(block))
+                let (rem, block) = PacketBlock::from_slice::<B>(body)?;
+                Ok((rem, Block::Packet(block)))
             },
             SIMPLE_PACKET_BLOCK => {
-                let (_, block) = SimplePacketBlock::from_slice::<B>(body).await?;
-                Ok(Block::SimplePacket(block))
+                let (rem, block) = SimplePacketBlock::from_slice::<B>(body)?;
+                Ok((rem, Block::SimplePacket(block)))
             },
             NAME_RESOLUTION_BLOCK => {
-                let (_, block) = NameResolutionBlock::from_slice::<B>(body).await?;
-                Ok(Block::NameResolution(block))
+                let (rem, block) = NameResolutionBlock::from_slice::<B>(body)?;
+                Ok((rem, Block::NameResolution(block)))
             },
             INTERFACE_STATISTIC_BLOCK => {
-                let (_, block) = InterfaceStatisticsBlock::from_slice::<B>(body).await?;
-                Ok(Block::InterfaceStatistics(block))
+                let (rem, block) = InterfaceStatisticsBlock::from_slice::<B>(body)?;
+                Ok((rem, Block::InterfaceStatistics(block)))
             },
             ENHANCED_PACKET_BLOCK => {
-                let (_, block) = EnhancedPacketBlock::from_slice::<B>(body).await?;
-                Ok(Block::EnhancedPacket(block))
+                let (rem, block) = EnhancedPacketBlock::from_slice::<B>(body)?;
+                Ok((rem, Block::EnhancedPacket(block)))
             },
             SYSTEMD_JOURNAL_EXPORT_BLOCK => {
-                let (_, block) = SystemdJournalExportBlock::from_slice::<B>(body).await?;
-                Ok(Block::SystemdJournalExport(block))
+                let (rem, block) = SystemdJournalExportBlock::from_slice::<B>(body)?;
+                Ok((rem, Block::SystemdJournalExport(block)))
             },
-            type_ => Ok(Block::Unknown(UnknownBlock::new(type_, raw_block.initial_len, body))),
+            type_ => Ok((&[], Block::Unknown(UnknownBlock::new(type_, raw_block.initial_len, body)))),
         }
     }
 
@@ -321,7 +331,7 @@ impl<'a> Block<'a> {
 #[async_trait::async_trait]
 pub trait PcapNgBlock<'a> {
     /// Parse a new block from a slice
-    async fn from_slice<B: ByteOrder + Send>(slice: &'a [u8]) -> Result<(&[u8], Self), PcapError>
+    fn from_slice<B: ByteOrder + Send>(slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError>
     where
         Self: std::marker::Sized;
 
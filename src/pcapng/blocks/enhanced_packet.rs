@@ -4,12 +4,13 @@ use std::borrow::Cow;
 use std::io::Result as IoResult;
 use std::time::Duration;
 
-use byteorder::ByteOrder;
+use byteorder::{ByteOrder, ReadBytesExt};
 use derive_into_owned::IntoOwned;
 use tokio::io::AsyncWrite;
-use tokio_byteorder::{AsyncWriteBytesExt, AsyncReadBytesExt};
+use tokio_byteorder::AsyncWriteBytesExt;
 
 use super::block_common::{Block, PcapNgBlock};
+use super::interface_description::InterfaceDescriptionBlock;
 use super::opt_common::{CustomBinaryOption, CustomUtf8Option, PcapNgOption, UnknownOption, WriteOptTo};
 use crate::errors::PcapError;
 
@@ -38,17 +39,17 @@ pub struct EnhancedPacketBlock<'a> {
 
 #[async_trait::async_trait]
 impl<'a> PcapNgBlock<'a> for EnhancedPacketBlock<'a> {
-    async fn from_slice<B: ByteOrder + Send>(mut slice: &'a [u8]) -> Result<(&'a [u8], EnhancedPacketBlock<'a>), PcapError> {
+    fn from_slice<B: ByteOrder + Send>(mut slice: &'a [u8]) -> Result<(&'a [u8], EnhancedPacketBlock<'a>), PcapError> {
         if slice.len() < 20 {
             return Err(PcapError::InvalidField("EnhancedPacketBlock: block length length < 20"));
         }
 
-        let interface_id = slice.read_u32::<B>().await.unwrap();
-        let timestamp_high = slice.read_u32::<B>().await.unwrap() as u64;
-        let timestamp_low = slice.read_u32::<B>().await.unwrap() as u64;
+        let interface_id = slice.read_u32::<B>().unwrap();
+        let timestamp_high = slice.read_u32::<B>().unwrap() as u64;
+        let timestamp_low = slice.read_u32::<B>().unwrap() as u64;
         let timestamp = (timestamp_high << 32) + timestamp_low;
-        let captured_len = slice.read_u32::<B>().await.unwrap();
-        let original_len = slice.read_u32::<B>().await.unwrap();
+        let captured_len = slice.read_u32::<B>().unwrap();
+        let original_len = slice.read_u32::<B>().unwrap();
 
         let pad_len = (4 - (captured_len as usize % 4)) % 4;
         let tot_len = captured_len as usize + pad_len;
@@ -60,7 +61,7 @@ impl<'a> PcapNgBlock<'a> for EnhancedPacketBlock<'a> {
         let data = &slice[..captured_len as usize];
         slice = &slice[tot_len..];
 
-        let (slice, options) = EnhancedPacketOption::opts_from_slice::<B>(slice).await?;
+        let (slice, options) = EnhancedPacketOption::opts_from_slice::<B>(slice)?;
         let block = EnhancedPacketBlock {
             interface_id,
             timestamp: Duration::from_nanos(timestamp),
@@ -98,6 +99,71 @@ impl<'a> PcapNgBlock<'a> for EnhancedPacketBlock<'a> {
     }
 }
 
+impl<'a> EnhancedPacketBlock<'a> {
+    /// Returns the absolute timestamp of the packet, using the resolution and offset of its interface.
+    ///
+    /// The `timestamp` field holds the raw value of the block, i.e. a number of `if_tsresol` units.
+    pub fn absolute_timestamp(&self, interface: &InterfaceDescriptionBlock) -> Duration {
+        interface.timestamp_to_duration(self.timestamp.as_nanos() as u64)
+    }
+
+    /// Sets the timestamp of the packet from an absolute timestamp, using the resolution and offset of its interface.
+    pub fn set_absolute_timestamp(&mut self, interface: &InterfaceDescriptionBlock, timestamp: Duration) -> Result<(), PcapError> {
+        let units = interface
+            .duration_to_timestamp(timestamp)
+            .ok_or(PcapError::InvalidField("EnhancedPacketBlock: timestamp not representable by the interface"))?;
+        self.timestamp = Duration::from_nanos(units);
+
+        Ok(())
+    }
+}
+
+
+/// An Enhanced Packet Block (EPB) whose payload is a [`Bytes`](bytes::Bytes).
+///
+/// The payload shares the buffer the block was parsed from, so the block is `'static` and cheap to clone.
+#[cfg(feature = "bytes")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BytesEnhancedPacketBlock {
+    /// Interface this packet comes from, see [`EnhancedPacketBlock::interface_id`]
+    pub interface_id: u32,
+    /// Number of units of time that have elapsed since 1970-01-01 00:00:00 UTC, see [`EnhancedPacketBlock::timestamp`]
+    pub timestamp: Duration,
+    /// Actual length of the packet when it was transmitted on the network
+    pub original_len: u32,
+    /// The data coming from the network, including link-layer headers
+    pub data: bytes::Bytes,
+    /// Options
+    pub options: Vec<EnhancedPacketOption<'static>>,
+}
+
+#[cfg(feature = "bytes")]
+impl BytesEnhancedPacketBlock {
+    /// Creates a [`BytesEnhancedPacketBlock`] from an [`EnhancedPacketBlock`] parsed from `buffer`, without copying its payload.
+    ///
+    /// The payload is copied if it isn't part of `buffer`. The options are always copied.
+    pub fn from_block(block: &EnhancedPacketBlock, buffer: &bytes::Bytes) -> Self {
+        BytesEnhancedPacketBlock {
+            interface_id: block.interface_id,
+            timestamp: block.timestamp,
+            original_len: block.original_len,
+            data: crate::common::share_bytes(buffer, &block.data),
+            options: block.options.iter().map(|option| option.clone().into_owned()).collect(),
+        }
+    }
+
+    /// Returns an [`EnhancedPacketBlock`] borrowing the payload of this block.
+    pub fn as_block(&self) -> EnhancedPacketBlock<'_> {
+        EnhancedPacketBlock {
+            interface_id: self.interface_id,
+            timestamp: self.timestamp,
+            original_len: self.original_len,
+            data: Cow::Borrowed(&self.data),
+            options: self.options.clone(),
+        }
+    }
+}
+
 /// The Enhanced Packet Block (EPB) options
 #[derive(Clone, Debug, IntoOwned, Eq, PartialEq)]
 pub enum EnhancedPacketOption<'a> {
@@ -128,25 +194,25 @@ pub enum EnhancedPacketOption<'a> {
 
 #[async_trait::async_trait]
 impl<'a> PcapNgOption<'a> for EnhancedPacketOption<'a> {
-    async fn from_slice<B: ByteOrder + Send>(code: u16, length: u16, mut slice: &'a [u8]) -> Result<Self, PcapError> {
+    fn from_slice<B: ByteOrder + Send>(code: u16, length: u16, mut slice: &'a [u8]) -> Result<Self, PcapError> {
         let opt = match code {
             1 => EnhancedPacketOption::Comment(Cow::Borrowed(std::str::from_utf8(slice)?)),
             2 => {
                 if slice.len() != 4 {
                     return Err(PcapError::InvalidField("EnhancedPacketOption: Flags length != 4"));
                 }
-                EnhancedPacketOption::Flags(slice.read_u32::<B>().await.map_err(|_| PcapError::IncompleteBuffer)?)
+                EnhancedPacketOption::Flags(slice.read_u32::<B>().map_err(|_| PcapError::IncompleteBuffer)?)
             },
             3 => EnhancedPacketOption::Hash(Cow::Borrowed(slice)),
             4 => {
                 if slice.len() != 8 {
                     return Err(PcapError::InvalidField("EnhancedPacketOption: DropCount length != 8"));
                 }
-                EnhancedPacketOption::DropCount(slice.read_u64::<B>().await.map_err(|_| PcapError::IncompleteBuffer)?)
+                EnhancedPacketOption::DropCount(slice.read_u64::<B>().map_err(|_| PcapError::IncompleteBuffer)?)
             },
 
-            2988 | 19372 => EnhancedPacketOption::CustomUtf8(CustomUtf8Option::from_slice::<B>(code, slice).await?),
-            2989 | 19373 => EnhancedPacketOption::CustomBinary(CustomBinaryOption::from_slice::<B>(code, slice).await?),
+            2988 | 19372 => EnhancedPacketOption::CustomUtf8(CustomUtf8Option::from_slice::<B>(code, slice)?),
+            2989 | 19373 => EnhancedPacketOption::CustomBinary(CustomBinaryOption::from_slice::<B>(code, slice)?),
 
             _ => EnhancedPacketOption::Unknown(UnknownOption::new(code, length, slice)),
         };
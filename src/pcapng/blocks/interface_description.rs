@@ -4,11 +4,12 @@
 
 use std::borrow::Cow;
 use std::io::Result as IoResult;
+use std::time::Duration;
 
-use byteorder::ByteOrder;
+use byteorder::{ByteOrder, ReadBytesExt};
 use derive_into_owned::IntoOwned;
 use tokio::io::AsyncWrite;
-use tokio_byteorder::{AsyncReadBytesExt, AsyncWriteBytesExt};
+use tokio_byteorder::AsyncWriteBytesExt;
 
 use super::block_common::{Block, PcapNgBlock};
 use super::opt_common::{CustomBinaryOption, CustomUtf8Option, PcapNgOption, UnknownOption, WriteOptTo};
@@ -38,20 +39,20 @@ pub struct InterfaceDescriptionBlock<'a> {
 
 #[async_trait::async_trait]
 impl<'a> PcapNgBlock<'a> for InterfaceDescriptionBlock<'a> {
-    async fn from_slice<B: ByteOrder + Send>(mut slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
+    fn from_slice<B: ByteOrder + Send>(mut slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
         if slice.len() < 8 {
             return Err(PcapError::InvalidField("InterfaceDescriptionBlock: block length < 8"));
         }
 
-        let linktype = (slice.read_u16::<B>().await.unwrap() as u32).into();
+        let linktype = (slice.read_u16::<B>().unwrap() as u32).into();
 
-        let reserved = slice.read_u16::<B>().await.unwrap();
+        let reserved = slice.read_u16::<B>().unwrap();
         if reserved != 0 {
             return Err(PcapError::InvalidField("InterfaceDescriptionBlock: reserved != 0"));
         }
 
-        let snaplen = slice.read_u32::<B>().await.unwrap();
-        let (slice, options) = InterfaceDescriptionOption::opts_from_slice::<B>(slice).await?;
+        let snaplen = slice.read_u32::<B>().unwrap();
+        let (slice, options) = InterfaceDescriptionOption::opts_from_slice::<B>(slice)?;
 
         let block = InterfaceDescriptionBlock { linktype, snaplen, options };
 
@@ -79,6 +80,83 @@ impl InterfaceDescriptionBlock<'static> {
     }
 }
 
+impl<'a> InterfaceDescriptionBlock<'a> {
+    /// Returns the value of the `if_tsresol` option.
+    ///
+    /// Defaults to 6 (microseconds) when the option is absent, as mandated by the specification.
+    pub fn ts_resolution(&self) -> u8 {
+        self.options
+            .iter()
+            .find_map(|opt| match opt {
+                InterfaceDescriptionOption::IfTsResol(resol) => Some(*resol),
+                _ => None,
+            })
+            .unwrap_or(6)
+    }
+
+    /// Returns the value of the `if_tsoffset` option, in seconds, or 0 when the option is absent.
+    pub fn ts_offset(&self) -> u64 {
+        self.options
+            .iter()
+            .find_map(|opt| match opt {
+                InterfaceDescriptionOption::IfTsOffset(offset) => Some(*offset),
+                _ => None,
+            })
+            .unwrap_or(0)
+    }
+
+    /// Converts a timestamp expressed in units of this interface into an absolute [`Duration`] since the EPOCH.
+    ///
+    /// Both `if_tsresol` and `if_tsoffset` are taken into account. Saturates instead of overflowing.
+    pub fn timestamp_to_duration(&self, units: u64) -> Duration {
+        let resol = self.ts_resolution();
+        let units = units as u128;
+
+        let nanos = if resol & 0x80 == 0 {
+            let exp = resol as u32;
+            if exp <= 9 {
+                units * 10_u128.pow(9 - exp)
+            }
+            else {
+                10_u128.checked_pow(exp - 9).map(|div| units / div).unwrap_or(0)
+            }
+        }
+        else {
+            // units < 2^64 so the product can't overflow and the shift is at most 127
+            (units * 1_000_000_000) >> (resol & 0x7F)
+        };
+
+        let secs = u64::try_from(nanos / 1_000_000_000).unwrap_or(u64::MAX);
+        let duration = Duration::new(secs, (nanos % 1_000_000_000) as u32);
+
+        duration.saturating_add(Duration::from_secs(self.ts_offset()))
+    }
+
+    /// Converts an absolute [`Duration`] since the EPOCH into a timestamp expressed in units of this interface.
+    ///
+    /// Both `if_tsresol` and `if_tsoffset` are taken into account.
+    /// Returns `None` if the timestamp is before `if_tsoffset` or doesn't fit in 64 bits once converted.
+    pub fn duration_to_timestamp(&self, timestamp: Duration) -> Option<u64> {
+        let resol = self.ts_resolution();
+        let nanos = timestamp.checked_sub(Duration::from_secs(self.ts_offset()))?.as_nanos();
+
+        let units = if resol & 0x80 == 0 {
+            let exp = resol as u32;
+            if exp <= 9 {
+                nanos / 10_u128.pow(9 - exp)
+            }
+            else {
+                nanos.checked_mul(10_u128.checked_pow(exp - 9)?)?
+            }
+        }
+        else {
+            nanos.checked_mul(1_u128 << (resol & 0x7F))? / 1_000_000_000
+        };
+
+        u64::try_from(units).ok()
+    }
+}
+
 /// The Interface Description Block (IDB) options
 #[derive(Clone, Debug, IntoOwned, Eq, PartialEq)]
 pub enum InterfaceDescriptionOption<'a> {
@@ -143,7 +221,7 @@ pub enum InterfaceDescriptionOption<'a> {
 
 #[async_trait::async_trait]
 impl<'a> PcapNgOption<'a> for InterfaceDescriptionOption<'a> {
-    async fn from_slice<B: ByteOrder + Send>(code: u16, length: u16, mut slice: &'a [u8]) -> Result<Self, PcapError> {
+    fn from_slice<B: ByteOrder + Send>(code: u16, length: u16, mut slice: &'a [u8]) -> Result<Self, PcapError> {
         let opt = match code {
             1 => InterfaceDescriptionOption::Comment(Cow::Borrowed(std::str::from_utf8(slice)?)),
             2 => InterfaceDescriptionOption::IfName(Cow::Borrowed(std::str::from_utf8(slice)?)),
@@ -170,25 +248,25 @@ impl<'a> PcapNgOption<'a> for InterfaceDescriptionOption<'a> {
                 if slice.len() != 8 {
                     return Err(PcapError::InvalidField("InterfaceDescriptionOption: IfEuIAddr length != 8"));
                 }
-                InterfaceDescriptionOption::IfEuIAddr(slice.read_u64::<B>().await.map_err(|_| PcapError::IncompleteBuffer)?)
+                InterfaceDescriptionOption::IfEuIAddr(slice.read_u64::<B>().map_err(|_| PcapError::IncompleteBuffer)?)
             },
             8 => {
                 if slice.len() != 8 {
                     return Err(PcapError::InvalidField("InterfaceDescriptionOption: IfSpeed length != 8"));
                 }
-                InterfaceDescriptionOption::IfSpeed(slice.read_u64::<B>().await.map_err(|_| PcapError::IncompleteBuffer)?)
+                InterfaceDescriptionOption::IfSpeed(slice.read_u64::<B>().map_err(|_| PcapError::IncompleteBuffer)?)
             },
             9 => {
                 if slice.len() != 1 {
                     return Err(PcapError::InvalidField("InterfaceDescriptionOption: IfTsResol length != 1"));
                 }
-                InterfaceDescriptionOption::IfTsResol(slice.read_u8().await.map_err(|_| PcapError::IncompleteBuffer)?)
+                InterfaceDescriptionOption::IfTsResol(slice.read_u8().map_err(|_| PcapError::IncompleteBuffer)?)
             },
             10 => {
                 if slice.len() != 1 {
                     return Err(PcapError::InvalidField("InterfaceDescriptionOption: IfTzone length != 1"));
                 }
-                InterfaceDescriptionOption::IfTzone(slice.read_u32::<B>().await.map_err(|_| PcapError::IncompleteBuffer)?)
+                InterfaceDescriptionOption::IfTzone(slice.read_u32::<B>().map_err(|_| PcapError::IncompleteBuffer)?)
             },
             11 => {
                 if slice.is_empty() {
@@ -201,18 +279,18 @@ impl<'a> PcapNgOption<'a> for InterfaceDescriptionOption<'a> {
                 if slice.len() != 1 {
                     return Err(PcapError::InvalidField("InterfaceDescriptionOption: IfFcsLen length != 1"));
                 }
-                InterfaceDescriptionOption::IfFcsLen(slice.read_u8().await.map_err(|_| PcapError::IncompleteBuffer)?)
+                InterfaceDescriptionOption::IfFcsLen(slice.read_u8().map_err(|_| PcapError::IncompleteBuffer)?)
             },
             14 => {
                 if slice.len() != 8 {
                     return Err(PcapError::InvalidField("InterfaceDescriptionOption: IfTsOffset length != 8"));
                 }
-                InterfaceDescriptionOption::IfTsOffset(slice.read_u64::<B>().await.map_err(|_| PcapError::IncompleteBuffer)?)
+                InterfaceDescriptionOption::IfTsOffset(slice.read_u64::<B>().map_err(|_| PcapError::IncompleteBuffer)?)
             },
             15 => InterfaceDescriptionOption::IfHardware(Cow::Borrowed(std::str::from_utf8(slice)?)),
 
-            2988 | 19372 => InterfaceDescriptionOption::CustomUtf8(CustomUtf8Option::from_slice::<B>(code, slice).await?),
-            2989 | 19373 => InterfaceDescriptionOption::CustomBinary(CustomBinaryOption::from_slice::<B>(code, slice).await?),
+            2988 | 19372 => InterfaceDescriptionOption::CustomUtf8(CustomUtf8Option::from_slice::<B>(code, slice)?),
+            2989 | 19373 => InterfaceDescriptionOption::CustomBinary(CustomBinaryOption::from_slice::<B>(code, slice)?),
 
             _ => InterfaceDescriptionOption::Unknown(UnknownOption::new(code, length, slice)),
         };
@@ -3,10 +3,10 @@
 use std::borrow::Cow;
 use std::io::Result as IoResult;
 
-use byteorder::ByteOrder;
+use byteorder::{ByteOrder, ReadBytesExt};
 use derive_into_owned::IntoOwned;
 use tokio::io::AsyncWrite;
-use tokio_byteorder::{AsyncReadBytesExt, AsyncWriteBytesExt};
+use tokio_byteorder::AsyncWriteBytesExt;
 
 use super::block_common::{Block, PcapNgBlock};
 use super::opt_common::{CustomBinaryOption, CustomUtf8Option, PcapNgOption, UnknownOption, WriteOptTo};
@@ -34,14 +34,14 @@ pub struct InterfaceStatisticsBlock<'a> {
 
 #[async_trait::async_trait]
 impl<'a> PcapNgBlock<'a> for InterfaceStatisticsBlock<'a> {
-    async fn from_slice<B: ByteOrder + Send>(mut slice: &'a [u8]) -> Result<(&[u8], Self), PcapError> {
+    fn from_slice<B: ByteOrder + Send>(mut slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
         if slice.len() < 12 {
             return Err(PcapError::InvalidField("InterfaceStatisticsBlock: block length < 12"));
         }
 
-        let interface_id = slice.read_u32::<B>().await.unwrap();
-        let timestamp = slice.read_u64::<B>().await.unwrap();
-        let (slice, options) = InterfaceStatisticsOption::opts_from_slice::<B>(slice).await?;
+        let interface_id = slice.read_u32::<B>().unwrap();
+        let timestamp = slice.read_u64::<B>().unwrap();
+        let (slice, options) = InterfaceStatisticsOption::opts_from_slice::<B>(slice)?;
 
         let block = InterfaceStatisticsBlock { interface_id, timestamp, options };
 
@@ -107,19 +107,19 @@ pub enum InterfaceStatisticsOption<'a> {
 
 #[async_trait::async_trait]
 impl<'a> PcapNgOption<'a> for InterfaceStatisticsOption<'a> {
-    async fn from_slice<B: ByteOrder + Send>(code: u16, length: u16, mut slice: &'a [u8]) -> Result<Self, PcapError> {
+    fn from_slice<B: ByteOrder + Send>(code: u16, length: u16, mut slice: &'a [u8]) -> Result<Self, PcapError> {
         let opt = match code {
             1 => InterfaceStatisticsOption::Comment(Cow::Borrowed(std::str::from_utf8(slice)?)),
-            2 => InterfaceStatisticsOption::IsbStartTime(slice.read_u64::<B>().await.map_err(|_| PcapError::IncompleteBuffer)?),
-            3 => InterfaceStatisticsOption::IsbEndTime(slice.read_u64::<B>().await.map_err(|_| PcapError::IncompleteBuffer)?),
-            4 => InterfaceStatisticsOption::IsbIfRecv(slice.read_u64::<B>().await.map_err(|_| PcapError::IncompleteBuffer)?),
-            5 => InterfaceStatisticsOption::IsbIfDrop(slice.read_u64::<B>().await.map_err(|_| PcapError::IncompleteBuffer)?),
-            6 => InterfaceStatisticsOption::IsbFilterAccept(slice.read_u64::<B>().await.map_err(|_| PcapError::IncompleteBuffer)?),
-            7 => InterfaceStatisticsOption::IsbOsDrop(slice.read_u64::<B>().await.map_err(|_| PcapError::IncompleteBuffer)?),
-            8 => InterfaceStatisticsOption::IsbUsrDeliv(slice.read_u64::<B>().await.map_err(|_| PcapError::IncompleteBuffer)?),
-
-            2988 | 19372 => InterfaceStatisticsOption::CustomUtf8(CustomUtf8Option::from_slice::<B>(code, slice).await?),
-            2989 | 19373 => InterfaceStatisticsOption::CustomBinary(CustomBinaryOption::from_slice::<B>(code, slice).await?),
+            2 => InterfaceStatisticsOption::IsbStartTime(slice.read_u64::<B>().map_err(|_| PcapError::IncompleteBuffer)?),
+            3 => InterfaceStatisticsOption::IsbEndTime(slice.read_u64::<B>().map_err(|_| PcapError::IncompleteBuffer)?),
+            4 => InterfaceStatisticsOption::IsbIfRecv(slice.read_u64::<B>().map_err(|_| PcapError::IncompleteBuffer)?),
+            5 => InterfaceStatisticsOption::IsbIfDrop(slice.read_u64::<B>().map_err(|_| PcapError::IncompleteBuffer)?),
+            6 => InterfaceStatisticsOption::IsbFilterAccept(slice.read_u64::<B>().map_err(|_| PcapError::IncompleteBuffer)?),
+            7 => InterfaceStatisticsOption::IsbOsDrop(slice.read_u64::<B>().map_err(|_| PcapError::IncompleteBuffer)?),
+            8 => InterfaceStatisticsOption::IsbUsrDeliv(slice.read_u64::<B>().map_err(|_| PcapError::IncompleteBuffer)?),
+
+            2988 | 19372 => InterfaceStatisticsOption::CustomUtf8(CustomUtf8Option::from_slice::<B>(code, slice)?),
+            2989 | 19373 => InterfaceStatisticsOption::CustomBinary(CustomBinaryOption::from_slice::<B>(code, slice)?),
 
             _ => InterfaceStatisticsOption::Unknown(UnknownOption::new(code, length, slice)),
         };
@@ -3,10 +3,10 @@
 use std::borrow::Cow;
 use std::io::Result as IoResult;
 
-use byteorder::ByteOrder;
+use byteorder::{ByteOrder, ReadBytesExt};
 use derive_into_owned::IntoOwned;
 use tokio::io::AsyncWrite;
-use tokio_byteorder::{AsyncReadBytesExt, AsyncWriteBytesExt};
+use tokio_byteorder::AsyncWriteBytesExt;
 
 use super::block_common::{Block, PcapNgBlock};
 use super::opt_common::{CustomBinaryOption, CustomUtf8Option, PcapNgOption, UnknownOption, WriteOptTo};
@@ -25,11 +25,11 @@ pub struct NameResolutionBlock<'a> {
 
 #[async_trait::async_trait]
 impl<'a> PcapNgBlock<'a> for NameResolutionBlock<'a> {
-    async fn from_slice<B: ByteOrder + Send>(mut slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
+    fn from_slice<B: ByteOrder + Send>(mut slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
         let mut records = Vec::new();
 
         loop {
-            let (slice_tmp, record) = Record::from_slice::<B>(slice).await?;
+            let (slice_tmp, record) = Record::from_slice::<B>(slice)?;
             slice = slice_tmp;
 
             match record {
@@ -38,7 +38,7 @@ impl<'a> PcapNgBlock<'a> for NameResolutionBlock<'a> {
             }
         }
 
-        let (rem, options) = NameResolutionOption::opts_from_slice::<B>(slice).await?;
+        let (rem, options) = NameResolutionOption::opts_from_slice::<B>(slice)?;
 
         let block = NameResolutionBlock { records, options };
 
@@ -78,9 +78,9 @@ pub enum Record<'a> {
 
 impl<'a> Record<'a> {
     /// Parse a [`Record`] from a slice
-    pub async fn from_slice<B: ByteOrder>(mut slice: &'a [u8]) -> Result<(&'a [u8], Record<'a>), PcapError> {
-        let type_ = slice.read_u16::<B>().await.map_err(|_| PcapError::IncompleteBuffer)?;
-        let length = slice.read_u16::<B>().await.map_err(|_| PcapError::IncompleteBuffer)?;
+    pub fn from_slice<B: ByteOrder>(mut slice: &'a [u8]) -> Result<(&'a [u8], Record<'a>), PcapError> {
+        let type_ = slice.read_u16::<B>().map_err(|_| PcapError::IncompleteBuffer)?;
+        let length = slice.read_u16::<B>().map_err(|_| PcapError::IncompleteBuffer)?;
         let pad_len = (4 - length % 4) % 4;
 
         if slice.len() < length as usize {
@@ -321,7 +321,7 @@ pub enum NameResolutionOption<'a> {
 
 #[async_trait::async_trait]
 impl<'a> PcapNgOption<'a> for NameResolutionOption<'a> {
-    async fn from_slice<B: ByteOrder + Send>(code: u16, length: u16, slice: &'a [u8]) -> Result<NameResolutionOption<'a>, PcapError> {
+    fn from_slice<B: ByteOrder + Send>(code: u16, length: u16, slice: &'a [u8]) -> Result<NameResolutionOption<'a>, PcapError> {
         let opt = match code {
             1 => NameResolutionOption::Comment(Cow::Borrowed(std::str::from_utf8(slice)?)),
             2 => NameResolutionOption::NsDnsName(Cow::Borrowed(std::str::from_utf8(slice)?)),
@@ -338,8 +338,8 @@ impl<'a> PcapNgOption<'a> for NameResolutionOption<'a> {
                 NameResolutionOption::NsDnsIpv6Addr(Cow::Borrowed(slice))
             },
 
-            2988 | 19372 => NameResolutionOption::CustomUtf8(CustomUtf8Option::from_slice::<B>(code, slice).await?),
-            2989 | 19373 => NameResolutionOption::CustomBinary(CustomBinaryOption::from_slice::<B>(code, slice).await?),
+            2988 | 19372 => NameResolutionOption::CustomUtf8(CustomUtf8Option::from_slice::<B>(code, slice)?),
+            2989 | 19373 => NameResolutionOption::CustomBinary(CustomBinaryOption::from_slice::<B>(code, slice)?),
 
             _ => NameResolutionOption::Unknown(UnknownOption::new(code, length, slice)),
         };
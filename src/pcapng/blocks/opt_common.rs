@@ -1,10 +1,10 @@
 use std::borrow::Cow;
 use std::io::Result as IoResult;
 
-use byteorder::ByteOrder;
+use byteorder::{ByteOrder, ReadBytesExt};
 use derive_into_owned::IntoOwned;
 use tokio::io::AsyncWrite;
-use tokio_byteorder::{AsyncWriteBytesExt, AsyncReadBytesExt};
+use tokio_byteorder::AsyncWriteBytesExt;
 
 use crate::errors::PcapError;
 
@@ -13,12 +13,12 @@ use crate::errors::PcapError;
 #[async_trait::async_trait]
 pub(crate) trait PcapNgOption<'a> {
     /// Parse an option from a slice
-    async fn from_slice<B: ByteOrder + Send>(code: u16, length: u16, slice: &'a [u8]) -> Result<Self, PcapError>
+    fn from_slice<B: ByteOrder + Send>(code: u16, length: u16, slice: &'a [u8]) -> Result<Self, PcapError>
     where
         Self: std::marker::Sized;
 
     /// Parse all options in a block
-    async fn opts_from_slice<B: ByteOrder + Send>(mut slice: &'a [u8]) -> Result<(&'a [u8], Vec<Self>), PcapError>
+    fn opts_from_slice<B: ByteOrder + Send>(mut slice: &'a [u8]) -> Result<(&'a [u8], Vec<Self>), PcapError>
     where
         Self: std::marker::Sized,
     {
@@ -34,8 +34,8 @@ pub(crate) trait PcapNgOption<'a> {
                 return Err(PcapError::InvalidField("Option: slice.len() < 4"));
             }
 
-            let code = slice.read_u16::<B>().await.unwrap();
-            let length = slice.read_u16::<B>().await.unwrap() as usize;
+            let code = slice.read_u16::<B>().unwrap();
+            let length = slice.read_u16::<B>().unwrap() as usize;
             let pad_len = (4 - (length % 4)) % 4;
 
             if code == 0 {
@@ -47,7 +47,7 @@ pub(crate) trait PcapNgOption<'a> {
             }
 
             let tmp_slice = &slice[..length];
-            let opt = Self::from_slice::<B>(code, length as u16, tmp_slice).await?;
+            let opt = Self::from_slice::<B>(code, length as u16, tmp_slice)?;
 
             // Jump over the padding
             slice = &slice[length + pad_len..];
@@ -114,8 +114,8 @@ pub struct CustomBinaryOption<'a> {
 
 impl<'a> CustomBinaryOption<'a> {
     /// Parse an [`CustomBinaryOption`] from a slice
-    pub async fn from_slice<B: ByteOrder>(code: u16, mut src: &'a [u8]) -> Result<CustomBinaryOption<'a>, PcapError> {
-        let pen = src.read_u32::<B>().await.map_err(|_| PcapError::IncompleteBuffer)?;
+    pub fn from_slice<B: ByteOrder>(code: u16, mut src: &'a [u8]) -> Result<CustomBinaryOption<'a>, PcapError> {
+        let pen = src.read_u32::<B>().map_err(|_| PcapError::IncompleteBuffer)?;
         let opt = CustomBinaryOption { code, pen, value: Cow::Borrowed(src) };
         Ok(opt)
     }
@@ -134,8 +134,8 @@ pub struct CustomUtf8Option<'a> {
 
 impl<'a> CustomUtf8Option<'a> {
     /// Parse a [`CustomUtf8Option`] from a slice
-    pub async fn from_slice<B: ByteOrder>(code: u16, mut src: &'a [u8]) -> Result<CustomUtf8Option<'a>, PcapError> {
-        let pen = src.read_u32::<B>().await.map_err(|_| PcapError::IncompleteBuffer)?;
+    pub fn from_slice<B: ByteOrder>(code: u16, mut src: &'a [u8]) -> Result<CustomUtf8Option<'a>, PcapError> {
+        let pen = src.read_u32::<B>().map_err(|_| PcapError::IncompleteBuffer)?;
         let opt = CustomUtf8Option { code, pen, value: Cow::Borrowed(std::str::from_utf8(src)?) };
         Ok(opt)
     }
@@ -3,10 +3,10 @@
 use std::borrow::Cow;
 use std::io::Result as IoResult;
 
-use byteorder::ByteOrder;
+use byteorder::{ByteOrder, ReadBytesExt};
 use derive_into_owned::IntoOwned;
 use tokio::io::AsyncWrite;
-use tokio_byteorder::{AsyncReadBytesExt, AsyncWriteBytesExt};
+use tokio_byteorder::AsyncWriteBytesExt;
 
 use super::block_common::{Block, PcapNgBlock};
 use super::opt_common::{CustomBinaryOption, CustomUtf8Option, PcapNgOption, UnknownOption, WriteOptTo};
@@ -44,16 +44,16 @@ pub struct PacketBlock<'a> {
 
 #[async_trait::async_trait]
 impl<'a> PcapNgBlock<'a> for PacketBlock<'a> {
-    async fn from_slice<B: ByteOrder + Send>(mut slice: &'a [u8]) -> Result<(&'a [u8], PacketBlock<'a>), PcapError> {
+    fn from_slice<B: ByteOrder + Send>(mut slice: &'a [u8]) -> Result<(&'a [u8], PacketBlock<'a>), PcapError> {
         if slice.len() < 20 {
             return Err(PcapError::InvalidField("EnhancedPacketBlock: block length length < 20"));
         }
 
-        let interface_id = slice.read_u16::<B>().await.unwrap();
-        let drop_count = slice.read_u16::<B>().await.unwrap();
-        let timestamp = slice.read_u64::<B>().await.unwrap();
-        let captured_len = slice.read_u32::<B>().await.unwrap();
-        let original_len = slice.read_u32::<B>().await.unwrap();
+        let interface_id = slice.read_u16::<B>().unwrap();
+        let drop_count = slice.read_u16::<B>().unwrap();
+        let timestamp = slice.read_u64::<B>().unwrap();
+        let captured_len = slice.read_u32::<B>().unwrap();
+        let original_len = slice.read_u32::<B>().unwrap();
 
         let pad_len = (4 - (captured_len as usize % 4)) % 4;
         let tot_len = captured_len as usize + pad_len;
@@ -65,7 +65,7 @@ impl<'a> PcapNgBlock<'a> for PacketBlock<'a> {
         let data = &slice[..captured_len as usize];
         slice = &slice[tot_len..];
 
-        let (slice, options) = PacketOption::opts_from_slice::<B>(slice).await?;
+        let (slice, options) = PacketOption::opts_from_slice::<B>(slice)?;
         let block = PacketBlock {
             interface_id,
             drop_count,
@@ -124,19 +124,19 @@ pub enum PacketOption<'a> {
 
 #[async_trait::async_trait]
 impl<'a> PcapNgOption<'a> for PacketOption<'a> {
-    async fn from_slice<B: ByteOrder + Send>(code: u16, length: u16, mut slice: &'a [u8]) -> Result<PacketOption<'a>, PcapError> {
+    fn from_slice<B: ByteOrder + Send>(code: u16, length: u16, mut slice: &'a [u8]) -> Result<PacketOption<'a>, PcapError> {
         let opt = match code {
             1 => PacketOption::Comment(Cow::Borrowed(std::str::from_utf8(slice)?)),
             2 => {
                 if slice.len() != 4 {
                     return Err(PcapError::InvalidField("PacketOption: Flags length != 4"));
                 }
-                PacketOption::Flags(slice.read_u32::<B>().await.map_err(|_| PcapError::IncompleteBuffer)?)
+                PacketOption::Flags(slice.read_u32::<B>().map_err(|_| PcapError::IncompleteBuffer)?)
             },
             3 => PacketOption::Hash(Cow::Borrowed(slice)),
 
-            2988 | 19372 => PacketOption::CustomUtf8(CustomUtf8Option::from_slice::<B>(code, slice).await?),
-            2989 | 19373 => PacketOption::CustomBinary(CustomBinaryOption::from_slice::<B>(code, slice).await?),
+            2988 | 19372 => PacketOption::CustomUtf8(CustomUtf8Option::from_slice::<B>(code, slice)?),
+            2989 | 19373 => PacketOption::CustomBinary(CustomBinaryOption::from_slice::<B>(code, slice)?),
 
             _ => PacketOption::Unknown(UnknownOption::new(code, length, slice)),
         };
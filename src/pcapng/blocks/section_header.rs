@@ -3,10 +3,10 @@
 use std::borrow::Cow;
 use std::io::Result as IoResult;
 
-use byteorder::{ByteOrder, BigEndian, LittleEndian};
+use byteorder::{ByteOrder, BigEndian, LittleEndian, ReadBytesExt};
 use derive_into_owned::IntoOwned;
 use tokio::io::AsyncWrite;
-use tokio_byteorder::{AsyncReadBytesExt, AsyncWriteBytesExt};
+use tokio_byteorder::AsyncWriteBytesExt;
 
 use super::block_common::{Block, PcapNgBlock};
 use super::opt_common::{CustomBinaryOption, CustomUtf8Option, PcapNgOption, UnknownOption, WriteOptTo};
@@ -40,12 +40,12 @@ pub struct SectionHeaderBlock<'a> {
 
 #[async_trait::async_trait]
 impl<'a> PcapNgBlock<'a> for SectionHeaderBlock<'a> {
-    async fn from_slice<B: ByteOrder>(mut slice: &'a [u8]) -> Result<(&'a [u8], SectionHeaderBlock<'a>), PcapError> {
+    fn from_slice<B: ByteOrder>(mut slice: &'a [u8]) -> Result<(&'a [u8], SectionHeaderBlock<'a>), PcapError> {
         if slice.len() < 16 {
             return Err(PcapError::InvalidField("SectionHeaderBlock: block length < 16"));
         }
 
-        let magic = slice.read_u32::<BigEndian>().await.unwrap();
+        let magic = slice.read_u32::<BigEndian>().unwrap();
         let endianness = match magic {
             0x1A2B3C4D => Endianness::Big,
             0x4D3C2B1A => Endianness::Little,
@@ -53,8 +53,8 @@ impl<'a> PcapNgBlock<'a> for SectionHeaderBlock<'a> {
         };
 
         let (rem, major_version, minor_version, section_length, options) = match endianness {
-            Endianness::Big => parse_inner::<BigEndian>(slice).await?,
-            Endianness::Little => parse_inner::<LittleEndian>(slice).await?,
+            Endianness::Big => parse_inner::<BigEndian>(slice)?,
+            Endianness::Little => parse_inner::<LittleEndian>(slice)?,
         };
 
         let block = SectionHeaderBlock { endianness, major_version, minor_version, section_length, options };
@@ -62,11 +62,11 @@ impl<'a> PcapNgBlock<'a> for SectionHeaderBlock<'a> {
         return Ok((rem, block));
 
         #[allow(clippy::type_complexity)]
-        async fn parse_inner<B: ByteOrder + Send>(mut slice: &[u8]) -> Result<(&[u8], u16, u16, i64, Vec<SectionHeaderOption>), PcapError> {
-            let maj_ver = slice.read_u16::<B>().await.unwrap();
-            let min_ver = slice.read_u16::<B>().await.unwrap();
-            let sec_len = slice.read_i64::<B>().await.unwrap();
-            let (rem, opts) = SectionHeaderOption::opts_from_slice::<B>(slice).await?;
+        fn parse_inner<B: ByteOrder + Send>(mut slice: &[u8]) -> Result<(&[u8], u16, u16, i64, Vec<SectionHeaderOption<'_>>), PcapError> {
+            let maj_ver = slice.read_u16::<B>().unwrap();
+            let min_ver = slice.read_u16::<B>().unwrap();
+            let sec_len = slice.read_i64::<B>().unwrap();
+            let (rem, opts) = SectionHeaderOption::opts_from_slice::<B>(slice)?;
 
             Ok((rem, maj_ver, min_ver, sec_len, opts))
         }
@@ -132,15 +132,15 @@ pub enum SectionHeaderOption<'a> {
 
 #[async_trait::async_trait]
 impl<'a> PcapNgOption<'a> for SectionHeaderOption<'a> {
-    async fn from_slice<B: ByteOrder + Send>(code: u16, length: u16, slice: &'a [u8]) -> Result<SectionHeaderOption<'a>, PcapError> {
+    fn from_slice<B: ByteOrder + Send>(code: u16, length: u16, slice: &'a [u8]) -> Result<SectionHeaderOption<'a>, PcapError> {
         let opt = match code {
             1 => SectionHeaderOption::Comment(Cow::Borrowed(std::str::from_utf8(slice)?)),
             2 => SectionHeaderOption::Hardware(Cow::Borrowed(std::str::from_utf8(slice)?)),
             3 => SectionHeaderOption::OS(Cow::Borrowed(std::str::from_utf8(slice)?)),
             4 => SectionHeaderOption::UserApplication(Cow::Borrowed(std::str::from_utf8(slice)?)),
 
-            2988 | 19372 => SectionHeaderOption::CustomUtf8(CustomUtf8Option::from_slice::<B>(code, slice).await?),
-            2989 | 19373 => SectionHeaderOption::CustomBinary(CustomBinaryOption::from_slice::<B>(code, slice).await?),
+            2988 | 19372 => SectionHeaderOption::CustomUtf8(CustomUtf8Option::from_slice::<B>(code, slice)?),
+            2989 | 19373 => SectionHeaderOption::CustomBinary(CustomBinaryOption::from_slice::<B>(code, slice)?),
 
             _ => SectionHeaderOption::Unknown(UnknownOption::new(code, length, slice)),
         };
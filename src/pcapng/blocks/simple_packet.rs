@@ -3,10 +3,10 @@
 use std::borrow::Cow;
 use std::io::Result as IoResult;
 
-use byteorder::ByteOrder;
+use byteorder::{ByteOrder, ReadBytesExt};
 use derive_into_owned::IntoOwned;
 use tokio::io::AsyncWrite;
-use tokio_byteorder::{AsyncReadBytesExt, AsyncWriteBytesExt};
+use tokio_byteorder::AsyncWriteBytesExt;
 
 use super::block_common::{Block, PcapNgBlock};
 use crate::errors::PcapError;
@@ -26,11 +26,11 @@ pub struct SimplePacketBlock<'a> {
 
 #[async_trait::async_trait]
 impl<'a> PcapNgBlock<'a> for SimplePacketBlock<'a> {
-    async fn from_slice<B: ByteOrder + Send>(mut slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
+    fn from_slice<B: ByteOrder + Send>(mut slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
         if slice.len() < 4 {
             return Err(PcapError::InvalidField("SimplePacketBlock: block length < 4"));
         }
-        let original_len = slice.read_u32::<B>().await.unwrap();
+        let original_len = slice.read_u32::<B>().unwrap();
 
         let packet = SimplePacketBlock { original_len, data: Cow::Borrowed(slice) };
 
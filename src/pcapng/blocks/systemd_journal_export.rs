@@ -20,7 +20,7 @@ pub struct SystemdJournalExportBlock<'a> {
 
 #[async_trait::async_trait]
 impl<'a> PcapNgBlock<'a> for SystemdJournalExportBlock<'a> {
-    async fn from_slice<B: ByteOrder>(slice: &'a [u8]) -> Result<(&'a [u8], SystemdJournalExportBlock<'a>), PcapError> {
+    fn from_slice<B: ByteOrder>(slice: &'a [u8]) -> Result<(&'a [u8], SystemdJournalExportBlock<'a>), PcapError> {
         let packet = SystemdJournalExportBlock { journal_entry: Cow::Borrowed(slice) };
         Ok((&[], packet))
     }
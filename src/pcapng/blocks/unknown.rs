@@ -31,7 +31,7 @@ impl<'a> UnknownBlock<'a> {
 
 #[async_trait::async_trait]
 impl<'a> PcapNgBlock<'a> for UnknownBlock<'a> {
-    async fn from_slice<B: ByteOrder>(_slice: &'a [u8]) -> Result<(&[u8], UnknownBlock<'a>), PcapError>
+    fn from_slice<B: ByteOrder>(_slice: &'a [u8]) -> Result<(&'a [u8], UnknownBlock<'a>), PcapError>
     where
         Self: Sized,
     {
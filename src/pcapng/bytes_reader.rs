@@ -0,0 +1,149 @@
+use bytes::{Buf, BytesMut};
+use tokio::io::AsyncRead;
+
+use super::blocks::block_common::Block;
+use super::blocks::enhanced_packet::BytesEnhancedPacketBlock;
+use super::blocks::interface_description::InterfaceDescriptionBlock;
+use super::blocks::section_header::SectionHeaderBlock;
+use super::PcapNgParser;
+use crate::errors::PcapError;
+use crate::read_buffer::BytesReadBuffer;
+use crate::{ParseLimits, ParseMode, ParseWarning, ReaderConfig};
+
+
+/// Block read by a [`BytesPcapNgReader`] or decoded by a [`PcapNgCodec`](crate::codec::PcapNgCodec).
+#[derive(Clone, Debug)]
+pub enum PcapNgFrame {
+    /// Enhanced Packet Block, whose payload shares the memory of the reader
+    Packet(BytesEnhancedPacketBlock),
+    /// Any other block, copied
+    Block(Block<'static>),
+}
+
+/// Reads a PcapNg from a reader, into Enhanced Packet Blocks whose payload is a [`Bytes`](bytes::Bytes).
+///
+/// The reader fills shared chunks of memory and the payload of each Enhanced Packet Block is a slice of a chunk: the
+/// packets are `'static`, cheap to clone and can be sent to other tasks without copying their payload.
+/// A chunk is freed once all its packets are dropped. The other blocks are copied.
+///
+/// # Example
+/// ```rust,no_run
+/// # tokio_test::block_on(async {
+/// use tokio::fs::File;
+///
+/// use pcap_file_tokio::pcapng::{BytesPcapNgReader, PcapNgFrame};
+///
+/// let file_in = File::open("test.pcapng").await.expect("Error opening file");
+/// let mut pcapng_reader = BytesPcapNgReader::new(file_in).await.unwrap();
+///
+/// while let Some(frame) = pcapng_reader.next_block().await {
+///     if let PcapNgFrame::Packet(packet) = frame.unwrap() {
+///         tokio::spawn(async move { println!("{} bytes", packet.data.len()) });
+///     }
+/// }
+/// # });
+/// ```
+pub struct BytesPcapNgReader<R: AsyncRead + Unpin> {
+    parser: PcapNgParser,
+    reader: BytesReadBuffer<R>,
+}
+
+impl<R: AsyncRead + Unpin> BytesPcapNgReader<R> {
+    /// Creates a new [`BytesPcapNgReader`] from a reader.
+    ///
+    /// Parses the first block which must be a valid SectionHeaderBlock.
+    pub async fn new(reader: R) -> Result<BytesPcapNgReader<R>, PcapError> {
+        Self::with_config(reader, ReaderConfig::default()).await
+    }
+
+    /// Creates a new [`BytesPcapNgReader`] from a reader, with the given [`ReaderConfig`].
+    ///
+    /// The chunks have the initial capacity of the config.
+    /// A block larger than [`ReaderConfig::with_max_record_size`] returns [`PcapError::RecordTooLarge`].
+    /// The [`ParseLimits`] and the [`ParseMode`] of the config are applied to every block, like by
+    /// [`PcapNgReader::next_block`](super::PcapNgReader::next_block).
+    pub async fn with_config(reader: R, config: ReaderConfig) -> Result<BytesPcapNgReader<R>, PcapError> {
+        let mut reader = BytesReadBuffer::with_config(reader, config);
+        let parser = match reader.decode_with(|src| decode_first_section(src, config.limits, config.mode)).await? {
+            Some(parser) => parser,
+            None => return Err(PcapError::IoError(std::io::ErrorKind::UnexpectedEof.into())),
+        };
+
+        Ok(Self { parser, reader })
+    }
+
+    /// Returns the next [`PcapNgFrame`].
+    pub async fn next_block(&mut self) -> Option<Result<PcapNgFrame, PcapError>> {
+        let parser = &mut self.parser;
+        self.reader.decode_with(|src| decode_frame(src, parser)).await.transpose()
+    }
+
+    /// Returns the current [`SectionHeaderBlock`].
+    pub fn section(&self) -> &SectionHeaderBlock<'static> {
+        self.parser.section()
+    }
+
+    /// Returns all the current [`InterfaceDescriptionBlock`].
+    pub fn interfaces(&self) -> &[InterfaceDescriptionBlock<'static>] {
+        self.parser.interfaces()
+    }
+
+    /// Returns the [`InterfaceDescriptionBlock`] corresponding to the given packet.
+    pub fn packet_interface(&self, packet: &BytesEnhancedPacketBlock) -> Option<&InterfaceDescriptionBlock<'static>> {
+        self.interfaces().get(packet.interface_id as usize)
+    }
+
+    /// Returns the warnings collected, see [`ParseMode`].
+    pub fn warnings(&self) -> &[ParseWarning] {
+        self.parser.warnings()
+    }
+
+    /// Returns and clears the warnings collected, see [`ParseMode`].
+    pub fn take_warnings(&mut self) -> Vec<ParseWarning> {
+        self.parser.take_warnings()
+    }
+
+    /// Consumes the [`Self`], returning the wrapped reader.
+    pub fn into_inner(self) -> R {
+        self.reader.into_inner()
+    }
+
+    /// Gets a reference to the wrapped reader.
+    pub fn get_ref(&self) -> &R {
+        self.reader.get_ref()
+    }
+}
+
+
+/// Decodes the first Section Header Block from the start of `src`, or returns `None` if `src` is too short.
+pub(crate) fn decode_first_section(src: &mut BytesMut, limits: ParseLimits, mode: ParseMode) -> Result<Option<PcapNgParser>, PcapError> {
+    let (len, parser) = match PcapNgParser::with_limits_and_mode(src, limits, mode) {
+        Ok((rem, parser)) => (src.len() - rem.len(), parser),
+        Err(PcapError::IncompleteBuffer) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    src.advance(len);
+
+    Ok(Some(parser))
+}
+
+/// Decodes a [`PcapNgFrame`] sharing the memory of `src`, or returns `None` if `src` is too short.
+pub(crate) fn decode_frame(src: &mut BytesMut, parser: &mut PcapNgParser) -> Result<Option<PcapNgFrame>, PcapError> {
+    // The length is checked against the limits before the block is buffered
+    let len = match parser.next_block_len(src) {
+        Ok(len) => len,
+        Err(PcapError::IncompleteBuffer) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    let buffer = src.split_to(len).freeze();
+    let (_, block) = parser.next_block(&buffer)?;
+
+    let frame = match block {
+        Block::EnhancedPacket(packet) => PcapNgFrame::Packet(BytesEnhancedPacketBlock::from_block(&packet, &buffer)),
+        block => PcapNgFrame::Block(block.into_owned()),
+    };
+
+    Ok(Some(frame))
+}
@@ -0,0 +1,421 @@
+use std::fmt;
+use std::time::Duration;
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use super::blocks::interface_description::InterfaceDescriptionBlock;
+use super::blocks::*;
+use super::validation::{check_option, options_start, try_into_block_validated};
+use crate::errors::PcapError;
+use crate::validation::Validator;
+use crate::{Endianness, ParseMode, PcapResult};
+
+
+/// Severity of a [`Violation`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Severity {
+    /// Allowed by the spec but likely to confuse the readers
+    Warning,
+    /// Forbidden by the spec
+    Error,
+}
+
+/// Spec violation reported by [`lint`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Violation {
+    /// The capture doesn't start with a Section Header Block
+    MissingSectionHeader,
+    /// The magic number of a Section Header Block is invalid
+    InvalidMagic(u32),
+    /// The major version of a section is unknown, the blocks of the section aren't checked
+    UnknownMajorVersion(u16),
+    /// The length of a block is less than 12 bytes
+    InvalidBlockLength(u32),
+    /// The length of a block isn't a multiple of 4
+    UnalignedBlockLength(u32),
+    /// The trailer length of a block differs from its initial length
+    TrailerLengthMismatch {
+        /// Initial length of the block
+        initial_len: u32,
+        /// Trailer length of the block
+        trailer_len: u32,
+    },
+    /// The capture ends in the middle of a block
+    TruncatedBlock,
+    /// The fixed fields of a block are invalid
+    InvalidBlock(&'static str),
+    /// A block references an interface which isn't defined in its section
+    UndefinedInterface(u32),
+    /// An option which can appear only once in a block is repeated
+    DuplicateOption(u16),
+    /// The length of an option is invalid for its code or overflows its block
+    OptionLength {
+        /// Code of the option
+        code: u16,
+        /// Length of the option
+        length: u16,
+    },
+    /// The string of an option isn't valid UTF-8
+    InvalidUtf8(u16),
+    /// The options of a block don't end with an end-of-options
+    MissingEndOfOptions,
+    /// The captured length of a packet is greater than the snaplen of its interface
+    CapturedLenExceedsSnaplen {
+        /// Captured length of the packet
+        captured_len: u32,
+        /// Snaplen of the interface
+        snaplen: u32,
+    },
+    /// The timestamp of a packet is less than the one of the previous packet of its interface
+    NonMonotonicTimestamp(u32),
+}
+
+impl Violation {
+    /// Returns the [`Severity`] of the violation.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Violation::MissingEndOfOptions | Violation::NonMonotonicTimestamp(_) => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::MissingSectionHeader => write!(f, "the capture doesn't start with a section header"),
+            Violation::InvalidMagic(magic) => write!(f, "invalid section header magic number {magic:#010x}"),
+            Violation::UnknownMajorVersion(version) => write!(f, "unknown major version {version}"),
+            Violation::InvalidBlockLength(len) => write!(f, "block length {len} < 12"),
+            Violation::UnalignedBlockLength(len) => write!(f, "block length {len} isn't a multiple of 4"),
+            Violation::TrailerLengthMismatch { initial_len, trailer_len } => {
+                write!(f, "trailer length {trailer_len} != initial length {initial_len}")
+            },
+            Violation::TruncatedBlock => write!(f, "truncated block"),
+            Violation::InvalidBlock(reason) => write!(f, "invalid block: {reason}"),
+            Violation::UndefinedInterface(id) => write!(f, "undefined interface {id}"),
+            Violation::DuplicateOption(code) => write!(f, "duplicate option {code}"),
+            Violation::OptionLength { code, length } => write!(f, "invalid length {length} for option {code}"),
+            Violation::InvalidUtf8(code) => write!(f, "invalid UTF-8 string in option {code}"),
+            Violation::MissingEndOfOptions => write!(f, "missing end of options"),
+            Violation::CapturedLenExceedsSnaplen { captured_len, snaplen } => {
+                write!(f, "captured length {captured_len} > snaplen {snaplen}")
+            },
+            Violation::NonMonotonicTimestamp(id) => write!(f, "timestamp earlier than the previous packet of interface {id}"),
+        }
+    }
+}
+
+/// Spec violation found by [`lint`], with its location in the capture
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Diagnostic {
+    /// Offset in the capture of the block, or of the option, violating the spec
+    pub offset: u64,
+    /// Index of the block in the capture, starting at 0
+    pub block_index: u64,
+    /// Severity of the violation
+    pub severity: Severity,
+    /// Violation found
+    pub violation: Violation,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+
+        write!(f, "block {} at offset {:#x}: {severity}: {}", self.block_index, self.offset, self.violation)
+    }
+}
+
+/// Walks every block of a pcapng and reports its spec violations, in the order of the capture.
+///
+/// Unlike the [`PcapNgReader`](super::PcapNgReader), the linter carries on after a malformed block as long as the
+/// length of the block can be trusted. It stops at the first block whose length is invalid or which is truncated.
+///
+/// Only the IO errors are returned as errors, the malformed captures are reported as [`Diagnostic`]s.
+///
+/// # Example
+/// ```rust,no_run
+/// # tokio_test::block_on(async {
+/// use tokio::fs::File;
+///
+/// use pcap_file_tokio::pcapng::{lint, Severity};
+///
+/// let file_in = File::open("test.pcapng").await.expect("Error opening file");
+/// let diagnostics = lint(file_in).await.unwrap();
+///
+/// for diagnostic in &diagnostics {
+///     println!("{diagnostic}");
+/// }
+///
+/// let conforming = diagnostics.iter().all(|diagnostic| diagnostic.severity < Severity::Error);
+/// # });
+/// ```
+pub async fn lint<R: AsyncRead + Unpin>(mut reader: R) -> PcapResult<Vec<Diagnostic>> {
+    let mut linter = Linter::default();
+    let mut block = vec![];
+
+    loop {
+        // The type, the length and the first 4 bytes of the body, or the trailer, of the next block
+        block.clear();
+        (&mut reader).take(12).read_to_end(&mut block).await.map_err(PcapError::IoError)?;
+        match block.len() {
+            0 => break,
+            12 => {},
+            _ => {
+                linter.report(0, Violation::TruncatedBlock);
+                break;
+            },
+        }
+
+        let type_ = BigEndian::read_u32(&block[..4]);
+        if type_ == SECTION_HEADER_BLOCK {
+            linter.endianness = match BigEndian::read_u32(&block[8..12]) {
+                0x1A2B3C4D => Some(Endianness::Big),
+                0x4D3C2B1A => Some(Endianness::Little),
+                magic => {
+                    linter.report(8, Violation::InvalidMagic(magic));
+                    break;
+                },
+            };
+        }
+
+        let endianness = match linter.endianness {
+            Some(endianness) => endianness,
+            None => {
+                linter.report(0, Violation::MissingSectionHeader);
+                break;
+            },
+        };
+
+        let initial_len = match endianness {
+            Endianness::Big => BigEndian::read_u32(&block[4..8]),
+            Endianness::Little => LittleEndian::read_u32(&block[4..8]),
+        };
+        if initial_len < 12 {
+            linter.report(4, Violation::InvalidBlockLength(initial_len));
+            break;
+        }
+
+        (&mut reader).take(initial_len as u64 - 12).read_to_end(&mut block).await.map_err(PcapError::IoError)?;
+        if block.len() < initial_len as usize {
+            linter.report(0, Violation::TruncatedBlock);
+            break;
+        }
+
+        // The checks of a block don't report its violations in the order of their offsets
+        let first = linter.diagnostics.len();
+        match endianness {
+            Endianness::Big => linter.check_block::<BigEndian>(&block),
+            Endianness::Little => linter.check_block::<LittleEndian>(&block),
+        }
+        linter.diagnostics[first..].sort_by_key(|diagnostic| diagnostic.offset);
+
+        linter.offset += block.len() as u64;
+        linter.index += 1;
+    }
+
+    Ok(linter.diagnostics)
+}
+
+
+/// State of [`lint`]
+#[derive(Default)]
+struct Linter {
+    /// Violations found so far
+    diagnostics: Vec<Diagnostic>,
+    /// Offset of the current block
+    offset: u64,
+    /// Index of the current block
+    index: u64,
+    /// Endianness of the current section, `None` before the first section
+    endianness: Option<Endianness>,
+    /// True if the blocks of the current section can't be checked
+    skip_section: bool,
+    /// Interfaces of the current section with the timestamp of their last packet, `None` for the invalid ones
+    interfaces: Vec<Option<(InterfaceDescriptionBlock<'static>, Option<Duration>)>>,
+}
+
+impl Linter {
+    /// Reports a violation at the given offset in the current block.
+    fn report(&mut self, offset: usize, violation: Violation) {
+        self.diagnostics.push(Diagnostic {
+            offset: self.offset + offset as u64,
+            block_index: self.index,
+            severity: violation.severity(),
+            violation,
+        });
+    }
+
+    /// Checks a complete block, its type and length included.
+    fn check_block<B: ByteOrder + Send>(&mut self, block: &[u8]) {
+        let type_ = B::read_u32(&block[..4]);
+        let initial_len = B::read_u32(&block[4..8]);
+        let trailer_len = B::read_u32(&block[block.len() - 4..]);
+
+        if initial_len % 4 != 0 {
+            self.report(4, Violation::UnalignedBlockLength(initial_len));
+        }
+
+        if trailer_len != initial_len {
+            self.report(block.len() - 4, Violation::TrailerLengthMismatch { initial_len, trailer_len });
+        }
+
+        let body = &block[8..block.len() - 4];
+        if type_ == SECTION_HEADER_BLOCK {
+            self.interfaces.clear();
+            self.skip_section = false;
+        }
+        else if self.skip_section {
+            return;
+        }
+
+        if let Some(start) = options_start::<B>(type_, body) {
+            self.check_options::<B>(type_, body, start);
+        }
+
+        // The malformed options are already reported, they are dropped to check the fixed fields
+        let raw_block = RawBlock { type_, initial_len, body: body.into(), trailer_len };
+        match try_into_block_validated::<B>(raw_block, &mut Validator::new(ParseMode::Lenient)) {
+            Ok(block) => self.check_fields(block),
+            Err(e) => {
+                let reason = match e {
+                    PcapError::InvalidField(reason) => reason,
+                    _ => "invalid fixed fields",
+                };
+                self.report(0, Violation::InvalidBlock(reason));
+
+                // Keeps the ids of the next interfaces
+                if type_ == INTERFACE_DESCRIPTION_BLOCK {
+                    self.interfaces.push(None);
+                }
+            },
+        }
+    }
+
+    /// Checks the options of a block, starting at `start` in its body.
+    fn check_options<B: ByteOrder + Send>(&mut self, type_: u32, body: &[u8], start: usize) {
+        // If there is nothing left in the body, it means that there is no option
+        if start == body.len() {
+            return;
+        }
+
+        let mut codes = vec![];
+        let mut pos = start;
+        loop {
+            // Offset of the option in the block
+            let offset = 8 + pos;
+            let slice = &body[pos..];
+
+            if slice.is_empty() {
+                self.report(offset, Violation::MissingEndOfOptions);
+                break;
+            }
+
+            if slice.len() < 4 {
+                self.report(offset, Violation::InvalidBlock("Option: slice.len() < 4"));
+                break;
+            }
+
+            let code = B::read_u16(&slice[..2]);
+            let length = B::read_u16(&slice[2..4]);
+            if code == 0 {
+                break;
+            }
+
+            let len = 4 + length as usize + (4 - length as usize % 4) % 4;
+            if slice.len() < len {
+                self.report(offset, Violation::OptionLength { code, length });
+                break;
+            }
+
+            match check_option::<B>(type_, code, length, &slice[4..4 + length as usize]) {
+                Ok(()) => {},
+                Err(PcapError::Utf8Error(_)) => self.report(offset, Violation::InvalidUtf8(code)),
+                Err(_) => self.report(offset, Violation::OptionLength { code, length }),
+            }
+
+            if codes.contains(&code) && is_single_instance(type_, code) {
+                self.report(offset, Violation::DuplicateOption(code));
+            }
+            codes.push(code);
+
+            pos += len;
+        }
+    }
+
+    /// Checks the fixed fields of a parsed block against the current section.
+    fn check_fields(&mut self, block: Block) {
+        match block {
+            Block::SectionHeader(section) if section.major_version != 1 => {
+                self.report(12, Violation::UnknownMajorVersion(section.major_version));
+                self.skip_section = true;
+            },
+            Block::InterfaceDescription(interface) => self.interfaces.push(Some((interface.into_owned(), None))),
+            Block::EnhancedPacket(packet) => {
+                let timestamp = |interface: &InterfaceDescriptionBlock| packet.absolute_timestamp(interface);
+                self.check_packet(packet.interface_id, packet.data.len(), timestamp);
+            },
+            Block::Packet(packet) => {
+                let timestamp = |interface: &InterfaceDescriptionBlock| interface.timestamp_to_duration(packet.timestamp);
+                self.check_packet(packet.interface_id as u32, packet.data.len(), timestamp);
+            },
+            Block::SimplePacket(_) => self.check_interface(0, 0),
+            Block::InterfaceStatistics(statistics) => self.check_interface(8, statistics.interface_id),
+            _ => {},
+        }
+    }
+
+    /// Checks that the interface with the given id, found at `offset` in the current block, is defined.
+    fn check_interface(&mut self, offset: usize, interface_id: u32) {
+        if interface_id as usize >= self.interfaces.len() {
+            self.report(offset, Violation::UndefinedInterface(interface_id));
+        }
+    }
+
+    /// Checks the interface, the captured length and the timestamp of a packet.
+    fn check_packet(&mut self, interface_id: u32, captured_len: usize, timestamp: impl Fn(&InterfaceDescriptionBlock) -> Duration) {
+        let (interface, last_timestamp) = match self.interfaces.get_mut(interface_id as usize) {
+            Some(Some(interface)) => interface,
+            Some(None) => return,
+            None => return self.report(8, Violation::UndefinedInterface(interface_id)),
+        };
+
+        let timestamp = timestamp(interface);
+        let non_monotonic = last_timestamp.is_some_and(|last| timestamp < last);
+        *last_timestamp = Some(timestamp);
+
+        // A snaplen of 0 means no limit
+        let snaplen = interface.snaplen;
+        let captured_len = captured_len as u32;
+        if snaplen != 0 && captured_len > snaplen {
+            self.report(20, Violation::CapturedLenExceedsSnaplen { captured_len, snaplen });
+        }
+
+        if non_monotonic {
+            self.report(12, Violation::NonMonotonicTimestamp(interface_id));
+        }
+    }
+}
+
+/// Returns true if the option can appear only once in a block of the given type.
+///
+/// The comments, the custom options and the options which aren't defined by the spec can be repeated.
+fn is_single_instance(type_: u32, code: u16) -> bool {
+    match type_ {
+        SECTION_HEADER_BLOCK => matches!(code, 2..=4),
+        // if_IPv4addr and if_IPv6addr can be repeated
+        INTERFACE_DESCRIPTION_BLOCK => matches!(code, 2 | 3 | 6..=17),
+        // pack_hash can be repeated
+        PACKET_BLOCK => code == 2,
+        NAME_RESOLUTION_BLOCK => matches!(code, 2..=4),
+        INTERFACE_STATISTIC_BLOCK => matches!(code, 2..=8),
+        // epb_hash and epb_verdict can be repeated
+        ENHANCED_PACKET_BLOCK => matches!(code, 2 | 4..=6),
+        _ => false,
+    }
+}
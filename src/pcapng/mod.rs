@@ -1,13 +1,23 @@
-//! Contains the PcapNg parser, reader and writer
+//! Contains the PcapNg parser, reader, writer and linter
 
 pub mod blocks;
 pub use blocks::{Block, PcapNgBlock, RawBlock};
 
+#[cfg(feature = "bytes")]
+pub(crate) mod bytes_reader;
+#[cfg(feature = "bytes")]
+pub use bytes_reader::*;
+
+pub(crate) mod lint;
+pub use lint::*;
+
 pub(crate) mod parser;
 pub use parser::*;
 
 pub(crate) mod reader;
 pub use reader::*;
 
+pub(crate) mod validation;
+
 pub(crate) mod writer;
 pub use writer::*;
@@ -5,12 +5,16 @@ use super::blocks::enhanced_packet::EnhancedPacketBlock;
 use super::blocks::interface_description::InterfaceDescriptionBlock;
 use super::blocks::section_header::SectionHeaderBlock;
 use super::blocks::{INTERFACE_DESCRIPTION_BLOCK, SECTION_HEADER_BLOCK};
+use super::validation::try_into_block_validated;
 use crate::errors::PcapError;
-use crate::Endianness;
+use crate::validation::Validator;
+use crate::{Endianness, ParseLimits, ParseMode, ParseWarning};
 
 
 /// Parses a PcapNg from a slice of bytes.
 ///
+/// The parser is synchronous, [`PcapNgReader`](super::PcapNgReader) is built on top of it to read from an [`AsyncRead`](tokio::io::AsyncRead).
+///
 /// You can match on [`PcapError::IncompleteBuffer`] to know if the parser need more data.
 ///
 /// # Example
@@ -24,11 +28,11 @@ use crate::Endianness;
 /// let pcap = tokio::fs::read("test.pcapng").await.expect("Error reading file");
 /// let mut src = &pcap[..];
 ///
-/// let (rem, mut pcapng_parser) = PcapNgParser::new(src).await.unwrap();
+/// let (rem, mut pcapng_parser) = PcapNgParser::new(src).unwrap();
 /// src = rem;
 ///
 /// loop {
-///     match pcapng_parser.next_block(src).await {
+///     match pcapng_parser.next_block(src) {
 ///         Ok((rem, block)) => {
 ///             // Do something
 ///
@@ -45,71 +49,144 @@ use crate::Endianness;
 /// }
 /// # });
 /// ```
+#[derive(Clone, Debug)]
 pub struct PcapNgParser {
     section: SectionHeaderBlock<'static>,
     interfaces: Vec<InterfaceDescriptionBlock<'static>>,
+    /// Number of bytes of the current section parsed after its header
+    section_read: u64,
+    /// Number of sections parsed
+    sections: usize,
+    limits: ParseLimits,
+    validator: Validator,
 }
 
 impl PcapNgParser {
     /// Creates a new [`PcapNgParser`].
     ///
     /// Parses the first block which must be a valid SectionHeaderBlock.
-    pub async fn new(src: &[u8]) -> Result<(&[u8], Self), PcapError> {
-        // Always use BigEndian here because we can't know the SectionHeaderBlock endianness
-        let (rem, section) = Block::from_slice::<BigEndian>(src).await?;
-        let section = match section {
-            Block::SectionHeader(section) => section.into_owned(),
-            _ => return Err(PcapError::InvalidField("PcapNg: SectionHeader invalid or missing")),
-        };
+    pub fn new(src: &[u8]) -> Result<(&[u8], Self), PcapError> {
+        Self::with_limits(src, ParseLimits::default())
+    }
+
+    /// Creates a new [`PcapNgParser`] with the given [`ParseLimits`].
+    ///
+    /// Parses the first block which must be a valid SectionHeaderBlock.
+    /// A block exceeding the limits returns [`PcapError::LimitExceeded`].
+    pub fn with_limits(src: &[u8], limits: ParseLimits) -> Result<(&[u8], Self), PcapError> {
+        Self::with_limits_and_mode(src, limits, ParseMode::default())
+    }
 
-        let parser = PcapNgParser { section, interfaces: vec![] };
+    /// Creates a new [`PcapNgParser`] with the given [`ParseLimits`] and [`ParseMode`].
+    ///
+    /// Parses the first block which must be a valid SectionHeaderBlock.
+    /// The mode controls the checks of the options of the blocks returned by [`PcapNgParser::next_block`], the raw
+    /// blocks aren't checked, except the section headers and the interfaces which are parsed by the parser.
+    pub fn with_limits_and_mode(src: &[u8], limits: ParseLimits, mode: ParseMode) -> Result<(&[u8], Self), PcapError> {
+        let mut validator = Validator::new(mode);
+        let res = parse_first_section(src, &limits, &mut validator);
+        let (rem, section) = validator.commit(0, res)?;
+
+        let parser = PcapNgParser { section, interfaces: vec![], section_read: 0, sections: 1, limits, validator };
 
         Ok((rem, parser))
     }
 
     /// Returns the remainder and the next [`Block`].
-    pub async fn next_block<'a>(&mut self, src: &'a [u8]) -> Result<(&'a [u8], Block<'a>), PcapError> {
-        // Read next Block
-        match self.section.endianness {
-            Endianness::Big => {
-                let (rem, raw_block) = self.next_raw_block_inner::<BigEndian>(src).await?;
-                let block = raw_block.try_into_block::<BigEndian>().await?;
-                Ok((rem, block))
-            },
-            Endianness::Little => {
-                let (rem, raw_block) = self.next_raw_block_inner::<LittleEndian>(src).await?;
-                let block = raw_block.try_into_block::<LittleEndian>().await?;
-                Ok((rem, block))
+    pub fn next_block<'a>(&mut self, src: &'a [u8]) -> Result<(&'a [u8], Block<'a>), PcapError> {
+        let mark = self.validator.mark();
+        let res = match self.section.endianness {
+            Endianness::Big => self.next_block_inner::<BigEndian>(src),
+            Endianness::Little => self.next_block_inner::<LittleEndian>(src),
+        };
+
+        self.validator.commit(mark, res)
+    }
+
+    /// Inner function to parse the next block.
+    fn next_block_inner<'a, B: ByteOrder + Send>(&mut self, src: &'a [u8]) -> Result<(&'a [u8], Block<'a>), PcapError> {
+        // The section headers and the interfaces are already parsed and checked
+        let (rem, raw_block, block) = self.next_raw_block_inner::<B>(src)?;
+        let block = match block {
+            Some(block) => block,
+            None => {
+                let block = try_into_block_validated::<B>(raw_block, &mut self.validator)?;
+                self.limits.check_block(&block)?;
+                block
             },
-        }
+        };
+
+        Ok((rem, block))
     }
 
     /// Returns the remainder and the next [`RawBlock`].
-    pub async fn next_raw_block<'a>(&mut self, src: &'a [u8]) -> Result<(&'a [u8], RawBlock<'a>), PcapError> {
+    pub fn next_raw_block<'a>(&mut self, src: &'a [u8]) -> Result<(&'a [u8], RawBlock<'a>), PcapError> {
         // Read next Block
-        match self.section.endianness {
-            Endianness::Big => self.next_raw_block_inner::<BigEndian>(src).await,
-            Endianness::Little => self.next_raw_block_inner::<LittleEndian>(src).await,
-        }
+        let mark = self.validator.mark();
+        let res = match self.section.endianness {
+            Endianness::Big => self.next_raw_block_inner::<BigEndian>(src),
+            Endianness::Little => self.next_raw_block_inner::<LittleEndian>(src),
+        };
+
+        self.validator.commit(mark, res).map(|(rem, raw_block, _)| (rem, raw_block))
     }
 
     /// Inner function to parse the next raw block.
-    async fn next_raw_block_inner<'a, B: ByteOrder + Send>(&mut self, src: &'a [u8]) -> Result<(&'a [u8], RawBlock<'a>), PcapError> {
-        let (rem, raw_block) = RawBlock::from_slice::<B>(src).await?;
+    ///
+    /// Also returns the block if it is a section header or an interface, which are parsed to update the parser.
+    #[allow(clippy::type_complexity)]
+    fn next_raw_block_inner<'a, B: ByteOrder + Send>(
+        &mut self,
+        src: &'a [u8],
+    ) -> Result<(&'a [u8], RawBlock<'a>, Option<Block<'static>>), PcapError> {
+        check_block_len::<B>(src, &self.limits)?;
+        let (rem, raw_block) = RawBlock::from_slice::<B>(src)?;
 
-        match raw_block.type_ {
+        // The limits are checked before the state of the parser is updated
+        let block = match raw_block.type_ {
             SECTION_HEADER_BLOCK => {
-                self.section = raw_block.clone().try_into_block::<B>().await?.into_owned().into_section_header().unwrap();
+                let block = try_into_block_validated::<B>(raw_block.clone(), &mut self.validator)?.into_owned();
+                self.limits.check_sections(self.sections + 1)?;
+                self.limits.check_block(&block)?;
+
+                self.section = block.clone().into_section_header().unwrap();
                 self.interfaces.clear();
+                self.section_read = 0;
+                self.sections += 1;
+                Some(block)
             },
             INTERFACE_DESCRIPTION_BLOCK => {
-                let interface = raw_block.clone().try_into_block::<B>().await?.into_owned().into_interface_description().unwrap();
-                self.interfaces.push(interface);
+                let block = try_into_block_validated::<B>(raw_block.clone(), &mut self.validator)?.into_owned();
+                self.limits.check_interfaces(self.interfaces.len() + 1)?;
+                self.limits.check_block(&block)?;
+
+                self.interfaces.push(block.clone().into_interface_description().unwrap());
+                self.section_read += (src.len() - rem.len()) as u64;
+                Some(block)
             },
-            _ => {},
-        }
+            _ => {
+                self.section_read += (src.len() - rem.len()) as u64;
+                None
+            },
+        };
+
+        Ok((rem, raw_block, block))
+    }
 
-        Ok((rem, raw_block))
+    /// Returns the length of the next block without parsing it, once checked against the limits.
+    #[cfg(feature = "bytes")]
+    pub(crate) fn next_block_len(&self, src: &[u8]) -> Result<usize, PcapError> {
+        return match self.section.endianness {
+            Endianness::Big => inner_next_block_len::<BigEndian>(src, &self.limits),
+            Endianness::Little => inner_next_block_len::<LittleEndian>(src, &self.limits),
+        };
+
+        fn inner_next_block_len<B: ByteOrder>(src: &[u8], limits: &ParseLimits) -> Result<usize, PcapError> {
+            check_block_len::<B>(src, limits)?;
+            let (rem, _) = RawBlock::from_slice::<B>(src)?;
+
+            Ok(src.len() - rem.len())
+        }
     }
 
     /// Returns the current [`SectionHeaderBlock`].
@@ -122,8 +199,65 @@ impl PcapNgParser {
         &self.interfaces[..]
     }
 
+    /// Returns the number of bytes of the current section parsed after its [`SectionHeaderBlock`].
+    pub(crate) fn section_read(&self) -> u64 {
+        self.section_read
+    }
+
     /// Returns the [`InterfaceDescriptionBlock`] corresponding to the given packet.
     pub fn packet_interface(&self, packet: &EnhancedPacketBlock) -> Option<&InterfaceDescriptionBlock> {
         self.interfaces.get(packet.interface_id as usize)
     }
+
+    /// Returns the warnings collected, see [`ParseMode`].
+    pub fn warnings(&self) -> &[ParseWarning] {
+        self.validator.warnings()
+    }
+
+    /// Returns and clears the warnings collected, see [`ParseMode`].
+    pub fn take_warnings(&mut self) -> Vec<ParseWarning> {
+        self.validator.take_warnings()
+    }
+}
+
+
+/// Parses the first Section Header Block.
+fn parse_first_section<'a>(
+    src: &'a [u8],
+    limits: &ParseLimits,
+    validator: &mut Validator,
+) -> Result<(&'a [u8], SectionHeaderBlock<'static>), PcapError> {
+    // Always use BigEndian here because we can't know the SectionHeaderBlock endianness
+    check_block_len::<BigEndian>(src, limits)?;
+    let (rem, raw_block) = RawBlock::from_slice::<BigEndian>(src)?;
+    if raw_block.type_ != SECTION_HEADER_BLOCK {
+        return Err(PcapError::InvalidField("PcapNg: SectionHeader invalid or missing"));
+    }
+
+    let block = try_into_block_validated::<BigEndian>(raw_block, validator)?;
+    limits.check_sections(1)?;
+    limits.check_block(&block)?;
+
+    Ok((rem, block.into_owned().into_section_header().unwrap()))
+}
+
+/// Checks the length of the next block against the limits, before it is buffered.
+fn check_block_len<B: ByteOrder>(src: &[u8], limits: &ParseLimits) -> Result<(), PcapError> {
+    if src.len() < 12 {
+        return Ok(());
+    }
+
+    // The endianness of a section header is given by its magic number
+    let len = if B::read_u32(&src[..4]) == SECTION_HEADER_BLOCK {
+        match BigEndian::read_u32(&src[8..12]) {
+            0x1A2B3C4D => BigEndian::read_u32(&src[4..8]),
+            0x4D3C2B1A => LittleEndian::read_u32(&src[4..8]),
+            _ => return Ok(()),
+        }
+    }
+    else {
+        B::read_u32(&src[4..8])
+    };
+
+    limits.check_block_len(len as usize)
 }
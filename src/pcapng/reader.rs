@@ -4,9 +4,11 @@ use super::blocks::block_common::{Block, RawBlock};
 use super::blocks::enhanced_packet::EnhancedPacketBlock;
 use super::blocks::interface_description::InterfaceDescriptionBlock;
 use super::blocks::section_header::SectionHeaderBlock;
+use super::blocks::SECTION_HEADER_BLOCK;
 use super::PcapNgParser;
 use crate::errors::PcapError;
 use crate::read_buffer::ReadBuffer;
+use crate::{ParseWarning, ReaderConfig};
 
 /// Reads a PcapNg from a reader.
 ///
@@ -39,8 +41,18 @@ impl<R: AsyncRead + Unpin> PcapNgReader<R> {
     ///
     /// Parses the first block which must be a valid SectionHeaderBlock.
     pub async fn new(reader: R) -> Result<PcapNgReader<R>, PcapError> {
-        let mut reader = ReadBuffer::new(reader);
-        let parser = reader.parse_with(PcapNgParser::new).await?;
+        Self::with_config(reader, ReaderConfig::default()).await
+    }
+
+    /// Creates a new [`PcapNgReader`] from a reader, with the given [`ReaderConfig`].
+    ///
+    /// A block larger than [`ReaderConfig::with_max_record_size`] returns [`PcapError::RecordTooLarge`].
+    /// A block exceeding the [`ParseLimits`](crate::ParseLimits) of the config returns [`PcapError::LimitExceeded`].
+    /// A block violating a check enforced by the [`ParseMode`](crate::ParseMode) of the config returns an error, the
+    /// violations of the other checks are collected as warnings, see [`Self::warnings`].
+    pub async fn with_config(reader: R, config: ReaderConfig) -> Result<PcapNgReader<R>, PcapError> {
+        let mut reader = ReadBuffer::with_config(reader, config);
+        let parser = reader.parse_with(|src| PcapNgParser::with_limits_and_mode(src, config.limits, config.mode)).await?;
         Ok(Self { parser, reader })
     }
 
@@ -49,11 +61,7 @@ impl<R: AsyncRead + Unpin> PcapNgReader<R> {
         match self.reader.has_data_left().await {
             Ok(has_data) => {
                 if has_data {
-                    Some(
-                        self.reader
-                            .parse_with_context(&mut self.parser, |parser, src| async { (parser.next_block(src).await, parser) })
-                            .await,
-                    )
+                    Some(self.reader.parse_with(|src| self.parser.next_block(src)).await)
                 } else {
                     None
                 }
@@ -67,7 +75,7 @@ impl<R: AsyncRead + Unpin> PcapNgReader<R> {
         match self.reader.has_data_left().await {
             Ok(has_data) => {
                 if has_data {
-                    Some(self.reader.parse_with_context(&mut self.parser, |parser, src| async { (parser.next_raw_block(src).await, parser) }).await)
+                    Some(self.reader.parse_with(|src| self.parser.next_raw_block(src)).await)
                 } else {
                     None
                 }
@@ -76,6 +84,46 @@ impl<R: AsyncRead + Unpin> PcapNgReader<R> {
         }
     }
 
+    /// Skips the rest of the current section and parses the [`SectionHeaderBlock`] of the next one.
+    ///
+    /// When the `section_length` of the current section is known, the bytes of the section are skipped without parsing
+    /// its blocks. Otherwise, the blocks are read as [`RawBlock`] until the next section.
+    ///
+    /// Returns `false` if there is no next section.
+    pub async fn skip_section(&mut self) -> Result<bool, PcapError> {
+        let section_length = self.parser.section().section_length;
+
+        if section_length >= 0 {
+            let section_length = section_length as u64;
+            let read = self.parser.section_read();
+            if read > section_length {
+                return Err(PcapError::InvalidField("PcapNg: section length smaller than the blocks of the section"));
+            }
+
+            self.reader.skip(section_length - read).await.map_err(PcapError::IoError)?;
+
+            return match self.next_raw_block().await {
+                None => Ok(false),
+                Some(Ok(block)) if block.type_ == SECTION_HEADER_BLOCK => Ok(true),
+                Some(Ok(_)) => Err(PcapError::InvalidField("PcapNg: section length doesn't point to a SectionHeaderBlock")),
+                Some(Err(e)) => Err(e),
+            };
+        }
+
+        while let Some(block) = self.next_raw_block().await {
+            if block?.type_ == SECTION_HEADER_BLOCK {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Returns the number of bytes of the pcapng consumed by the reader.
+    pub(crate) fn position(&self) -> u64 {
+        self.reader.consumed()
+    }
+
     /// Returns the current [`SectionHeaderBlock`].
     pub fn section(&self) -> &SectionHeaderBlock<'static> {
         self.parser.section()
@@ -86,6 +134,18 @@ impl<R: AsyncRead + Unpin> PcapNgReader<R> {
         self.parser.interfaces()
     }
 
+    /// Returns the warnings collected by the parser, see [`ParseMode`](crate::ParseMode).
+    pub fn warnings(&self) -> &[ParseWarning] {
+        self.parser.warnings()
+    }
+
+    /// Returns and clears the warnings collected by the parser, see [`ParseMode`](crate::ParseMode).
+    ///
+    /// The warnings are kept until they are taken, this should be called regularly on long captures read leniently.
+    pub fn take_warnings(&mut self) -> Vec<ParseWarning> {
+        self.parser.take_warnings()
+    }
+
     /// Returns the [`InterfaceDescriptionBlock`] corresponding to the given packet
     pub fn packet_interface(&self, packet: &EnhancedPacketBlock) -> Option<&InterfaceDescriptionBlock> {
         self.interfaces().get(packet.interface_id as usize)
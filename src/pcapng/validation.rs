@@ -0,0 +1,214 @@
+use std::borrow::Cow;
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+
+use super::blocks::enhanced_packet::EnhancedPacketOption;
+use super::blocks::interface_description::InterfaceDescriptionOption;
+use super::blocks::interface_statistics::InterfaceStatisticsOption;
+use super::blocks::name_resolution::NameResolutionOption;
+use super::blocks::opt_common::PcapNgOption;
+use super::blocks::packet::PacketOption;
+use super::blocks::section_header::SectionHeaderOption;
+use super::blocks::*;
+use crate::errors::PcapError;
+use crate::validation::Validator;
+use crate::Check;
+
+
+/// Tries to create a [`Block`] from a [`RawBlock`], with the checks of the given [`Validator`].
+///
+/// The options violating a check which isn't enforced are repaired, in which case the block is owned.
+pub(crate) fn try_into_block_validated<'a, B: ByteOrder + Send>(
+    raw_block: RawBlock<'a>,
+    validator: &mut Validator,
+) -> Result<Block<'a>, PcapError> {
+    // The options of a block accepted by its parser are parsed once, the bytes after their end are the only violation
+    // the parsers don't reject
+    if let Ok((rem, block)) = Block::try_from_raw_block_with_rem::<B>(raw_block.clone()) {
+        if !rem.is_empty() {
+            let reason = "Option: bytes after the end of options";
+            validator.violation(Check::TrailingBytes, reason, PcapError::InvalidField(reason))?;
+        }
+
+        return Ok(block);
+    }
+
+    // Otherwise each option is checked, to report the violation and repair it if its check isn't enforced
+    match validate_options::<B>(&raw_block, validator)? {
+        None => raw_block.try_into_block::<B>(),
+        Some(body) => {
+            let raw_block = RawBlock { body: Cow::Borrowed(&body[..]), ..raw_block };
+            Ok(raw_block.try_into_block::<B>()?.into_owned())
+        },
+    }
+}
+
+/// Validates the options of a raw block, returns the repaired body if some options were repaired.
+fn validate_options<B: ByteOrder + Send>(raw_block: &RawBlock, validator: &mut Validator) -> Result<Option<Vec<u8>>, PcapError> {
+    let body = &raw_block.body[..];
+
+    // The errors of the fixed fields are left to the block parsers
+    let start = match options_start::<B>(raw_block.type_, body) {
+        Some(start) => start,
+        None => return Ok(None),
+    };
+
+    // The section header has its own endianness, given by its magic number
+    match (raw_block.type_, BigEndian::read_u32(&body[..4])) {
+        (SECTION_HEADER_BLOCK, 0x1A2B3C4D) => validate::<BigEndian>(raw_block.type_, body, start, validator),
+        (SECTION_HEADER_BLOCK, 0x4D3C2B1A) => validate::<LittleEndian>(raw_block.type_, body, start, validator),
+        (SECTION_HEADER_BLOCK, _) => Ok(None),
+        _ => validate::<B>(raw_block.type_, body, start, validator),
+    }
+}
+
+/// Returns the offset of the options in the body of a block, `None` if its type has no options or if it is too short.
+pub(crate) fn options_start<B: ByteOrder>(type_: u32, body: &[u8]) -> Option<usize> {
+    let start = match type_ {
+        SECTION_HEADER_BLOCK => 16,
+        INTERFACE_DESCRIPTION_BLOCK => 8,
+        PACKET_BLOCK | ENHANCED_PACKET_BLOCK if body.len() >= 20 => {
+            let captured_len = B::read_u32(&body[12..16]) as usize;
+            20 + captured_len + (4 - captured_len % 4) % 4
+        },
+        NAME_RESOLUTION_BLOCK => records_len::<B>(body)?,
+        INTERFACE_STATISTIC_BLOCK => 12,
+        _ => return None,
+    };
+
+    (body.len() >= start).then_some(start)
+}
+
+/// Parses an option of a block of the given type, to check its length and its content.
+pub(crate) fn check_option<B: ByteOrder + Send>(type_: u32, code: u16, length: u16, value: &[u8]) -> Result<(), PcapError> {
+    match type_ {
+        SECTION_HEADER_BLOCK => SectionHeaderOption::from_slice::<B>(code, length, value).map(drop),
+        INTERFACE_DESCRIPTION_BLOCK => InterfaceDescriptionOption::from_slice::<B>(code, length, value).map(drop),
+        PACKET_BLOCK => PacketOption::from_slice::<B>(code, length, value).map(drop),
+        NAME_RESOLUTION_BLOCK => NameResolutionOption::from_slice::<B>(code, length, value).map(drop),
+        INTERFACE_STATISTIC_BLOCK => InterfaceStatisticsOption::from_slice::<B>(code, length, value).map(drop),
+        ENHANCED_PACKET_BLOCK => EnhancedPacketOption::from_slice::<B>(code, length, value).map(drop),
+        _ => Ok(()),
+    }
+}
+
+/// Returns the length of the records of a Name Resolution Block, end of records included.
+fn records_len<B: ByteOrder>(body: &[u8]) -> Option<usize> {
+    let mut start = 0;
+    loop {
+        let record = body.get(start..start + 4)?;
+        let type_ = B::read_u16(&record[..2]);
+        let length = B::read_u16(&record[2..]) as usize;
+
+        start += 4 + length + (4 - length % 4) % 4;
+        if type_ == 0 {
+            return (start <= body.len()).then_some(start);
+        }
+    }
+}
+
+/// Validates the options starting at `start` in `body`, returns the repaired body if some options were repaired.
+fn validate<B: ByteOrder + Send>(
+    type_: u32,
+    body: &[u8],
+    start: usize,
+    validator: &mut Validator,
+) -> Result<Option<Vec<u8>>, PcapError> {
+    let mut slice = &body[start..];
+
+    // If there is nothing left in the slice, it means that there is no option
+    if slice.is_empty() {
+        return Ok(None);
+    }
+
+    let mut options = Vec::with_capacity(slice.len());
+    let mut repaired = false;
+
+    loop {
+        if slice.is_empty() {
+            let reason = "Option: missing end of options";
+            validator.violation(Check::OptionLength, reason, PcapError::InvalidField(reason))?;
+            repaired = true;
+            break;
+        }
+
+        if slice.len() < 4 {
+            let reason = "Option: slice.len() < 4";
+            validator.violation(Check::OptionLength, reason, PcapError::InvalidField(reason))?;
+            repaired = true;
+            break;
+        }
+
+        let code = B::read_u16(&slice[..2]);
+        let length = B::read_u16(&slice[2..4]) as usize;
+        let pad_len = (4 - (length % 4)) % 4;
+
+        if code == 0 {
+            if slice.len() > 4 {
+                let reason = "Option: bytes after the end of options";
+                validator.violation(Check::TrailingBytes, reason, PcapError::InvalidField(reason))?;
+                repaired = true;
+            }
+            break;
+        }
+
+        if slice.len() < 4 + length + pad_len {
+            let reason = "Option: length + pad.len() > slice.len()";
+            validator.violation(Check::OptionLength, reason, PcapError::InvalidField(reason))?;
+            repaired = true;
+            break;
+        }
+
+        let value = &slice[4..4 + length];
+        match check_option::<B>(type_, code, length as u16, value) {
+            Ok(()) => options.extend_from_slice(&slice[..4 + length + pad_len]),
+
+            // The string is replaced by its lossy conversion, the PEN of a custom option is kept
+            Err(PcapError::Utf8Error(e)) => {
+                validator.violation(Check::Utf8, "Option: invalid UTF-8 string", PcapError::Utf8Error(e))?;
+                repaired = true;
+
+                let pen_len = if matches!(code, 2988 | 19372) { 4 } else { 0 };
+                let mut lossy = value[..pen_len].to_vec();
+                lossy.extend_from_slice(String::from_utf8_lossy(&value[pen_len..]).as_bytes());
+                if let Ok(length) = u16::try_from(lossy.len()) {
+                    write_option::<B>(&mut options, code, length, &lossy);
+                }
+            },
+
+            Err(e) => {
+                let reason = match e {
+                    PcapError::InvalidField(reason) => reason,
+                    _ => "Option: invalid length",
+                };
+                validator.violation(Check::OptionLength, reason, e)?;
+                repaired = true;
+            },
+        }
+
+        slice = &slice[4 + length + pad_len..];
+    }
+
+    if !repaired {
+        return Ok(None);
+    }
+
+    let mut repaired_body = body[..start].to_vec();
+    if !options.is_empty() {
+        repaired_body.extend_from_slice(&options);
+        repaired_body.extend_from_slice(&[0, 0, 0, 0]);
+    }
+
+    Ok(Some(repaired_body))
+}
+
+/// Writes an option with its padding.
+fn write_option<B: ByteOrder>(dst: &mut Vec<u8>, code: u16, length: u16, value: &[u8]) {
+    let mut header = [0_u8; 4];
+    B::write_u16(&mut header[..2], code);
+    B::write_u16(&mut header[2..], length);
+
+    dst.extend_from_slice(&header);
+    dst.extend_from_slice(value);
+    dst.extend_from_slice(&[0_u8; 3][..(4 - value.len() % 4) % 4]);
+}
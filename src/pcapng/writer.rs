@@ -1,13 +1,24 @@
-use tokio::io::AsyncWrite;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::{ErrorKind, SeekFrom};
+use std::pin::Pin;
+use std::time::{Duration, SystemTime};
 
-use byteorder::{BigEndian, LittleEndian, ByteOrder};
+use tokio::io::{AsyncRead, AsyncSeek, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
+
+use byteorder::{BigEndian, LittleEndian};
 
 use super::blocks::block_common::{Block, PcapNgBlock};
-use super::blocks::interface_description::InterfaceDescriptionBlock;
+use super::blocks::enhanced_packet::{EnhancedPacketBlock, EnhancedPacketOption};
+use super::blocks::interface_description::{InterfaceDescriptionBlock, InterfaceDescriptionOption};
+use super::blocks::interface_statistics::{InterfaceStatisticsBlock, InterfaceStatisticsOption};
 use super::blocks::section_header::SectionHeaderBlock;
-use super::blocks::SECTION_HEADER_BLOCK;
-use super::RawBlock;
-use crate::{Endianness, PcapError, PcapResult};
+use super::blocks::{ENHANCED_PACKET_BLOCK, PACKET_BLOCK, SECTION_HEADER_BLOCK, SIMPLE_PACKET_BLOCK};
+use super::{PcapNgReader, RawBlock};
+use crate::common::FinishGuard;
+use crate::pcap::PcapPacket;
+use crate::{DataLink, Endianness, PcapError, PcapResult, Truncate, WriteSummary};
 
 
 /// Writes a PcapNg to a writer.
@@ -33,14 +44,150 @@ use crate::{Endianness, PcapError, PcapResult};
 ///     // Write back parsed Block
 ///     pcapng_writer.write_block(&block).await.unwrap();
 /// }
+///
+/// // Flush and shut down the writer
+/// let (out, summary) = pcapng_writer.finish().await.unwrap();
+/// println!("{} blocks written", summary.blocks);
 /// # });
 /// ```
+///
+/// In debug builds, a warning is printed if the writer is dropped without calling [`PcapNgWriter::finish`],
+/// [`PcapNgWriter::close`] or [`PcapNgWriter::into_inner`].
 pub struct PcapNgWriter<W: AsyncWrite> {
     section: SectionHeaderBlock<'static>,
     interfaces: Vec<InterfaceDescriptionBlock<'static>>,
+    /// Counters of the interfaces of the current section, if the tracking is enabled
+    counters: Option<Vec<InterfaceCounters>>,
+    /// Interface ids of the [`InterfaceKey`]s of the current section, by name
+    interface_keys: HashMap<String, Vec<(DataLink, u8, u32)>>,
+    /// Length of the existing pcapng the writer appends to, 0 otherwise
+    appended: u64,
+    /// Offsets of the current Section Header Block and of the end of this block
+    section_start: u64,
+    section_body_start: u64,
+    /// Back-patcher of the section lengths, if the writer is seekable and the tracking is enabled
+    patcher: Option<SectionPatcher<W>>,
+    summary: WriteSummary,
+    guard: FinishGuard,
     writer: W,
 }
 
+/// Writes a section length at the given offset of a seekable writer, then seeks back to the end of the writer.
+type PatchFn<W> = for<'a> fn(&'a mut W, u64, i64, Endianness) -> Pin<Box<dyn Future<Output = std::io::Result<()>> + Send + 'a>>;
+
+struct SectionPatcher<W> {
+    patch: PatchFn<W>,
+    /// Position of the writer at the start of the pcapng
+    base: u64,
+}
+
+fn patch_section_length<W: AsyncWrite + AsyncSeek + Unpin + Send>(
+    writer: &mut W,
+    offset: u64,
+    length: i64,
+    endianness: Endianness,
+) -> Pin<Box<dyn Future<Output = std::io::Result<()>> + Send + '_>> {
+    Box::pin(async move {
+        let end = writer.stream_position().await?;
+        writer.seek(SeekFrom::Start(offset)).await?;
+        match endianness {
+            Endianness::Big => writer.write_i64(length).await?,
+            Endianness::Little => writer.write_i64_le(length).await?,
+        }
+        writer.seek(SeekFrom::Start(end)).await?;
+
+        Ok(())
+    })
+}
+
+/// Returns a [`RawBlock`] borrowing the body of `block`, which can be parsed even if `block` is owned.
+fn borrow_raw_block<'a>(block: &'a RawBlock<'_>) -> RawBlock<'a> {
+    RawBlock { type_: block.type_, initial_len: block.initial_len, body: Cow::Borrowed(&block.body[..]), trailer_len: block.trailer_len }
+}
+
+/// Identifies an interface for [`PcapNgWriter::write_packet`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InterfaceKey {
+    /// Name of the interface, written in the `if_name` option
+    pub name: String,
+    /// Link type of the interface
+    pub linktype: DataLink,
+    /// Timestamp resolution of the interface, written in the `if_tsresol` option
+    pub ts_resolution: u8,
+}
+
+impl InterfaceKey {
+    /// Creates a new [`InterfaceKey`] with a microsecond timestamp resolution.
+    pub fn new(name: impl Into<String>, linktype: DataLink) -> Self {
+        InterfaceKey { name: name.into(), linktype, ts_resolution: 6 }
+    }
+
+    /// Sets the timestamp resolution, with the encoding of the `if_tsresol` option.
+    pub fn with_ts_resolution(mut self, ts_resolution: u8) -> Self {
+        self.ts_resolution = ts_resolution;
+        self
+    }
+
+    /// Creates the [`InterfaceDescriptionBlock`] of this interface.
+    pub fn interface_description(&self) -> InterfaceDescriptionBlock<'static> {
+        let mut interface = InterfaceDescriptionBlock::new(self.linktype, 0);
+        interface.options.push(InterfaceDescriptionOption::IfName(Cow::Owned(self.name.clone())));
+        interface.options.push(InterfaceDescriptionOption::IfTsResol(self.ts_resolution));
+        interface
+    }
+
+    /// Returns true if the interface has the name, link type and timestamp resolution of this key.
+    fn matches(&self, interface: &InterfaceDescriptionBlock) -> bool {
+        let has_name = interface.options.iter().any(|opt| matches!(opt, InterfaceDescriptionOption::IfName(name) if *name == self.name));
+        has_name && interface.linktype == self.linktype && interface.ts_resolution() == self.ts_resolution
+    }
+}
+
+/// Statistics of an interface, tracked by a [`PcapNgWriter`] to write its Interface Statistics Blocks.
+///
+/// The timestamps are in units of the interface, like in the Enhanced Packet Blocks.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct InterfaceCounters {
+    /// Number of packets written
+    pub packets: u64,
+    /// Sum of the drop counts of the packets written
+    pub drops: u64,
+    /// Earliest packet timestamp
+    pub start: Option<u64>,
+    /// Latest packet timestamp
+    pub end: Option<u64>,
+}
+
+impl InterfaceCounters {
+    fn add_packet(&mut self, timestamp: Option<u64>, drops: u64) {
+        self.packets += 1;
+        self.drops += drops;
+
+        if let Some(timestamp) = timestamp {
+            self.start = Some(self.start.map_or(timestamp, |start| start.min(timestamp)));
+            self.end = Some(self.end.map_or(timestamp, |end| end.max(timestamp)));
+        }
+    }
+
+    /// Creates the Interface Statistics Block of these counters.
+    ///
+    /// The packets received by the interface are the packets written plus the packets dropped.
+    pub fn to_statistics_block(&self, interface_id: u32, timestamp: u64) -> InterfaceStatisticsBlock<'static> {
+        let mut options = vec![];
+        if let Some(start) = self.start {
+            options.push(InterfaceStatisticsOption::IsbStartTime(start));
+        }
+        if let Some(end) = self.end {
+            options.push(InterfaceStatisticsOption::IsbEndTime(end));
+        }
+        options.push(InterfaceStatisticsOption::IsbIfRecv(self.packets + self.drops));
+        options.push(InterfaceStatisticsOption::IsbIfDrop(self.drops));
+        options.push(InterfaceStatisticsOption::IsbUsrDeliv(self.packets));
+
+        InterfaceStatisticsBlock { interface_id, timestamp, options }
+    }
+}
+
 impl<W: AsyncWrite + Unpin + Send> PcapNgWriter<W> {
     /// Creates a new [`PcapNgWriter`] from an existing writer.
     ///
@@ -73,12 +220,26 @@ impl<W: AsyncWrite + Unpin + Send> PcapNgWriter<W> {
 
     /// Creates a new [`PcapNgWriter`] from an existing writer with the given section header.
     pub async fn with_section_header(mut writer: W, section: SectionHeaderBlock<'static>) -> PcapResult<Self> {
-        match section.endianness {
+        let bytes = match section.endianness {
             Endianness::Big => section.clone().into_block().write_to::<BigEndian, _>(&mut writer).await.map_err(PcapError::IoError)?,
             Endianness::Little => section.clone().into_block().write_to::<LittleEndian, _>(&mut writer).await.map_err(PcapError::IoError)?,
         };
 
-        Ok(Self { section, interfaces: vec![], writer })
+        let summary = WriteSummary { bytes: bytes as u64, packets: 0, blocks: 1 };
+
+        Ok(Self {
+            section,
+            interfaces: vec![],
+            counters: None,
+            interface_keys: HashMap::new(),
+            appended: 0,
+            section_start: 0,
+            section_body_start: summary.bytes,
+            patcher: None,
+            summary,
+            guard: FinishGuard::new("PcapNgWriter"),
+            writer,
+        })
     }
 
     /// Writes a [`Block`].
@@ -120,12 +281,12 @@ impl<W: AsyncWrite + Unpin + Send> PcapNgWriter<W> {
     /// ```
     pub async fn write_block(&mut self, block: &Block<'_>) -> PcapResult<usize> {
         match block {
-            Block::SectionHeader(a) => {
-                self.section = a.clone().into_owned();
-                self.interfaces.clear();
-            },
+            Block::SectionHeader(a) => return self.start_section(a.clone().into_owned()).await,
             Block::InterfaceDescription(a) => {
                 self.interfaces.push(a.clone().into_owned());
+                if let Some(counters) = &mut self.counters {
+                    counters.push(InterfaceCounters::default());
+                }
             },
             Block::InterfaceStatistics(a) => {
                 if a.interface_id as usize >= self.interfaces.len() {
@@ -136,15 +297,67 @@ impl<W: AsyncWrite + Unpin + Send> PcapNgWriter<W> {
                 if a.interface_id as usize >= self.interfaces.len() {
                     return Err(PcapError::InvalidInterfaceId(a.interface_id));
                 }
+
+                if let Some(counters) = &mut self.counters {
+                    let drops = a
+                        .options
+                        .iter()
+                        .find_map(|opt| match opt {
+                            EnhancedPacketOption::DropCount(drops) => Some(*drops),
+                            _ => None,
+                        })
+                        .unwrap_or(0);
+
+                    counters[a.interface_id as usize].add_packet(Some(a.timestamp.as_nanos() as u64), drops);
+                }
+            },
+            Block::Packet(a) => {
+                if let Some(counters) = self.counters.as_mut().and_then(|counters| counters.get_mut(a.interface_id as usize)) {
+                    counters.add_packet(Some(a.timestamp), a.drop_count as u64);
+                }
+            },
+            Block::SimplePacket(_) => {
+                if let Some(counters) = self.counters.as_mut().and_then(|counters| counters.first_mut()) {
+                    counters.add_packet(None, 0);
+                }
             },
 
             _ => (),
         }
 
-        match self.section.endianness {
-            Endianness::Big => block.write_to::<BigEndian, _>(&mut self.writer).await.map_err(PcapError::IoError),
-            Endianness::Little => block.write_to::<LittleEndian, _>(&mut self.writer).await.map_err(PcapError::IoError),
-        }
+        let bytes = match self.section.endianness {
+            Endianness::Big => block.write_to::<BigEndian, _>(&mut self.writer).await.map_err(PcapError::IoError)?,
+            Endianness::Little => block.write_to::<LittleEndian, _>(&mut self.writer).await.map_err(PcapError::IoError)?,
+        };
+
+        let is_packet = matches!(block, Block::EnhancedPacket(_) | Block::Packet(_) | Block::SimplePacket(_));
+        self.add_block(bytes, is_packet);
+
+        Ok(bytes)
+    }
+
+    /// Starts a new section, writing its [`SectionHeaderBlock`].
+    ///
+    /// The Interface Statistics Blocks of the current section are written first if the tracking is enabled, and its
+    /// length is back-patched if enabled. The interfaces are reset and the following blocks are written with the
+    /// endianness of the new section.
+    ///
+    /// Writing a [`Block::SectionHeader`] with [`PcapNgWriter::write_block`] is the same as calling this method.
+    pub async fn start_section(&mut self, section: SectionHeaderBlock<'static>) -> PcapResult<usize> {
+        self.end_section().await?;
+        self.reset_section(section);
+
+        let start = self.position();
+        let block = self.section.clone().into_block();
+        let bytes = match self.section.endianness {
+            Endianness::Big => block.write_to::<BigEndian, _>(&mut self.writer).await.map_err(PcapError::IoError)?,
+            Endianness::Little => block.write_to::<LittleEndian, _>(&mut self.writer).await.map_err(PcapError::IoError)?,
+        };
+
+        self.add_block(bytes, false);
+        self.mark_section_start(start);
+
+        Ok(bytes)
     }
 
     /// Writes a [`PcapNgBlock`].
@@ -188,26 +401,273 @@ impl<W: AsyncWrite + Unpin + Send> PcapNgWriter<W> {
         self.write_block(&block.into_block()).await
     }
 
+    /// Writes a packet on the interface identified by `key`, as an [`EnhancedPacketBlock`].
+    ///
+    /// The first time a key is seen in a section, the interface is looked up in the [`InterfaceDescriptionBlock`]s
+    /// already written, by name, link type and timestamp resolution. If there is none, its
+    /// [`InterfaceDescriptionBlock`] is written before the packet.
+    /// The timestamp of the packet is converted into the resolution of the interface.
+    ///
+    /// Returns the number of bytes written, the [`InterfaceDescriptionBlock`] included.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # tokio_test::block_on(async {
+    /// use std::time::Duration;
+    ///
+    /// use pcap_file_tokio::pcap::PcapPacket;
+    /// use pcap_file_tokio::pcapng::{InterfaceKey, PcapNgWriter};
+    /// use pcap_file_tokio::DataLink;
+    ///
+    /// let mut pcapng_writer = PcapNgWriter::new(Vec::new()).await.unwrap();
+    ///
+    /// let eth0 = InterfaceKey::new("eth0", DataLink::ETHERNET).with_ts_resolution(9);
+    /// let packet = PcapPacket::new(Duration::from_secs(1), 4, &[0, 1, 2, 3]);
+    /// pcapng_writer.write_packet(&eth0, &packet).await.unwrap();
+    /// # });
+    /// ```
+    ///
+    /// # Errors
+    /// The timestamp can't be represented by the interface, or the writer can't be written to.
+    pub async fn write_packet(&mut self, key: &InterfaceKey, packet: &PcapPacket<'_>) -> PcapResult<usize> {
+        let mut len = 0;
+
+        let interface_id = match self.interface_id(key) {
+            Some(interface_id) => interface_id,
+            None => {
+                len += self.write_block(&key.interface_description().into_block()).await?;
+
+                let interface_id = self.interfaces.len() as u32 - 1;
+                self.interface_keys.entry(key.name.clone()).or_default().push((key.linktype, key.ts_resolution, interface_id));
+                interface_id
+            },
+        };
+
+        let mut block = EnhancedPacketBlock {
+            interface_id,
+            timestamp: Duration::ZERO,
+            original_len: packet.orig_len,
+            data: Cow::Borrowed(&packet.data[..]),
+            options: vec![],
+        };
+        block.set_absolute_timestamp(&self.interfaces[interface_id as usize], packet.timestamp)?;
+
+        len += self.write_block(&block.into_block()).await?;
+
+        Ok(len)
+    }
+
+    /// Returns the interface id of `key` in the current section, if any.
+    fn interface_id(&mut self, key: &InterfaceKey) -> Option<u32> {
+        let known = self
+            .interface_keys
+            .get(&key.name)
+            .and_then(|ids| ids.iter().find(|(linktype, ts_resolution, _)| *linktype == key.linktype && *ts_resolution == key.ts_resolution));
+        if let Some((_, _, interface_id)) = known {
+            return Some(*interface_id);
+        }
+
+        let interface_id = self.interfaces.iter().position(|interface| key.matches(interface))? as u32;
+        self.interface_keys.entry(key.name.clone()).or_default().push((key.linktype, key.ts_resolution, interface_id));
+
+        Some(interface_id)
+    }
+
     /// Writes a [`RawBlock`].
     ///
+    /// The body of the block must be encoded with the endianness of the current section, except for a Section Header
+    /// Block which starts a new section like [`PcapNgWriter::start_section`].
+    ///
     /// Doesn't check the validity of the written blocks.
     pub async fn write_raw_block(&mut self, block: &RawBlock<'_>) -> PcapResult<usize> {
-        return match self.section.endianness {
-            Endianness::Big => inner::<BigEndian, _>(&mut self.section, block, &mut self.writer).await,
-            Endianness::Little => inner::<LittleEndian, _>(&mut self.section, block, &mut self.writer).await,
+        if block.type_ == SECTION_HEADER_BLOCK {
+            // The section header finds its endianness from its magic number
+            let section = borrow_raw_block(block).try_into_block::<BigEndian>()?.into_owned().into_section_header().unwrap();
+
+            self.end_section().await?;
+            self.reset_section(section);
+        }
+
+        let bytes = match self.section.endianness {
+            Endianness::Big => block.write_to::<BigEndian, _>(&mut self.writer).await.map_err(PcapError::IoError)?,
+            Endianness::Little => block.write_to::<LittleEndian, _>(&mut self.writer).await.map_err(PcapError::IoError)?,
         };
 
-        async fn inner<B: ByteOrder + Send, W: AsyncWrite + Unpin>(section: &mut SectionHeaderBlock<'_>, block: &RawBlock<'_>, writer: &mut W) -> PcapResult<usize> {
-            if block.type_ == SECTION_HEADER_BLOCK {
-                *section = block.clone().try_into_block::<B>().await?.into_owned().into_section_header().unwrap();
-            }
+        let is_packet = matches!(block.type_, ENHANCED_PACKET_BLOCK | PACKET_BLOCK | SIMPLE_PACKET_BLOCK);
+        self.add_block(bytes, is_packet);
+        if block.type_ == SECTION_HEADER_BLOCK {
+            self.mark_section_start(self.position() - bytes as u64);
+        }
+
+        Ok(bytes)
+    }
+
+    /// Writes a [`RawBlock`] encoded with the given endianness, e.g. read from another pcapng.
+    ///
+    /// If the endianness differs from the one of the current section, the block is parsed and re-encoded with the
+    /// endianness of the section, and written like [`PcapNgWriter::write_block`]. The body of the unknown blocks can't
+    /// be re-encoded and is written as is.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # tokio_test::block_on(async {
+    /// use pcap_file_tokio::pcapng::{PcapNgReader, PcapNgWriter};
+    /// use pcap_file_tokio::Endianness;
+    ///
+    /// let pcapng = tokio::fs::read("test.pcapng").await.unwrap();
+    /// let mut reader = PcapNgReader::new(&pcapng[..]).await.unwrap();
+    /// let mut writer = PcapNgWriter::with_endianness(Vec::new(), Endianness::Little).await.unwrap();
+    ///
+    /// while let Some(block) = reader.next_raw_block().await {
+    ///     let block = block.unwrap().into_owned();
+    ///     writer.write_raw_block_from(&block, reader.section().endianness).await.unwrap();
+    /// }
+    /// # });
+    /// ```
+    pub async fn write_raw_block_from(&mut self, block: &RawBlock<'_>, endianness: Endianness) -> PcapResult<usize> {
+        if block.type_ == SECTION_HEADER_BLOCK || endianness == self.section.endianness {
+            return self.write_raw_block(block).await;
+        }
+
+        let block = match endianness {
+            Endianness::Big => borrow_raw_block(block).try_into_block::<BigEndian>()?,
+            Endianness::Little => borrow_raw_block(block).try_into_block::<LittleEndian>()?,
+        };
+
+        self.write_block(&block).await
+    }
+
+    /// Enables or disables the tracking of the statistics of the interfaces.
+    ///
+    /// When enabled, the packets, drops and timestamps of the Enhanced Packet Blocks, Packet Blocks and Simple Packet Blocks
+    /// written with [`PcapNgWriter::write_block`] are counted per interface, and the Interface Statistics Blocks are written
+    /// by [`PcapNgWriter::write_interface_statistics`], before each new section and by [`PcapNgWriter::close`], like dumpcap does.
+    /// The counters of the interfaces already written start at zero.
+    ///
+    /// Disabled by default.
+    pub fn track_statistics(&mut self, enabled: bool) {
+        self.counters = enabled.then(|| vec![InterfaceCounters::default(); self.interfaces.len()]);
+    }
+
+    /// Returns the counters of the interfaces of the current section, or `None` if the tracking is disabled.
+    pub fn statistics(&self) -> Option<&[InterfaceCounters]> {
+        self.counters.as_deref()
+    }
+
+    /// Writes an Interface Statistics Block for each interface of the current section, from the tracked counters.
+    ///
+    /// The timestamp of the blocks is the current time. Does nothing if the tracking is disabled.
+    ///
+    /// Returns the number of bytes written.
+    pub async fn write_interface_statistics(&mut self) -> PcapResult<usize> {
+        let counters = match &self.counters {
+            Some(counters) => counters.clone(),
+            None => return Ok(0),
+        };
+
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default();
+
+        let mut len = 0;
+        for (interface_id, (counters, interface)) in counters.iter().zip(&self.interfaces).enumerate() {
+            let timestamp = interface.duration_to_timestamp(now).unwrap_or_default();
+            let block = counters.to_statistics_block(interface_id as u32, timestamp).into_block();
+
+            let bytes = match self.section.endianness {
+                Endianness::Big => block.write_to::<BigEndian, _>(&mut self.writer).await.map_err(PcapError::IoError)?,
+                Endianness::Little => block.write_to::<LittleEndian, _>(&mut self.writer).await.map_err(PcapError::IoError)?,
+            };
+
+            self.summary.bytes += bytes as u64;
+            self.summary.blocks += 1;
+            len += bytes;
+        }
+
+        Ok(len)
+    }
+
+    /// Writes the Interface Statistics Blocks if the tracking is enabled, flushes, shuts down and returns the wrapped writer.
+    ///
+    /// Same as [`PcapNgWriter::finish`] without the summary.
+    pub async fn close(self) -> PcapResult<W> {
+        self.finish().await.map(|(writer, _)| writer)
+    }
+
+    /// Writes the Interface Statistics Blocks if the tracking is enabled, flushes and shuts down the wrapped writer,
+    /// returning it with a summary of what was written.
+    ///
+    /// Must be called to make sure that the end of the pcapng reaches its destination,
+    /// e.g. with a `BufWriter` or a compressing writer.
+    pub async fn finish(mut self) -> PcapResult<(W, WriteSummary)> {
+        // An error below is reported to the caller, the guard has nothing to add
+        self.guard.disarm();
+
+        self.end_section().await?;
+        self.writer.flush().await.map_err(PcapError::IoError)?;
+        self.writer.shutdown().await.map_err(PcapError::IoError)?;
 
-            block.write_to::<B, _>(writer).await.map_err(PcapError::IoError)
+        let summary = self.summary;
+        Ok((self.into_inner(), summary))
+    }
+
+    /// Flushes the wrapped writer.
+    pub async fn flush(&mut self) -> PcapResult<()> {
+        self.writer.flush().await.map_err(PcapError::IoError)
+    }
+
+    /// Returns a summary of what was written so far.
+    pub fn summary(&self) -> WriteSummary {
+        self.summary
+    }
+
+    /// Returns the offset of the next block in the pcapng.
+    fn position(&self) -> u64 {
+        self.appended + self.summary.bytes
+    }
+
+    /// Replaces the current section, resetting the state of its interfaces.
+    fn reset_section(&mut self, section: SectionHeaderBlock<'static>) {
+        self.section = section;
+        self.interfaces.clear();
+        self.interface_keys.clear();
+        if let Some(counters) = &mut self.counters {
+            counters.clear();
+        }
+    }
+
+    /// Records the offsets of a Section Header Block written at `start`.
+    fn mark_section_start(&mut self, start: u64) {
+        self.section_start = start;
+        self.section_body_start = self.position();
+    }
+
+    /// Writes the statistics of the current section and back-patches its length, if enabled.
+    async fn end_section(&mut self) -> PcapResult<()> {
+        self.write_interface_statistics().await?;
+
+        if let Some(patcher) = &self.patcher {
+            // The section length is after the block type, the block length, the magic and the version
+            let offset = patcher.base + self.section_start + 16;
+            let length = (self.position() - self.section_body_start) as i64;
+
+            (patcher.patch)(&mut self.writer, offset, length, self.section.endianness).await.map_err(PcapError::IoError)?;
+        }
+
+        Ok(())
+    }
+
+    fn add_block(&mut self, bytes: usize, is_packet: bool) {
+        self.summary.bytes += bytes as u64;
+        self.summary.blocks += 1;
+        if is_packet {
+            self.summary.packets += 1;
         }
     }
 
     /// Consumes [`Self`], returning the wrapped writer.
-    pub fn into_inner(self) -> W {
+    ///
+    /// The writer is neither flushed nor shut down, see [`PcapNgWriter::finish`].
+    pub fn into_inner(mut self) -> W {
+        self.guard.disarm();
         self.writer
     }
 
@@ -233,3 +693,90 @@ impl<W: AsyncWrite + Unpin + Send> PcapNgWriter<W> {
         &self.interfaces
     }
 }
+
+impl<W: AsyncWrite + AsyncSeek + Unpin + Send> PcapNgWriter<W> {
+    /// Enables the back-patching of the section lengths.
+    ///
+    /// The bytes written in each section are counted, and the `section_length` of its [`SectionHeaderBlock`] is
+    /// overwritten with the real length when a new section starts and by [`PcapNgWriter::finish`], allowing readers to
+    /// skip the sections, e.g. with [`PcapNgReader::skip_section`](super::PcapNgReader::skip_section).
+    ///
+    /// The writer must be positioned right after the blocks written by [`Self`].
+    pub async fn track_section_lengths(&mut self) -> PcapResult<()> {
+        let position = self.writer.stream_position().await.map_err(PcapError::IoError)?;
+        let base = position
+            .checked_sub(self.position())
+            .ok_or(PcapError::InvalidField("PcapNgWriter: writer position before the start of the pcapng"))?;
+
+        self.patcher = Some(SectionPatcher { patch: patch_section_length::<W>, base });
+
+        Ok(())
+    }
+}
+
+impl<W: AsyncRead + AsyncWrite + AsyncSeek + Truncate + Unpin + Send> PcapNgWriter<W> {
+    /// Creates a new [`PcapNgWriter`] appending to an existing pcapng file.
+    ///
+    /// The last section of the file is scanned to rebuild the current [`SectionHeaderBlock`] and
+    /// [`InterfaceDescriptionBlock`]s, so the blocks appended use the endianness and the interface ids of this section.
+    /// A partial block at the end of the file is truncated. If the section length of the last section is known, it is
+    /// kept up to date like with [`PcapNgWriter::track_section_lengths`].
+    /// If the file is empty, a default section header is written like [`PcapNgWriter::new`].
+    ///
+    /// The [`WriteSummary`] of the writer only counts the blocks appended.
+    ///
+    /// # Errors
+    /// The file is not a valid pcapng or can't be read, truncated or written to.
+    pub async fn append(mut file: W) -> PcapResult<Self> {
+        if file.seek(SeekFrom::End(0)).await.map_err(PcapError::IoError)? == 0 {
+            return Self::new(file).await;
+        }
+        file.seek(SeekFrom::Start(0)).await.map_err(PcapError::IoError)?;
+
+        let mut reader = PcapNgReader::new(&mut file).await?;
+        let mut section_start = 0;
+        let mut section_body_start = reader.position();
+        let mut end = section_body_start;
+        loop {
+            match reader.next_raw_block().await {
+                Some(Ok(block)) => {
+                    if block.type_ == SECTION_HEADER_BLOCK {
+                        section_start = end;
+                        section_body_start = reader.position();
+                    }
+                    end = reader.position();
+                },
+                Some(Err(PcapError::IoError(e))) if e.kind() == ErrorKind::UnexpectedEof => break,
+                Some(Err(e)) => return Err(e),
+                None => break,
+            }
+        }
+
+        let section = reader.section().clone();
+        let interfaces = reader.interfaces().to_vec();
+        drop(reader);
+
+        file.truncate(end).await.map_err(PcapError::IoError)?;
+        file.seek(SeekFrom::Start(end)).await.map_err(PcapError::IoError)?;
+
+        let mut writer = Self {
+            section,
+            interfaces,
+            counters: None,
+            interface_keys: HashMap::new(),
+            appended: end,
+            section_start,
+            section_body_start,
+            patcher: None,
+            summary: WriteSummary::default(),
+            guard: FinishGuard::new("PcapNgWriter"),
+            writer: file,
+        };
+
+        if writer.section.section_length >= 0 {
+            writer.track_section_lengths().await?;
+        }
+
+        Ok(writer)
+    }
+}
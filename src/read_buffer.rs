@@ -1,11 +1,8 @@
-use std::{
-    future::Future,
-    io::{Error, ErrorKind},
-};
+use std::io::{Error, ErrorKind};
 
 use tokio::io::{AsyncRead, AsyncReadExt};
 
-use crate::PcapError;
+use crate::{PcapError, ReaderConfig};
 
 /// Internal structure that bufferize its input and allow to parse element from its buffer.
 #[derive(Debug)]
@@ -18,31 +15,32 @@ pub(crate) struct ReadBuffer<R: AsyncRead + Unpin> {
     pos: usize,
     /// Current end position of the buffer
     len: usize,
+    /// Number of bytes consumed since the start of the reader
+    consumed: u64,
+    /// Capacity of the buffer when it is created or shrunk
+    initial_capacity: usize,
+    /// Maximum capacity of the buffer
+    max_capacity: usize,
+    /// Shrink the buffer to its initial capacity when its data fit in it
+    shrink: bool,
 }
 
 impl<R: AsyncRead + Unpin> ReadBuffer<R> {
-    /// Creates a new ReadBuffer with capacity of 8_000_000
-    pub fn new(reader: R) -> Self {
-        Self::with_capacity(reader, 8_000_000)
-    }
-
-    /// Creates a new ReadBuffer with the given capacity
-    pub fn with_capacity(reader: R, capacity: usize) -> Self {
-        Self { reader, buffer: vec![0_u8; capacity], pos: 0, len: 0 }
-    }
+    /// Creates a new ReadBuffer with the given config
+    pub fn with_config(reader: R, config: ReaderConfig) -> Self {
+        let max_capacity = config.max_record_size.max(1);
+        let initial_capacity = config.initial_capacity.clamp(1, max_capacity);
 
-    pub async fn parse_with<'a, 'b: 'a, 'c: 'a, F, Fut, O>(&'c mut self, mut parser: F) -> Result<O, PcapError>
-    where
-        F: FnMut(&'a [u8]) -> Fut,
-        F: 'b,
-        Fut: Future<Output = Result<(&'a [u8], O), PcapError>> + 'a,
-        O: 'a,
-    {
-        self.parse_with_context((), move |_, src| {
-            let fut = parser(src);
-            async { (fut.await, ()) }
-        })
-        .await
+        Self {
+            reader,
+            buffer: vec![0_u8; initial_capacity],
+            pos: 0,
+            len: 0,
+            consumed: 0,
+            initial_capacity,
+            max_capacity,
+            shrink: config.shrink,
+        }
     }
 
     /// Parse data from the internal buffer
@@ -50,35 +48,36 @@ impl<R: AsyncRead + Unpin> ReadBuffer<R> {
     /// Safety
     ///
     /// The parser must NOT keep a reference to the buffer in input.
-    pub async fn parse_with_context<'a, 'b: 'a, 'c: 'a, Context, F, Fut, O>(
-        &'c mut self,
-        mut context: Context,
-        mut parser: F,
-    ) -> Result<O, PcapError>
+    pub async fn parse_with<'a, 'b: 'a, 'c: 'a, F, O>(&'c mut self, mut parser: F) -> Result<O, PcapError>
     where
-        F: FnMut(Context, &'a [u8]) -> Fut,
+        F: FnMut(&'a [u8]) -> Result<(&'a [u8], O), PcapError>,
         F: 'b,
-        Fut: Future<Output = (Result<(&'a [u8], O), PcapError>, Context)> + 'a,
         O: 'a,
     {
+        // The values parsed before can't reference the buffer anymore
+        self.shrink_to_initial();
+
         loop {
             let buf = &self.buffer[self.pos..self.len];
 
             // Sound because 'b and 'c must outlive 'a so the buffer cannot be modified while someone has a ref on it
             let buf: &'a [u8] = unsafe { std::mem::transmute(buf) };
 
-            let result = parser(context, buf).await;
-            context = result.1;
-            match result.0 {
+            match parser(buf) {
                 Ok((rem, value)) => {
                     self.advance_with_slice(rem);
                     return Ok(value);
                 },
 
                 Err(PcapError::IncompleteBuffer) => {
-                    // The parsed data len should never be more than the buffer capacity
+                    // The record doesn't fit in the buffer
                     if buf.len() == self.buffer.len() {
-                        return Err(PcapError::IoError(Error::from(ErrorKind::UnexpectedEof)));
+                        if self.buffer.len() >= self.max_capacity {
+                            return Err(PcapError::RecordTooLarge(self.max_capacity));
+                        }
+
+                        let capacity = (self.buffer.len() * 2).min(self.max_capacity);
+                        self.buffer.resize(capacity, 0);
                     }
 
                     let nb_read = self.fill_buf().await.map_err(PcapError::IoError)?;
@@ -111,10 +110,25 @@ impl<R: AsyncRead + Unpin> ReadBuffer<R> {
         Ok(nb_read)
     }
 
+    /// Shrink the buffer back to its initial capacity, if enabled and if its remaining data fit in it.
+    fn shrink_to_initial(&mut self) {
+        let rem_len = self.len - self.pos;
+        if !self.shrink || self.buffer.len() <= self.initial_capacity || rem_len > self.initial_capacity {
+            return;
+        }
+
+        self.buffer.copy_within(self.pos..self.len, 0);
+        self.buffer.truncate(self.initial_capacity);
+        self.buffer.shrink_to_fit();
+        self.pos = 0;
+        self.len = rem_len;
+    }
+
     /// Advance the internal buffer position.
     fn advance(&mut self, nb_bytes: usize) {
         assert!(self.pos + nb_bytes <= self.len);
         self.pos += nb_bytes;
+        self.consumed += nb_bytes as u64;
     }
 
     /// Advance the internal buffer position.
@@ -127,11 +141,38 @@ impl<R: AsyncRead + Unpin> ReadBuffer<R> {
         self.advance(diff_len)
     }
 
+    /// Discard the next `nb_bytes` bytes, from the internal buffer then from the reader.
+    pub async fn skip(&mut self, nb_bytes: u64) -> Result<(), std::io::Error> {
+        let buffered = (self.len - self.pos) as u64;
+        if nb_bytes <= buffered {
+            self.advance(nb_bytes as usize);
+            return Ok(());
+        }
+
+        self.pos = 0;
+        self.len = 0;
+        self.consumed += buffered;
+
+        let rem = nb_bytes - buffered;
+        let skipped = tokio::io::copy(&mut (&mut self.reader).take(rem), &mut tokio::io::sink()).await?;
+        self.consumed += skipped;
+        if skipped < rem {
+            return Err(Error::from(ErrorKind::UnexpectedEof));
+        }
+
+        Ok(())
+    }
+
     /// Return the valid data of the internal buffer
     pub fn buffer(&self) -> &[u8] {
         &self.buffer[self.pos..self.len]
     }
 
+    /// Return the number of bytes consumed since the start of the reader
+    pub fn consumed(&self) -> u64 {
+        self.consumed
+    }
+
     /// Return true there are some data that can be read
     pub async fn has_data_left(&mut self) -> Result<bool, std::io::Error> {
         // The buffer can be empty and the reader can still have data
@@ -156,6 +197,87 @@ impl<R: AsyncRead + Unpin> ReadBuffer<R> {
     }
 }
 
+/// Internal structure that reads its input into shared [`BytesMut`](bytes::BytesMut) chunks.
+///
+/// Unlike [`ReadBuffer`], the parsed elements can keep a reference to the chunks, as a [`Bytes`](bytes::Bytes).
+#[cfg(feature = "bytes")]
+#[derive(Debug)]
+pub(crate) struct BytesReadBuffer<R: AsyncRead + Unpin> {
+    /// Reader from which we read the data from
+    reader: R,
+    /// Data read and not decoded yet, followed by the free space of the current chunk
+    buffer: bytes::BytesMut,
+    /// Size of the chunks allocated when the current one is full
+    chunk_size: usize,
+    /// Maximum size of a decoded record
+    max_record_size: usize,
+}
+
+#[cfg(feature = "bytes")]
+impl<R: AsyncRead + Unpin> BytesReadBuffer<R> {
+    /// Creates a new BytesReadBuffer with the given config, its chunks have the initial capacity of the config
+    pub fn with_config(reader: R, config: ReaderConfig) -> Self {
+        let max_record_size = config.max_record_size.max(1);
+        let chunk_size = config.initial_capacity.clamp(1, max_record_size);
+
+        Self { reader, buffer: bytes::BytesMut::new(), chunk_size, max_record_size }
+    }
+
+    /// Decode data from the internal buffer, reading more data until `decoder` returns a value
+    ///
+    /// The decoder returns `Ok(None)` if it needs more data, otherwise it must remove the decoded data from the buffer.
+    /// Returns `Ok(None)` if the reader is exhausted and the buffer is empty.
+    pub async fn decode_with<O, F>(&mut self, mut decoder: F) -> Result<Option<O>, PcapError>
+    where
+        F: FnMut(&mut bytes::BytesMut) -> Result<Option<O>, PcapError>,
+    {
+        loop {
+            if let Some(value) = decode_bounded(&mut self.buffer, self.max_record_size, &mut decoder)? {
+                return Ok(Some(value));
+            }
+
+            // The chunk is full, the data not decoded yet is moved to a new one
+            if self.buffer.len() == self.buffer.capacity() {
+                self.buffer.reserve(self.chunk_size);
+            }
+
+            let nb_read = self.reader.read_buf(&mut self.buffer).await.map_err(PcapError::IoError)?;
+            if nb_read == 0 {
+                if self.buffer.is_empty() {
+                    return Ok(None);
+                }
+
+                return Err(PcapError::IoError(Error::from(ErrorKind::UnexpectedEof)));
+            }
+        }
+    }
+
+    /// Return the inner reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Return a reference over the inner reader
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+}
+
+/// Runs `decoder` on `src`, failing with [`PcapError::RecordTooLarge`] if the decoded record is larger than
+/// `max_record_size` bytes, or if it needs more data while `src` already holds `max_record_size` bytes.
+#[cfg(feature = "bytes")]
+pub(crate) fn decode_bounded<O, F>(src: &mut bytes::BytesMut, max_record_size: usize, decoder: F) -> Result<Option<O>, PcapError>
+where
+    F: FnOnce(&mut bytes::BytesMut) -> Result<Option<O>, PcapError>,
+{
+    let len = src.len();
+    match decoder(src)? {
+        Some(_) if len - src.len() > max_record_size => Err(PcapError::RecordTooLarge(max_record_size)),
+        None if len >= max_record_size => Err(PcapError::RecordTooLarge(max_record_size)),
+        decoded => Ok(decoded),
+    }
+}
+
 #[cfg(test)]
 mod test {
     /*
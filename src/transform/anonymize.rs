@@ -0,0 +1,527 @@
+//! Prefix-preserving anonymization of IP and MAC addresses.
+//!
+//! Requires the `anonymize` feature.
+
+use std::borrow::Cow;
+
+use aes::cipher::generic_array::GenericArray;
+use aes::cipher::{BlockEncrypt, KeyInit};
+use aes::Aes128;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use super::layers::{
+    self, read_u16, ETHERTYPE_IPV4, ETHERTYPE_IPV6, IPPROTO_ICMPV6, IPPROTO_TCP, IPPROTO_UDP, IPV6_DESTINATION, IPV6_FRAGMENT, IPV6_HOP_BY_HOP,
+    IPV6_ROUTING,
+};
+use crate::pcap::{PcapPacket, PcapReader, PcapWriter};
+use crate::pcapng::blocks::enhanced_packet::EnhancedPacketOption;
+use crate::pcapng::blocks::interface_description::{InterfaceDescriptionBlock, InterfaceDescriptionOption};
+use crate::pcapng::blocks::name_resolution::{NameResolutionOption, Record};
+use crate::pcapng::blocks::packet::PacketOption;
+use crate::pcapng::blocks::section_header::SectionHeaderOption;
+use crate::pcapng::{Block, PcapNgReader, PcapNgWriter};
+use crate::{DataLink, PcapError, PcapResult};
+
+
+/// ARP ethertype
+const ETHERTYPE_ARP: u16 = 0x0806;
+
+/// Crypto-PAn prefix-preserving address anonymization.
+///
+/// Two addresses sharing a prefix of `n` bits are anonymized into two addresses sharing a prefix of exactly `n` bits.
+/// The mapping is a bijection that only depends on the 32 bytes key: the first 16 bytes are the AES key,
+/// the last 16 bytes are used to compute the padding.
+///
+/// IPv4 addresses are compatible with the reference implementation of Crypto-PAn,
+/// IPv6 addresses use the same algorithm on 128 bits.
+#[derive(Clone)]
+pub struct CryptoPan {
+    cipher: Aes128,
+    pad: u128,
+}
+
+impl CryptoPan {
+    /// Creates a new [`CryptoPan`] from a 32 bytes key.
+    pub fn new(key: &[u8; 32]) -> Self {
+        let cipher = Aes128::new(GenericArray::from_slice(&key[..16]));
+
+        let mut pad = GenericArray::clone_from_slice(&key[16..]);
+        cipher.encrypt_block(&mut pad);
+
+        CryptoPan { cipher, pad: u128::from_be_bytes(pad.into()) }
+    }
+
+    /// Anonymizes an IPv4 address.
+    pub fn anonymize_ipv4(&self, addr: [u8; 4]) -> [u8; 4] {
+        let addr = (u32::from_be_bytes(addr) as u128) << 96;
+        let anonymized = self.anonymize_bits(addr, 32);
+
+        ((anonymized >> 96) as u32).to_be_bytes()
+    }
+
+    /// Anonymizes an IPv6 address.
+    pub fn anonymize_ipv6(&self, addr: [u8; 16]) -> [u8; 16] {
+        self.anonymize_bits(u128::from_be_bytes(addr), 128).to_be_bytes()
+    }
+
+    /// Anonymizes the `bits` most significant bits of `addr`.
+    fn anonymize_bits(&self, addr: u128, bits: u32) -> u128 {
+        let mut otp = 0_u128;
+
+        for i in 0..bits {
+            let mask = if i == 0 { 0 } else { u128::MAX << (128 - i) };
+            let input = (addr & mask) | (self.pad & !mask);
+
+            let output = self.encrypt(input);
+            otp |= (output >> 127) << (127 - i);
+        }
+
+        addr ^ otp
+    }
+
+    fn encrypt(&self, input: u128) -> u128 {
+        let mut block = GenericArray::from(input.to_be_bytes());
+        self.cipher.encrypt_block(&mut block);
+
+        u128::from_be_bytes(block.into())
+    }
+}
+
+impl std::fmt::Debug for CryptoPan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CryptoPan").finish_non_exhaustive()
+    }
+}
+
+
+/// Anonymizes packets and pcapng metadata.
+///
+/// In the packets:
+/// - the IPv4 and IPv6 addresses of the IP headers and of the ARP packets are anonymized with [`CryptoPan`]
+/// - the MAC addresses of the Ethernet, Linux cooked and ARP headers are hashed, keeping their OUI
+/// - the IPv4 header checksums and the TCP, UDP and ICMPv6 checksums are updated. They are recomputed when the whole
+///   datagram is captured, and updated incrementally otherwise, e.g. for a truncated packet.
+///
+/// The addresses inside the payloads (ICMP errors, tunnels, IPv6 routing headers, application protocols...) are not anonymized.
+///
+/// In the pcapng metadata:
+/// - the addresses of the Name Resolution Blocks are anonymized and their names replaced by pseudonyms
+/// - the `if_IPv4addr`, `if_IPv6addr`, `if_MACaddr` and `if_EUIaddr` interface options are anonymized
+/// - the `shb_hardware`, `shb_os`, `if_description`, `if_hardware` and `if_os` strings are removed
+/// - the comments are removed, unless disabled with [`Anonymizer::scrub_comments`]
+///
+/// [`anonymize_pcap`] and [`anonymize_pcapng`] anonymize whole files.
+///
+/// # Example
+/// ```rust,no_run
+/// # tokio_test::block_on(async {
+/// use tokio::fs::File;
+///
+/// use pcap_file_tokio::transform::anonymize::{anonymize_pcapng, Anonymizer};
+///
+/// let file_in = File::open("test.pcapng").await.expect("Error opening file");
+/// let file_out = File::create("out.pcapng").await.expect("Error creating file");
+///
+/// let key = [0x42; 32];
+/// anonymize_pcapng(file_in, file_out, &Anonymizer::new(&key)).await.unwrap();
+/// # });
+/// ```
+#[derive(Clone, Debug)]
+pub struct Anonymizer {
+    cryptopan: CryptoPan,
+    scrub_comments: bool,
+}
+
+impl Anonymizer {
+    /// Creates a new [`Anonymizer`] from a 32 bytes key.
+    pub fn new(key: &[u8; 32]) -> Self {
+        Anonymizer { cryptopan: CryptoPan::new(key), scrub_comments: true }
+    }
+
+    /// Enables or disables the removal of the comments, enabled by default.
+    pub fn scrub_comments(mut self, enabled: bool) -> Self {
+        self.scrub_comments = enabled;
+        self
+    }
+
+    /// Returns the [`CryptoPan`] used for the IP addresses.
+    pub fn cryptopan(&self) -> &CryptoPan {
+        &self.cryptopan
+    }
+
+    /// Anonymizes a MAC address, keeping its OUI.
+    ///
+    /// The broadcast and null addresses are not modified.
+    pub fn anonymize_mac(&self, mac: [u8; 6]) -> [u8; 6] {
+        if mac == [0xFF; 6] || mac == [0; 6] {
+            return mac;
+        }
+
+        let hash = self.keyed_hash(b"mac", &mac).to_be_bytes();
+        [mac[0], mac[1], mac[2], hash[0], hash[1], hash[2]]
+    }
+
+    /// Anonymizes the data of a packet captured on a link of type `datalink`, in place.
+    ///
+    /// Packets of unsupported link types are left unchanged.
+    pub fn anonymize_data(&self, datalink: DataLink, data: &mut [u8]) {
+        match datalink {
+            DataLink::ETHERNET => {
+                self.anonymize_mac_at(data, 0);
+                self.anonymize_mac_at(data, 6);
+            },
+            // Link-layer address length then address
+            DataLink::LINUX_SLL if read_u16(data, 4) == Some(6) => self.anonymize_mac_at(data, 6),
+            DataLink::LINUX_SLL2 if data.get(11) == Some(&6) => self.anonymize_mac_at(data, 12),
+            _ => {},
+        }
+
+        let (offset, ethertype) = match layers::network_layer(datalink, data) {
+            Some(layer) => layer,
+            None => return,
+        };
+
+        let network = match data.get_mut(offset..) {
+            Some(network) => network,
+            None => return,
+        };
+
+        match ethertype {
+            ETHERTYPE_IPV4 => self.anonymize_ipv4_packet(network),
+            ETHERTYPE_IPV6 => self.anonymize_ipv6_packet(network),
+            ETHERTYPE_ARP => self.anonymize_arp_packet(network),
+            _ => {},
+        }
+    }
+
+    /// Anonymizes a [`PcapPacket`] of a pcap whose link type is `datalink`.
+    pub fn anonymize_pcap_packet(&self, packet: &mut PcapPacket, datalink: DataLink) {
+        self.anonymize_data(datalink, packet.data.to_mut());
+    }
+
+    /// Anonymizes a pcapng [`Block`].
+    ///
+    /// `interfaces` are the interfaces of the current section, as returned by
+    /// [`PcapNgReader::interfaces`](crate::pcapng::PcapNgReader::interfaces). They are used to find the link type of the packets.
+    /// The Simple Packet Blocks use the first interface.
+    ///
+    /// # Errors
+    /// Returns [`PcapError::InvalidInterfaceId`] if a packet references an unknown interface.
+    pub fn anonymize_block(&self, block: &mut Block, interfaces: &[InterfaceDescriptionBlock]) -> PcapResult<()> {
+        let linktype = |interface_id: u32| {
+            interfaces
+                .get(interface_id as usize)
+                .map(|interface| interface.linktype)
+                .ok_or(PcapError::InvalidInterfaceId(interface_id))
+        };
+
+        match block {
+            Block::SectionHeader(section) => {
+                section.options.retain(|opt| match opt {
+                    SectionHeaderOption::Hardware(_) | SectionHeaderOption::OS(_) => false,
+                    SectionHeaderOption::Comment(_) => !self.scrub_comments,
+                    _ => true,
+                });
+            },
+
+            Block::InterfaceDescription(interface) => self.anonymize_interface(interface),
+
+            Block::EnhancedPacket(packet) => {
+                self.anonymize_data(linktype(packet.interface_id)?, packet.data.to_mut());
+                if self.scrub_comments {
+                    packet.options.retain(|opt| !matches!(opt, EnhancedPacketOption::Comment(_)));
+                }
+            },
+
+            Block::Packet(packet) => {
+                self.anonymize_data(linktype(packet.interface_id as u32)?, packet.data.to_mut());
+                if self.scrub_comments {
+                    packet.options.retain(|opt| !matches!(opt, PacketOption::Comment(_)));
+                }
+            },
+
+            Block::SimplePacket(packet) => self.anonymize_data(linktype(0)?, packet.data.to_mut()),
+
+            Block::NameResolution(nrb) => {
+                for record in nrb.records.iter_mut() {
+                    match record {
+                        Record::Ipv4(record) => {
+                            self.anonymize_ipv4_at(record.ip_addr.to_mut(), 0);
+                            record.names = record.names.iter().map(|name| Cow::Owned(self.pseudonym(name))).collect();
+                        },
+                        Record::Ipv6(record) => {
+                            self.anonymize_ipv6_at(record.ip_addr.to_mut(), 0);
+                            record.names = record.names.iter().map(|name| Cow::Owned(self.pseudonym(name))).collect();
+                        },
+                        Record::End | Record::Unknown(_) => {},
+                    }
+                }
+
+                let scrub_comments = self.scrub_comments;
+                nrb.options.retain(|opt| !(scrub_comments && matches!(opt, NameResolutionOption::Comment(_))));
+                for opt in nrb.options.iter_mut() {
+                    match opt {
+                        NameResolutionOption::NsDnsName(name) => *name = Cow::Owned(self.pseudonym(name)),
+                        NameResolutionOption::NsDnsIpv4Addr(addr) => self.anonymize_ipv4_at(addr.to_mut(), 0),
+                        NameResolutionOption::NsDnsIpv6Addr(addr) => self.anonymize_ipv6_at(addr.to_mut(), 0),
+                        _ => {},
+                    }
+                }
+            },
+
+            _ => {},
+        }
+
+        Ok(())
+    }
+
+    fn anonymize_interface(&self, interface: &mut InterfaceDescriptionBlock) {
+        interface.options.retain(|opt| match opt {
+            InterfaceDescriptionOption::IfDescription(_) | InterfaceDescriptionOption::IfHardware(_) | InterfaceDescriptionOption::IfOs(_) => false,
+            InterfaceDescriptionOption::Comment(_) => !self.scrub_comments,
+            _ => true,
+        });
+
+        for opt in interface.options.iter_mut() {
+            match opt {
+                // Address then netmask
+                InterfaceDescriptionOption::IfIpv4Addr(addr) => self.anonymize_ipv4_at(addr.to_mut(), 0),
+                // Address then prefix length
+                InterfaceDescriptionOption::IfIpv6Addr(addr) => self.anonymize_ipv6_at(addr.to_mut(), 0),
+                InterfaceDescriptionOption::IfMacAddr(addr) => self.anonymize_mac_at(addr.to_mut(), 0),
+                InterfaceDescriptionOption::IfEuIAddr(addr) => {
+                    let hash = self.keyed_hash(b"eui", &addr.to_be_bytes()) as u64;
+                    *addr = (*addr & 0xFFFFFF00_00000000) | (hash & 0x000000FF_FFFFFFFF);
+                },
+                _ => {},
+            }
+        }
+    }
+
+    fn anonymize_ipv4_packet(&self, ip: &mut [u8]) {
+        if ip.len() < 20 || ip[0] >> 4 != 4 {
+            return;
+        }
+
+        let old_addrs: [u8; 8] = ip[12..20].try_into().unwrap();
+        self.anonymize_ipv4_at(ip, 12);
+        self.anonymize_ipv4_at(ip, 16);
+
+        let header_len = (ip[0] & 0x0F) as usize * 4;
+        if header_len < 20 || ip.len() < header_len {
+            return;
+        }
+        layers::update_ipv4_header_checksum(&mut ip[..header_len]);
+
+        // Only the first fragment contains the transport header
+        let flags_offset = read_u16(ip, 6).unwrap();
+        if flags_offset & 0x1FFF != 0 {
+            return;
+        }
+
+        let total_len = read_u16(ip, 2).unwrap() as usize;
+        let complete = flags_offset & 0x2000 == 0 && total_len >= header_len && ip.len() >= total_len;
+        let protocol = ip[9];
+
+        let (header, payload) = ip.split_at_mut(header_len);
+        let payload = if complete { &mut payload[..total_len - header_len] } else { payload };
+        update_transport_checksum(protocol, payload, &old_addrs, &header[12..20], complete, false);
+    }
+
+    fn anonymize_ipv6_packet(&self, ip: &mut [u8]) {
+        if ip.len() < 40 || ip[0] >> 4 != 6 {
+            return;
+        }
+
+        let old_addrs: [u8; 32] = ip[8..40].try_into().unwrap();
+        self.anonymize_ipv6_at(ip, 8);
+        self.anonymize_ipv6_at(ip, 24);
+
+        // Finds the transport header
+        let mut next_header = ip[6];
+        let mut offset = 40;
+        let mut more_fragments = false;
+        loop {
+            match next_header {
+                IPV6_HOP_BY_HOP | IPV6_ROUTING | IPV6_DESTINATION => {
+                    let len = match ip.get(offset + 1) {
+                        Some(len) => (*len as usize + 1) * 8,
+                        None => return,
+                    };
+                    next_header = ip[offset];
+                    offset += len;
+                },
+                IPV6_FRAGMENT => {
+                    let flags_offset = match read_u16(ip, offset + 2) {
+                        Some(flags_offset) => flags_offset,
+                        None => return,
+                    };
+                    if flags_offset & 0xFFF8 != 0 {
+                        return;
+                    }
+                    more_fragments = flags_offset & 1 == 1;
+                    next_header = ip[offset];
+                    offset += 8;
+                },
+                _ => break,
+            }
+        }
+
+        let total_len = 40 + read_u16(ip, 4).unwrap() as usize;
+        let complete = !more_fragments && total_len >= offset && ip.len() >= total_len;
+        if ip.len() < offset {
+            return;
+        }
+
+        let (header, payload) = ip.split_at_mut(offset);
+        let payload = if complete { &mut payload[..total_len - offset] } else { payload };
+        update_transport_checksum(next_header, payload, &old_addrs, &header[8..40], complete, true);
+    }
+
+    fn anonymize_arp_packet(&self, arp: &mut [u8]) {
+        // Ethernet and IPv4 only
+        if read_u16(arp, 0) != Some(1) || read_u16(arp, 2) != Some(ETHERTYPE_IPV4) || arp.get(4..6) != Some(&[6, 4]) {
+            return;
+        }
+
+        self.anonymize_mac_at(arp, 8);
+        self.anonymize_ipv4_at(arp, 14);
+        self.anonymize_mac_at(arp, 18);
+        self.anonymize_ipv4_at(arp, 24);
+    }
+
+    fn anonymize_mac_at(&self, data: &mut [u8], offset: usize) {
+        if let Some(bytes) = data.get_mut(offset..offset + 6) {
+            let mac = self.anonymize_mac(bytes.try_into().unwrap());
+            bytes.copy_from_slice(&mac);
+        }
+    }
+
+    fn anonymize_ipv4_at(&self, data: &mut [u8], offset: usize) {
+        if let Some(bytes) = data.get_mut(offset..offset + 4) {
+            let addr = self.cryptopan.anonymize_ipv4(bytes.try_into().unwrap());
+            bytes.copy_from_slice(&addr);
+        }
+    }
+
+    fn anonymize_ipv6_at(&self, data: &mut [u8], offset: usize) {
+        if let Some(bytes) = data.get_mut(offset..offset + 16) {
+            let addr = self.cryptopan.anonymize_ipv6(bytes.try_into().unwrap());
+            bytes.copy_from_slice(&addr);
+        }
+    }
+
+    /// Returns a pseudonym for a host name.
+    fn pseudonym(&self, name: &str) -> String {
+        format!("host-{:016x}", self.keyed_hash(b"name", name.as_bytes()) as u64)
+    }
+
+    /// Hashes `data` with AES in CBC-MAC mode, `domain` separating the different uses of the key.
+    fn keyed_hash(&self, domain: &[u8], data: &[u8]) -> u128 {
+        let mut state = self.cryptopan.encrypt(u128::from_be_bytes(pad_block(domain)) ^ data.len() as u128);
+        for chunk in data.chunks(16) {
+            state = self.cryptopan.encrypt(state ^ u128::from_be_bytes(pad_block(chunk)));
+        }
+
+        state
+    }
+}
+
+/// Zero pads a chunk of at most 16 bytes.
+fn pad_block(chunk: &[u8]) -> [u8; 16] {
+    let mut block = [0; 16];
+    block[..chunk.len()].copy_from_slice(chunk);
+    block
+}
+
+/// Updates the TCP, UDP or ICMPv6 checksum after the modification of the IP addresses.
+///
+/// The checksum is recomputed if the payload is `complete`, otherwise it is updated incrementally (RFC 1624).
+fn update_transport_checksum(protocol: u8, payload: &mut [u8], old_addrs: &[u8], new_addrs: &[u8], complete: bool, ipv6: bool) {
+    let checksum_offset = match protocol {
+        IPPROTO_TCP => 16,
+        IPPROTO_UDP => 6,
+        IPPROTO_ICMPV6 if ipv6 => 2,
+        _ => return,
+    };
+
+    let old_checksum = match read_u16(payload, checksum_offset) {
+        Some(checksum) => checksum,
+        None => return,
+    };
+
+    // No checksum for this UDP over IPv4 packet
+    if protocol == IPPROTO_UDP && !ipv6 && old_checksum == 0 {
+        return;
+    }
+
+    let mut checksum = if complete {
+        payload[checksum_offset..checksum_offset + 2].copy_from_slice(&[0, 0]);
+
+        let (src, dst) = new_addrs.split_at(new_addrs.len() / 2);
+        let sum = layers::ones_complement_sum(0, src);
+        let sum = layers::ones_complement_sum(sum, dst);
+        let sum = layers::ones_complement_sum(sum, &(payload.len() as u32).to_be_bytes());
+        let sum = layers::ones_complement_sum(sum, &[0, protocol]);
+
+        !(layers::ones_complement_sum(sum, payload) as u16)
+    }
+    else {
+        // HC' = ~(~HC + ~m + m')
+        let negated_old: Vec<u8> = old_addrs.iter().map(|byte| !byte).collect();
+        let sum = layers::ones_complement_sum(!old_checksum as u32, &negated_old);
+
+        !(layers::ones_complement_sum(sum, new_addrs) as u16)
+    };
+
+    if protocol == IPPROTO_UDP && checksum == 0 {
+        checksum = 0xFFFF;
+    }
+
+    payload[checksum_offset..checksum_offset + 2].copy_from_slice(&checksum.to_be_bytes());
+}
+
+
+/// Anonymizes all the packets of a pcap.
+///
+/// Returns the wrapped writer.
+pub async fn anonymize_pcap<R, W>(reader: R, writer: W, anonymizer: &Anonymizer) -> PcapResult<W>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut reader = PcapReader::new(reader).await?;
+    let header = reader.header();
+    let mut writer = PcapWriter::with_header(writer, header).await?;
+
+    while let Some(packet) = reader.next_packet().await {
+        let mut packet = packet?;
+        anonymizer.anonymize_pcap_packet(&mut packet, header.datalink);
+        writer.write_packet(&packet).await?;
+    }
+
+    Ok(writer.into_writer())
+}
+
+/// Anonymizes all the blocks of a pcapng.
+///
+/// Returns the wrapped writer.
+pub async fn anonymize_pcapng<R, W>(reader: R, writer: W, anonymizer: &Anonymizer) -> PcapResult<W>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin + Send,
+{
+    let mut reader = PcapNgReader::new(reader).await?;
+
+    let mut section = Block::SectionHeader(reader.section().clone());
+    anonymizer.anonymize_block(&mut section, &[])?;
+    let mut writer = PcapNgWriter::with_section_header(writer, section.into_section_header().unwrap()).await?;
+
+    while let Some(block) = reader.next_block().await {
+        let mut block = block?.into_owned();
+        anonymizer.anonymize_block(&mut block, reader.interfaces())?;
+        writer.write_block(&block).await?;
+    }
+
+    Ok(writer.into_inner())
+}
@@ -0,0 +1,661 @@
+//! Tunnel decapsulation.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use super::layers::{self, *};
+use super::TransformPacket;
+use crate::pcap::{PcapPacket, PcapReader, PcapWriter};
+use crate::pcapng::blocks::enhanced_packet::EnhancedPacketOption;
+use crate::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use crate::pcapng::blocks::packet::PacketOption;
+use crate::pcapng::{Block, PcapNgReader, PcapNgWriter};
+use crate::{DataLink, PcapError, PcapResult};
+
+
+/// Maximum number of nested encapsulations removed by [`decapsulate`].
+const MAX_DEPTH: usize = 8;
+
+/// Private Enterprise Number of the custom options added by the default [`Decapsulator`].
+///
+/// This is the PEN reserved for documentation by RFC 5612, replace it with the PEN of your organization with
+/// [`Decapsulator::with_pen`].
+pub const DEFAULT_DECAP_PEN: u32 = 32473;
+
+/// Encapsulation protocol of a [`TunnelLayer`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Tunnel {
+    /// Generic Routing Encapsulation
+    Gre {
+        /// Key field of the GRE header
+        key: Option<u32>,
+    },
+    /// Encapsulated Remote SPAN, inside GRE
+    Erspan {
+        /// ERSPAN version: 2 for type II, 3 for type III
+        version: u8,
+        /// Session ID of the mirrored traffic
+        session_id: u16,
+    },
+    /// Virtual eXtensible LAN
+    Vxlan {
+        /// VXLAN Network Identifier
+        vni: u32,
+    },
+    /// Generic Network Virtualization Encapsulation
+    Geneve {
+        /// Virtual Network Identifier
+        vni: u32,
+    },
+    /// IPv4 or IPv6 directly inside IPv4 or IPv6
+    IpInIp,
+    /// MPLS label stack
+    Mpls {
+        /// Labels of the stack, from the top to the bottom
+        labels: Vec<u32>,
+    },
+}
+
+/// An encapsulation removed by [`decapsulate`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TunnelLayer {
+    /// Encapsulation protocol
+    pub tunnel: Tunnel,
+    /// Source and destination addresses of the outer IP header, `None` for MPLS
+    pub endpoints: Option<(IpAddr, IpAddr)>,
+}
+
+/// Formats the layer as a packet comment, e.g. "VXLAN 10.0.0.1 > 10.0.0.2, VNI 42".
+impl fmt::Display for TunnelLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.tunnel {
+            Tunnel::Gre { .. } => write!(f, "GRE")?,
+            Tunnel::Erspan { version: 2, .. } => write!(f, "ERSPAN type II")?,
+            Tunnel::Erspan { .. } => write!(f, "ERSPAN type III")?,
+            Tunnel::Vxlan { .. } => write!(f, "VXLAN")?,
+            Tunnel::Geneve { .. } => write!(f, "GENEVE")?,
+            Tunnel::IpInIp => write!(f, "IP-in-IP")?,
+            Tunnel::Mpls { .. } => write!(f, "MPLS")?,
+        }
+
+        if let Some((src, dst)) = self.endpoints {
+            write!(f, " {src} > {dst}")?;
+        }
+
+        match &self.tunnel {
+            Tunnel::Gre { key: Some(key) } => write!(f, ", key {key}"),
+            Tunnel::Erspan { session_id, .. } => write!(f, ", session {session_id}"),
+            Tunnel::Vxlan { vni } | Tunnel::Geneve { vni } => write!(f, ", VNI {vni}"),
+            Tunnel::Mpls { labels } => {
+                let labels: Vec<String> = labels.iter().map(|label| label.to_string()).collect();
+                write!(f, " labels {}", labels.join("/"))
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+impl TunnelLayer {
+    /// Encodes the layer as the value of a custom option, all the integers being big endian:
+    /// - the tunnel: 1 for GRE, 2 for ERSPAN, 3 for VXLAN, 4 for GENEVE, 5 for IP-in-IP and 6 for MPLS, on 1 byte
+    /// - the IP version of the endpoints, 0 without endpoints, on 1 byte, followed by the source and destination addresses
+    /// - for GRE, 1 and the key or 0 without key, for ERSPAN, the version on 1 byte and the session id on 2 bytes, for
+    ///   VXLAN and GENEVE the VNI on 4 bytes, for MPLS the number of labels on 1 byte followed by the labels on 4 bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let tunnel = match self.tunnel {
+            Tunnel::Gre { .. } => 1,
+            Tunnel::Erspan { .. } => 2,
+            Tunnel::Vxlan { .. } => 3,
+            Tunnel::Geneve { .. } => 4,
+            Tunnel::IpInIp => 5,
+            Tunnel::Mpls { .. } => 6,
+        };
+        let mut bytes = vec![tunnel];
+
+        match self.endpoints {
+            None => bytes.push(0),
+            Some((IpAddr::V4(src), IpAddr::V4(dst))) => {
+                bytes.push(4);
+                bytes.extend_from_slice(&src.octets());
+                bytes.extend_from_slice(&dst.octets());
+            },
+            Some((src, dst)) => {
+                bytes.push(6);
+                bytes.extend_from_slice(&to_ipv6(src).octets());
+                bytes.extend_from_slice(&to_ipv6(dst).octets());
+            },
+        }
+
+        match &self.tunnel {
+            Tunnel::Gre { key: Some(key) } => {
+                bytes.push(1);
+                bytes.extend_from_slice(&key.to_be_bytes());
+            },
+            Tunnel::Gre { key: None } => bytes.push(0),
+            Tunnel::Erspan { version, session_id } => {
+                bytes.push(*version);
+                bytes.extend_from_slice(&session_id.to_be_bytes());
+            },
+            Tunnel::Vxlan { vni } | Tunnel::Geneve { vni } => bytes.extend_from_slice(&vni.to_be_bytes()),
+            Tunnel::IpInIp => {},
+            Tunnel::Mpls { labels } => {
+                // The stack is bounded by the decapsulation, but not the layers built by hand
+                let labels = &labels[..labels.len().min(u8::MAX as usize)];
+                bytes.push(labels.len() as u8);
+                for label in labels {
+                    bytes.extend_from_slice(&label.to_be_bytes());
+                }
+            },
+        }
+
+        bytes
+    }
+
+    /// Decodes a layer encoded by [`TunnelLayer::to_bytes`], returns `None` if the bytes are invalid.
+    pub fn from_bytes(bytes: &[u8]) -> Option<TunnelLayer> {
+        let (&tunnel, bytes) = bytes.split_first()?;
+        let (&version, mut bytes) = bytes.split_first()?;
+
+        let endpoints = match version {
+            0 => None,
+            4 => {
+                let src: [u8; 4] = take(&mut bytes, 4)?.try_into().ok()?;
+                let dst: [u8; 4] = take(&mut bytes, 4)?.try_into().ok()?;
+                Some((IpAddr::from(src), IpAddr::from(dst)))
+            },
+            6 => {
+                let src: [u8; 16] = take(&mut bytes, 16)?.try_into().ok()?;
+                let dst: [u8; 16] = take(&mut bytes, 16)?.try_into().ok()?;
+                Some((IpAddr::from(src), IpAddr::from(dst)))
+            },
+            _ => return None,
+        };
+
+        let u32_at = |bytes: &mut &[u8]| Some(u32::from_be_bytes(take(bytes, 4)?.try_into().ok()?));
+        let tunnel = match tunnel {
+            1 => match take(&mut bytes, 1)?[0] {
+                0 => Tunnel::Gre { key: None },
+                _ => Tunnel::Gre { key: Some(u32_at(&mut bytes)?) },
+            },
+            2 => {
+                let version = take(&mut bytes, 1)?[0];
+                let session_id = u16::from_be_bytes(take(&mut bytes, 2)?.try_into().ok()?);
+                Tunnel::Erspan { version, session_id }
+            },
+            3 => Tunnel::Vxlan { vni: u32_at(&mut bytes)? },
+            4 => Tunnel::Geneve { vni: u32_at(&mut bytes)? },
+            5 => Tunnel::IpInIp,
+            6 => {
+                let count = take(&mut bytes, 1)?[0];
+                let labels = (0..count).map(|_| u32_at(&mut bytes)).collect::<Option<Vec<_>>>()?;
+                Tunnel::Mpls { labels }
+            },
+            _ => return None,
+        };
+
+        bytes.is_empty().then_some(TunnelLayer { tunnel, endpoints })
+    }
+}
+
+/// Returns the IPv6 address, or the IPv4-mapped IPv6 address, of an IP address.
+fn to_ipv6(addr: IpAddr) -> Ipv6Addr {
+    match addr {
+        IpAddr::V4(addr) => addr.to_ipv6_mapped(),
+        IpAddr::V6(addr) => addr,
+    }
+}
+
+/// Splits the first `len` bytes from the slice.
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+    if bytes.len() < len {
+        return None;
+    }
+
+    let (head, tail) = bytes.split_at(len);
+    *bytes = tail;
+    Some(head)
+}
+
+/// Returns the layers added by a [`Decapsulator`] to a packet as custom options with the given PEN.
+///
+/// The options which can't be decoded are ignored.
+pub fn tunnel_layers(block: &Block, pen: u32) -> Vec<TunnelLayer> {
+    let options: Vec<_> = match block {
+        Block::EnhancedPacket(packet) => packet
+            .options
+            .iter()
+            .filter_map(|option| match option {
+                EnhancedPacketOption::CustomBinary(option) if option.pen == pen => Some(&option.value[..]),
+                _ => None,
+            })
+            .collect(),
+        Block::Packet(packet) => packet
+            .options
+            .iter()
+            .filter_map(|option| match option {
+                PacketOption::CustomBinary(option) if option.pen == pen => Some(&option.value[..]),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    };
+
+    options.into_iter().filter_map(TunnelLayer::from_bytes).collect()
+}
+
+/// Inner packet found by [`decapsulate`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Decapsulation {
+    /// Link type of the inner packet: [`DataLink::ETHERNET`] or [`DataLink::RAW`]
+    pub datalink: DataLink,
+    /// Offset of the inner packet in the data
+    pub offset: usize,
+    /// Removed encapsulations, from the outermost to the innermost
+    pub layers: Vec<TunnelLayer>,
+}
+
+/// Finds the innermost packet of a packet captured on a link of type `datalink`.
+///
+/// The GRE, ERSPAN type II and III, VXLAN, GENEVE, IP-in-IP and MPLS encapsulations are removed, as many times as they are nested.
+/// VXLAN and GENEVE are only detected on their standard UDP ports. Fragmented packets are not decapsulated.
+///
+/// Returns `None` if the packet isn't encapsulated.
+pub fn decapsulate(datalink: DataLink, data: &[u8]) -> Option<Decapsulation> {
+    let mut decapsulation = Decapsulation { datalink, offset: 0, layers: vec![] };
+
+    while decapsulation.layers.len() < MAX_DEPTH {
+        match decapsulate_once(decapsulation.datalink, &data[decapsulation.offset..]) {
+            Some((datalink, offset, layer)) => {
+                decapsulation.datalink = datalink;
+                decapsulation.offset += offset;
+                decapsulation.layers.push(layer);
+            },
+            None => break,
+        }
+    }
+
+    (!decapsulation.layers.is_empty()).then_some(decapsulation)
+}
+
+/// Removes one encapsulation, returning the inner link type, the offset of the inner packet and the removed layer.
+fn decapsulate_once(datalink: DataLink, data: &[u8]) -> Option<(DataLink, usize, TunnelLayer)> {
+    let (offset, ethertype) = layers::network_layer(datalink, data)?;
+    let network = data.get(offset..)?;
+
+    let (header_len, protocol, endpoints) = match ethertype {
+        ETHERTYPE_IPV4 => ipv4_header(network)?,
+        ETHERTYPE_IPV6 => ipv6_header(network)?,
+        ETHERTYPE_MPLS | ETHERTYPE_MPLS_MULTICAST => {
+            let (len, datalink, labels) = mpls_header(network)?;
+            let layer = TunnelLayer { tunnel: Tunnel::Mpls { labels }, endpoints: None };
+            return Some((datalink, offset + len, layer));
+        },
+        _ => return None,
+    };
+
+    let transport = &network[header_len..];
+    let (len, datalink, tunnel) = match protocol {
+        IPPROTO_IPIP | IPPROTO_IPV6 => (0, DataLink::RAW, Tunnel::IpInIp),
+
+        IPPROTO_GRE => {
+            let (len, protocol) = layers::gre_header(transport)?;
+            match protocol {
+                ETHERTYPE_ERSPAN_II | ETHERTYPE_ERSPAN_III => {
+                    let erspan_len = layers::erspan_header(protocol, transport.get(len..)?)?;
+                    let version = if protocol == ETHERTYPE_ERSPAN_II { 2 } else { 3 };
+                    let session_id = read_u16(transport, len + 2)? & 0x03FF;
+                    (len + erspan_len, DataLink::ETHERNET, Tunnel::Erspan { version, session_id })
+                },
+                _ => {
+                    // The key follows the optional checksum
+                    let flags = read_u16(transport, 0)?;
+                    let key = if flags & 0x2000 != 0 { read_u32(transport, if flags & 0x8000 != 0 { 8 } else { 4 }) } else { None };
+                    (len, inner_datalink(protocol)?, Tunnel::Gre { key })
+                },
+            }
+        },
+
+        IPPROTO_UDP => {
+            let payload = transport.get(8..)?;
+            let is_port = |port| read_u16(transport, 0) == Some(port) || read_u16(transport, 2) == Some(port);
+
+            if is_port(VXLAN_PORT) && payload.len() >= 8 {
+                (16, DataLink::ETHERNET, Tunnel::Vxlan { vni: read_u32(payload, 4)? >> 8 })
+            }
+            else if is_port(GENEVE_PORT) {
+                let (len, protocol) = layers::geneve_header(payload)?;
+                (8 + len, inner_datalink(protocol)?, Tunnel::Geneve { vni: read_u32(payload, 4)? >> 8 })
+            }
+            else {
+                return None;
+            }
+        },
+
+        _ => return None,
+    };
+
+    let inner = offset + header_len + len;
+    (inner < data.len()).then_some((datalink, inner, TunnelLayer { tunnel, endpoints: Some(endpoints) }))
+}
+
+/// Returns the link type of a packet encapsulated with this protocol type.
+fn inner_datalink(protocol: u16) -> Option<DataLink> {
+    match protocol {
+        ETHERTYPE_TEB => Some(DataLink::ETHERNET),
+        ETHERTYPE_IPV4 | ETHERTYPE_IPV6 => Some(DataLink::RAW),
+        _ => None,
+    }
+}
+
+/// Parses an unfragmented IPv4 header, returning its length, its protocol and its addresses.
+fn ipv4_header(data: &[u8]) -> Option<(usize, u8, (IpAddr, IpAddr))> {
+    let header_len = (data.first()? & 0x0F) as usize * 4;
+    if header_len < 20 || data.len() < header_len {
+        return None;
+    }
+
+    // More fragments flag or fragment offset
+    if read_u16(data, 6)? & 0x3FFF != 0 {
+        return None;
+    }
+
+    let src = Ipv4Addr::from(read_u32(data, 12)?);
+    let dst = Ipv4Addr::from(read_u32(data, 16)?);
+
+    Some((header_len, data[9], (src.into(), dst.into())))
+}
+
+/// Parses an unfragmented IPv6 header and its extensions, returning their length, the protocol and the addresses.
+fn ipv6_header(data: &[u8]) -> Option<(usize, u8, (IpAddr, IpAddr))> {
+    if data.len() < 40 {
+        return None;
+    }
+
+    let src: [u8; 16] = data[8..24].try_into().unwrap();
+    let dst: [u8; 16] = data[24..40].try_into().unwrap();
+
+    let mut next_header = data[6];
+    let mut offset = 40;
+    while matches!(next_header, IPV6_HOP_BY_HOP | IPV6_ROUTING | IPV6_DESTINATION) {
+        next_header = *data.get(offset)?;
+        offset += (*data.get(offset + 1)? as usize + 1) * 8;
+    }
+
+    if next_header == IPV6_FRAGMENT || offset > data.len() {
+        return None;
+    }
+
+    Some((offset, next_header, (Ipv6Addr::from(src).into(), Ipv6Addr::from(dst).into())))
+}
+
+/// Parses an MPLS label stack, returning its length, the link type of the payload and the labels.
+fn mpls_header(data: &[u8]) -> Option<(usize, DataLink, Vec<u32>)> {
+    let mut labels = vec![];
+    let mut offset = 0;
+    loop {
+        let entry = read_u32(data, offset)?;
+        labels.push(entry >> 12);
+        offset += 4;
+
+        // Bottom of stack
+        if entry & 0x100 != 0 {
+            break;
+        }
+    }
+
+    // No protocol field after MPLS: IP is guessed from the version nibble,
+    // a first nibble of 0 is the control word of an Ethernet pseudowire
+    match data.get(offset)? >> 4 {
+        4 | 6 => Some((offset, DataLink::RAW, labels)),
+        0 if data.len() > offset + 4 => Some((offset + 4, DataLink::ETHERNET, labels)),
+        _ => None,
+    }
+}
+
+
+/// Decapsulates the packets of a pcapng, creating the interfaces of the inner packets.
+///
+/// A decapsulated packet is moved to an interface with the same options as its original interface but with the inner link type.
+/// These interfaces are created the first time they are needed and reused afterwards.
+/// The removed layers are kept as custom binary options of the packets, one per layer, from the outermost to the innermost,
+/// encoded by [`TunnelLayer::to_bytes`] and read back by [`tunnel_layers`]. They can also be kept as comments.
+///
+/// The Enhanced Packet Blocks and Packet Blocks are decapsulated, the other blocks are only renumbered.
+///
+/// # Example
+/// ```rust,no_run
+/// # tokio_test::block_on(async {
+/// use tokio::fs::File;
+///
+/// use pcap_file_tokio::pcapng::{Block, PcapNgReader, PcapNgWriter};
+/// use pcap_file_tokio::transform::decap::Decapsulator;
+///
+/// let file_in = File::open("test.pcapng").await.expect("Error opening file");
+/// let mut reader = PcapNgReader::new(file_in).await.unwrap();
+///
+/// let file_out = File::create("out.pcapng").await.expect("Error creating file");
+/// let mut writer = PcapNgWriter::with_section_header(file_out, reader.section().clone()).await.unwrap();
+///
+/// let mut decapsulator = Decapsulator::default();
+/// while let Some(block) = reader.next_block().await {
+///     let mut block = block.unwrap();
+///     if let Some(interface) = decapsulator.decap_block(&mut block).unwrap() {
+///         writer.write_block(&Block::InterfaceDescription(interface)).await.unwrap();
+///     }
+///     writer.write_block(&block).await.unwrap();
+/// }
+/// # });
+/// ```
+#[derive(Clone, Debug)]
+pub struct Decapsulator {
+    /// Input interfaces of the current section
+    inputs: Vec<InterfaceDescriptionBlock<'static>>,
+    /// Output interface of an input interface and a link type
+    outputs: HashMap<(u32, u32), u32>,
+    /// Number of output interfaces in the current section
+    output_count: u32,
+    /// PEN of the custom options of the removed layers, `None` to not add them
+    pen: Option<u32>,
+    comments: bool,
+}
+
+/// Creates a new [`Decapsulator`] which adds custom options with the [`DEFAULT_DECAP_PEN`] and comments.
+impl Default for Decapsulator {
+    fn default() -> Self {
+        Decapsulator { inputs: vec![], outputs: HashMap::new(), output_count: 0, pen: Some(DEFAULT_DECAP_PEN), comments: true }
+    }
+}
+
+impl Decapsulator {
+    /// Sets the PEN of the custom options of the removed layers, `None` to not add them.
+    pub fn with_pen(mut self, pen: Option<u32>) -> Self {
+        self.pen = pen;
+        self
+    }
+
+    /// Sets whether the removed layers are added as comments to the packets, enabled by default.
+    pub fn with_comments(mut self, comments: bool) -> Self {
+        self.comments = comments;
+        self
+    }
+
+    /// Decapsulates a pcapng [`Block`] and renumbers its interface.
+    ///
+    /// All the blocks must be passed, in order, as the input interfaces are tracked.
+    /// Returns the new interface to write before the block, if any.
+    ///
+    /// # Errors
+    /// Returns [`PcapError::InvalidInterfaceId`] if a block references an unknown interface.
+    pub fn decap_block(&mut self, block: &mut Block) -> PcapResult<Option<InterfaceDescriptionBlock<'static>>> {
+        let interface_id = match block {
+            Block::SectionHeader(_) => {
+                self.inputs.clear();
+                self.outputs.clear();
+                self.output_count = 0;
+                return Ok(None);
+            },
+            Block::InterfaceDescription(interface) => {
+                let input_id = self.inputs.len() as u32;
+                self.outputs.insert((input_id, u32::from(interface.linktype)), self.output_count);
+                self.output_count += 1;
+                self.inputs.push(interface.clone().into_owned());
+                return Ok(None);
+            },
+            Block::InterfaceStatistics(stats) => {
+                stats.interface_id = self.output_id(stats.interface_id)?;
+                return Ok(None);
+            },
+            Block::EnhancedPacket(packet) => packet.interface_id,
+            Block::Packet(packet) => packet.interface_id as u32,
+            _ => return Ok(None),
+        };
+
+        let input = self.inputs.get(interface_id as usize).ok_or(PcapError::InvalidInterfaceId(interface_id))?;
+
+        let decapsulation = match decapsulate(input.linktype, block.data()) {
+            Some(decapsulation) => decapsulation,
+            None => {
+                let output_id = self.output_id(interface_id)?;
+                set_interface_id(block, output_id);
+                return Ok(None);
+            },
+        };
+
+        let mut new_interface = None;
+        let output_id = *self.outputs.entry((interface_id, u32::from(decapsulation.datalink))).or_insert_with(|| {
+            let mut interface = input.clone();
+            interface.linktype = decapsulation.datalink;
+            new_interface = Some(interface);
+
+            self.output_count += 1;
+            self.output_count - 1
+        });
+
+        let offset = decapsulation.offset;
+        match block {
+            Block::EnhancedPacket(packet) => {
+                packet.data = packet.data[offset..].to_vec().into();
+                packet.original_len = packet.original_len.saturating_sub(offset as u32);
+            },
+            Block::Packet(packet) => {
+                packet.data = packet.data[offset..].to_vec().into();
+                packet.captured_len = packet.data.len() as u32;
+                packet.original_len = packet.original_len.saturating_sub(offset as u32);
+            },
+            _ => unreachable!(),
+        }
+        set_interface_id(block, output_id);
+
+        if let Some(pen) = self.pen {
+            for layer in &decapsulation.layers {
+                block.add_custom_binary(pen, layer.to_bytes());
+            }
+        }
+
+        if self.comments {
+            for layer in &decapsulation.layers {
+                block.add_comment(layer.to_string());
+            }
+        }
+
+        Ok(new_interface)
+    }
+
+    /// Returns the output id of an input interface, for the packets which aren't decapsulated.
+    fn output_id(&self, interface_id: u32) -> PcapResult<u32> {
+        let input = self.inputs.get(interface_id as usize).ok_or(PcapError::InvalidInterfaceId(interface_id))?;
+        Ok(self.outputs[&(interface_id, u32::from(input.linktype))])
+    }
+}
+
+fn set_interface_id(block: &mut Block, interface_id: u32) {
+    match block {
+        Block::EnhancedPacket(packet) => packet.interface_id = interface_id,
+        Block::Packet(packet) => packet.interface_id = interface_id as u16,
+        _ => {},
+    }
+}
+
+/// Decapsulates a [`PcapPacket`] captured on a link of type `datalink`.
+///
+/// Returns the removed layers and the link type of the inner packet, or `None` if the packet isn't encapsulated.
+pub fn decap_pcap_packet(packet: &mut PcapPacket, datalink: DataLink) -> Option<(DataLink, Vec<TunnelLayer>)> {
+    let decapsulation = decapsulate(datalink, &packet.data)?;
+
+    packet.data = packet.data[decapsulation.offset..].to_vec().into();
+    packet.orig_len = packet.orig_len.saturating_sub(decapsulation.offset as u32);
+
+    Some((decapsulation.datalink, decapsulation.layers))
+}
+
+
+/// Decapsulates all the packets of a pcap.
+///
+/// The link type of the output is the link type of the first packet, after decapsulation.
+///
+/// # Errors
+/// Returns [`PcapError::MixedLinkTypes`] if the packets don't all have the same link type after decapsulation,
+/// e.g. if some packets aren't encapsulated.
+pub async fn decap_pcap<R, W>(reader: R, writer: W) -> PcapResult<W>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut reader = PcapReader::new(reader).await?;
+    let header = reader.header();
+
+    let mut pending = Some(writer);
+    let mut output: Option<(DataLink, PcapWriter<W>)> = None;
+
+    while let Some(packet) = reader.next_packet().await {
+        let mut packet = packet?;
+        let datalink = decap_pcap_packet(&mut packet, header.datalink).map_or(header.datalink, |(datalink, _)| datalink);
+
+        let pcap_writer = match &mut output {
+            Some((linktype, pcap_writer)) => {
+                if *linktype != datalink {
+                    return Err(PcapError::MixedLinkTypes(*linktype, datalink));
+                }
+                pcap_writer
+            },
+            None => {
+                let header = crate::pcap::PcapHeader { datalink, ..header };
+                let pcap_writer = PcapWriter::with_header(pending.take().unwrap(), header).await?;
+                &mut output.insert((datalink, pcap_writer)).1
+            },
+        };
+
+        pcap_writer.write_packet(&packet).await?;
+    }
+
+    match (output, pending) {
+        (Some((_, pcap_writer)), _) => Ok(pcap_writer.into_writer()),
+        (None, Some(writer)) => Ok(PcapWriter::with_header(writer, header).await?.into_writer()),
+        (None, None) => unreachable!(),
+    }
+}
+
+/// Decapsulates all the packets of a pcapng with a [`Decapsulator`].
+///
+/// Returns the wrapped writer.
+pub async fn decap_pcapng<R, W>(reader: R, writer: W, mut decapsulator: Decapsulator) -> PcapResult<W>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin + Send,
+{
+    let mut reader = PcapNgReader::new(reader).await?;
+    let mut writer = PcapNgWriter::with_section_header(writer, reader.section().clone()).await?;
+
+    while let Some(block) = reader.next_block().await {
+        let mut block = block?;
+        if let Some(interface) = decapsulator.decap_block(&mut block)? {
+            writer.write_block(&Block::InterfaceDescription(interface)).await?;
+        }
+        writer.write_block(&block).await?;
+    }
+
+    Ok(writer.into_inner())
+}
@@ -0,0 +1,457 @@
+//! Duplicate packets removal, like `editcap -d`.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hasher;
+use std::ops::Range;
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use super::TransformPacket;
+use crate::pcap::{PcapPacket, PcapReader, PcapWriter};
+use crate::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use crate::pcapng::{Block, PcapNgReader, PcapNgWriter};
+use crate::{PcapError, PcapResult};
+
+
+/// Window in which a [`Deduplicator`] looks for duplicates.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DedupWindow {
+    /// A packet is compared to this number of previous unique packets.
+    Packets(usize),
+    /// A packet is compared to the unique packets captured at most this long before it.
+    Duration(Duration),
+}
+
+/// How the packets kept by a [`Deduplicator`] record the number of duplicates dropped.
+///
+/// Only pcapng packets can be tagged, a packet without duplicates is never tagged.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DedupTag {
+    /// The packets are not tagged.
+    None,
+    /// A comment "`N` duplicates dropped" is added to the packet.
+    Comment,
+    /// A custom binary option with this Private Enterprise Number is added to the packet,
+    /// its value is the number of duplicates dropped as a big endian u64.
+    CustomOption {
+        /// Private Enterprise Number of the option
+        pen: u32,
+    },
+}
+
+/// Configuration of a [`Deduplicator`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DedupConfig {
+    /// Window in which the duplicates are searched.
+    pub window: DedupWindow,
+
+    /// Byte ranges of the packet data that are ignored when comparing packets, e.g. the IPv4 TTL and checksum.
+    pub ignored_ranges: Vec<Range<usize>>,
+
+    /// How the kept packets are tagged.
+    ///
+    /// When the packets are tagged, a kept packet is held by the deduplicator until it leaves the window.
+    pub tag: DedupTag,
+}
+
+/// Creates a new [`DedupConfig`] with these parameters, the defaults of `editcap -d`:
+///
+/// ```rust,ignore
+/// DedupConfig {
+///     window: DedupWindow::Packets(5),
+///     ignored_ranges: vec![],
+///     tag: DedupTag::None,
+/// };
+/// ```
+impl Default for DedupConfig {
+    fn default() -> Self {
+        DedupConfig { window: DedupWindow::Packets(5), ignored_ranges: vec![], tag: DedupTag::None }
+    }
+}
+
+/// Counters maintained by a [`Deduplicator`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct DedupStats {
+    /// Number of packets pushed.
+    pub packets: u64,
+    /// Number of duplicate packets dropped.
+    pub duplicates: u64,
+}
+
+
+/// Removes the duplicate packets of a capture.
+///
+/// Packets are pushed in capture order and the unique ones are popped in the same order.
+/// Two packets are duplicates if their data, excluding the [`DedupConfig::ignored_ranges`], are equal. The data are hashed
+/// to find the candidates, then compared byte-for-byte, so the data of the unique packets of the window are kept.
+///
+/// [`dedup_pcap`] and [`dedup_pcapng`] deduplicate whole files.
+///
+/// # Example
+/// ```rust,no_run
+/// # tokio_test::block_on(async {
+/// use tokio::fs::File;
+///
+/// use pcap_file_tokio::pcap::{PcapReader, PcapWriter};
+/// use pcap_file_tokio::transform::dedup::Deduplicator;
+///
+/// let file_in = File::open("test.pcap").await.expect("Error opening file");
+/// let mut pcap_reader = PcapReader::new(file_in).await.unwrap();
+///
+/// let file_out = File::create("out.pcap").await.expect("Error creating file");
+/// let mut pcap_writer = PcapWriter::with_header(file_out, pcap_reader.header()).await.unwrap();
+///
+/// let mut deduplicator = Deduplicator::default();
+/// while let Some(pkt) = pcap_reader.next_packet().await {
+///     deduplicator.push_packet(pkt.unwrap().into_owned());
+///
+///     while let Some(pkt) = deduplicator.pop() {
+///         pcap_writer.write_packet(&pkt).await.unwrap();
+///     }
+/// }
+///
+/// while let Some(pkt) = deduplicator.flush() {
+///     pcap_writer.write_packet(&pkt).await.unwrap();
+/// }
+///
+/// println!("{} duplicates dropped", deduplicator.stats().duplicates);
+/// # });
+/// ```
+#[derive(Debug)]
+pub struct Deduplicator<T> {
+    config: DedupConfig,
+    window: VecDeque<Fingerprint>,
+    /// Unique packets of the window by hash and length of their data, with their sequence number and their data
+    index: HashMap<(u64, usize), Vec<Candidate>>,
+    queue: VecDeque<Queued<T>>,
+    next_seq: u64,
+    last_timestamp: Duration,
+    stats: DedupStats,
+}
+
+impl<T: TransformPacket> Deduplicator<T> {
+    /// Creates a new [`Deduplicator`] with the given configuration.
+    pub fn new(mut config: DedupConfig) -> Self {
+        config.ignored_ranges.sort_by_key(|range| range.start);
+
+        Deduplicator {
+            config,
+            window: VecDeque::new(),
+            index: HashMap::new(),
+            queue: VecDeque::new(),
+            next_seq: 0,
+            last_timestamp: Duration::ZERO,
+            stats: DedupStats::default(),
+        }
+    }
+
+    /// Pushes a packet with the given absolute timestamp.
+    ///
+    /// Returns false if the packet is a duplicate and has been dropped.
+    pub fn push(&mut self, timestamp: Duration, packet: T) -> bool {
+        self.stats.packets += 1;
+        self.last_timestamp = timestamp;
+
+        if let DedupWindow::Duration(duration) = self.config.window {
+            while self.window.front().is_some_and(|oldest| timestamp.saturating_sub(oldest.timestamp) > duration) {
+                self.evict_oldest();
+            }
+        }
+
+        let (key, data) = self.fingerprint(packet.data());
+        let original = self.index.get(&key).and_then(|entries| entries.iter().find(|(_, other)| *other == data));
+        if let Some(&(seq, _)) = original {
+            self.stats.duplicates += 1;
+            if let Some(queued) = self.queued_mut(seq) {
+                queued.duplicates += 1;
+            }
+            return false;
+        }
+
+        let seq = self.enqueue(packet, self.config.tag != DedupTag::None);
+        self.index.entry(key).or_default().push((seq, data));
+        self.window.push_back(Fingerprint { seq, timestamp, key });
+
+        if let DedupWindow::Packets(count) = self.config.window {
+            while self.window.len() > count {
+                self.evict_oldest();
+            }
+        }
+
+        true
+    }
+
+    /// Pushes an item that isn't deduplicated, it is popped after the packets pushed before it.
+    pub fn push_unordered(&mut self, item: T) {
+        self.enqueue(item, false);
+    }
+
+    /// Pops the next unique packet if it can't get more duplicates or if the packets are not tagged.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.queue.front()?.held {
+            return None;
+        }
+
+        self.pop_front()
+    }
+
+    /// Pops the next unique packet regardless of the window.
+    ///
+    /// Must be called in a loop at the end of the capture to retrieve the remaining packets.
+    pub fn flush(&mut self) -> Option<T> {
+        self.pop_front()
+    }
+
+    /// Forgets all the previous packets, the next packets are never considered as their duplicates.
+    pub fn clear_window(&mut self) {
+        while !self.window.is_empty() {
+            self.evict_oldest();
+        }
+    }
+
+    /// Returns the number of packets waiting to be popped.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns true if no packet is waiting to be popped.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Returns the counters of the deduplicator.
+    pub fn stats(&self) -> DedupStats {
+        self.stats
+    }
+
+    /// Returns the configuration of the deduplicator.
+    pub fn config(&self) -> &DedupConfig {
+        &self.config
+    }
+
+    /// Returns the key of the data in the index and the data without the ignored ranges.
+    fn fingerprint(&self, data: &[u8]) -> ((u64, usize), Vec<u8>) {
+        let mut masked = Vec::with_capacity(data.len());
+
+        let mut start = 0;
+        for range in &self.config.ignored_ranges {
+            let end = range.start.min(data.len());
+            if start < end {
+                masked.extend_from_slice(&data[start..end]);
+            }
+            start = start.max(range.end);
+        }
+        if start < data.len() {
+            masked.extend_from_slice(&data[start..]);
+        }
+
+        let mut hasher = DefaultHasher::new();
+        hasher.write(&masked);
+
+        ((hasher.finish(), data.len()), masked)
+    }
+
+    fn enqueue(&mut self, item: T, held: bool) -> u64 {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.queue.push_back(Queued { seq, item, duplicates: 0, held });
+
+        seq
+    }
+
+    fn queued_mut(&mut self, seq: u64) -> Option<&mut Queued<T>> {
+        // The sequence numbers of the queue are contiguous
+        let first = self.queue.front()?.seq;
+        self.queue.get_mut(seq.checked_sub(first)? as usize)
+    }
+
+    fn evict_oldest(&mut self) {
+        if let Some(fingerprint) = self.window.pop_front() {
+            if let Some(entries) = self.index.get_mut(&fingerprint.key) {
+                entries.retain(|(seq, _)| *seq != fingerprint.seq);
+                if entries.is_empty() {
+                    self.index.remove(&fingerprint.key);
+                }
+            }
+            if let Some(queued) = self.queued_mut(fingerprint.seq) {
+                queued.held = false;
+            }
+        }
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        let Queued { mut item, duplicates, .. } = self.queue.pop_front()?;
+
+        if duplicates > 0 {
+            match self.config.tag {
+                DedupTag::None => {},
+                DedupTag::Comment => {
+                    item.add_comment(format!("{duplicates} duplicates dropped"));
+                },
+                DedupTag::CustomOption { pen } => {
+                    item.add_custom_binary(pen, duplicates.to_be_bytes().to_vec());
+                },
+            }
+        }
+
+        Some(item)
+    }
+}
+
+impl<T: TransformPacket> Default for Deduplicator<T> {
+    fn default() -> Self {
+        Deduplicator::new(DedupConfig::default())
+    }
+}
+
+impl<'a> Deduplicator<PcapPacket<'a>> {
+    /// Pushes a [`PcapPacket`].
+    ///
+    /// Returns false if the packet is a duplicate and has been dropped.
+    pub fn push_packet(&mut self, packet: PcapPacket<'a>) -> bool {
+        self.push(packet.timestamp, packet)
+    }
+}
+
+impl<'a> Deduplicator<Block<'a>> {
+    /// Pushes a pcapng [`Block`].
+    ///
+    /// `interfaces` are the interfaces of the current section, as returned by
+    /// [`PcapNgReader::interfaces`](crate::pcapng::PcapNgReader::interfaces). They are used to compute the timestamps of the packets.
+    ///
+    /// Enhanced Packet Blocks, Packet Blocks and Simple Packet Blocks are deduplicated, the Simple Packet Blocks get the timestamp
+    /// of the previous packet. A Section Header Block clears the window. The other blocks are kept as is.
+    ///
+    /// Returns false if the block is a duplicate and has been dropped.
+    ///
+    /// # Errors
+    /// Returns [`PcapError::InvalidInterfaceId`] if a packet references an unknown interface.
+    pub fn push_block(&mut self, block: Block<'a>, interfaces: &[InterfaceDescriptionBlock]) -> PcapResult<bool> {
+        let timestamp = match &block {
+            Block::EnhancedPacket(packet) => {
+                let interface = interfaces.get(packet.interface_id as usize).ok_or(PcapError::InvalidInterfaceId(packet.interface_id))?;
+                packet.absolute_timestamp(interface)
+            },
+            Block::Packet(packet) => {
+                let interface_id = packet.interface_id as u32;
+                let interface = interfaces.get(interface_id as usize).ok_or(PcapError::InvalidInterfaceId(interface_id))?;
+                interface.timestamp_to_duration(packet.timestamp)
+            },
+            Block::SimplePacket(_) => self.last_timestamp,
+            Block::SectionHeader(_) => {
+                self.clear_window();
+                self.push_unordered(block);
+                return Ok(true);
+            },
+            _ => {
+                self.push_unordered(block);
+                return Ok(true);
+            },
+        };
+
+        Ok(self.push(timestamp, block))
+    }
+}
+
+
+/// Removes the duplicate packets of a pcap.
+///
+/// Returns the wrapped writer and the [`DedupStats`].
+pub async fn dedup_pcap<R, W>(reader: R, writer: W, config: DedupConfig) -> PcapResult<(W, DedupStats)>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut reader = PcapReader::new(reader).await?;
+    let mut writer = PcapWriter::with_header(writer, reader.header()).await?;
+    let mut deduplicator = Deduplicator::new(config);
+
+    while let Some(packet) = reader.next_packet().await {
+        deduplicator.push_packet(packet?.into_owned());
+
+        while let Some(packet) = deduplicator.pop() {
+            writer.write_packet(&packet).await?;
+        }
+    }
+
+    while let Some(packet) = deduplicator.flush() {
+        writer.write_packet(&packet).await?;
+    }
+
+    Ok((writer.into_writer(), deduplicator.stats()))
+}
+
+/// Removes the duplicate packets of a pcapng.
+///
+/// The packets are tagged according to [`DedupConfig::tag`].
+/// Returns the wrapped writer and the [`DedupStats`].
+///
+/// # Example
+/// ```rust,no_run
+/// # tokio_test::block_on(async {
+/// use std::time::Duration;
+/// use tokio::fs::File;
+///
+/// use pcap_file_tokio::transform::dedup::{dedup_pcapng, DedupConfig, DedupTag, DedupWindow};
+///
+/// let file_in = File::open("test.pcapng").await.expect("Error opening file");
+/// let file_out = File::create("out.pcapng").await.expect("Error creating file");
+///
+/// let config = DedupConfig {
+///     window: DedupWindow::Duration(Duration::from_millis(1)),
+///     // Ignores the TTL and the checksum of IPv4 over Ethernet
+///     ignored_ranges: vec![22..23, 24..26],
+///     tag: DedupTag::Comment,
+/// };
+///
+/// let (_file_out, stats) = dedup_pcapng(file_in, file_out, config).await.unwrap();
+/// println!("{} duplicates dropped", stats.duplicates);
+/// # });
+/// ```
+pub async fn dedup_pcapng<R, W>(reader: R, writer: W, config: DedupConfig) -> PcapResult<(W, DedupStats)>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin + Send,
+{
+    let mut reader = PcapNgReader::new(reader).await?;
+    let mut writer = PcapNgWriter::with_section_header(writer, reader.section().clone()).await?;
+    let mut deduplicator = Deduplicator::new(config);
+
+    while let Some(block) = reader.next_block().await {
+        let block = block?.into_owned();
+        deduplicator.push_block(block, reader.interfaces())?;
+
+        while let Some(block) = deduplicator.pop() {
+            writer.write_block(&block).await?;
+        }
+    }
+
+    while let Some(block) = deduplicator.flush() {
+        writer.write_block(&block).await?;
+    }
+
+    Ok((writer.into_inner(), deduplicator.stats()))
+}
+
+
+/// Fingerprint of a unique packet in the window.
+#[derive(Debug)]
+struct Fingerprint {
+    seq: u64,
+    timestamp: Duration,
+    key: (u64, usize),
+}
+
+/// Sequence number and data, without the ignored ranges, of a unique packet in the window.
+type Candidate = (u64, Vec<u8>);
+
+/// Item waiting to be popped.
+#[derive(Debug)]
+struct Queued<T> {
+    seq: u64,
+    item: T,
+    duplicates: u64,
+    held: bool,
+}
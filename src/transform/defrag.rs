@@ -0,0 +1,528 @@
+//! IPv4 and IPv6 fragment reassembly.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::layers::{self, ETHERTYPE_IPV4, ETHERTYPE_IPV6, IPV6_DESTINATION, IPV6_FRAGMENT, IPV6_HOP_BY_HOP, IPV6_ROUTING};
+use crate::pcap::PcapPacket;
+use crate::pcapng::blocks::enhanced_packet::{EnhancedPacketBlock, EnhancedPacketOption};
+use crate::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use crate::{DataLink, PcapResult};
+
+
+/// Configuration of a [`Defragmenter`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct DefragConfig {
+    /// Maximum time between the first fragment of a datagram and its reassembly.
+    ///
+    /// Incomplete datagrams older than this are discarded.
+    pub timeout: Duration,
+
+    /// Maximum length of a reassembled datagram payload, excluding the headers of the first fragment.
+    pub max_datagram_len: usize,
+
+    /// Maximum number of datagrams being reassembled at the same time.
+    pub max_flows: usize,
+
+    /// Maximum number of bytes buffered for all the datagrams being reassembled.
+    pub max_buffered_bytes: usize,
+}
+
+/// Creates a new [`DefragConfig`] with these parameters:
+///
+/// ```rust,ignore
+/// DefragConfig {
+///     timeout: Duration::from_secs(60),
+///     max_datagram_len: 65535,
+///     max_flows: 4096,
+///     max_buffered_bytes: 16 * 1024 * 1024,
+/// };
+/// ```
+impl Default for DefragConfig {
+    fn default() -> Self {
+        DefragConfig { timeout: Duration::from_secs(60), max_datagram_len: 65535, max_flows: 4096, max_buffered_bytes: 16 * 1024 * 1024 }
+    }
+}
+
+/// Counters maintained by a [`Defragmenter`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct DefragStats {
+    /// Number of fragments buffered.
+    pub fragments: u64,
+    /// Number of datagrams reassembled.
+    pub reassembled: u64,
+    /// Number of incomplete datagrams discarded because of the timeout.
+    pub timed_out: u64,
+    /// Number of incomplete datagrams discarded because of the memory limits.
+    pub evicted: u64,
+    /// Number of fragments that couldn't be reassembled (truncated, overlapping the end, too long...).
+    pub malformed: u64,
+}
+
+/// Result of pushing a packet into a [`Defragmenter`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DefragOutcome {
+    /// The packet is not a fragment, or is a fragment that can't be reassembled: it should be written as is.
+    Passthrough,
+    /// The packet is a fragment that has been buffered until the rest of its datagram arrives.
+    Buffered,
+    /// The packet completed a datagram.
+    Reassembled(ReassembledPacket),
+}
+
+/// A datagram reassembled from several fragments.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReassembledPacket {
+    /// Interface of the fragments (always 0 for pcap packets).
+    pub interface_id: u32,
+    /// Timestamp of the first fragment received.
+    pub timestamp: Duration,
+    /// Link-layer header, network header and reassembled payload.
+    pub data: Vec<u8>,
+    /// Frame numbers, starting at 1, of the fragments in the order they were received.
+    pub source_frames: Vec<u64>,
+}
+
+impl ReassembledPacket {
+    /// Returns a comment listing the source frames of the packet.
+    pub fn comment(&self) -> String {
+        let frames: Vec<String> = self.source_frames.iter().map(|frame| frame.to_string()).collect();
+        format!("Reassembled from frames {}", frames.join(", "))
+    }
+
+    /// Returns a borrowed [`PcapPacket`] of the reassembled datagram.
+    pub fn to_pcap_packet(&self) -> PcapPacket<'_> {
+        PcapPacket::new(self.timestamp, self.data.len() as u32, &self.data)
+    }
+
+    /// Returns a borrowed [`EnhancedPacketBlock`] of the reassembled datagram, with a comment listing the source frames.
+    ///
+    /// The `interface` is used to convert the timestamp into its resolution.
+    pub fn to_enhanced_packet(&self, interface: &InterfaceDescriptionBlock) -> PcapResult<EnhancedPacketBlock<'_>> {
+        let mut packet = EnhancedPacketBlock {
+            interface_id: self.interface_id,
+            timestamp: Duration::ZERO,
+            original_len: self.data.len() as u32,
+            data: Cow::Borrowed(&self.data),
+            options: vec![EnhancedPacketOption::Comment(Cow::Owned(self.comment()))],
+        };
+        packet.set_absolute_timestamp(interface, self.timestamp)?;
+
+        Ok(packet)
+    }
+}
+
+
+/// Reassembles IPv4 and IPv6 fragmented datagrams.
+///
+/// Packets are pushed one by one, in capture order. Every pushed packet is numbered, starting at 1,
+/// and these numbers are reported in [`ReassembledPacket::source_frames`].
+///
+/// # Example
+/// ```rust,no_run
+/// # tokio_test::block_on(async {
+/// use tokio::fs::File;
+///
+/// use pcap_file_tokio::pcap::{PcapReader, PcapWriter};
+/// use pcap_file_tokio::transform::defrag::{DefragOutcome, Defragmenter};
+///
+/// let file_in = File::open("test.pcap").await.expect("Error opening file");
+/// let mut pcap_reader = PcapReader::new(file_in).await.unwrap();
+/// let header = pcap_reader.header();
+///
+/// let file_out = File::create("out.pcap").await.expect("Error creating file");
+/// let mut pcap_writer = PcapWriter::with_header(file_out, header).await.unwrap();
+///
+/// let mut defragmenter = Defragmenter::default();
+/// while let Some(pkt) = pcap_reader.next_packet().await {
+///     let pkt = pkt.unwrap();
+///
+///     match defragmenter.push_pcap_packet(&pkt, header.datalink) {
+///         DefragOutcome::Passthrough => pcap_writer.write_packet(&pkt).await.unwrap(),
+///         DefragOutcome::Buffered => 0,
+///         DefragOutcome::Reassembled(datagram) => pcap_writer.write_packet(&datagram.to_pcap_packet()).await.unwrap(),
+///     };
+/// }
+/// # });
+/// ```
+#[derive(Debug, Default)]
+pub struct Defragmenter {
+    config: DefragConfig,
+    flows: HashMap<FlowKey, Flow>,
+    buffered_bytes: usize,
+    frame_count: u64,
+    flow_count: u64,
+    stats: DefragStats,
+}
+
+impl Defragmenter {
+    /// Creates a new [`Defragmenter`] with the given configuration.
+    pub fn new(config: DefragConfig) -> Self {
+        Defragmenter { config, ..Default::default() }
+    }
+
+    /// Pushes a [`PcapPacket`] captured on a link of type `datalink`.
+    pub fn push_pcap_packet(&mut self, packet: &PcapPacket, datalink: DataLink) -> DefragOutcome {
+        self.push(0, packet.timestamp, datalink, &packet.data)
+    }
+
+    /// Pushes an [`EnhancedPacketBlock`] captured on `interface`.
+    pub fn push_enhanced_packet(&mut self, packet: &EnhancedPacketBlock, interface: &InterfaceDescriptionBlock) -> DefragOutcome {
+        self.push(packet.interface_id, packet.absolute_timestamp(interface), interface.linktype, &packet.data)
+    }
+
+    /// Pushes the next packet of the capture.
+    ///
+    /// Fragments are only reassembled with fragments from the same `interface_id`.
+    pub fn push(&mut self, interface_id: u32, timestamp: Duration, datalink: DataLink, data: &[u8]) -> DefragOutcome {
+        self.frame_count += 1;
+        self.expire(timestamp);
+
+        let fragment = match parse_fragment(interface_id, datalink, data) {
+            Ok(Some(fragment)) => fragment,
+            Ok(None) => return DefragOutcome::Passthrough,
+            Err(()) => {
+                self.stats.malformed += 1;
+                return DefragOutcome::Passthrough;
+            },
+        };
+
+        let end = fragment.offset + fragment.payload.len();
+        let header_len = fragment.header.as_ref().map(|header| header.bytes.len()).unwrap_or(0);
+        let size = fragment.payload.len() + header_len;
+
+        if end > self.config.max_datagram_len || size > self.config.max_buffered_bytes || (fragment.more && fragment.payload.len() % 8 != 0) {
+            self.discard(&fragment.key);
+            self.stats.malformed += 1;
+            return DefragOutcome::Passthrough;
+        }
+
+        // Make room for the fragment
+        if !self.flows.contains_key(&fragment.key) && self.flows.len() >= self.config.max_flows {
+            self.evict_oldest();
+        }
+        while self.buffered_bytes + size > self.config.max_buffered_bytes && !self.flows.is_empty() {
+            self.evict_oldest();
+        }
+
+        self.flow_count += 1;
+        let flow_count = self.flow_count;
+        let flow = self.flows.entry(fragment.key.clone()).or_insert_with(|| Flow::new(flow_count, timestamp));
+
+        let consistent = match (fragment.more, flow.total_len) {
+            (false, Some(total_len)) => total_len == end,
+            (false, None) => flow.fragments.iter().all(|frag| frag.offset + frag.data.len() <= end),
+            (true, Some(total_len)) => end <= total_len,
+            (true, None) => true,
+        };
+        if !consistent {
+            self.discard(&fragment.key);
+            self.stats.malformed += 1;
+            return DefragOutcome::Passthrough;
+        }
+
+        if !fragment.more {
+            flow.total_len = Some(end);
+        }
+        if flow.header.is_none() {
+            flow.header = fragment.header;
+        }
+        flow.fragments.push(FragmentData { offset: fragment.offset, data: fragment.payload.to_vec() });
+        flow.frames.push(self.frame_count);
+        flow.size += size;
+        self.buffered_bytes += size;
+        self.stats.fragments += 1;
+
+        if !flow.is_complete() {
+            return DefragOutcome::Buffered;
+        }
+
+        let flow = self.flows.remove(&fragment.key).unwrap();
+        self.buffered_bytes -= flow.size;
+
+        match flow.assemble() {
+            Some(packet) => {
+                self.stats.reassembled += 1;
+                DefragOutcome::Reassembled(ReassembledPacket { interface_id, ..packet })
+            },
+            None => {
+                self.stats.malformed += 1;
+                DefragOutcome::Passthrough
+            },
+        }
+    }
+
+    /// Discards the incomplete datagrams whose first fragment is older than the timeout.
+    ///
+    /// This is done automatically by [`Self::push()`], but can be called to release memory during idle periods.
+    ///
+    /// Returns the number of discarded datagrams.
+    pub fn expire(&mut self, now: Duration) -> usize {
+        let timeout = self.config.timeout;
+        let expired: Vec<FlowKey> =
+            self.flows.iter().filter(|(_, flow)| now.saturating_sub(flow.first_seen) > timeout).map(|(key, _)| key.clone()).collect();
+
+        for key in &expired {
+            self.discard(key);
+        }
+        self.stats.timed_out += expired.len() as u64;
+
+        expired.len()
+    }
+
+    /// Returns the number of datagrams currently being reassembled.
+    pub fn pending(&self) -> usize {
+        self.flows.len()
+    }
+
+    /// Returns the number of bytes currently buffered.
+    pub fn buffered_bytes(&self) -> usize {
+        self.buffered_bytes
+    }
+
+    /// Returns the counters of the defragmenter.
+    pub fn stats(&self) -> DefragStats {
+        self.stats
+    }
+
+    /// Returns the configuration of the defragmenter.
+    pub fn config(&self) -> &DefragConfig {
+        &self.config
+    }
+
+    /// Removes the oldest flow.
+    fn evict_oldest(&mut self) {
+        let oldest = self.flows.iter().min_by_key(|(_, flow)| flow.seq).map(|(key, _)| key.clone());
+        if let Some(key) = oldest {
+            self.discard(&key);
+            self.stats.evicted += 1;
+        }
+    }
+
+    /// Removes a flow and releases its memory.
+    fn discard(&mut self, key: &FlowKey) {
+        if let Some(flow) = self.flows.remove(key) {
+            self.buffered_bytes -= flow.size;
+        }
+    }
+}
+
+
+/// Identifies the fragments of a same datagram.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum FlowKey {
+    V4 { interface_id: u32, src: [u8; 4], dst: [u8; 4], id: u16, protocol: u8 },
+    V6 { interface_id: u32, src: [u8; 16], dst: [u8; 16], id: u32 },
+}
+
+/// Headers preceding the payload of the first fragment.
+#[derive(Clone, Debug)]
+struct FirstHeader {
+    /// Link-layer and network headers of the fragment at offset 0
+    bytes: Vec<u8>,
+    kind: HeaderKind,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum HeaderKind {
+    V4 {
+        /// Offset of the IPv4 header
+        ip_offset: usize,
+    },
+    V6 {
+        /// Offset of the IPv6 header
+        ip_offset: usize,
+        /// Offset of the "next header" field pointing to the fragment header
+        next_header_offset: usize,
+        /// "Next header" field of the fragment header
+        next_header: u8,
+    },
+}
+
+/// A parsed fragment.
+struct Fragment<'a> {
+    key: FlowKey,
+    offset: usize,
+    more: bool,
+    payload: &'a [u8],
+    header: Option<FirstHeader>,
+}
+
+#[derive(Debug)]
+struct FragmentData {
+    offset: usize,
+    data: Vec<u8>,
+}
+
+#[derive(Debug)]
+struct Flow {
+    seq: u64,
+    first_seen: Duration,
+    header: Option<FirstHeader>,
+    total_len: Option<usize>,
+    fragments: Vec<FragmentData>,
+    frames: Vec<u64>,
+    size: usize,
+}
+
+impl Flow {
+    fn new(seq: u64, first_seen: Duration) -> Self {
+        Flow { seq, first_seen, header: None, total_len: None, fragments: vec![], frames: vec![], size: 0 }
+    }
+
+    /// Returns true if all the bytes of the datagram have been received.
+    fn is_complete(&self) -> bool {
+        let total_len = match (self.total_len, &self.header) {
+            (Some(total_len), Some(_)) => total_len,
+            _ => return false,
+        };
+
+        let mut ranges: Vec<(usize, usize)> = self.fragments.iter().map(|frag| (frag.offset, frag.offset + frag.data.len())).collect();
+        ranges.sort_unstable();
+
+        let mut covered = 0;
+        for (start, end) in ranges {
+            if start > covered {
+                return false;
+            }
+            covered = covered.max(end);
+        }
+
+        covered >= total_len
+    }
+
+    /// Builds the reassembled datagram. The flow must be complete.
+    fn assemble(self) -> Option<ReassembledPacket> {
+        let total_len = self.total_len?;
+        let header = self.header?;
+
+        // Write the fragments in reverse order so that the first received wins on overlaps
+        let mut payload = vec![0_u8; total_len];
+        for frag in self.fragments.iter().rev() {
+            payload[frag.offset..frag.offset + frag.data.len()].copy_from_slice(&frag.data);
+        }
+
+        let mut data = header.bytes;
+        match header.kind {
+            HeaderKind::V4 { ip_offset } => {
+                let ip_len = u16::try_from(data.len() - ip_offset + total_len).ok()?;
+                let flags = layers::read_u16(&data, ip_offset + 6)? & 0x4000;
+
+                data[ip_offset + 2..ip_offset + 4].copy_from_slice(&ip_len.to_be_bytes());
+                data[ip_offset + 6..ip_offset + 8].copy_from_slice(&flags.to_be_bytes());
+                layers::update_ipv4_header_checksum(&mut data[ip_offset..]);
+            },
+            HeaderKind::V6 { ip_offset, next_header_offset, next_header } => {
+                let payload_len = u16::try_from(data.len() - ip_offset - 40 + total_len).ok()?;
+
+                data[next_header_offset] = next_header;
+                data[ip_offset + 4..ip_offset + 6].copy_from_slice(&payload_len.to_be_bytes());
+            },
+        }
+        data.extend_from_slice(&payload);
+
+        Some(ReassembledPacket { interface_id: 0, timestamp: self.first_seen, data, source_frames: self.frames })
+    }
+}
+
+/// Parses a fragment.
+///
+/// Returns `Ok(None)` if the packet is not a fragment and `Err(())` if it is a fragment that can't be reassembled.
+fn parse_fragment(interface_id: u32, datalink: DataLink, data: &[u8]) -> Result<Option<Fragment<'_>>, ()> {
+    match layers::network_layer(datalink, data) {
+        Some((ip_offset, ETHERTYPE_IPV4)) => parse_ipv4_fragment(interface_id, data, ip_offset),
+        Some((ip_offset, ETHERTYPE_IPV6)) => parse_ipv6_fragment(interface_id, data, ip_offset),
+        _ => Ok(None),
+    }
+}
+
+fn parse_ipv4_fragment(interface_id: u32, data: &[u8], ip_offset: usize) -> Result<Option<Fragment<'_>>, ()> {
+    let ip = &data[ip_offset..];
+    if ip.len() < 20 || ip[0] >> 4 != 4 {
+        return Ok(None);
+    }
+
+    let ihl = (ip[0] & 0x0F) as usize * 4;
+    let total_len = layers::read_u16(ip, 2).unwrap() as usize;
+    let flags_offset = layers::read_u16(ip, 6).unwrap();
+    let more = flags_offset & 0x2000 != 0;
+    let offset = (flags_offset & 0x1FFF) as usize * 8;
+
+    if !more && offset == 0 {
+        return Ok(None);
+    }
+    if ihl < 20 || total_len < ihl || ip.len() < total_len {
+        return Err(());
+    }
+
+    let key = FlowKey::V4 {
+        interface_id,
+        src: ip[12..16].try_into().unwrap(),
+        dst: ip[16..20].try_into().unwrap(),
+        id: layers::read_u16(ip, 4).unwrap(),
+        protocol: ip[9],
+    };
+
+    let header = (offset == 0).then(|| FirstHeader { bytes: data[..ip_offset + ihl].to_vec(), kind: HeaderKind::V4 { ip_offset } });
+
+    Ok(Some(Fragment { key, offset, more, payload: &ip[ihl..total_len], header }))
+}
+
+fn parse_ipv6_fragment(interface_id: u32, data: &[u8], ip_offset: usize) -> Result<Option<Fragment<'_>>, ()> {
+    let ip = &data[ip_offset..];
+    if ip.len() < 40 || ip[0] >> 4 != 6 {
+        return Ok(None);
+    }
+
+    // Walk the extension headers until the fragment header
+    let mut next_header = ip[6];
+    let mut next_header_offset = ip_offset + 6;
+    let mut pos = 40;
+    loop {
+        match next_header {
+            IPV6_HOP_BY_HOP | IPV6_ROUTING | IPV6_DESTINATION => {
+                if ip.len() < pos + 2 {
+                    return Ok(None);
+                }
+                next_header = ip[pos];
+                next_header_offset = ip_offset + pos;
+                pos += (ip[pos + 1] as usize + 1) * 8;
+            },
+            IPV6_FRAGMENT => break,
+            _ => return Ok(None),
+        }
+    }
+
+    if ip.len() < pos + 8 {
+        return Err(());
+    }
+
+    let offset_flags = layers::read_u16(ip, pos + 2).unwrap();
+    let offset = (offset_flags >> 3) as usize * 8;
+    let more = offset_flags & 1 != 0;
+
+    // Atomic fragment (RFC 6946)
+    if !more && offset == 0 {
+        return Ok(None);
+    }
+
+    let end = 40 + layers::read_u16(ip, 4).unwrap() as usize;
+    if ip.len() < end || end < pos + 8 {
+        return Err(());
+    }
+
+    let key = FlowKey::V6 {
+        interface_id,
+        src: ip[8..24].try_into().unwrap(),
+        dst: ip[24..40].try_into().unwrap(),
+        id: layers::read_u32(ip, pos + 4).unwrap(),
+    };
+
+    let header = (offset == 0).then(|| FirstHeader {
+        bytes: data[..ip_offset + pos].to_vec(),
+        kind: HeaderKind::V6 { ip_offset, next_header_offset, next_header: ip[pos] },
+    });
+
+    Ok(Some(Fragment { key, offset, more, payload: &ip[pos + 8..end], header }))
+}
@@ -0,0 +1,207 @@
+//! Minimal protocol helpers shared by the transforms.
+
+use crate::DataLink;
+
+
+/// IPv4 ethertype
+pub(crate) const ETHERTYPE_IPV4: u16 = 0x0800;
+/// IPv6 ethertype
+pub(crate) const ETHERTYPE_IPV6: u16 = 0x86DD;
+/// 802.1Q ethertype
+pub(crate) const ETHERTYPE_VLAN: u16 = 0x8100;
+/// 802.1ad ethertype
+pub(crate) const ETHERTYPE_QINQ: u16 = 0x88A8;
+/// Legacy double tagging ethertype
+pub(crate) const ETHERTYPE_QINQ_OLD: u16 = 0x9100;
+
+/// MPLS unicast ethertype
+pub(crate) const ETHERTYPE_MPLS: u16 = 0x8847;
+/// MPLS multicast ethertype
+pub(crate) const ETHERTYPE_MPLS_MULTICAST: u16 = 0x8848;
+/// Transparent Ethernet bridging, i.e. an Ethernet frame, used as GRE and GENEVE protocol type
+pub(crate) const ETHERTYPE_TEB: u16 = 0x6558;
+/// ERSPAN type II GRE protocol type
+pub(crate) const ETHERTYPE_ERSPAN_II: u16 = 0x88BE;
+/// ERSPAN type III GRE protocol type
+pub(crate) const ETHERTYPE_ERSPAN_III: u16 = 0x22EB;
+
+/// IPv4 encapsulation protocol number
+pub(crate) const IPPROTO_IPIP: u8 = 4;
+/// TCP protocol number
+pub(crate) const IPPROTO_TCP: u8 = 6;
+/// UDP protocol number
+pub(crate) const IPPROTO_UDP: u8 = 17;
+/// IPv6 encapsulation protocol number
+pub(crate) const IPPROTO_IPV6: u8 = 41;
+/// GRE protocol number
+pub(crate) const IPPROTO_GRE: u8 = 47;
+/// ICMP protocol number
+pub(crate) const IPPROTO_ICMP: u8 = 1;
+/// IPv6 authentication header
+pub(crate) const IPPROTO_AH: u8 = 51;
+/// ICMPv6 protocol number
+pub(crate) const IPPROTO_ICMPV6: u8 = 58;
+/// SCTP protocol number
+pub(crate) const IPPROTO_SCTP: u8 = 132;
+
+/// VXLAN UDP port
+pub(crate) const VXLAN_PORT: u16 = 4789;
+/// GENEVE UDP port
+pub(crate) const GENEVE_PORT: u16 = 6081;
+
+/// IPv6 Hop-by-Hop options extension header
+pub(crate) const IPV6_HOP_BY_HOP: u8 = 0;
+/// IPv6 routing extension header
+pub(crate) const IPV6_ROUTING: u8 = 43;
+/// IPv6 fragment extension header
+pub(crate) const IPV6_FRAGMENT: u8 = 44;
+/// IPv6 destination options extension header
+pub(crate) const IPV6_DESTINATION: u8 = 60;
+
+/// Returns true if the ethertype is a VLAN tag.
+pub(crate) fn is_vlan_ethertype(ethertype: u16) -> bool {
+    matches!(ethertype, ETHERTYPE_VLAN | ETHERTYPE_QINQ | ETHERTYPE_QINQ_OLD)
+}
+
+/// Reads a big endian u16 at `offset`.
+pub(crate) fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+/// Reads a big endian u32 at `offset`.
+pub(crate) fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Returns the offset of the network layer and its ethertype.
+///
+/// VLAN tags of Ethernet frames are skipped.
+/// Returns `None` if the link type is not supported or the packet is too short.
+pub(crate) fn network_layer(datalink: DataLink, data: &[u8]) -> Option<(usize, u16)> {
+    match datalink {
+        DataLink::ETHERNET => {
+            let mut offset = 12;
+            let mut ethertype = read_u16(data, offset)?;
+            while is_vlan_ethertype(ethertype) {
+                offset += 4;
+                ethertype = read_u16(data, offset)?;
+            }
+
+            Some((offset + 2, ethertype))
+        },
+
+        DataLink::RAW | DataLink::IPV4 | DataLink::IPV6 => ip_version_ethertype(data).map(|ethertype| (0, ethertype)),
+
+        DataLink::LINUX_SLL => Some((16, read_u16(data, 14)?)),
+        DataLink::LINUX_SLL2 => Some((20, read_u16(data, 0).filter(|_| data.len() >= 20)?)),
+
+        DataLink::NULL | DataLink::LOOP => {
+            let bytes: [u8; 4] = data.get(..4)?.try_into().unwrap();
+
+            // NULL uses the byte order of the capturing host, LOOP is always big endian
+            let family = match datalink {
+                DataLink::LOOP => u32::from_be_bytes(bytes),
+                _ => {
+                    let family = u32::from_le_bytes(bytes);
+                    if family > 0xFFFF {
+                        family.swap_bytes()
+                    }
+                    else {
+                        family
+                    }
+                },
+            };
+
+            let ethertype = match family {
+                2 => ETHERTYPE_IPV4,
+                10 | 24 | 28 | 30 => ETHERTYPE_IPV6,
+                _ => return None,
+            };
+
+            Some((4, ethertype))
+        },
+
+        _ => None,
+    }
+}
+
+/// Parses a GRE header (version 0), returning its length and its protocol type.
+pub(crate) fn gre_header(data: &[u8]) -> Option<(usize, u16)> {
+    let flags = read_u16(data, 0)?;
+    if flags & 0x0007 != 0 {
+        return None;
+    }
+
+    // Checksum, key and sequence number
+    let len = 4 + [0x8000, 0x2000, 0x1000].iter().filter(|&&flag| flags & flag != 0).count() * 4;
+    let protocol = read_u16(data, 2)?;
+
+    (data.len() >= len).then_some((len, protocol))
+}
+
+/// Parses an ERSPAN type II or III header, returning its length.
+pub(crate) fn erspan_header(protocol: u16, data: &[u8]) -> Option<usize> {
+    let len = match protocol {
+        ETHERTYPE_ERSPAN_II => 8,
+        // The O flag indicates an optional platform specific subheader
+        ETHERTYPE_ERSPAN_III if data.get(11)? & 0x01 != 0 => 20,
+        ETHERTYPE_ERSPAN_III => 12,
+        _ => return None,
+    };
+
+    (data.len() >= len).then_some(len)
+}
+
+/// Parses a GENEVE header, returning its length and its protocol type.
+pub(crate) fn geneve_header(data: &[u8]) -> Option<(usize, u16)> {
+    let first = *data.first()?;
+    if first >> 6 != 0 {
+        return None;
+    }
+
+    let len = 8 + (first & 0x3F) as usize * 4;
+    let protocol = read_u16(data, 2)?;
+
+    (data.len() >= len).then_some((len, protocol))
+}
+
+/// Returns the ethertype corresponding to the version nibble of a raw IP packet.
+pub(crate) fn ip_version_ethertype(data: &[u8]) -> Option<u16> {
+    match data.first()? >> 4 {
+        4 => Some(ETHERTYPE_IPV4),
+        6 => Some(ETHERTYPE_IPV6),
+        _ => None,
+    }
+}
+
+/// Computes the one's complement sum of `data`, folded to 16 bits.
+pub(crate) fn ones_complement_sum(mut sum: u32, data: &[u8]) -> u32 {
+    let mut chunks = data.chunks_exact(2);
+    for chunk in &mut chunks {
+        sum += u16::from_be_bytes([chunk[0], chunk[1]]) as u32;
+    }
+    if let [last] = chunks.remainder() {
+        sum += (*last as u32) << 8;
+    }
+
+    while sum > 0xFFFF {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+
+    sum
+}
+
+/// Computes the internet checksum (RFC 1071) of `data`.
+pub(crate) fn internet_checksum(data: &[u8]) -> u16 {
+    !(ones_complement_sum(0, data) as u16)
+}
+
+/// Recomputes the header checksum of the IPv4 header starting at the beginning of `header`.
+pub(crate) fn update_ipv4_header_checksum(header: &mut [u8]) {
+    header[10] = 0;
+    header[11] = 0;
+    let checksum = internet_checksum(header);
+    header[10..12].copy_from_slice(&checksum.to_be_bytes());
+}
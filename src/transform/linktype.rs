@@ -0,0 +1,390 @@
+//! VLAN tags and link type rewriting.
+
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use super::layers::{self, read_u16, ETHERTYPE_IPV4, ETHERTYPE_IPV6, ETHERTYPE_QINQ, ETHERTYPE_VLAN};
+use crate::pcap::{PcapHeader, PcapPacket, PcapReader, PcapWriter};
+use crate::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use crate::pcapng::{Block, PcapNgReader, PcapNgWriter};
+use crate::{DataLink, PcapError, PcapResult};
+
+
+/// An 802.1Q or 802.1ad VLAN tag.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct VlanTag {
+    /// Tag protocol identifier, i.e. the ethertype of the tag
+    pub tpid: u16,
+    /// Tag control information: priority, drop eligible indicator and VLAN identifier
+    pub tci: u16,
+}
+
+impl VlanTag {
+    /// Creates a new 802.1Q tag.
+    pub fn dot1q(vid: u16) -> Self {
+        VlanTag { tpid: ETHERTYPE_VLAN, tci: vid & 0x0FFF }
+    }
+
+    /// Creates a new 802.1ad (QinQ) service tag.
+    pub fn dot1ad(vid: u16) -> Self {
+        VlanTag { tpid: ETHERTYPE_QINQ, tci: vid & 0x0FFF }
+    }
+
+    /// Sets the priority code point of the tag.
+    pub fn with_priority(mut self, priority: u8) -> Self {
+        self.tci = (self.tci & 0x1FFF) | (((priority & 0x07) as u16) << 13);
+        self
+    }
+
+    /// Returns the VLAN identifier.
+    pub fn vid(&self) -> u16 {
+        self.tci & 0x0FFF
+    }
+
+    /// Returns the priority code point.
+    pub fn priority(&self) -> u8 {
+        (self.tci >> 13) as u8
+    }
+}
+
+/// Inserts a VLAN tag after the MAC addresses of an Ethernet frame, i.e. as the outermost tag.
+///
+/// Returns false if the frame is too short.
+pub fn push_vlan(frame: &mut Vec<u8>, tag: VlanTag) -> bool {
+    if frame.len() < 14 {
+        return false;
+    }
+
+    let mut bytes = [0; 4];
+    bytes[..2].copy_from_slice(&tag.tpid.to_be_bytes());
+    bytes[2..].copy_from_slice(&tag.tci.to_be_bytes());
+    frame.splice(12..12, bytes);
+
+    true
+}
+
+/// Removes the outermost VLAN tag of an Ethernet frame.
+///
+/// Returns `None` if the frame isn't tagged.
+pub fn pop_vlan(frame: &mut Vec<u8>) -> Option<VlanTag> {
+    let tpid = read_u16(frame, 12).filter(|&tpid| layers::is_vlan_ethertype(tpid))?;
+    let tci = read_u16(frame, 14).filter(|_| frame.len() >= 18)?;
+    frame.drain(12..16);
+
+    Some(VlanTag { tpid, tci })
+}
+
+
+/// Rewrites the link type and the VLAN tags of packets, like `editcap -T`.
+///
+/// The link type is converted first, then the VLAN tags are removed, then the new tags are added.
+///
+/// The supported conversions are:
+/// - to [`DataLink::ETHERNET`] from [`DataLink::LINUX_SLL`], [`DataLink::LINUX_SLL2`], [`DataLink::RAW`], [`DataLink::IPV4`],
+///   [`DataLink::IPV6`], [`DataLink::NULL`] and [`DataLink::LOOP`]. The MAC addresses come from the Linux cooked header when possible,
+///   otherwise from [`LinkTypeRewriter::with_macs`].
+/// - to [`DataLink::RAW`] from the same link types and [`DataLink::ETHERNET`], for IPv4 and IPv6 packets only.
+///
+/// The original length of the packets and the snaplen are adjusted to the new headers.
+///
+/// # Example
+/// ```rust,no_run
+/// # tokio_test::block_on(async {
+/// use tokio::fs::File;
+///
+/// use pcap_file_tokio::transform::linktype::{rewrite_pcap, LinkTypeRewriter, VlanTag};
+/// use pcap_file_tokio::DataLink;
+///
+/// let file_in = File::open("test.pcap").await.expect("Error opening file");
+/// let file_out = File::create("out.pcap").await.expect("Error creating file");
+///
+/// let rewriter = LinkTypeRewriter::default().with_link_type(DataLink::ETHERNET).push_vlan(VlanTag::dot1q(100));
+/// rewrite_pcap(file_in, file_out, &rewriter).await.unwrap();
+/// # });
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LinkTypeRewriter {
+    link_type: Option<DataLink>,
+    pop_vlans: usize,
+    push_vlans: Vec<VlanTag>,
+    src_mac: [u8; 6],
+    dst_mac: [u8; 6],
+}
+
+/// Creates a new [`LinkTypeRewriter`] which doesn't modify the packets.
+///
+/// The default MAC addresses are the locally administered 02:00:00:00:00:01 and 02:00:00:00:00:02.
+impl Default for LinkTypeRewriter {
+    fn default() -> Self {
+        LinkTypeRewriter {
+            link_type: None,
+            pop_vlans: 0,
+            push_vlans: vec![],
+            src_mac: [0x02, 0, 0, 0, 0, 0x01],
+            dst_mac: [0x02, 0, 0, 0, 0, 0x02],
+        }
+    }
+}
+
+impl LinkTypeRewriter {
+    /// Converts the packets to the link type `link_type`.
+    pub fn with_link_type(mut self, link_type: DataLink) -> Self {
+        self.link_type = Some(link_type);
+        self
+    }
+
+    /// Sets the MAC addresses of the synthesized Ethernet headers.
+    pub fn with_macs(mut self, src: [u8; 6], dst: [u8; 6]) -> Self {
+        self.src_mac = src;
+        self.dst_mac = dst;
+        self
+    }
+
+    /// Removes up to `count` outermost VLAN tags.
+    pub fn pop_vlans(mut self, count: usize) -> Self {
+        self.pop_vlans = count;
+        self
+    }
+
+    /// Removes all the VLAN tags.
+    pub fn strip_vlans(self) -> Self {
+        self.pop_vlans(usize::MAX)
+    }
+
+    /// Adds a VLAN tag, after the removal of the tags. The last pushed tag is the outermost.
+    pub fn push_vlan(mut self, tag: VlanTag) -> Self {
+        self.push_vlans.push(tag);
+        self
+    }
+
+    /// Returns the link type of the packets of a link of type `from` after the rewrite.
+    ///
+    /// # Errors
+    /// Returns [`PcapError::UnsupportedLinkTypeConversion`] if the link type can't be converted
+    /// and [`PcapError::VlanUnsupported`] if VLAN tags are edited but the output isn't Ethernet.
+    pub fn output_link_type(&self, from: DataLink) -> PcapResult<DataLink> {
+        let to = self.link_type.unwrap_or(from);
+        let supported = match to {
+            _ if to == from => true,
+            DataLink::ETHERNET => matches!(from, DataLink::LINUX_SLL | DataLink::LINUX_SLL2 | DataLink::NULL | DataLink::LOOP) || is_raw_ip(from),
+            DataLink::RAW => matches!(from, DataLink::ETHERNET | DataLink::LINUX_SLL | DataLink::LINUX_SLL2 | DataLink::NULL | DataLink::LOOP) || is_raw_ip(from),
+            _ => false,
+        };
+
+        if !supported {
+            return Err(PcapError::UnsupportedLinkTypeConversion(from, to));
+        }
+        if (self.pop_vlans > 0 || !self.push_vlans.is_empty()) && to != DataLink::ETHERNET {
+            return Err(PcapError::VlanUnsupported(to));
+        }
+
+        Ok(to)
+    }
+
+    /// Returns the maximum number of bytes added to a packet of a link of type `from`.
+    fn max_growth(&self, from: DataLink) -> PcapResult<u32> {
+        let link = match self.output_link_type(from)? {
+            DataLink::ETHERNET if is_raw_ip(from) => 14,
+            DataLink::ETHERNET if matches!(from, DataLink::NULL | DataLink::LOOP) => 10,
+            _ => 0,
+        };
+
+        Ok(link + 4 * self.push_vlans.len() as u32)
+    }
+
+    /// Rewrites the data of a packet of a link of type `from`.
+    ///
+    /// # Errors
+    /// Returns the errors of [`LinkTypeRewriter::output_link_type`] and [`PcapError::InvalidPacketForLinkType`]
+    /// if the packet can't be converted, e.g. a non IP packet to [`DataLink::RAW`].
+    pub fn rewrite_data(&self, from: DataLink, data: &[u8]) -> PcapResult<Vec<u8>> {
+        let to = self.output_link_type(from)?;
+        let invalid = |reason| PcapError::InvalidPacketForLinkType(from, to, reason);
+
+        let mut out = match to {
+            _ if to == from => data.to_vec(),
+
+            DataLink::RAW => match layers::network_layer(from, data) {
+                Some((offset, ETHERTYPE_IPV4 | ETHERTYPE_IPV6)) if offset <= data.len() => data[offset..].to_vec(),
+                _ => return Err(invalid("not an IPv4 or IPv6 packet")),
+            },
+
+            _ => {
+                let (src, dst, ethertype, payload) = match from {
+                    DataLink::LINUX_SLL | DataLink::LINUX_SLL2 => self.cooked_header(from, data).map_err(invalid)?,
+                    _ => match layers::network_layer(from, data) {
+                        Some((offset, ethertype)) if offset <= data.len() => (self.src_mac, self.dst_mac, ethertype, &data[offset..]),
+                        _ => return Err(invalid("not an IPv4 or IPv6 packet")),
+                    },
+                };
+
+                let mut frame = Vec::with_capacity(14 + payload.len() + 4 * self.push_vlans.len());
+                frame.extend_from_slice(&dst);
+                frame.extend_from_slice(&src);
+                frame.extend_from_slice(&ethertype.to_be_bytes());
+                frame.extend_from_slice(payload);
+                frame
+            },
+        };
+
+        if to == DataLink::ETHERNET {
+            for _ in 0..self.pop_vlans {
+                if pop_vlan(&mut out).is_none() {
+                    break;
+                }
+            }
+
+            for tag in &self.push_vlans {
+                if !push_vlan(&mut out, *tag) {
+                    return Err(invalid("truncated Ethernet header"));
+                }
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// Parses a Linux cooked header, returning the source and destination MACs, the ethertype and the payload.
+    fn cooked_header<'a>(&self, from: DataLink, data: &'a [u8]) -> Result<EthernetParts<'a>, &'static str> {
+        // Packet type, address length, address, protocol and header length
+        let (packet_type, addr_len, addr, protocol, header_len) = match from {
+            DataLink::LINUX_SLL if data.len() >= 16 => (read_u16(data, 0).unwrap(), read_u16(data, 4).unwrap(), &data[6..14], read_u16(data, 14).unwrap(), 16),
+            DataLink::LINUX_SLL2 if data.len() >= 20 => (data[10] as u16, data[11] as u16, &data[12..20], read_u16(data, 0).unwrap(), 20),
+            _ => return Err("truncated link-layer header"),
+        };
+
+        if protocol < 0x0600 {
+            return Err("protocol is not an ethertype");
+        }
+
+        let src = if addr_len == 6 { addr[..6].try_into().unwrap() } else { self.src_mac };
+        let dst = match packet_type {
+            // Broadcast
+            1 => [0xFF; 6],
+            _ => self.dst_mac,
+        };
+
+        Ok((src, dst, protocol, &data[header_len..]))
+    }
+
+    /// Rewrites the link type and the snaplen of a [`PcapHeader`].
+    pub fn rewrite_pcap_header(&self, header: &mut PcapHeader) -> PcapResult<()> {
+        header.snaplen = header.snaplen.saturating_add(self.max_growth(header.datalink)?);
+        header.datalink = self.output_link_type(header.datalink)?;
+        Ok(())
+    }
+
+    /// Rewrites a [`PcapPacket`] of a pcap whose link type is `from`.
+    pub fn rewrite_pcap_packet(&self, packet: &mut PcapPacket, from: DataLink) -> PcapResult<()> {
+        let data = self.rewrite_data(from, &packet.data)?;
+        packet.orig_len = adjust_len(packet.orig_len, packet.data.len(), data.len());
+        packet.data = data.into();
+        Ok(())
+    }
+
+    /// Rewrites a pcapng [`Block`].
+    ///
+    /// `interfaces` are the interfaces of the current section before the rewrite, as returned by
+    /// [`PcapNgReader::interfaces`](crate::pcapng::PcapNgReader::interfaces).
+    /// The link type and the snaplen of the Interface Description Blocks are rewritten, as well as the data of the packets.
+    ///
+    /// # Errors
+    /// Returns [`PcapError::InvalidInterfaceId`] if a packet references an unknown interface
+    /// and the errors of [`LinkTypeRewriter::rewrite_data`].
+    pub fn rewrite_block(&self, block: &mut Block, interfaces: &[InterfaceDescriptionBlock]) -> PcapResult<()> {
+        let interface_id = match block {
+            Block::InterfaceDescription(interface) => {
+                if interface.snaplen != 0 {
+                    interface.snaplen = interface.snaplen.saturating_add(self.max_growth(interface.linktype)?);
+                }
+                interface.linktype = self.output_link_type(interface.linktype)?;
+                return Ok(());
+            },
+            Block::EnhancedPacket(packet) => packet.interface_id,
+            Block::Packet(packet) => packet.interface_id as u32,
+            Block::SimplePacket(_) => 0,
+            _ => return Ok(()),
+        };
+
+        let from = interfaces.get(interface_id as usize).ok_or(PcapError::InvalidInterfaceId(interface_id))?.linktype;
+
+        match block {
+            Block::EnhancedPacket(packet) => {
+                let data = self.rewrite_data(from, &packet.data)?;
+                packet.original_len = adjust_len(packet.original_len, packet.data.len(), data.len());
+                packet.data = data.into();
+            },
+            Block::Packet(packet) => {
+                let data = self.rewrite_data(from, &packet.data)?;
+                packet.original_len = adjust_len(packet.original_len, packet.data.len(), data.len());
+                packet.captured_len = data.len() as u32;
+                packet.data = data.into();
+            },
+            Block::SimplePacket(packet) => {
+                let data = self.rewrite_data(from, &packet.data)?;
+                packet.original_len = adjust_len(packet.original_len, packet.data.len(), data.len());
+                packet.data = data.into();
+            },
+            _ => unreachable!(),
+        }
+
+        Ok(())
+    }
+}
+
+/// Source MAC, destination MAC, ethertype and payload of an Ethernet frame.
+type EthernetParts<'a> = ([u8; 6], [u8; 6], u16, &'a [u8]);
+
+fn is_raw_ip(datalink: DataLink) -> bool {
+    matches!(datalink, DataLink::RAW | DataLink::IPV4 | DataLink::IPV6)
+}
+
+/// Adjusts an original length to the new length of the captured data.
+fn adjust_len(orig_len: u32, old_len: usize, new_len: usize) -> u32 {
+    let orig_len = orig_len as i64 + new_len as i64 - old_len as i64;
+    orig_len.clamp(new_len as i64, u32::MAX as i64) as u32
+}
+
+
+/// Rewrites all the packets of a pcap.
+///
+/// Returns the wrapped writer.
+pub async fn rewrite_pcap<R, W>(reader: R, writer: W, rewriter: &LinkTypeRewriter) -> PcapResult<W>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut reader = PcapReader::new(reader).await?;
+    let from = reader.header().datalink;
+
+    let mut header = reader.header();
+    rewriter.rewrite_pcap_header(&mut header)?;
+    let mut writer = PcapWriter::with_header(writer, header).await?;
+
+    while let Some(packet) = reader.next_packet().await {
+        let mut packet = packet?;
+        rewriter.rewrite_pcap_packet(&mut packet, from)?;
+        writer.write_packet(&packet).await?;
+    }
+
+    Ok(writer.into_writer())
+}
+
+/// Rewrites all the interfaces and packets of a pcapng.
+///
+/// Returns the wrapped writer.
+pub async fn rewrite_pcapng<R, W>(reader: R, writer: W, rewriter: &LinkTypeRewriter) -> PcapResult<W>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin + Send,
+{
+    let mut reader = PcapNgReader::new(reader).await?;
+    let mut writer = PcapNgWriter::with_section_header(writer, reader.section().clone()).await?;
+
+    while let Some(block) = reader.next_block().await {
+        let mut block = block?.into_owned();
+        rewriter.rewrite_block(&mut block, reader.interfaces())?;
+        writer.write_block(&block).await?;
+    }
+
+    Ok(writer.into_inner())
+}
@@ -0,0 +1,119 @@
+//! Contains transforms that sit between a reader and a writer to rewrite, reassemble or filter packets.
+//!
+//! The `anonymize` module requires the `anonymize` feature.
+
+use std::borrow::Cow;
+
+use crate::pcap::PcapPacket;
+use crate::pcapng::blocks::enhanced_packet::{EnhancedPacketBlock, EnhancedPacketOption};
+use crate::pcapng::blocks::opt_common::CustomBinaryOption;
+use crate::pcapng::blocks::packet::{PacketBlock, PacketOption};
+use crate::pcapng::Block;
+
+#[cfg(feature = "anonymize")]
+pub mod anonymize;
+pub mod decap;
+pub mod dedup;
+pub mod defrag;
+pub(crate) mod layers;
+pub mod linktype;
+pub mod reorder;
+pub mod timestamp;
+pub mod truncate;
+
+
+/// Common interface of the packets handled by the generic transforms.
+///
+/// Implemented for [`PcapPacket`], [`EnhancedPacketBlock`], [`PacketBlock`] and [`Block`].
+pub trait TransformPacket {
+    /// Returns the captured data of the packet, empty for the blocks that aren't packets.
+    fn data(&self) -> &[u8];
+
+    /// Adds a comment to the packet.
+    ///
+    /// Returns false if the packet can't hold a comment.
+    fn add_comment(&mut self, comment: String) -> bool;
+
+    /// Adds a custom binary option to the packet.
+    ///
+    /// Returns false if the packet can't hold options.
+    fn add_custom_binary(&mut self, pen: u32, value: Vec<u8>) -> bool;
+}
+
+/// Option code of a copyable custom binary option.
+const CUSTOM_BINARY_COPYABLE: u16 = 2989;
+
+impl<'a> TransformPacket for PcapPacket<'a> {
+    fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    fn add_comment(&mut self, _comment: String) -> bool {
+        false
+    }
+
+    fn add_custom_binary(&mut self, _pen: u32, _value: Vec<u8>) -> bool {
+        false
+    }
+}
+
+impl<'a> TransformPacket for EnhancedPacketBlock<'a> {
+    fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    fn add_comment(&mut self, comment: String) -> bool {
+        self.options.push(EnhancedPacketOption::Comment(Cow::Owned(comment)));
+        true
+    }
+
+    fn add_custom_binary(&mut self, pen: u32, value: Vec<u8>) -> bool {
+        let option = CustomBinaryOption { code: CUSTOM_BINARY_COPYABLE, pen, value: Cow::Owned(value) };
+        self.options.push(EnhancedPacketOption::CustomBinary(option));
+        true
+    }
+}
+
+impl<'a> TransformPacket for PacketBlock<'a> {
+    fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    fn add_comment(&mut self, comment: String) -> bool {
+        self.options.push(PacketOption::Comment(Cow::Owned(comment)));
+        true
+    }
+
+    fn add_custom_binary(&mut self, pen: u32, value: Vec<u8>) -> bool {
+        let option = CustomBinaryOption { code: CUSTOM_BINARY_COPYABLE, pen, value: Cow::Owned(value) };
+        self.options.push(PacketOption::CustomBinary(option));
+        true
+    }
+}
+
+impl<'a> TransformPacket for Block<'a> {
+    fn data(&self) -> &[u8] {
+        match self {
+            Block::EnhancedPacket(packet) => &packet.data,
+            Block::Packet(packet) => &packet.data,
+            Block::SimplePacket(packet) => &packet.data,
+            _ => &[],
+        }
+    }
+
+    fn add_comment(&mut self, comment: String) -> bool {
+        match self {
+            Block::EnhancedPacket(packet) => packet.add_comment(comment),
+            Block::Packet(packet) => packet.add_comment(comment),
+            _ => false,
+        }
+    }
+
+    fn add_custom_binary(&mut self, pen: u32, value: Vec<u8>) -> bool {
+        match self {
+            Block::EnhancedPacket(packet) => packet.add_custom_binary(pen, value),
+            Block::Packet(packet) => packet.add_custom_binary(pen, value),
+            _ => false,
+        }
+    }
+}
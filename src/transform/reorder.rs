@@ -0,0 +1,260 @@
+//! Timestamp reordering, like `reordercap`.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, VecDeque};
+use std::time::Duration;
+
+use crate::pcap::PcapPacket;
+use crate::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use crate::pcapng::Block;
+use crate::{PcapError, PcapResult};
+
+
+/// Size of the reordering window of a [`Reorderer`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ReorderWindow {
+    /// Keeps up to this number of packets buffered.
+    Packets(usize),
+    /// Keeps the packets buffered until a packet at least this much newer is pushed.
+    Duration(Duration),
+}
+
+/// Counters maintained by a [`Reorderer`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ReorderStats {
+    /// Number of packets pushed.
+    pub packets: u64,
+    /// Number of packets pushed after a newer packet was already emitted, they are emitted out of order.
+    pub late: u64,
+    /// Biggest difference between the timestamp of a late packet and the newest emitted timestamp.
+    pub max_lateness: Duration,
+    /// Biggest number of packets buffered at the same time.
+    pub max_buffered: usize,
+}
+
+/// Reorders packets by timestamp using a bounded window.
+///
+/// Packets are pushed in capture order and popped sorted by timestamp, packets with the same timestamp keep their capture order.
+/// A packet is only popped once the window is full, so a packet can be moved before at most the size of the window.
+/// Packets arriving too late to be sorted are emitted as soon as possible and counted in [`ReorderStats::late`].
+///
+/// Any item can be reordered with [`Reorderer::push`], helpers are provided for [`PcapPacket`] and pcapng [`Block`].
+///
+/// # Example
+/// ```rust,no_run
+/// # tokio_test::block_on(async {
+/// use std::time::Duration;
+/// use tokio::fs::File;
+///
+/// use pcap_file_tokio::pcapng::{PcapNgReader, PcapNgWriter};
+/// use pcap_file_tokio::transform::reorder::{ReorderWindow, Reorderer};
+///
+/// let file_in = File::open("test.pcapng").await.expect("Error opening file");
+/// let mut pcapng_reader = PcapNgReader::new(file_in).await.unwrap();
+///
+/// let file_out = File::create("out.pcapng").await.expect("Error creating file");
+/// let mut pcapng_writer = PcapNgWriter::with_section_header(file_out, pcapng_reader.section().clone()).await.unwrap();
+///
+/// let mut reorderer = Reorderer::new(ReorderWindow::Duration(Duration::from_millis(100)));
+/// while let Some(block) = pcapng_reader.next_block().await {
+///     let block = block.unwrap().into_owned();
+///     reorderer.push_block(block, pcapng_reader.interfaces()).unwrap();
+///
+///     while let Some(block) = reorderer.pop() {
+///         pcapng_writer.write_block(&block).await.unwrap();
+///     }
+/// }
+///
+/// while let Some(block) = reorderer.flush() {
+///     pcapng_writer.write_block(&block).await.unwrap();
+/// }
+///
+/// println!("{} packets were too late", reorderer.stats().late);
+/// # });
+/// ```
+#[derive(Debug)]
+pub struct Reorderer<T> {
+    window: ReorderWindow,
+    heap: BinaryHeap<Reverse<Entry<T>>>,
+    ready: VecDeque<T>,
+    seq: u64,
+    newest_pushed: Option<Duration>,
+    newest_emitted: Option<Duration>,
+    stats: ReorderStats,
+}
+
+impl<T> Reorderer<T> {
+    /// Creates a new [`Reorderer`] with the given window.
+    pub fn new(window: ReorderWindow) -> Self {
+        Reorderer {
+            window,
+            heap: BinaryHeap::new(),
+            ready: VecDeque::new(),
+            seq: 0,
+            newest_pushed: None,
+            newest_emitted: None,
+            stats: ReorderStats::default(),
+        }
+    }
+
+    /// Pushes an item with the given absolute timestamp.
+    pub fn push(&mut self, timestamp: Duration, item: T) {
+        self.stats.packets += 1;
+
+        if let Some(newest) = self.newest_emitted {
+            if timestamp < newest {
+                self.stats.late += 1;
+                self.stats.max_lateness = self.stats.max_lateness.max(newest - timestamp);
+            }
+        }
+
+        self.newest_pushed = Some(self.newest_pushed.map_or(timestamp, |newest| newest.max(timestamp)));
+        self.heap.push(Reverse(Entry { timestamp, seq: self.seq, item }));
+        self.seq += 1;
+        self.stats.max_buffered = self.stats.max_buffered.max(self.heap.len());
+    }
+
+    /// Pushes an item that isn't reordered, it is popped before all the items pushed after it
+    /// but can be popped before some of the buffered items.
+    pub fn push_unordered(&mut self, item: T) {
+        self.ready.push_back(item);
+    }
+
+    /// Pops the next item if the window allows it.
+    pub fn pop(&mut self) -> Option<T> {
+        if let Some(item) = self.ready.pop_front() {
+            return Some(item);
+        }
+
+        let Reverse(oldest) = self.heap.peek()?;
+        let is_ready = match self.window {
+            ReorderWindow::Packets(count) => self.heap.len() > count,
+            // newest_pushed is always set when the heap isn't empty
+            ReorderWindow::Duration(duration) => self.newest_pushed.unwrap_or_default().saturating_sub(oldest.timestamp) >= duration,
+        };
+
+        if is_ready {
+            self.pop_oldest()
+        }
+        else {
+            None
+        }
+    }
+
+    /// Pops the next item regardless of the window.
+    ///
+    /// Must be called in a loop at the end of the capture to retrieve the remaining items.
+    pub fn flush(&mut self) -> Option<T> {
+        match self.ready.pop_front() {
+            Some(item) => Some(item),
+            None => self.pop_oldest(),
+        }
+    }
+
+    /// Returns the number of items waiting to be popped.
+    pub fn len(&self) -> usize {
+        self.heap.len() + self.ready.len()
+    }
+
+    /// Returns true if no item is waiting to be popped.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the window of the reorderer.
+    pub fn window(&self) -> ReorderWindow {
+        self.window
+    }
+
+    /// Returns the counters of the reorderer.
+    pub fn stats(&self) -> ReorderStats {
+        self.stats
+    }
+
+    fn pop_oldest(&mut self) -> Option<T> {
+        let Reverse(entry) = self.heap.pop()?;
+        self.newest_emitted = Some(self.newest_emitted.map_or(entry.timestamp, |newest| newest.max(entry.timestamp)));
+
+        Some(entry.item)
+    }
+
+    /// Moves all the buffered items, sorted, before the next items and forgets the previous timestamps.
+    fn flush_into_ready(&mut self) {
+        while let Some(item) = self.pop_oldest() {
+            self.ready.push_back(item);
+        }
+
+        self.newest_pushed = None;
+        self.newest_emitted = None;
+    }
+}
+
+impl<'a> Reorderer<PcapPacket<'a>> {
+    /// Pushes a [`PcapPacket`].
+    pub fn push_packet(&mut self, packet: PcapPacket<'a>) {
+        self.push(packet.timestamp, packet);
+    }
+}
+
+impl<'a> Reorderer<Block<'a>> {
+    /// Pushes a pcapng [`Block`].
+    ///
+    /// `interfaces` are the interfaces of the current section, including the pushed block if it is an Interface Description Block,
+    /// as returned by [`PcapNgReader::interfaces`](crate::pcapng::PcapNgReader::interfaces).
+    /// They are used to compute the absolute timestamps of the packets, so interfaces with different resolutions are sorted correctly.
+    ///
+    /// Enhanced Packet Blocks and Packet Blocks are reordered. A Section Header Block flushes all the buffered blocks
+    /// because the next packets belong to a different section. The other blocks are not reordered.
+    ///
+    /// # Errors
+    /// Returns [`PcapError::InvalidInterfaceId`] if a packet references an unknown interface.
+    pub fn push_block(&mut self, block: Block<'a>, interfaces: &[InterfaceDescriptionBlock]) -> PcapResult<()> {
+        let (interface_id, units) = match &block {
+            Block::EnhancedPacket(packet) => (packet.interface_id, packet.timestamp.as_nanos() as u64),
+            Block::Packet(packet) => (packet.interface_id as u32, packet.timestamp),
+            Block::SectionHeader(_) => {
+                self.flush_into_ready();
+                self.push_unordered(block);
+                return Ok(());
+            },
+            _ => {
+                self.push_unordered(block);
+                return Ok(());
+            },
+        };
+
+        let interface = interfaces.get(interface_id as usize).ok_or(PcapError::InvalidInterfaceId(interface_id))?;
+        self.push(interface.timestamp_to_duration(units), block);
+
+        Ok(())
+    }
+}
+
+
+/// Buffered item, ordered by timestamp then by push order.
+#[derive(Debug)]
+struct Entry<T> {
+    timestamp: Duration,
+    seq: u64,
+    item: T,
+}
+
+impl<T> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Entry<T> {}
+
+impl<T> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.timestamp, self.seq).cmp(&(other.timestamp, other.seq))
+    }
+}
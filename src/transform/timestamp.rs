@@ -0,0 +1,353 @@
+//! Timestamp shifting, drift correction and alignment.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::pcap::{PcapPacket, PcapReader, PcapWriter};
+use crate::pcapng::blocks::enhanced_packet::EnhancedPacketBlock;
+use crate::pcapng::blocks::interface_description::{InterfaceDescriptionBlock, InterfaceDescriptionOption};
+use crate::pcapng::blocks::interface_statistics::InterfaceStatisticsOption;
+use crate::pcapng::{Block, PcapNgReader, PcapNgWriter};
+use crate::{PcapError, PcapResult, TsResolution};
+
+
+const NANOS_PER_SEC: i128 = 1_000_000_000;
+
+/// Linear transformation of absolute timestamps.
+///
+/// A timestamp `t` becomes `reference + (t - reference) * (1 + drift_ppb / 10^9) + offset`.
+/// All the computations are done on integer nanoseconds, so a pure shift is exact.
+///
+/// The default transform leaves the timestamps unchanged.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct TimestampTransform {
+    reference: Duration,
+    drift_ppb: i64,
+    offset_nanos: i64,
+}
+
+impl TimestampTransform {
+    /// Creates a transform adding `offset_nanos` nanoseconds, which can be negative, to the timestamps.
+    pub fn shift(offset_nanos: i64) -> Self {
+        TimestampTransform { offset_nanos, ..Default::default() }
+    }
+
+    /// Creates a transform correcting a clock drift of `drift_ppb` parts per billion around `reference`,
+    /// then adding `offset_nanos` nanoseconds.
+    ///
+    /// A clock running 10 ppm too slow is corrected by a drift of 10_000 ppb.
+    pub fn linear(reference: Duration, drift_ppb: i64, offset_nanos: i64) -> Self {
+        TimestampTransform { reference, drift_ppb, offset_nanos }
+    }
+
+    /// Creates a shift mapping the timestamp `from` to `to`.
+    ///
+    /// # Errors
+    /// The difference between the timestamps doesn't fit in an i64 of nanoseconds.
+    pub fn anchor(from: Duration, to: Duration) -> PcapResult<Self> {
+        let offset_nanos = i64::try_from(to.as_nanos() as i128 - from.as_nanos() as i128)
+            .map_err(|_| PcapError::InvalidField("TimestampTransform: anchor offset overflow"))?;
+
+        Ok(TimestampTransform::shift(offset_nanos))
+    }
+
+    /// Creates a linear transform mapping the timestamps `first.0` to `first.1` and `second.0` to `second.1`.
+    ///
+    /// # Errors
+    /// The two source timestamps are equal, or the drift or the offset don't fit in an i64.
+    pub fn anchors(first: (Duration, Duration), second: (Duration, Duration)) -> PcapResult<Self> {
+        let from_delta = second.0.as_nanos() as i128 - first.0.as_nanos() as i128;
+        let to_delta = second.1.as_nanos() as i128 - first.1.as_nanos() as i128;
+        if from_delta == 0 {
+            return Err(PcapError::InvalidField("TimestampTransform: anchors with the same source timestamp"));
+        }
+
+        let drift_ppb = i64::try_from((to_delta - from_delta) * NANOS_PER_SEC / from_delta)
+            .map_err(|_| PcapError::InvalidField("TimestampTransform: anchor drift overflow"))?;
+        let TimestampTransform { offset_nanos, .. } = TimestampTransform::anchor(first.0, first.1)?;
+
+        Ok(TimestampTransform { reference: first.0, drift_ppb, offset_nanos })
+    }
+
+    /// Returns true if the transform leaves the timestamps unchanged.
+    pub fn is_identity(&self) -> bool {
+        self.drift_ppb == 0 && self.offset_nanos == 0
+    }
+
+    /// Returns the shift in seconds if the transform is a shift by a whole number of seconds.
+    pub fn whole_seconds_shift(&self) -> Option<i64> {
+        if self.drift_ppb == 0 && self.offset_nanos as i128 % NANOS_PER_SEC == 0 {
+            Some(self.offset_nanos / NANOS_PER_SEC as i64)
+        }
+        else {
+            None
+        }
+    }
+
+    /// Applies the transform to an absolute timestamp.
+    ///
+    /// # Errors
+    /// The transformed timestamp is before the EPOCH or too big for a [`Duration`].
+    pub fn apply(&self, timestamp: Duration) -> PcapResult<Duration> {
+        let reference = self.reference.as_nanos() as i128;
+        let delta = timestamp.as_nanos() as i128 - reference;
+        let overflow = || PcapError::InvalidField("TimestampTransform: timestamp overflow");
+
+        // A large drift can overflow even an i128
+        let drift = delta.checked_mul(self.drift_ppb as i128).ok_or_else(overflow)? / NANOS_PER_SEC;
+        let nanos = (reference + delta)
+            .checked_add(drift)
+            .and_then(|nanos| nanos.checked_add(self.offset_nanos as i128))
+            .ok_or_else(overflow)?;
+
+        if nanos < 0 {
+            return Err(PcapError::InvalidField("TimestampTransform: timestamp before the EPOCH"));
+        }
+
+        let secs = u64::try_from(nanos / NANOS_PER_SEC).map_err(|_| overflow())?;
+        Ok(Duration::new(secs, (nanos % NANOS_PER_SEC) as u32))
+    }
+
+    /// Applies the transform to a [`PcapPacket`] of a pcap with the given timestamp resolution.
+    ///
+    /// The new timestamp is truncated to the resolution. The packet is left unchanged on error.
+    ///
+    /// # Errors
+    /// The transformed timestamp is before the EPOCH or its seconds don't fit in the u32 of a pcap.
+    pub fn apply_pcap_packet(&self, packet: &mut PcapPacket, ts_resolution: TsResolution) -> PcapResult<()> {
+        let timestamp = self.apply(packet.timestamp)?;
+        if timestamp.as_secs() > u32::MAX as u64 {
+            return Err(PcapError::InvalidField("PcapPacket: timestamp_secs > u32::MAX"));
+        }
+
+        let nanos = match ts_resolution {
+            TsResolution::MicroSecond => timestamp.subsec_micros() * 1000,
+            TsResolution::NanoSecond => timestamp.subsec_nanos(),
+        };
+        packet.timestamp = Duration::new(timestamp.as_secs(), nanos);
+
+        Ok(())
+    }
+
+    /// Applies the transform to an [`EnhancedPacketBlock`] captured on `interface`.
+    ///
+    /// The packet is left unchanged on error.
+    ///
+    /// # Errors
+    /// The transformed timestamp can't be represented by the interface.
+    pub fn apply_enhanced_packet(&self, packet: &mut EnhancedPacketBlock, interface: &InterfaceDescriptionBlock) -> PcapResult<()> {
+        let timestamp = self.apply(packet.absolute_timestamp(interface))?;
+        packet.set_absolute_timestamp(interface, timestamp)
+    }
+
+    /// Applies the transform to a timestamp in units of `interface`, returning the new value in the same units.
+    fn apply_units(&self, units: u64, interface: &InterfaceDescriptionBlock) -> PcapResult<u64> {
+        let timestamp = self.apply(interface.timestamp_to_duration(units))?;
+        interface
+            .duration_to_timestamp(timestamp)
+            .ok_or(PcapError::InvalidField("TimestampTransform: timestamp not representable by the interface"))
+    }
+}
+
+
+/// Rewrites the timestamps of a pcapng, with a [`TimestampTransform`] per interface.
+///
+/// The interfaces are identified by their index in their section, the transform of an interface applies to all the sections.
+///
+/// When the transform of an interface is a shift by a whole number of seconds, the `if_tsoffset` option of the
+/// Interface Description Block is rewritten instead of the timestamps of the packets. This can be disabled with
+/// [`PcapNgTimestampRewriter::rewrite_ts_offset`].
+///
+/// The timestamps of the Enhanced Packet Blocks, Packet Blocks and Interface Statistics Blocks are rewritten.
+///
+/// # Example
+/// ```rust,no_run
+/// # tokio_test::block_on(async {
+/// use std::time::Duration;
+/// use tokio::fs::File;
+///
+/// use pcap_file_tokio::transform::timestamp::{rewrite_pcapng, PcapNgTimestampRewriter, TimestampTransform};
+///
+/// let file_in = File::open("test.pcapng").await.expect("Error opening file");
+/// let file_out = File::create("out.pcapng").await.expect("Error creating file");
+///
+/// // Interface 1 is 1.5s late and drifts by 20 ppm
+/// let transform = TimestampTransform::anchors(
+///     (Duration::from_secs(1_600_000_000), Duration::from_millis(1_600_000_001_500)),
+///     (Duration::from_secs(1_600_001_000), Duration::from_millis(1_600_001_001_520)),
+/// )
+/// .unwrap();
+///
+/// let rewriter = PcapNgTimestampRewriter::default().with_interface(1, transform);
+/// rewrite_pcapng(file_in, file_out, rewriter).await.unwrap();
+/// # });
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct PcapNgTimestampRewriter {
+    default: TimestampTransform,
+    transforms: HashMap<u32, TimestampTransform>,
+    keep_ts_offset: bool,
+    interfaces: Vec<RewrittenInterface>,
+}
+
+impl PcapNgTimestampRewriter {
+    /// Creates a new [`PcapNgTimestampRewriter`] applying `default` to all the interfaces.
+    pub fn new(default: TimestampTransform) -> Self {
+        PcapNgTimestampRewriter { default, ..Default::default() }
+    }
+
+    /// Sets the transform of an interface, replacing the default one.
+    pub fn with_interface(mut self, interface_id: u32, transform: TimestampTransform) -> Self {
+        self.transforms.insert(interface_id, transform);
+        self
+    }
+
+    /// Enables or disables the rewriting of `if_tsoffset` for the whole seconds shifts, enabled by default.
+    pub fn rewrite_ts_offset(mut self, enabled: bool) -> Self {
+        self.keep_ts_offset = !enabled;
+        self
+    }
+
+    /// Rewrites the timestamps of a block.
+    ///
+    /// All the blocks of the pcapng, including the Section Header Blocks, must be rewritten in order.
+    /// The block is left unchanged on error.
+    ///
+    /// # Errors
+    /// A transformed timestamp can't be represented, or a block references an unknown interface.
+    pub fn rewrite_block(&mut self, block: &mut Block) -> PcapResult<()> {
+        match block {
+            Block::SectionHeader(_) => self.interfaces.clear(),
+
+            Block::InterfaceDescription(interface) => {
+                let original = interface.clone().into_owned();
+                let transform = self.transforms.get(&(self.interfaces.len() as u32)).copied().unwrap_or(self.default);
+
+                let new_offset = match transform.whole_seconds_shift() {
+                    Some(shift) if !self.keep_ts_offset && !transform.is_identity() => {
+                        u64::try_from(original.ts_offset() as i128 + shift as i128).ok()
+                    },
+                    _ => None,
+                };
+
+                let packets_transform = match new_offset {
+                    Some(offset) => {
+                        interface.options.retain(|opt| !matches!(opt, InterfaceDescriptionOption::IfTsOffset(_)));
+                        if offset != 0 {
+                            interface.options.push(InterfaceDescriptionOption::IfTsOffset(offset));
+                        }
+                        None
+                    },
+                    None if transform.is_identity() => None,
+                    None => Some(transform),
+                };
+
+                self.interfaces.push(RewrittenInterface { original, rewritten: interface.clone().into_owned(), packets_transform });
+            },
+
+            Block::EnhancedPacket(packet) => {
+                if let Some((interface, transform)) = self.packets_transform(packet.interface_id)? {
+                    let units = transform.apply_units(packet.timestamp.as_nanos() as u64, interface)?;
+                    packet.timestamp = Duration::from_nanos(units);
+                }
+            },
+
+            Block::Packet(packet) => {
+                if let Some((interface, transform)) = self.packets_transform(packet.interface_id as u32)? {
+                    packet.timestamp = transform.apply_units(packet.timestamp, interface)?;
+                }
+            },
+
+            Block::InterfaceStatistics(stats) => {
+                if let Some((interface, transform)) = self.packets_transform(stats.interface_id)? {
+                    let timestamp = transform.apply_units(stats.timestamp, interface)?;
+
+                    let mut options = stats.options.clone();
+                    for opt in options.iter_mut() {
+                        match opt {
+                            InterfaceStatisticsOption::IsbStartTime(units) | InterfaceStatisticsOption::IsbEndTime(units) => {
+                                *units = transform.apply_units(*units, interface)?;
+                            },
+                            _ => {},
+                        }
+                    }
+
+                    stats.timestamp = timestamp;
+                    stats.options = options;
+                }
+            },
+
+            _ => {},
+        }
+
+        Ok(())
+    }
+
+    /// Returns the interfaces of the current section, as written.
+    pub fn interfaces(&self) -> impl Iterator<Item = &InterfaceDescriptionBlock<'static>> {
+        self.interfaces.iter().map(|interface| &interface.rewritten)
+    }
+
+    /// Returns the original interface and the transform to apply to the timestamps of its blocks, if any.
+    ///
+    /// The timestamps are converted with the original interface, then back with the same resolution.
+    /// The offset of the original interface is kept because the packets are only rewritten when `if_tsoffset` isn't.
+    fn packets_transform(&self, interface_id: u32) -> PcapResult<Option<(&InterfaceDescriptionBlock<'static>, TimestampTransform)>> {
+        let interface = self.interfaces.get(interface_id as usize).ok_or(PcapError::InvalidInterfaceId(interface_id))?;
+        Ok(interface.packets_transform.map(|transform| (&interface.original, transform)))
+    }
+}
+
+#[derive(Clone, Debug)]
+struct RewrittenInterface {
+    original: InterfaceDescriptionBlock<'static>,
+    rewritten: InterfaceDescriptionBlock<'static>,
+    packets_transform: Option<TimestampTransform>,
+}
+
+
+/// Applies a [`TimestampTransform`] to all the packets of a pcap.
+///
+/// Returns the wrapped writer.
+pub async fn rewrite_pcap<R, W>(reader: R, writer: W, transform: TimestampTransform) -> PcapResult<W>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut reader = PcapReader::new(reader).await?;
+    let header = reader.header();
+    let mut writer = PcapWriter::with_header(writer, header).await?;
+
+    while let Some(packet) = reader.next_packet().await {
+        let mut packet = packet?;
+        transform.apply_pcap_packet(&mut packet, header.ts_resolution)?;
+        writer.write_packet(&packet).await?;
+    }
+
+    Ok(writer.into_writer())
+}
+
+/// Rewrites the timestamps of all the blocks of a pcapng.
+///
+/// Returns the wrapped writer.
+pub async fn rewrite_pcapng<R, W>(reader: R, writer: W, mut rewriter: PcapNgTimestampRewriter) -> PcapResult<W>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin + Send,
+{
+    let mut reader = PcapNgReader::new(reader).await?;
+
+    let mut section = Block::SectionHeader(reader.section().clone());
+    rewriter.rewrite_block(&mut section)?;
+    let mut writer = PcapNgWriter::with_section_header(writer, section.into_section_header().unwrap()).await?;
+
+    while let Some(block) = reader.next_block().await {
+        let mut block = block?;
+        rewriter.rewrite_block(&mut block)?;
+        writer.write_block(&block).await?;
+    }
+
+    Ok(writer.into_inner())
+}
@@ -0,0 +1,388 @@
+//! Payload removal, keeping only the protocol headers.
+
+use std::borrow::Cow;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use super::layers::{self, *};
+use super::TransformPacket;
+use crate::pcap::{PcapPacket, PcapReader, PcapWriter};
+use crate::pcapng::blocks::enhanced_packet::EnhancedPacketBlock;
+use crate::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use crate::pcapng::{Block, PcapNgReader, PcapNgWriter};
+use crate::{DataLink, PcapError, PcapResult};
+
+
+/// Maximum number of nested encapsulations followed by [`headers_len`].
+const MAX_DEPTH: usize = 8;
+
+/// How the packets truncated by a [`Truncator`] record what was removed.
+///
+/// Only pcapng packets can be tagged, a packet that wasn't truncated is never tagged.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TruncateTag {
+    /// The packets are not tagged.
+    None,
+    /// A comment "Payload truncated: `N` bytes removed" is added to the packet.
+    Comment,
+    /// A custom binary option with this Private Enterprise Number is added to the packet,
+    /// its value is the number of bytes removed as a big endian u32.
+    CustomOption {
+        /// Private Enterprise Number of the option
+        pen: u32,
+    },
+}
+
+/// Returns the length of the protocol headers at the beginning of a packet captured on a link of type `datalink`.
+///
+/// The headers are the link-layer header, then the IPv4 or IPv6 header with its extensions, then the TCP, UDP, SCTP,
+/// ICMP or ICMPv6 header. The IP-in-IP, GRE, ERSPAN, VXLAN, GENEVE and MPLS encapsulations are followed, keeping the inner headers.
+/// When a protocol is unknown, the headers stop before it. The length never exceeds the length of the data.
+///
+/// Returns `None` if the link type is not supported.
+pub fn headers_len(datalink: DataLink, data: &[u8]) -> Option<usize> {
+    if datalink == DataLink::ETHERNET {
+        return Some(ethernet_headers_len(data, 0));
+    }
+
+    let (offset, ethertype) = layers::network_layer(datalink, data)?;
+    Some(offset + network_headers_len(ethertype, data.get(offset..)?, 0))
+}
+
+fn ethernet_headers_len(data: &[u8], depth: usize) -> usize {
+    match layers::network_layer(DataLink::ETHERNET, data) {
+        Some((offset, ethertype)) => offset + network_headers_len(ethertype, &data[offset.min(data.len())..], depth),
+        None => data.len().min(14),
+    }
+}
+
+fn network_headers_len(ethertype: u16, data: &[u8], depth: usize) -> usize {
+    if depth > MAX_DEPTH {
+        return 0;
+    }
+
+    let len = match ethertype {
+        ETHERTYPE_IPV4 => {
+            let header_len = match data.first() {
+                Some(first) => (first & 0x0F) as usize * 4,
+                None => return 0,
+            };
+            let (flags_offset, protocol) = match (read_u16(data, 6), data.get(9)) {
+                (Some(flags_offset), Some(protocol)) if header_len >= 20 && data.len() >= header_len => (flags_offset, *protocol),
+                _ => return data.len(),
+            };
+
+            // Only the first fragment contains the transport header
+            if flags_offset & 0x1FFF != 0 {
+                header_len
+            }
+            else {
+                header_len + transport_headers_len(protocol, &data[header_len..], depth)
+            }
+        },
+
+        ETHERTYPE_IPV6 => {
+            let mut next_header = match data.get(6) {
+                Some(next_header) if data.len() >= 40 => *next_header,
+                _ => return data.len(),
+            };
+
+            let mut offset = 40;
+            loop {
+                let ext_len = match next_header {
+                    IPV6_HOP_BY_HOP | IPV6_ROUTING | IPV6_DESTINATION => data.get(offset + 1).map(|len| (*len as usize + 1) * 8),
+                    IPPROTO_AH => data.get(offset + 1).map(|len| (*len as usize + 2) * 4),
+                    IPV6_FRAGMENT => match read_u16(data, offset + 2) {
+                        // Only the first fragment contains the transport header
+                        Some(flags_offset) if flags_offset & 0xFFF8 != 0 => return (offset + 8).min(data.len()),
+                        Some(_) => Some(8),
+                        None => None,
+                    },
+                    _ => break,
+                };
+
+                match ext_len {
+                    Some(ext_len) => {
+                        next_header = data[offset];
+                        offset += ext_len;
+                    },
+                    None => return data.len(),
+                }
+            }
+
+            match data.get(offset..) {
+                Some(transport) => offset + transport_headers_len(next_header, transport, depth),
+                None => data.len(),
+            }
+        },
+
+        ETHERTYPE_MPLS | ETHERTYPE_MPLS_MULTICAST => {
+            // Labels until the bottom of stack bit
+            let mut offset = 0;
+            loop {
+                match data.get(offset + 2) {
+                    Some(byte) => {
+                        offset += 4;
+                        if byte & 0x01 != 0 {
+                            break;
+                        }
+                    },
+                    None => return data.len(),
+                }
+            }
+
+            // No protocol field after MPLS, guess from the version nibble
+            match data.get(offset..).and_then(layers::ip_version_ethertype) {
+                Some(inner) => offset + network_headers_len(inner, &data[offset..], depth + 1),
+                None => offset,
+            }
+        },
+
+        _ => 0,
+    };
+
+    len.min(data.len())
+}
+
+fn transport_headers_len(protocol: u8, data: &[u8], depth: usize) -> usize {
+    let len = match protocol {
+        IPPROTO_TCP => match data.get(12) {
+            Some(offset) => ((offset >> 4) as usize * 4).max(20),
+            None => data.len(),
+        },
+
+        IPPROTO_UDP => {
+            let ports = (read_u16(data, 0), read_u16(data, 2));
+            let payload = data.get(8..).unwrap_or_default();
+
+            if ports.0 == Some(VXLAN_PORT) || ports.1 == Some(VXLAN_PORT) {
+                // VXLAN header then Ethernet frame
+                match payload.get(8..) {
+                    Some(frame) => 16 + ethernet_headers_len(frame, depth + 1),
+                    None => data.len(),
+                }
+            }
+            else if ports.0 == Some(GENEVE_PORT) || ports.1 == Some(GENEVE_PORT) {
+                match layers::geneve_header(payload) {
+                    Some((len, protocol)) => 8 + len + inner_headers_len(protocol, &payload[len..], depth),
+                    None => data.len(),
+                }
+            }
+            else {
+                8
+            }
+        },
+
+        IPPROTO_ICMP | IPPROTO_ICMPV6 => 8,
+
+        // Common header, the chunks contain the payload
+        IPPROTO_SCTP => 12,
+
+        IPPROTO_GRE => match layers::gre_header(data) {
+            Some((len, protocol @ (ETHERTYPE_ERSPAN_II | ETHERTYPE_ERSPAN_III))) => match layers::erspan_header(protocol, &data[len..]) {
+                Some(erspan_len) => len + erspan_len + ethernet_headers_len(&data[len + erspan_len..], depth + 1),
+                None => data.len(),
+            },
+            Some((len, protocol)) => len + inner_headers_len(protocol, &data[len..], depth),
+            None => data.len().min(4),
+        },
+
+        IPPROTO_IPIP => network_headers_len(ETHERTYPE_IPV4, data, depth + 1),
+        IPPROTO_IPV6 => network_headers_len(ETHERTYPE_IPV6, data, depth + 1),
+
+        _ => 0,
+    };
+
+    len.min(data.len())
+}
+
+/// Returns the length of the headers of an encapsulated packet identified by an ethertype.
+fn inner_headers_len(protocol: u16, data: &[u8], depth: usize) -> usize {
+    match protocol {
+        ETHERTYPE_TEB => ethernet_headers_len(data, depth + 1),
+        _ => network_headers_len(protocol, data, depth + 1),
+    }
+}
+
+
+/// Removes the payload of the packets, keeping only the protocol headers found by [`headers_len`].
+///
+/// The original length of the packets is preserved. The packets of unsupported link types are only truncated to the snaplen.
+///
+/// # Example
+/// ```rust,no_run
+/// # tokio_test::block_on(async {
+/// use tokio::fs::File;
+///
+/// use pcap_file_tokio::transform::truncate::{truncate_pcapng, TruncateTag, Truncator};
+///
+/// let file_in = File::open("test.pcapng").await.expect("Error opening file");
+/// let file_out = File::create("out.pcapng").await.expect("Error creating file");
+///
+/// let truncator = Truncator::default().with_tag(TruncateTag::Comment);
+/// truncate_pcapng(file_in, file_out, &truncator).await.unwrap();
+/// # });
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Truncator {
+    snaplen: Option<u32>,
+    tag: TruncateTag,
+}
+
+/// Creates a new [`Truncator`] without snaplen and without tags.
+impl Default for Truncator {
+    fn default() -> Self {
+        Truncator { snaplen: None, tag: TruncateTag::None }
+    }
+}
+
+impl Truncator {
+    /// Also truncates the packets to `snaplen` bytes, e.g. the snaplen of the output writer.
+    pub fn with_snaplen(mut self, snaplen: u32) -> Self {
+        self.snaplen = Some(snaplen);
+        self
+    }
+
+    /// Sets how the truncated packets are tagged.
+    pub fn with_tag(mut self, tag: TruncateTag) -> Self {
+        self.tag = tag;
+        self
+    }
+
+    /// Returns the length to keep for a packet.
+    ///
+    /// `snaplen` is an additional limit, e.g. the snaplen of the interface of the packet.
+    pub fn keep_len(&self, datalink: DataLink, data: &[u8], snaplen: Option<u32>) -> usize {
+        let limits = [headers_len(datalink, data), self.snaplen.map(|len| len as usize), snaplen.map(|len| len as usize)];
+        limits.into_iter().flatten().fold(data.len(), usize::min)
+    }
+
+    /// Truncates a [`PcapPacket`] of a pcap whose link type is `datalink`.
+    ///
+    /// Returns the number of bytes removed.
+    pub fn truncate_pcap_packet(&self, packet: &mut PcapPacket, datalink: DataLink) -> usize {
+        let len = self.keep_len(datalink, &packet.data, None);
+        truncate_cow(&mut packet.data, len)
+    }
+
+    /// Truncates an [`EnhancedPacketBlock`] captured on `interface`, respecting the snaplen of the interface.
+    ///
+    /// Returns the number of bytes removed.
+    pub fn truncate_enhanced_packet(&self, packet: &mut EnhancedPacketBlock, interface: &InterfaceDescriptionBlock) -> usize {
+        let mut block = Block::EnhancedPacket(std::mem::replace(packet, empty_enhanced_packet()));
+        let removed = self.truncate_packet_block(&mut block, interface);
+        *packet = block.into_enhanced_packet().unwrap();
+
+        removed
+    }
+
+    /// Truncates a pcapng [`Block`].
+    ///
+    /// `interfaces` are the interfaces of the current section, as returned by
+    /// [`PcapNgReader::interfaces`](crate::pcapng::PcapNgReader::interfaces).
+    /// The Enhanced Packet Blocks, Packet Blocks and Simple Packet Blocks are truncated, the Simple Packet Blocks use the first interface.
+    ///
+    /// Returns the number of bytes removed.
+    ///
+    /// # Errors
+    /// Returns [`PcapError::InvalidInterfaceId`] if a packet references an unknown interface.
+    pub fn truncate_block(&self, block: &mut Block, interfaces: &[InterfaceDescriptionBlock]) -> PcapResult<usize> {
+        let interface_id = match block {
+            Block::EnhancedPacket(packet) => packet.interface_id,
+            Block::Packet(packet) => packet.interface_id as u32,
+            Block::SimplePacket(_) => 0,
+            _ => return Ok(0),
+        };
+
+        let interface = interfaces.get(interface_id as usize).ok_or(PcapError::InvalidInterfaceId(interface_id))?;
+        Ok(self.truncate_packet_block(block, interface))
+    }
+
+    fn truncate_packet_block(&self, block: &mut Block, interface: &InterfaceDescriptionBlock) -> usize {
+        let snaplen = Some(interface.snaplen).filter(|&snaplen| snaplen != 0);
+        let len = self.keep_len(interface.linktype, block.data(), snaplen);
+
+        let removed = match block {
+            Block::EnhancedPacket(packet) => truncate_cow(&mut packet.data, len),
+            Block::Packet(packet) => {
+                let removed = truncate_cow(&mut packet.data, len);
+                packet.captured_len = len as u32;
+                removed
+            },
+            Block::SimplePacket(packet) => truncate_cow(&mut packet.data, len),
+            _ => 0,
+        };
+
+        if removed > 0 {
+            match self.tag {
+                TruncateTag::None => {},
+                TruncateTag::Comment => {
+                    block.add_comment(format!("Payload truncated: {removed} bytes removed"));
+                },
+                TruncateTag::CustomOption { pen } => {
+                    block.add_custom_binary(pen, (removed as u32).to_be_bytes().to_vec());
+                },
+            }
+        }
+
+        removed
+    }
+}
+
+/// Truncates a borrowed or owned buffer, returning the number of bytes removed.
+fn truncate_cow(data: &mut Cow<[u8]>, len: usize) -> usize {
+    let removed = data.len().saturating_sub(len);
+    match data {
+        Cow::Borrowed(slice) => *slice = &slice[..len.min(slice.len())],
+        Cow::Owned(vec) => vec.truncate(len),
+    }
+
+    removed
+}
+
+fn empty_enhanced_packet() -> EnhancedPacketBlock<'static> {
+    EnhancedPacketBlock { interface_id: 0, timestamp: Default::default(), original_len: 0, data: Cow::Borrowed(&[]), options: vec![] }
+}
+
+
+/// Truncates all the packets of a pcap, respecting its snaplen.
+///
+/// Returns the wrapped writer.
+pub async fn truncate_pcap<R, W>(reader: R, writer: W, truncator: &Truncator) -> PcapResult<W>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut reader = PcapReader::new(reader).await?;
+    let header = reader.header();
+    let mut writer = PcapWriter::with_header(writer, header).await?;
+    let truncator = truncator.with_snaplen(truncator.snaplen.map_or(header.snaplen, |snaplen| snaplen.min(header.snaplen)));
+
+    while let Some(packet) = reader.next_packet().await {
+        let mut packet = packet?;
+        truncator.truncate_pcap_packet(&mut packet, header.datalink);
+        writer.write_packet(&packet).await?;
+    }
+
+    Ok(writer.into_writer())
+}
+
+/// Truncates all the packets of a pcapng, respecting the snaplen of their interfaces.
+///
+/// Returns the wrapped writer.
+pub async fn truncate_pcapng<R, W>(reader: R, writer: W, truncator: &Truncator) -> PcapResult<W>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin + Send,
+{
+    let mut reader = PcapNgReader::new(reader).await?;
+    let mut writer = PcapNgWriter::with_section_header(writer, reader.section().clone()).await?;
+
+    while let Some(block) = reader.next_block().await {
+        let mut block = block?.into_owned();
+        truncator.truncate_block(&mut block, reader.interfaces())?;
+        writer.write_block(&block).await?;
+    }
+
+    Ok(writer.into_inner())
+}
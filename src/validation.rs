@@ -0,0 +1,190 @@
+use crate::errors::PcapError;
+
+
+/// Check controlled by a [`ParseMode`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Check {
+    /// The included and original lengths of a pcap packet are not greater than the snaplen of the pcap
+    Snaplen,
+    /// The fractional part of the timestamp of a pcap packet is less than a second
+    Timestamp,
+    /// The options of a pcapng block are well formed, have the length required by their code and end with an
+    /// end-of-options
+    OptionLength,
+    /// The string options of a pcapng block are valid UTF-8
+    Utf8,
+    /// The end-of-options of a pcapng block is the end of the block
+    TrailingBytes,
+}
+
+/// Set of the [`Check`] enforced by a [`ParseMode::Custom`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Checks {
+    /// Enforces [`Check::Snaplen`]
+    pub snaplen: bool,
+    /// Enforces [`Check::Timestamp`]
+    pub timestamp: bool,
+    /// Enforces [`Check::OptionLength`]
+    pub option_length: bool,
+    /// Enforces [`Check::Utf8`]
+    pub utf8: bool,
+    /// Enforces [`Check::TrailingBytes`]
+    pub trailing_bytes: bool,
+}
+
+/// Creates new [`Checks`] enforcing the checks enforced by the parsers before the parse modes, i.e. all of them except
+/// [`Check::TrailingBytes`]:
+///
+/// ```rust,ignore
+/// Checks {
+///     snaplen: true,
+///     timestamp: true,
+///     option_length: true,
+///     utf8: true,
+///     trailing_bytes: false,
+/// };
+/// ```
+impl Default for Checks {
+    fn default() -> Self {
+        Checks { trailing_bytes: false, ..Checks::all() }
+    }
+}
+
+impl Checks {
+    /// Creates new [`Checks`] enforcing all the checks.
+    pub fn all() -> Self {
+        Checks { snaplen: true, timestamp: true, option_length: true, utf8: true, trailing_bytes: true }
+    }
+
+    /// Creates new [`Checks`] enforcing none of the checks.
+    pub fn none() -> Self {
+        Checks { snaplen: false, timestamp: false, option_length: false, utf8: false, trailing_bytes: false }
+    }
+
+    /// Sets whether the given check is enforced.
+    pub fn with_check(mut self, check: Check, enforced: bool) -> Self {
+        match check {
+            Check::Snaplen => self.snaplen = enforced,
+            Check::Timestamp => self.timestamp = enforced,
+            Check::OptionLength => self.option_length = enforced,
+            Check::Utf8 => self.utf8 = enforced,
+            Check::TrailingBytes => self.trailing_bytes = enforced,
+        }
+
+        self
+    }
+
+    /// Returns true if the given check is enforced.
+    pub fn enforces(&self, check: Check) -> bool {
+        match check {
+            Check::Snaplen => self.snaplen,
+            Check::Timestamp => self.timestamp,
+            Check::OptionLength => self.option_length,
+            Check::Utf8 => self.utf8,
+            Check::TrailingBytes => self.trailing_bytes,
+        }
+    }
+}
+
+/// Checks enforced by the parsers and the readers.
+///
+/// A violation of an enforced check is an error. A violation of another check is collected as a [`ParseWarning`] and
+/// the record is recovered:
+/// - a pcap packet longer than the snaplen is kept as is
+/// - a timestamp whose fractional part overflows is carried into its seconds
+/// - a malformed option is dropped, a missing end-of-options is added
+/// - an invalid UTF-8 string option is replaced by its lossy conversion
+/// - the bytes after an end-of-options are dropped
+///
+/// The default mode is `Custom(Checks::default())`, the behavior of the parsers before the parse modes.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ParseMode {
+    /// Enforces all the checks
+    Strict,
+    /// Enforces none of the checks
+    Lenient,
+    /// Enforces the given checks
+    Custom(Checks),
+}
+
+impl Default for ParseMode {
+    fn default() -> Self {
+        ParseMode::Custom(Checks::default())
+    }
+}
+
+impl ParseMode {
+    /// Returns true if the given check is enforced.
+    pub fn enforces(&self, check: Check) -> bool {
+        match self {
+            ParseMode::Strict => true,
+            ParseMode::Lenient => false,
+            ParseMode::Custom(checks) => checks.enforces(check),
+        }
+    }
+}
+
+/// Violation of a check which is not enforced by the [`ParseMode`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ParseWarning {
+    /// Check violated
+    pub check: Check,
+    /// Index of the record in the capture: the index of the packet for a pcap, of the block for a pcapng
+    pub index: u64,
+    /// Description of the violation
+    pub reason: &'static str,
+}
+
+
+/// Applies a [`ParseMode`] to the records of a capture and collects its warnings.
+#[derive(Clone, Debug)]
+pub(crate) struct Validator {
+    mode: ParseMode,
+    warnings: Vec<ParseWarning>,
+    /// Index of the record being parsed
+    index: u64,
+}
+
+impl Validator {
+    pub(crate) fn new(mode: ParseMode) -> Self {
+        Validator { mode, warnings: vec![], index: 0 }
+    }
+
+    pub(crate) fn mode(&self) -> ParseMode {
+        self.mode
+    }
+
+    /// Reports a violation of `check`: returns `error` if the check is enforced, otherwise collects a warning.
+    pub(crate) fn violation(&mut self, check: Check, reason: &'static str, error: PcapError) -> Result<(), PcapError> {
+        if self.mode.enforces(check) {
+            return Err(error);
+        }
+
+        self.warnings.push(ParseWarning { check, index: self.index, reason });
+
+        Ok(())
+    }
+
+    /// Returns a mark to give to [`Validator::commit`] once the current record is parsed.
+    pub(crate) fn mark(&self) -> usize {
+        self.warnings.len()
+    }
+
+    /// Moves to the next record if the current one was parsed, otherwise drops its warnings, it will be parsed again.
+    pub(crate) fn commit<T>(&mut self, mark: usize, res: Result<T, PcapError>) -> Result<T, PcapError> {
+        match &res {
+            Ok(_) => self.index += 1,
+            Err(_) => self.warnings.truncate(mark),
+        }
+
+        res
+    }
+
+    pub(crate) fn warnings(&self) -> &[ParseWarning] {
+        &self.warnings
+    }
+
+    pub(crate) fn take_warnings(&mut self) -> Vec<ParseWarning> {
+        std::mem::take(&mut self.warnings)
+    }
+}
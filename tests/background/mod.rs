@@ -0,0 +1,125 @@
+use std::borrow::Cow;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use pcap_file_tokio::background::{spawn_writer, BackgroundConfig, Backpressure};
+use pcap_file_tokio::pcap::{PcapPacket, PcapReader, PcapWriter};
+use pcap_file_tokio::pcapng::blocks::enhanced_packet::EnhancedPacketBlock;
+use pcap_file_tokio::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use pcap_file_tokio::pcapng::{Block, PcapNgBlock, PcapNgReader, PcapNgWriter};
+use pcap_file_tokio::{DataLink, PcapError};
+use tokio::io::AsyncWrite;
+
+fn packet(i: u64) -> PcapPacket<'static> {
+    PcapPacket { timestamp: Duration::from_secs(i), orig_len: 8, data: Cow::Owned(i.to_be_bytes().to_vec()) }
+}
+
+/// Discards the written bytes and records the shutdown.
+struct ShutdownWriter(Arc<AtomicBool>);
+
+impl AsyncWrite for ShutdownWriter {
+    fn poll_write(self: Pin<&mut Self>, _: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        self.0.store(true, Ordering::Relaxed);
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn multiple_producers() {
+    let writer = PcapWriter::new(Vec::new()).await.unwrap();
+    let handle = spawn_writer(writer, BackgroundConfig::default().with_capacity(4).with_batch_size(3));
+
+    let mut tasks = vec![];
+    for producer in 0..4 {
+        let handle = handle.clone();
+        tasks.push(tokio::spawn(async move {
+            for i in 0..25 {
+                handle.write(packet(producer * 100 + i)).await.unwrap();
+            }
+        }));
+    }
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    let summary = handle.close().await.unwrap();
+    assert_eq!(summary.summary.packets, 100);
+    assert_eq!(summary.dropped, 0);
+    assert!(summary.batches >= 100 / 3);
+
+    let mut reader = PcapReader::new(&summary.writer[..]).await.unwrap();
+    let mut seen = vec![];
+    while let Some(packet) = reader.next_packet().await {
+        seen.push(packet.unwrap().timestamp.as_secs());
+    }
+
+    // The packets of each producer are written in order
+    assert_eq!(seen.len(), 100);
+    for producer in 0..4 {
+        let packets: Vec<_> = seen.iter().filter(|&&secs| secs / 100 == producer).collect();
+        assert!(packets.windows(2).all(|w| w[0] < w[1]));
+    }
+}
+
+#[tokio::test]
+async fn backpressure() {
+    // The writer task can't run before the test yields on the current thread runtime
+    let writer = PcapWriter::new(Vec::new()).await.unwrap();
+    let handle = spawn_writer(writer, BackgroundConfig::default().with_capacity(2).with_backpressure(Backpressure::Fail));
+    handle.write(packet(0)).await.unwrap();
+    handle.write(packet(1)).await.unwrap();
+    assert!(matches!(handle.write(packet(2)).await, Err(PcapError::QueueFull)));
+    assert_eq!(handle.close().await.unwrap().summary.packets, 2);
+
+    let writer = PcapWriter::new(Vec::new()).await.unwrap();
+    let handle = spawn_writer(writer, BackgroundConfig::default().with_capacity(2).with_backpressure(Backpressure::Drop));
+    for i in 0..5 {
+        handle.write(packet(i)).await.unwrap();
+    }
+    assert_eq!(handle.dropped(), 3);
+    let summary = handle.close().await.unwrap();
+    assert_eq!((summary.summary.packets, summary.dropped), (2, 3));
+}
+
+#[tokio::test]
+async fn errors() {
+    let writer = PcapNgWriter::new(Vec::new()).await.unwrap();
+    let handle = spawn_writer(writer, BackgroundConfig::default());
+    let other = handle.clone();
+
+    let packet = EnhancedPacketBlock { interface_id: 0, timestamp: Duration::ZERO, original_len: 1, data: Cow::Borrowed(&[0]), options: vec![] };
+    handle.write(InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0).into_block()).await.unwrap();
+    handle.write(packet.clone().into_block()).await.unwrap();
+    handle.write(Block::EnhancedPacket(EnhancedPacketBlock { interface_id: 1, ..packet.clone() })).await.unwrap();
+
+    // The first error is returned by close, the writer is closed for the other handles
+    assert!(matches!(handle.close().await, Err(PcapError::InvalidInterfaceId(1))));
+    assert!(matches!(other.write(InterfaceDescriptionBlock::new(DataLink::RAW, 0).into_block()).await, Err(PcapError::WriterClosed)));
+    assert!(matches!(other.close().await, Err(PcapError::WriterClosed)));
+
+    // The output is still shut down after an error
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let writer = PcapNgWriter::new(ShutdownWriter(shutdown.clone())).await.unwrap();
+    let handle = spawn_writer(writer, BackgroundConfig::default());
+    handle.write(packet.clone().into_block()).await.unwrap();
+    assert!(matches!(handle.close().await, Err(PcapError::InvalidInterfaceId(0))));
+    assert!(shutdown.load(Ordering::Relaxed));
+
+    let writer = PcapNgWriter::new(Vec::new()).await.unwrap();
+    let handle = spawn_writer(writer, BackgroundConfig::default());
+    handle.write(InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0).into_block()).await.unwrap();
+    let pcapng = handle.close().await.unwrap().writer;
+    let mut reader = PcapNgReader::new(&pcapng[..]).await.unwrap();
+    assert!(reader.next_block().await.unwrap().is_ok());
+}
@@ -0,0 +1,152 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use pcap_file_tokio::pcap::{BytesPcapReader, PcapPacket, PcapReader, PcapWriter};
+use pcap_file_tokio::pcapng::blocks::enhanced_packet::{EnhancedPacketBlock, EnhancedPacketOption};
+use pcap_file_tokio::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use pcap_file_tokio::pcapng::blocks::section_header::SectionHeaderBlock;
+use pcap_file_tokio::pcapng::{Block, BytesPcapNgReader, InterfaceKey, PcapNgBlock, PcapNgFrame, PcapNgReader, PcapNgWriter};
+use pcap_file_tokio::{DataLink, Limit, ParseLimits, PcapError, ReaderConfig};
+
+#[tokio::test]
+async fn pcap() {
+    let mut writer = PcapWriter::new(Vec::new()).await.unwrap();
+    for i in 0..1000_u32 {
+        let data = vec![i as u8; (i % 1500) as usize];
+        writer.write_packet(&PcapPacket::new(Duration::from_millis(i as u64), data.len() as u32, &data)).await.unwrap();
+    }
+    let pcap = writer.into_writer();
+
+    let mut reader = BytesPcapReader::new(&pcap[..]).await.unwrap();
+    let mut expected = PcapReader::new(&pcap[..]).await.unwrap();
+    let mut packets = vec![];
+    while let Some(packet) = reader.next_packet().await {
+        let packet = packet.unwrap();
+        let expected = expected.next_packet().await.unwrap().unwrap();
+        assert_eq!(packet.as_pcap_packet().timestamp, expected.timestamp);
+        assert_eq!(&packet.data[..], &expected.data[..]);
+        packets.push(packet);
+    }
+    assert!(expected.next_packet().await.is_none());
+    assert_eq!(reader.header(), expected.header());
+
+    // The packets outlive the reader and can be sent to other tasks
+    drop(reader);
+    let len = tokio::spawn(async move { packets.iter().map(|packet| packet.data.len()).sum::<usize>() }).await.unwrap();
+    assert_eq!(len, (0..1000).sum::<usize>());
+
+    // A truncated packet is an error
+    let mut reader = BytesPcapReader::new(&pcap[..pcap.len() - 1]).await.unwrap();
+    let mut packets = 0;
+    loop {
+        match reader.next_packet().await {
+            Some(Ok(_)) => packets += 1,
+            Some(Err(_)) => break,
+            None => panic!("Truncated packet not detected"),
+        }
+    }
+    assert_eq!(packets, 999);
+}
+
+#[tokio::test]
+async fn pcapng() {
+    let eth0 = InterfaceKey::new("eth0", DataLink::ETHERNET);
+    let mut writer = PcapNgWriter::new(Vec::new()).await.unwrap();
+    for i in 0..500_u32 {
+        let data = vec![i as u8; (i * 7 % 1500) as usize];
+        writer.write_packet(&eth0, &PcapPacket::new(Duration::from_millis(i as u64), data.len() as u32, &data)).await.unwrap();
+        if i == 250 {
+            writer.write_block(&SectionHeaderBlock::default().into_block()).await.unwrap();
+        }
+    }
+    let pcapng = writer.into_inner();
+
+    let mut reader = BytesPcapNgReader::new(&pcapng[..]).await.unwrap();
+    let mut expected = PcapNgReader::new(&pcapng[..]).await.unwrap();
+    let mut packets = 0;
+    while let Some(frame) = reader.next_block().await {
+        let expected = expected.next_block().await.unwrap().unwrap();
+        match (frame.unwrap(), expected) {
+            (PcapNgFrame::Packet(packet), Block::EnhancedPacket(expected)) => {
+                assert_eq!(packet.as_block(), expected);
+                assert_eq!(reader.packet_interface(&packet).unwrap().linktype, DataLink::ETHERNET);
+                packets += 1;
+            },
+            (PcapNgFrame::Block(block), expected) => assert_eq!(block, expected),
+            (frame, expected) => panic!("{frame:?} read instead of {expected:?}"),
+        }
+    }
+
+    assert!(expected.next_block().await.is_none());
+    assert_eq!(packets, 500);
+    assert_eq!(reader.section(), &SectionHeaderBlock::default());
+    assert_eq!(reader.interfaces().len(), 1);
+}
+
+#[tokio::test]
+async fn max_record_size() {
+    let mut writer = PcapWriter::new(Vec::new()).await.unwrap();
+    let mut ng_writer = PcapNgWriter::new(Vec::new()).await.unwrap();
+    let eth0 = InterfaceKey::new("eth0", DataLink::ETHERNET);
+    for size in [10, 1_000, 5_000] {
+        let data = vec![0; size];
+        let packet = PcapPacket::new(Duration::ZERO, size as u32, &data);
+        writer.write_packet(&packet).await.unwrap();
+        ng_writer.write_packet(&eth0, &packet).await.unwrap();
+    }
+    let pcap = writer.into_writer();
+    let pcapng = ng_writer.into_inner();
+
+    // The records larger than the chunks are read, the larger than the maximum record size are rejected
+    let config = ReaderConfig::default().with_initial_capacity(64).with_max_record_size(2_000);
+    let mut reader = BytesPcapReader::with_config(&pcap[..], config).await.unwrap();
+    assert_eq!(reader.next_packet().await.unwrap().unwrap().data.len(), 10);
+    assert_eq!(reader.next_packet().await.unwrap().unwrap().data.len(), 1_000);
+    assert!(matches!(reader.next_packet().await, Some(Err(PcapError::RecordTooLarge(2_000)))));
+
+    let mut reader = BytesPcapNgReader::with_config(&pcapng[..], config).await.unwrap();
+    for _ in 0..3 {
+        reader.next_block().await.unwrap().unwrap();
+    }
+    assert!(matches!(reader.next_block().await, Some(Err(PcapError::RecordTooLarge(2_000)))));
+
+    // A truncated record isn't mistaken for a record too large
+    let config = ReaderConfig::default().with_initial_capacity(64);
+    let mut reader = BytesPcapReader::with_config(&pcap[..pcap.len() - 1], config).await.unwrap();
+    for _ in 0..2 {
+        reader.next_packet().await.unwrap().unwrap();
+    }
+    assert!(matches!(reader.next_packet().await, Some(Err(PcapError::IoError(_)))));
+}
+
+#[tokio::test]
+async fn limits() {
+    let mut writer = PcapWriter::new(Vec::new()).await.unwrap();
+    for size in [10, 1_000] {
+        writer.write_packet(&PcapPacket::new(Duration::ZERO, size as u32, &vec![0; size])).await.unwrap();
+    }
+    let pcap = writer.into_writer();
+
+    let config = ReaderConfig::default().with_limits(ParseLimits::default().with_max_block_len(100));
+    let mut reader = BytesPcapReader::with_config(&pcap[..], config).await.unwrap();
+    reader.next_packet().await.unwrap().unwrap();
+    assert!(matches!(reader.next_packet().await, Some(Err(PcapError::LimitExceeded(Limit::BlockLen, 100)))));
+
+    // The options of the Enhanced Packet Blocks are checked too
+    let mut writer = PcapNgWriter::new(Vec::new()).await.unwrap();
+    writer.write_block(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0).into_block()).await.unwrap();
+    let packet = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::ZERO,
+        original_len: 4,
+        data: Cow::Borrowed(&[1, 2, 3, 4]),
+        options: vec![EnhancedPacketOption::Comment("a".into()), EnhancedPacketOption::Flags(0), EnhancedPacketOption::DropCount(0)],
+    };
+    writer.write_block(&packet.into_block()).await.unwrap();
+    let pcapng = writer.into_inner();
+
+    let config = ReaderConfig::default().with_limits(ParseLimits::default().with_max_options(2));
+    let mut reader = BytesPcapNgReader::with_config(&pcapng[..], config).await.unwrap();
+    reader.next_block().await.unwrap().unwrap();
+    assert!(matches!(reader.next_block().await, Some(Err(PcapError::LimitExceeded(Limit::Options, 2)))));
+}
@@ -0,0 +1,188 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use bytes::BytesMut;
+use futures::{SinkExt, StreamExt};
+use pcap_file_tokio::codec::{PcapCodec, PcapNgCodec, PcapNgFrame};
+use pcap_file_tokio::pcap::{PcapPacket, PcapWriter};
+use pcap_file_tokio::pcapng::blocks::enhanced_packet::{EnhancedPacketBlock, EnhancedPacketOption};
+use pcap_file_tokio::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use pcap_file_tokio::pcapng::{Block, PcapNgBlock, PcapNgWriter};
+use pcap_file_tokio::{DataLink, Endianness, Limit, ParseLimits, PcapError};
+use tokio_util::codec::{Decoder, Encoder, FramedRead, FramedWrite};
+
+#[tokio::test]
+async fn pcap() {
+    let packets = [PcapPacket::new(Duration::from_secs(1), 4, &[1, 2, 3, 4]), PcapPacket::new(Duration::from_secs(2), 2, &[5, 6])];
+
+    // Same output as the writer
+    let mut codec = PcapCodec::new();
+    let mut encoded = BytesMut::new();
+    for packet in &packets {
+        codec.encode(packet.clone(), &mut encoded).unwrap();
+    }
+
+    let mut writer = PcapWriter::new(Vec::new()).await.unwrap();
+    for packet in &packets {
+        writer.write_packet(packet).await.unwrap();
+    }
+    assert_eq!(&encoded[..], &writer.into_writer()[..]);
+
+    // Decoded byte by byte, the payload shares the buffer of the decoder
+    let mut codec = PcapCodec::new();
+    let mut src = BytesMut::new();
+    let mut decoded = vec![];
+    for byte in &encoded[..] {
+        src.extend_from_slice(&[*byte]);
+        if let Some(packet) = codec.decode(&mut src).unwrap() {
+            decoded.push(packet);
+        }
+    }
+    assert_eq!(codec.decoded_header(), Some(codec.header()));
+    assert_eq!(decoded.len(), 2);
+    for (decoded, packet) in decoded.iter().zip(&packets) {
+        assert_eq!(decoded.as_pcap_packet().timestamp, packet.timestamp);
+        assert_eq!(decoded.orig_len, packet.orig_len);
+        assert_eq!(&decoded.data[..], &packet.data[..]);
+    }
+
+    let mut src = encoded.clone();
+    let start = src.as_ptr() as usize;
+    let packet = PcapCodec::new().decode(&mut src).unwrap().unwrap();
+    assert_eq!(packet.data.as_ptr() as usize, start + 24 + 16);
+}
+
+#[tokio::test]
+async fn pcapng() {
+    let interface = InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0);
+    let packet = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::from_micros(1_500_000),
+        original_len: 5,
+        data: Cow::Borrowed(&[1, 2, 3, 4, 5]),
+        options: vec![],
+    };
+
+    // Same output as the writer
+    let mut codec = PcapNgCodec::new();
+    let mut encoded = BytesMut::new();
+    codec.encode(interface.clone().into_block(), &mut encoded).unwrap();
+    codec.encode(packet.clone().into_block(), &mut encoded).unwrap();
+
+    let mut writer = PcapNgWriter::new(Vec::new()).await.unwrap();
+    writer.write_block(&interface.clone().into_block()).await.unwrap();
+    writer.write_block(&packet.clone().into_block()).await.unwrap();
+    assert_eq!(&encoded[..], &writer.into_inner()[..]);
+
+    // Decoded byte by byte
+    let mut codec = PcapNgCodec::new();
+    let mut src = BytesMut::new();
+    let mut frames = vec![];
+    for byte in &encoded[..] {
+        src.extend_from_slice(&[*byte]);
+        if let Some(frame) = codec.decode(&mut src).unwrap() {
+            frames.push(frame);
+        }
+    }
+    assert_eq!(codec.decoded_section().unwrap().endianness, Endianness::native());
+    assert_eq!(codec.decoded_interfaces(), std::slice::from_ref(&interface));
+
+    assert_eq!(frames.len(), 3);
+    assert!(matches!(&frames[0], PcapNgFrame::Block(Block::SectionHeader(_))));
+    assert!(matches!(&frames[1], PcapNgFrame::Block(Block::InterfaceDescription(idb)) if *idb == interface));
+    match &frames[2] {
+        PcapNgFrame::Packet(decoded) => assert_eq!(decoded.as_block(), packet),
+        frame => panic!("Unexpected frame {frame:?}"),
+    }
+}
+
+#[tokio::test]
+async fn framed() {
+    let mut sink = FramedWrite::new(Vec::new(), PcapNgCodec::new());
+    sink.send(InterfaceDescriptionBlock::new(DataLink::RAW, 0).into_block()).await.unwrap();
+    for i in 0..10_u8 {
+        let packet = EnhancedPacketBlock {
+            interface_id: 0,
+            timestamp: Duration::from_micros(i as u64),
+            original_len: i as u32,
+            data: Cow::Owned(vec![i; i as usize]),
+            options: vec![],
+        };
+        sink.send(packet.into_block()).await.unwrap();
+    }
+    let pcapng = sink.into_inner();
+
+    let mut stream = FramedRead::new(&pcapng[..], PcapNgCodec::new());
+    let mut packets = vec![];
+    while let Some(frame) = stream.next().await {
+        if let PcapNgFrame::Packet(packet) = frame.unwrap() {
+            packets.push(packet);
+        }
+    }
+
+    assert_eq!(packets.len(), 10);
+    for (i, packet) in packets.iter().enumerate() {
+        assert_eq!(&packet.data[..], &vec![i as u8; i][..]);
+    }
+    assert_eq!(stream.decoder().decoded_interfaces()[0].linktype, DataLink::RAW);
+}
+
+#[tokio::test]
+async fn max_record_size() {
+    let mut sink = FramedWrite::new(Vec::new(), PcapCodec::new());
+    for size in [100, 1_000] {
+        sink.send(PcapPacket::new(Duration::ZERO, size as u32, &vec![0; size])).await.unwrap();
+    }
+    let pcap = sink.into_inner();
+
+    let mut stream = FramedRead::new(&pcap[..], PcapCodec::new().with_max_record_size(500));
+    assert_eq!(stream.next().await.unwrap().unwrap().data.len(), 100);
+    assert!(matches!(stream.next().await, Some(Err(PcapError::RecordTooLarge(500)))));
+
+    let mut sink = FramedWrite::new(Vec::new(), PcapNgCodec::new());
+    sink.send(InterfaceDescriptionBlock::new(DataLink::RAW, 0).into_block()).await.unwrap();
+    let packet = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::ZERO,
+        original_len: 1_000,
+        data: Cow::Owned(vec![0; 1_000]),
+        options: vec![],
+    };
+    sink.send(packet.into_block()).await.unwrap();
+    let pcapng = sink.into_inner();
+
+    let mut stream = FramedRead::new(&pcapng[..], PcapNgCodec::new().with_max_record_size(500));
+    assert!(matches!(stream.next().await, Some(Ok(PcapNgFrame::Block(Block::SectionHeader(_))))));
+    assert!(matches!(stream.next().await, Some(Ok(PcapNgFrame::Block(Block::InterfaceDescription(_))))));
+    assert!(matches!(stream.next().await, Some(Err(PcapError::RecordTooLarge(500)))));
+}
+
+#[tokio::test]
+async fn limits() {
+    let mut sink = FramedWrite::new(Vec::new(), PcapCodec::new());
+    for size in [10, 1_000] {
+        sink.send(PcapPacket::new(Duration::ZERO, size as u32, &vec![0; size])).await.unwrap();
+    }
+    let pcap = sink.into_inner();
+
+    let mut stream = FramedRead::new(&pcap[..], PcapCodec::new().with_limits(ParseLimits::default().with_max_block_len(100)));
+    stream.next().await.unwrap().unwrap();
+    assert!(matches!(stream.next().await, Some(Err(PcapError::LimitExceeded(Limit::BlockLen, 100)))));
+
+    let mut sink = FramedWrite::new(Vec::new(), PcapNgCodec::new());
+    sink.send(InterfaceDescriptionBlock::new(DataLink::RAW, 0).into_block()).await.unwrap();
+    let packet = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::ZERO,
+        original_len: 0,
+        data: Cow::Borrowed(&[]),
+        options: vec![EnhancedPacketOption::Flags(0), EnhancedPacketOption::DropCount(0)],
+    };
+    sink.send(packet.into_block()).await.unwrap();
+    let pcapng = sink.into_inner();
+
+    let mut stream = FramedRead::new(&pcapng[..], PcapNgCodec::new().with_limits(ParseLimits::default().with_max_options(1)));
+    stream.next().await.unwrap().unwrap();
+    stream.next().await.unwrap().unwrap();
+    assert!(matches!(stream.next().await, Some(Err(PcapError::LimitExceeded(Limit::Options, 1)))));
+}
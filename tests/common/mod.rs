@@ -0,0 +1,40 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use pcap_file_tokio::pcapng::blocks::enhanced_packet::{EnhancedPacketBlock, EnhancedPacketOption};
+use pcap_file_tokio::pcapng::Block;
+
+pub const ETHERNET_IPV4: [u8; 14] = [0x00, 0x1B, 0x21, 0x01, 0x02, 0x03, 0x3C, 0xFD, 0xFE, 0x04, 0x05, 0x06, 0x08, 0x00];
+
+/// Enhanced Packet Block with a timestamp in nanoseconds and the full data captured.
+pub fn packet(interface_id: u32, timestamp: u64, data: &[u8]) -> Block<'_> {
+    packet_with_options(interface_id, timestamp, data, vec![])
+}
+
+pub fn packet_with_options<'a>(interface_id: u32, timestamp: u64, data: &'a [u8], options: Vec<EnhancedPacketOption<'a>>) -> Block<'a> {
+    Block::EnhancedPacket(EnhancedPacketBlock {
+        interface_id,
+        timestamp: Duration::from_nanos(timestamp),
+        original_len: data.len() as u32,
+        data: Cow::Borrowed(data),
+        options,
+    })
+}
+
+/// IPv4 header without options, followed by the payload.
+pub fn ipv4(protocol: u8, src: [u8; 4], dst: [u8; 4], payload: &[u8]) -> Vec<u8> {
+    let mut ip = vec![0x45, 0, 0, 0, 0, 1, 0, 0, 64, protocol, 0, 0];
+    ip[2..4].copy_from_slice(&((20 + payload.len()) as u16).to_be_bytes());
+    ip.extend_from_slice(&src);
+    ip.extend_from_slice(&dst);
+    ip.extend_from_slice(payload);
+    ip
+}
+
+pub fn udp(dst_port: u16, payload: &[u8]) -> Vec<u8> {
+    let mut udp = vec![0x04, 0xD2, 0, 0, 0, 0, 0, 0];
+    udp[2..4].copy_from_slice(&dst_port.to_be_bytes());
+    udp[4..6].copy_from_slice(&((8 + payload.len()) as u16).to_be_bytes());
+    udp.extend_from_slice(payload);
+    udp
+}
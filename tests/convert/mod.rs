@@ -0,0 +1,127 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use pcap_file_tokio::convert::{pcap_to_pcapng, pcapng_to_pcap, pcapng_to_pcap_split, ConversionReport};
+use pcap_file_tokio::pcap::{PcapHeader, PcapPacket, PcapReader, PcapWriter};
+use pcap_file_tokio::pcapng::blocks::enhanced_packet::{EnhancedPacketBlock, EnhancedPacketOption};
+use pcap_file_tokio::pcapng::blocks::interface_description::{InterfaceDescriptionBlock, InterfaceDescriptionOption};
+use pcap_file_tokio::pcapng::blocks::name_resolution::NameResolutionBlock;
+use pcap_file_tokio::pcapng::{Block, PcapNgReader, PcapNgWriter};
+use pcap_file_tokio::{DataLink, Endianness, PcapError, TsResolution};
+
+use crate::common::packet;
+
+#[tokio::test]
+async fn pcap_pcapng_round_trip() {
+    let header = PcapHeader {
+        snaplen: 1500,
+        datalink: DataLink::LINUX_SLL,
+        ts_resolution: TsResolution::NanoSecond,
+        endianness: Endianness::Big,
+        ..Default::default()
+    };
+
+    let data = [0xAB_u8; 64];
+    let packets = [
+        PcapPacket::new(Duration::new(1_600_000_000, 123_456_789), 100, &data),
+        PcapPacket::new(Duration::new(1_600_000_001, 1), 64, &data),
+    ];
+
+    let mut pcap_writer = PcapWriter::with_header(Vec::new(), header).await.unwrap();
+    for packet in &packets {
+        pcap_writer.write_packet(packet).await.unwrap();
+    }
+    let pcap = pcap_writer.into_writer();
+
+    let (pcapng, report) = pcap_to_pcapng(&pcap[..], Vec::new()).await.unwrap();
+    assert_eq!(report, ConversionReport { packets: 2, ..Default::default() });
+
+    let mut pcapng_reader = PcapNgReader::new(&pcapng[..]).await.unwrap();
+    assert_eq!(pcapng_reader.section().endianness, Endianness::Big);
+    let interface = pcapng_reader.next_block().await.unwrap().unwrap().into_interface_description().unwrap();
+    assert_eq!(interface.linktype, DataLink::LINUX_SLL);
+    assert_eq!(interface.snaplen, 1500);
+    assert_eq!(interface.ts_resolution(), 9);
+
+    let (out, report) = pcapng_to_pcap(&pcapng[..], Vec::new()).await.unwrap();
+    assert!(report.is_lossless());
+    assert_eq!(out, pcap);
+
+    let pcap_reader = PcapReader::new(&out[..]).await.unwrap();
+    assert_eq!(pcap_reader.header(), header);
+}
+
+#[tokio::test]
+async fn pcapng_to_pcap_lossy() {
+    let data = [0_u8; 32];
+
+    let mut interface = InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0);
+    interface.options = vec![InterfaceDescriptionOption::IfName("eth0".into()), InterfaceDescriptionOption::IfTsResol(9)];
+
+    let mut commented = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::from_nanos(1_500),
+        original_len: 32,
+        data: Cow::Borrowed(&data),
+        options: vec![EnhancedPacketOption::Comment("comment".into())],
+    };
+
+    let mut writer = PcapNgWriter::with_endianness(Vec::new(), Endianness::Little).await.unwrap();
+    writer.write_block(&Block::InterfaceDescription(interface)).await.unwrap();
+    writer.write_block(&Block::NameResolution(NameResolutionBlock { records: vec![], options: vec![] })).await.unwrap();
+    writer.write_block(&Block::EnhancedPacket(commented.clone())).await.unwrap();
+    commented.options.clear();
+    writer.write_block(&Block::EnhancedPacket(commented)).await.unwrap();
+    let pcapng = writer.into_inner();
+
+    let (pcap, report) = pcapng_to_pcap(&pcapng[..], Vec::new()).await.unwrap();
+    let expected = ConversionReport { packets: 2, dropped_comments: 1, dropped_options: 1, dropped_name_resolutions: 1, ..Default::default() };
+    assert_eq!(report, expected);
+    assert!(!report.is_lossless());
+
+    let mut pcap_reader = PcapReader::new(&pcap[..]).await.unwrap();
+    let header = pcap_reader.header();
+    assert_eq!(header.endianness, Endianness::Little);
+    assert_eq!(header.ts_resolution, TsResolution::NanoSecond);
+    assert_eq!(header.snaplen, 262144);
+
+    let packet = pcap_reader.next_packet().await.unwrap().unwrap();
+    assert_eq!(packet.timestamp, Duration::from_nanos(1_500));
+}
+
+#[tokio::test]
+async fn pcapng_mixed_link_types() {
+    let data = [0_u8; 16];
+
+    let mut writer = PcapNgWriter::new(Vec::new()).await.unwrap();
+    writer.write_block(&Block::InterfaceDescription(InterfaceDescriptionBlock::new(DataLink::ETHERNET, 1500))).await.unwrap();
+    writer.write_block(&Block::InterfaceDescription(InterfaceDescriptionBlock::new(DataLink::RAW, 9000))).await.unwrap();
+    writer.write_block(&packet(0, 1, &data)).await.unwrap();
+    writer.write_block(&packet(1, 2, &data)).await.unwrap();
+    writer.write_block(&packet(0, 3, &data)).await.unwrap();
+    let pcapng = writer.into_inner();
+
+    let err = pcapng_to_pcap(&pcapng[..], Vec::new()).await.unwrap_err();
+    assert!(matches!(err, PcapError::MixedLinkTypes(DataLink::ETHERNET, DataLink::RAW)));
+
+    let (outputs, report) = pcapng_to_pcap_split(&pcapng[..], |_| async { Ok(Vec::new()) }).await.unwrap();
+    assert_eq!(report.packets, 3);
+    assert_eq!(outputs.len(), 2);
+
+    let expected = [(DataLink::ETHERNET, 1500, 2), (DataLink::RAW, 9000, 1)];
+    for ((linktype, pcap), (expected_linktype, snaplen, count)) in outputs.iter().zip(expected) {
+        assert_eq!(*linktype, expected_linktype);
+
+        let mut pcap_reader = PcapReader::new(&pcap[..]).await.unwrap();
+        assert_eq!(pcap_reader.header().datalink, expected_linktype);
+        assert_eq!(pcap_reader.header().snaplen, snaplen);
+        assert_eq!(pcap_reader.header().ts_resolution, TsResolution::MicroSecond);
+
+        let mut i = 0;
+        while let Some(packet) = pcap_reader.next_packet().await {
+            packet.unwrap();
+            i += 1;
+        }
+        assert_eq!(i, count);
+    }
+}
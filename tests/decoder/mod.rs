@@ -0,0 +1,105 @@
+use std::time::Duration;
+
+use pcap_file_tokio::decoder::{Decoder, DecoderEvent};
+use pcap_file_tokio::info::CaptureFormat;
+use pcap_file_tokio::pcap::{PcapPacket, PcapWriter};
+use pcap_file_tokio::pcapng::blocks::section_header::SectionHeaderBlock;
+use pcap_file_tokio::pcapng::{Block, InterfaceKey, PcapNgBlock, PcapNgWriter};
+use pcap_file_tokio::{DataLink, Endianness, Limit, ParseLimits, PcapError};
+
+/// Feeds `data` in chunks of `chunk_size` bytes and returns a summary of the events.
+fn decode(data: &[u8], chunk_size: usize) -> (Decoder, Vec<String>) {
+    let mut decoder = Decoder::new();
+    let mut events = vec![];
+
+    for chunk in data.chunks(chunk_size) {
+        decoder.feed(chunk);
+
+        loop {
+            let event = match decoder.next_event().unwrap() {
+                DecoderEvent::Header(header) => format!("header {:?}", header.datalink),
+                DecoderEvent::Section(section) => format!("section {:?}", section.endianness),
+                DecoderEvent::Interface(interface) => format!("interface {:?}", interface.linktype),
+                DecoderEvent::Packet(packet) => format!("packet {:?} {:?}", packet.timestamp, packet.data),
+                DecoderEvent::Block(Block::EnhancedPacket(packet)) => format!("block {} {:?}", packet.interface_id, packet.data),
+                DecoderEvent::Block(block) => format!("block {block:?}"),
+                DecoderEvent::NeedMore => break,
+            };
+            events.push(event);
+        }
+    }
+
+    (decoder, events)
+}
+
+#[tokio::test]
+async fn pcap() {
+    let mut writer = PcapWriter::new(Vec::new()).await.unwrap();
+    writer.write_packet(&PcapPacket::new(Duration::from_secs(1), 4, &[1, 2, 3, 4])).await.unwrap();
+    writer.write_packet(&PcapPacket::new(Duration::from_secs(2), 2, &[5, 6])).await.unwrap();
+    let pcap = writer.into_writer();
+
+    let expected = vec!["header ETHERNET", "packet 1s [1, 2, 3, 4]", "packet 2s [5, 6]"];
+    for chunk_size in [1, 3, 7, pcap.len()] {
+        let (decoder, events) = decode(&pcap, chunk_size);
+        assert_eq!(events, expected);
+        assert_eq!(decoder.format(), Some(CaptureFormat::Pcap));
+        assert_eq!(decoder.buffered(), 0);
+    }
+
+    // A truncated packet stays buffered
+    let (decoder, events) = decode(&pcap[..pcap.len() - 1], 5);
+    assert_eq!(events, expected[..2]);
+    assert_eq!(decoder.buffered(), 17);
+}
+
+#[tokio::test]
+async fn pcapng() {
+    let eth0 = InterfaceKey::new("eth0", DataLink::ETHERNET);
+    let mut writer = PcapNgWriter::new(Vec::new()).await.unwrap();
+    writer.write_packet(&eth0, &PcapPacket::new(Duration::from_secs(1), 4, &[1, 2, 3, 4])).await.unwrap();
+    writer.write_block(&SectionHeaderBlock::default().into_block()).await.unwrap();
+    writer.write_packet(&eth0, &PcapPacket::new(Duration::from_secs(2), 2, &[5, 6])).await.unwrap();
+    let pcapng = writer.into_inner();
+
+    // The writer starts with a native endian section
+    let first_section = format!("section {:?}", Endianness::native());
+    let expected = vec![
+        first_section.as_str(),
+        "interface ETHERNET",
+        "block 0 [1, 2, 3, 4]",
+        "section Big",
+        "interface ETHERNET",
+        "block 0 [5, 6]",
+    ];
+    for chunk_size in [1, 5, 64, pcapng.len()] {
+        let (decoder, events) = decode(&pcapng, chunk_size);
+        assert_eq!(events, expected);
+        assert_eq!(decoder.format(), Some(CaptureFormat::PcapNg));
+        assert_eq!(decoder.buffered(), 0);
+    }
+}
+
+#[test]
+fn errors() {
+    let mut decoder = Decoder::new();
+    decoder.feed(&[0xD4, 0xC3]);
+    assert!(matches!(decoder.next_event(), Ok(DecoderEvent::NeedMore)));
+    assert_eq!(decoder.format(), None);
+
+    decoder.feed(&[0; 30]);
+    assert!(matches!(decoder.next_event(), Err(PcapError::InvalidField(_))));
+}
+
+#[tokio::test]
+async fn limits() {
+    let mut writer = PcapWriter::new(Vec::new()).await.unwrap();
+    writer.write_packet(&PcapPacket::new(Duration::ZERO, 1_000, &[0; 1_000])).await.unwrap();
+    let pcap = writer.into_writer();
+
+    // The packet is rejected from its header, before its data is fed
+    let mut decoder = Decoder::with_limits(ParseLimits::default().with_max_block_len(100));
+    decoder.feed(&pcap[..24 + 16]);
+    assert!(matches!(decoder.next_event(), Ok(DecoderEvent::Header(_))));
+    assert!(matches!(decoder.next_event(), Err(PcapError::LimitExceeded(Limit::BlockLen, 100))));
+}
@@ -0,0 +1,104 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use pcap_file_tokio::info::{CaptureFormat, CaptureInfo, InterfaceStatistics};
+use pcap_file_tokio::pcap::{PcapHeader, PcapPacket, PcapWriter};
+use pcap_file_tokio::pcapng::blocks::enhanced_packet::EnhancedPacketBlock;
+use pcap_file_tokio::pcapng::blocks::interface_description::{InterfaceDescriptionBlock, InterfaceDescriptionOption};
+use pcap_file_tokio::pcapng::blocks::interface_statistics::{InterfaceStatisticsBlock, InterfaceStatisticsOption};
+use pcap_file_tokio::pcapng::blocks::section_header::{SectionHeaderBlock, SectionHeaderOption};
+use pcap_file_tokio::pcapng::{Block, PcapNgWriter};
+use pcap_file_tokio::{DataLink, Endianness, TsResolution};
+
+#[tokio::test]
+async fn pcap_info() {
+    let header = PcapHeader { datalink: DataLink::RAW, ts_resolution: TsResolution::NanoSecond, endianness: Endianness::Big, ..Default::default() };
+    let mut writer = PcapWriter::with_header(Vec::new(), header).await.unwrap();
+    writer.write_packet(&PcapPacket::new(Duration::new(10, 0), 100, &[0; 60])).await.unwrap();
+    writer.write_packet(&PcapPacket::new(Duration::new(12, 0), 40, &[0; 40])).await.unwrap();
+    writer.write_packet(&PcapPacket::new(Duration::new(11, 0), 20, &[0; 20])).await.unwrap();
+    let pcap = writer.into_writer();
+
+    let info = CaptureInfo::compute(&pcap[..]).await.unwrap();
+    assert_eq!(info.format, CaptureFormat::Pcap);
+    assert_eq!(info.endianness, Endianness::Big);
+    assert_eq!((info.version_major, info.version_minor), (2, 4));
+    assert_eq!(info.interfaces.len(), 1);
+    assert_eq!(info.interfaces[0].ts_resolution, 9);
+    assert_eq!(info.interfaces[0].packets, 3);
+    assert_eq!((info.packets, info.total_bytes, info.captured_bytes), (3, 160, 120));
+    assert_eq!(info.first_timestamp, Some(Duration::from_secs(10)));
+    assert_eq!(info.last_timestamp, Some(Duration::from_secs(12)));
+    assert_eq!(info.duration(), Some(Duration::from_secs(2)));
+    assert_eq!(info.packet_rate(), Some(1.5));
+    assert_eq!(info.data_bit_rate(), Some(480.0));
+    assert_eq!((info.min_packet_size, info.max_packet_size, info.average_packet_size()), (Some(20), Some(60), Some(40.0)));
+    assert!(!info.strict_time_order);
+
+    let text = info.to_string();
+    assert!(text.contains("File format:         pcap\n"));
+    assert!(text.contains("Interface #0:        RAW, snaplen 65535, timestamp resolution 10^-9 s, 3 packets\n"));
+    assert!(text.contains("Strict time order:   false\n"));
+}
+
+#[tokio::test]
+async fn pcapng_info() {
+    let section = SectionHeaderBlock {
+        options: vec![SectionHeaderOption::Hardware("x86 \"test\"".into()), SectionHeaderOption::UserApplication("tests".into())],
+        ..Default::default()
+    };
+    let mut writer = PcapNgWriter::with_section_header(Vec::new(), section.clone()).await.unwrap();
+
+    let mut interface = InterfaceDescriptionBlock::new(DataLink::ETHERNET, 1500);
+    interface.options.push(InterfaceDescriptionOption::IfTsResol(9));
+    writer.write_block(&Block::InterfaceDescription(interface)).await.unwrap();
+
+    for secs in [1, 2] {
+        let packet = EnhancedPacketBlock {
+            interface_id: 0,
+            timestamp: Duration::from_secs(secs),
+            original_len: 64,
+            data: Cow::Borrowed(&[0; 64]),
+            options: vec![],
+        };
+        writer.write_block(&Block::EnhancedPacket(packet)).await.unwrap();
+    }
+
+    let stats = InterfaceStatisticsBlock {
+        interface_id: 0,
+        timestamp: 0,
+        options: vec![InterfaceStatisticsOption::IsbIfRecv(10), InterfaceStatisticsOption::IsbIfDrop(1)],
+    };
+    writer.write_block(&Block::InterfaceStatistics(stats)).await.unwrap();
+
+    // Second section with its own interface
+    writer.write_block(&Block::SectionHeader(SectionHeaderBlock::default())).await.unwrap();
+    writer.write_block(&Block::InterfaceDescription(InterfaceDescriptionBlock::new(DataLink::RAW, 0))).await.unwrap();
+    let packet = EnhancedPacketBlock { interface_id: 0, timestamp: Duration::from_nanos(3_000_000), original_len: 20, data: Cow::Borrowed(&[0; 20]), options: vec![] };
+    writer.write_block(&Block::EnhancedPacket(packet)).await.unwrap();
+    let pcapng = writer.into_inner();
+
+    let info = CaptureInfo::compute(&pcapng[..]).await.unwrap();
+    assert_eq!(info.format, CaptureFormat::PcapNg);
+    assert_eq!(info.sections, 2);
+    assert_eq!(info.hardware.as_deref(), Some("x86 \"test\""));
+    assert_eq!(info.os, None);
+    assert_eq!(info.application.as_deref(), Some("tests"));
+
+    assert_eq!(info.interfaces.len(), 2);
+    assert_eq!(info.interfaces[0].packets, 2);
+    assert_eq!(info.interfaces[0].statistics, Some(InterfaceStatistics { received: Some(10), dropped: Some(1), ..Default::default() }));
+    assert_eq!(info.interfaces[1].linktype, DataLink::RAW);
+    assert_eq!(info.interfaces[1].packets, 1);
+
+    // 1s, 2s then 3s with the default microsecond resolution
+    assert_eq!(info.first_timestamp, Some(Duration::from_secs(1)));
+    assert_eq!(info.last_timestamp, Some(Duration::from_secs(3)));
+    assert!(info.strict_time_order);
+
+    let json = info.to_json();
+    assert!(json.starts_with(r#"{"format":"pcapng","endianness":"big","version":"1.0","sections":2,"hardware":"x86 \"test\"","os":null,"#));
+    assert!(json.contains(r#"{"link_type":1,"link_type_name":"ETHERNET","snaplen":1500,"ts_resolution":9,"packets":2,"statistics":{"start_time":null,"end_time":null,"received":10,"dropped":1,"filter_accepted":null,"os_dropped":null,"delivered":null}}"#));
+    assert!(json.contains(r#""packets":3,"total_bytes":148,"captured_bytes":148,"first_timestamp":1000000000,"last_timestamp":3000000000,"duration":2000000000"#));
+    assert!(json.ends_with(r#""strict_time_order":true}"#));
+}
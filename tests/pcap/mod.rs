@@ -1,10 +1,13 @@
 extern crate pcap_file_tokio;
 
 use std::borrow::Cow;
+use std::io::Cursor;
 use std::time::Duration;
 
-use pcap_file_tokio::pcap::{PcapHeader, PcapPacket, PcapReader, PcapWriter};
-use pcap_file_tokio::TsResolution;
+use pcap_file_tokio::pcap::{PcapHeader, PcapPacket, PcapParser, PcapReader, PcapWriter, RawPcapPacket};
+use pcap_file_tokio::{
+    Check, Checks, Endianness, Limit, ParseLimits, ParseMode, ParseWarning, PcapError, ReaderConfig, TsResolution, WriteSummary,
+};
 
 static DATA: &[u8; 1455] = include_bytes!("little_endian.pcap");
 
@@ -137,3 +140,182 @@ async fn little_endian() {
     assert_eq!(pkt.orig_len, pkt_truth.orig_len);
     assert_eq!(pkt.data, pkt_truth.data);
 }
+
+#[tokio::test]
+async fn finish() {
+    let writer = tokio::io::BufWriter::new(Vec::new());
+    let mut pcap_writer = PcapWriter::new(writer).await.unwrap();
+    pcap_writer.write_packet(&PcapPacket::new(Duration::ZERO, 4, &[1, 2, 3, 4])).await.unwrap();
+    pcap_writer.write_packet(&PcapPacket::new(Duration::ZERO, 2, &[1, 2])).await.unwrap();
+    assert!(pcap_writer.into_writer().get_ref().is_empty());
+
+    let writer = tokio::io::BufWriter::new(Vec::new());
+    let mut pcap_writer = PcapWriter::new(writer).await.unwrap();
+    pcap_writer.write_packet(&PcapPacket::new(Duration::ZERO, 4, &[1, 2, 3, 4])).await.unwrap();
+    pcap_writer.write_packet(&PcapPacket::new(Duration::ZERO, 2, &[1, 2])).await.unwrap();
+    let (writer, summary) = pcap_writer.finish().await.unwrap();
+
+    assert_eq!(summary, WriteSummary { bytes: 24 + 16 + 4 + 16 + 2, packets: 2, blocks: 0 });
+    assert_eq!(writer.get_ref().len() as u64, summary.bytes);
+}
+
+#[tokio::test]
+async fn append() {
+    let header = PcapHeader { ts_resolution: TsResolution::NanoSecond, endianness: Endianness::Big, ..Default::default() };
+    let mut pcap_writer = PcapWriter::with_header(Cursor::new(Vec::new()), header).await.unwrap();
+    pcap_writer.write_packet(&PcapPacket::new(Duration::new(1, 5), 4, &[1, 2, 3, 4])).await.unwrap();
+    let mut file = pcap_writer.into_writer().into_inner();
+
+    // Partial packet left by a killed writer
+    file.extend_from_slice(&[0, 0, 0, 2, 0, 0]);
+
+    let mut pcap_writer = PcapWriter::append(Cursor::new(file)).await.unwrap();
+    assert_eq!(pcap_writer.endianness(), Endianness::Big);
+    assert_eq!(pcap_writer.ts_resolution(), TsResolution::NanoSecond);
+    pcap_writer.write_packet(&PcapPacket::new(Duration::new(2, 5), 2, &[5, 6])).await.unwrap();
+    let (file, summary) = pcap_writer.finish().await.unwrap();
+    assert_eq!(summary, WriteSummary { bytes: 18, packets: 1, blocks: 0 });
+
+    let file = file.into_inner();
+    assert_eq!(file.len(), 24 + 20 + 18);
+    let mut pcap_reader = PcapReader::new(&file[..]).await.unwrap();
+    assert_eq!(pcap_reader.header(), header);
+    assert_eq!(pcap_reader.next_packet().await.unwrap().unwrap().data.as_ref(), &[1, 2, 3, 4]);
+    let packet = pcap_reader.next_packet().await.unwrap().unwrap();
+    assert_eq!((packet.timestamp, packet.data.as_ref()), (Duration::new(2, 5), &[5, 6][..]));
+    assert!(pcap_reader.next_packet().await.is_none());
+
+    // Appending to an empty file writes a new header
+    let pcap_writer = PcapWriter::append(Cursor::new(Vec::new())).await.unwrap();
+    assert_eq!(pcap_writer.into_writer().into_inner().len(), 24);
+}
+
+#[test]
+fn parser_sync() {
+    let data = tokio_test::block_on(async {
+        let mut pcap_writer = PcapWriter::new(Vec::new()).await.unwrap();
+        pcap_writer.write_packet(&PcapPacket::new(Duration::from_secs(1), 4, &[1, 2, 3, 4])).await.unwrap();
+        pcap_writer.write_packet(&PcapPacket::new(Duration::from_secs(2), 2, &[1, 2])).await.unwrap();
+        pcap_writer.into_writer()
+    });
+
+    let (mut src, parser) = PcapParser::new(&data).unwrap();
+    let mut packets = vec![];
+    while !src.is_empty() {
+        let (rem, packet) = parser.next_packet(src).unwrap();
+        packets.push(packet);
+        src = rem;
+    }
+
+    assert_eq!(packets.len(), 2);
+    assert_eq!(packets[0].timestamp, Duration::from_secs(1));
+    assert_eq!(&packets[0].data[..], &[1, 2, 3, 4]);
+    assert_eq!(packets[1].timestamp, Duration::from_secs(2));
+    assert_eq!(&packets[1].data[..], &[1, 2]);
+    assert!(matches!(parser.next_packet(&data[24..30]), Err(pcap_file_tokio::PcapError::IncompleteBuffer)));
+}
+
+#[tokio::test]
+async fn reader_config() {
+    let sizes = [10_usize, 5_000, 60_000, 10, 20_000];
+    let mut pcap_writer = PcapWriter::new(Vec::new()).await.unwrap();
+    for (i, size) in sizes.iter().enumerate() {
+        let data = vec![i as u8; *size];
+        pcap_writer.write_packet(&PcapPacket::new(Duration::from_secs(i as u64), *size as u32, &data)).await.unwrap();
+    }
+    let data = pcap_writer.into_writer();
+
+    // The buffer grows to fit the large packets, with or without shrinking
+    for shrink in [true, false] {
+        let config = ReaderConfig::default().with_initial_capacity(64).with_shrink(shrink);
+        let mut pcap_reader = PcapReader::with_config(&data[..], config).await.unwrap();
+        for (i, size) in sizes.iter().enumerate() {
+            let pkt = pcap_reader.next_packet().await.unwrap().unwrap();
+            assert_eq!(pkt.timestamp, Duration::from_secs(i as u64));
+            assert_eq!(&pkt.data[..], &vec![i as u8; *size][..]);
+        }
+        assert!(pcap_reader.next_packet().await.is_none());
+    }
+
+    // A packet larger than the maximum record size is rejected
+    let config = ReaderConfig::default().with_initial_capacity(64).with_max_record_size(10_000);
+    let mut pcap_reader = PcapReader::with_config(&data[..], config).await.unwrap();
+    assert_eq!(pcap_reader.next_packet().await.unwrap().unwrap().data.len(), 10);
+    assert_eq!(pcap_reader.next_packet().await.unwrap().unwrap().data.len(), 5_000);
+    assert!(matches!(pcap_reader.next_packet().await, Some(Err(PcapError::RecordTooLarge(10_000)))));
+
+    // While a truncated packet is still an unexpected EOF
+    let mut pcap_reader = PcapReader::with_config(&data[..data.len() - 1], ReaderConfig::default().with_initial_capacity(64)).await.unwrap();
+    for _ in 0..sizes.len() - 1 {
+        pcap_reader.next_packet().await.unwrap().unwrap();
+    }
+    match pcap_reader.next_packet().await {
+        Some(Err(PcapError::IoError(e))) => assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof),
+        res => panic!("Unexpected result {res:?}"),
+    }
+}
+
+#[tokio::test]
+async fn parse_limits() {
+    let mut pcap_writer = PcapWriter::new(Vec::new()).await.unwrap();
+    pcap_writer.write_packet(&PcapPacket::new(Duration::from_secs(1), 4, &[1, 2, 3, 4])).await.unwrap();
+    pcap_writer.write_packet(&PcapPacket::new(Duration::from_secs(2), 100, &[0; 100])).await.unwrap();
+    let data = pcap_writer.into_writer();
+
+    // The packet length is checked from its header, before the packet is buffered
+    let (src, parser) = PcapParser::with_limits(&data, ParseLimits::default().with_max_block_len(115)).unwrap();
+    let (src, _) = parser.next_packet(src).unwrap();
+    assert!(matches!(parser.next_raw_packet(&src[..16]), Err(PcapError::LimitExceeded(Limit::BlockLen, 115))));
+
+    let config = ReaderConfig::default().with_limits(ParseLimits::default().with_max_block_len(116));
+    let mut pcap_reader = PcapReader::with_config(&data[..], config).await.unwrap();
+    assert_eq!(pcap_reader.next_packet().await.unwrap().unwrap().data.len(), 4);
+    assert_eq!(pcap_reader.next_packet().await.unwrap().unwrap().data.len(), 100);
+    assert!(pcap_reader.next_packet().await.is_none());
+}
+
+#[tokio::test]
+async fn parse_mode() {
+    let header = PcapHeader { snaplen: 4, ..Default::default() };
+    let mut pcap_writer = PcapWriter::with_header(Vec::new(), header).await.unwrap();
+    let raw = |ts_frac, orig_len| RawPcapPacket { ts_sec: 1, ts_frac, incl_len: 4, orig_len, data: Cow::Borrowed(&[1, 2, 3, 4]) };
+    pcap_writer.write_raw_packet(&raw(0, 4)).await.unwrap();
+    pcap_writer.write_raw_packet(&raw(0, 100)).await.unwrap();
+    pcap_writer.write_raw_packet(&raw(1_500_000, 4)).await.unwrap();
+    let data = pcap_writer.into_writer();
+
+    // The default mode rejects the packets longer than the snaplen
+    let mut pcap_reader = PcapReader::new(&data[..]).await.unwrap();
+    pcap_reader.next_packet().await.unwrap().unwrap();
+    assert!(matches!(pcap_reader.next_packet().await, Some(Err(PcapError::InvalidField("PacketHeader orig_len > snap_len")))));
+
+    // The lenient mode keeps them and carries the overflowing timestamps into their seconds
+    let mut pcap_reader = PcapReader::with_config(&data[..], ReaderConfig::default().with_mode(ParseMode::Lenient)).await.unwrap();
+    assert_eq!(pcap_reader.next_packet().await.unwrap().unwrap().orig_len, 4);
+    assert_eq!(pcap_reader.next_packet().await.unwrap().unwrap().orig_len, 100);
+    assert_eq!(pcap_reader.next_packet().await.unwrap().unwrap().timestamp, Duration::from_millis(2_500));
+    assert!(pcap_reader.next_packet().await.is_none());
+
+    let warnings = [
+        ParseWarning { check: Check::Snaplen, index: 1, reason: "PacketHeader orig_len > snap_len" },
+        ParseWarning { check: Check::Timestamp, index: 2, reason: "PacketHeader ts_nanosecond >= 1_000_000_000" },
+    ];
+    assert_eq!(pcap_reader.warnings(), &warnings[..]);
+    assert_eq!(pcap_reader.take_warnings(), warnings);
+    assert!(pcap_reader.warnings().is_empty());
+
+    // A custom mode only enforces its checks
+    let mode = ParseMode::Custom(Checks::none().with_check(Check::Timestamp, true));
+    let (src, mut parser) = PcapParser::with_limits_and_mode(&data, ParseLimits::default(), mode).unwrap();
+    let (src, _) = parser.next_packet_with_warnings(src).unwrap();
+    let (src, _) = parser.next_packet_with_warnings(src).unwrap();
+    assert!(matches!(parser.next_packet_with_warnings(src), Err(PcapError::InvalidField(_))));
+    assert_eq!(parser.take_warnings(), &warnings[..1]);
+
+    // Without collecting the warnings
+    let (src, parser) = PcapParser::with_limits_and_mode(&data, ParseLimits::default(), mode).unwrap();
+    let (src, _) = parser.next_packet(src).unwrap();
+    let (src, _) = parser.next_packet(src).unwrap();
+    assert!(matches!(parser.next_packet(src), Err(PcapError::InvalidField(_))));
+    assert!(parser.warnings().is_empty());
+}
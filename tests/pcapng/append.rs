@@ -0,0 +1,53 @@
+use std::io::Cursor;
+
+use pcap_file_tokio::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use pcap_file_tokio::pcapng::blocks::section_header::SectionHeaderBlock;
+use pcap_file_tokio::pcapng::{PcapNgBlock, PcapNgReader, PcapNgWriter};
+use pcap_file_tokio::{DataLink, Endianness, PcapError};
+
+use crate::common::packet;
+
+#[tokio::test]
+async fn append() {
+    let mut writer = PcapNgWriter::with_endianness(Cursor::new(Vec::new()), Endianness::Little).await.unwrap();
+    writer.track_section_lengths().await.unwrap();
+    writer.write_block(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0).into_block()).await.unwrap();
+    writer.write_block(&packet(0, 0, &[1, 2])).await.unwrap();
+    writer.write_block(&SectionHeaderBlock { endianness: Endianness::Big, ..Default::default() }.into_block()).await.unwrap();
+    writer.write_block(&InterfaceDescriptionBlock::new(DataLink::RAW, 0).into_block()).await.unwrap();
+    writer.write_block(&InterfaceDescriptionBlock::new(DataLink::LINUX_SLL, 0).into_block()).await.unwrap();
+    writer.write_block(&packet(1, 0, &[1, 2])).await.unwrap();
+    let mut file = writer.finish().await.unwrap().0.into_inner();
+
+    // Partial Enhanced Packet Block left by a killed writer
+    file.extend_from_slice(&[0, 0, 0, 6, 0, 0, 0, 36, 0, 0]);
+
+    let mut writer = PcapNgWriter::append(Cursor::new(file)).await.unwrap();
+    assert_eq!(writer.section().endianness, Endianness::Big);
+    assert_eq!(writer.interfaces().len(), 2);
+    assert!(matches!(writer.write_block(&packet(2, 0, &[1, 2])).await, Err(PcapError::InvalidInterfaceId(2))));
+    writer.write_block(&packet(0, 0, &[1, 2])).await.unwrap();
+    let (file, summary) = writer.finish().await.unwrap();
+    assert_eq!((summary.blocks, summary.packets, summary.bytes), (1, 1, 36));
+
+    let file = file.into_inner();
+    let mut reader = PcapNgReader::new(&file[..]).await.unwrap();
+    assert!(reader.skip_section().await.unwrap());
+    assert_eq!(reader.section().section_length, 20 + 20 + 36 + 36);
+
+    let mut blocks = vec![];
+    while let Some(block) = reader.next_block().await {
+        blocks.push(block.unwrap().into_owned());
+    }
+    assert_eq!(blocks.len(), 4);
+    assert_eq!(blocks[3], packet(0, 0, &[1, 2]));
+}
+
+#[tokio::test]
+async fn append_empty() {
+    let writer = PcapNgWriter::append(Cursor::new(Vec::new())).await.unwrap();
+    let file = writer.into_inner().into_inner();
+
+    let reader = PcapNgReader::new(&file[..]).await.unwrap();
+    assert_eq!(reader.section(), &SectionHeaderBlock { endianness: Endianness::native(), ..Default::default() });
+}
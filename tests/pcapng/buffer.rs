@@ -0,0 +1,58 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use pcap_file_tokio::pcapng::blocks::enhanced_packet::EnhancedPacketBlock;
+use pcap_file_tokio::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use pcap_file_tokio::pcapng::{Block, PcapNgBlock, PcapNgReader, PcapNgWriter};
+use pcap_file_tokio::{DataLink, PcapError, ReaderConfig};
+
+fn packet(size: usize) -> Block<'static> {
+    EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::from_micros(size as u64),
+        original_len: size as u32,
+        data: Cow::Owned(vec![size as u8; size]),
+        options: vec![],
+    }
+    .into_block()
+}
+
+#[tokio::test]
+async fn growable_buffer() {
+    let sizes = [100_usize, 200_000, 100, 1_000_000, 100];
+    let mut writer = PcapNgWriter::new(Vec::new()).await.unwrap();
+    writer.write_block(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0).into_block()).await.unwrap();
+    for size in sizes {
+        writer.write_block(&packet(size)).await.unwrap();
+    }
+    let data = writer.into_inner();
+
+    // The blocks larger than the initial capacity are read
+    for shrink in [true, false] {
+        let config = ReaderConfig::default().with_initial_capacity(128).with_shrink(shrink);
+        let mut reader = PcapNgReader::with_config(&data[..], config).await.unwrap();
+        assert!(matches!(reader.next_block().await, Some(Ok(Block::InterfaceDescription(_)))));
+        for size in sizes {
+            assert_eq!(reader.next_block().await.unwrap().unwrap(), packet(size));
+        }
+        assert!(reader.next_block().await.is_none());
+    }
+
+    // The blocks larger than the maximum record size are rejected
+    let config = ReaderConfig::default().with_max_record_size(500_000);
+    let mut reader = PcapNgReader::with_config(&data[..], config).await.unwrap();
+    for _ in 0..4 {
+        reader.next_block().await.unwrap().unwrap();
+    }
+    assert!(matches!(reader.next_block().await, Some(Err(PcapError::RecordTooLarge(500_000)))));
+
+    // A truncated block isn't mistaken for a block too large
+    let mut reader = PcapNgReader::with_config(&data[..data.len() - 1], ReaderConfig::default().with_initial_capacity(128)).await.unwrap();
+    for _ in 0..sizes.len() {
+        reader.next_block().await.unwrap().unwrap();
+    }
+    match reader.next_block().await {
+        Some(Err(PcapError::IoError(e))) => assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof),
+        res => panic!("Unexpected result {res:?}"),
+    }
+}
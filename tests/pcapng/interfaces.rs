@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use pcap_file_tokio::pcap::PcapPacket;
+use pcap_file_tokio::pcapng::blocks::section_header::SectionHeaderBlock;
+use pcap_file_tokio::pcapng::{Block, InterfaceKey, PcapNgBlock, PcapNgReader, PcapNgWriter};
+use pcap_file_tokio::DataLink;
+
+#[tokio::test]
+async fn write_packet() {
+    let eth0 = InterfaceKey::new("eth0", DataLink::ETHERNET).with_ts_resolution(9);
+    let eth0_raw = InterfaceKey::new("eth0", DataLink::RAW);
+    let wlan0 = InterfaceKey::new("wlan0", DataLink::ETHERNET).with_ts_resolution(0x83);
+    let timestamp = Duration::new(1_700_000_000, 123_456_789);
+    let packet = PcapPacket::new(timestamp, 4, &[0, 1, 2, 3]);
+
+    let mut writer = PcapNgWriter::new(Vec::new()).await.unwrap();
+    writer.write_packet(&eth0, &packet).await.unwrap();
+    writer.write_packet(&eth0_raw, &packet).await.unwrap();
+    writer.write_packet(&eth0, &packet).await.unwrap();
+    writer.write_packet(&wlan0, &packet).await.unwrap();
+    assert_eq!(writer.interfaces().len(), 3);
+
+    // The interfaces of a new section are written again, an interface written by hand is reused
+    writer.write_block(&SectionHeaderBlock::default().into_block()).await.unwrap();
+    writer.write_block(&wlan0.interface_description().into_block()).await.unwrap();
+    writer.write_packet(&eth0, &packet).await.unwrap();
+    writer.write_packet(&wlan0, &packet).await.unwrap();
+    let pcapng = writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&pcapng[..]).await.unwrap();
+    let mut packets = vec![];
+    while let Some(block) = reader.next_block().await {
+        match block.unwrap() {
+            Block::EnhancedPacket(packet) => {
+                let packet = packet.into_owned();
+                let interface = reader.packet_interface(&packet).unwrap();
+                packets.push((packet.interface_id, interface.linktype, packet.absolute_timestamp(interface), packet.timestamp));
+            },
+            Block::InterfaceDescription(_) | Block::SectionHeader(_) => {},
+            block => panic!("Unexpected block {block:?}"),
+        }
+    }
+
+    assert_eq!(packets, vec![
+        (0, DataLink::ETHERNET, timestamp, Duration::from_nanos(1_700_000_000_123_456_789)),
+        (1, DataLink::RAW, Duration::new(1_700_000_000, 123_456_000), Duration::from_nanos(1_700_000_000_123_456)),
+        (0, DataLink::ETHERNET, timestamp, Duration::from_nanos(1_700_000_000_123_456_789)),
+        (2, DataLink::ETHERNET, Duration::from_secs(1_700_000_000), Duration::from_nanos(13_600_000_000)),
+        (1, DataLink::ETHERNET, timestamp, Duration::from_nanos(1_700_000_000_123_456_789)),
+        (0, DataLink::ETHERNET, Duration::from_secs(1_700_000_000), Duration::from_nanos(13_600_000_000)),
+    ]);
+    assert_eq!(reader.interfaces()[1].options, eth0.interface_description().options);
+}
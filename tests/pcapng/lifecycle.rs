@@ -0,0 +1,43 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use pcap_file_tokio::pcapng::blocks::enhanced_packet::EnhancedPacketBlock;
+use pcap_file_tokio::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use pcap_file_tokio::pcapng::blocks::simple_packet::SimplePacketBlock;
+use pcap_file_tokio::pcapng::{Block, PcapNgBlock, PcapNgReader, PcapNgWriter};
+use pcap_file_tokio::{DataLink, WriteSummary};
+use tokio::io::BufWriter;
+
+#[tokio::test]
+async fn finish() {
+    let mut writer = PcapNgWriter::new(BufWriter::new(Vec::new())).await.unwrap();
+    writer.track_statistics(true);
+    writer.write_block(&Block::InterfaceDescription(InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0))).await.unwrap();
+
+    let packet = EnhancedPacketBlock { interface_id: 0, timestamp: Duration::ZERO, original_len: 4, data: Cow::Borrowed(&[1, 2, 3, 4]), options: vec![] };
+    writer.write_block(&packet.into_block()).await.unwrap();
+    writer.write_block(&SimplePacketBlock { original_len: 2, data: Cow::Borrowed(&[1, 2]) }.into_block()).await.unwrap();
+
+    writer.flush().await.unwrap();
+    let summary = writer.summary();
+    assert_eq!(writer.get_ref().get_ref().len() as u64, summary.bytes);
+    assert_eq!((summary.packets, summary.blocks), (2, 4));
+
+    // The statistics are written by finish
+    let (out, summary) = writer.finish().await.unwrap();
+    let out = out.into_inner();
+    assert_eq!(out.len() as u64, summary.bytes);
+    assert_eq!((summary.packets, summary.blocks), (2, 5));
+
+    let mut reader = PcapNgReader::new(&out[..]).await.unwrap();
+    let mut blocks = 1;
+    while let Some(block) = reader.next_block().await {
+        block.unwrap();
+        blocks += 1;
+    }
+    assert_eq!(blocks, summary.blocks);
+
+    let pcapng = PcapNgWriter::new(Vec::new()).await.unwrap().into_inner();
+    let (_, summary) = PcapNgWriter::new(Vec::new()).await.unwrap().finish().await.unwrap();
+    assert_eq!(summary, WriteSummary { bytes: pcapng.len() as u64, packets: 0, blocks: 1 });
+}
@@ -0,0 +1,136 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use pcap_file_tokio::pcapng::blocks::enhanced_packet::{EnhancedPacketBlock, EnhancedPacketOption};
+use pcap_file_tokio::pcapng::blocks::interface_description::{InterfaceDescriptionBlock, InterfaceDescriptionOption};
+use pcap_file_tokio::pcapng::blocks::name_resolution::{Ipv4Record, NameResolutionBlock, Record};
+use pcap_file_tokio::pcapng::blocks::section_header::{SectionHeaderBlock, SectionHeaderOption};
+use pcap_file_tokio::pcapng::{PcapNgBlock, PcapNgParser, PcapNgReader, PcapNgWriter};
+use pcap_file_tokio::{DataLink, Endianness, Limit, ParseLimits, PcapError, ReaderConfig};
+
+/// Two sections of different endianness, the largest block is the Enhanced Packet Block of 1064 bytes
+async fn capture() -> Vec<u8> {
+    let section = SectionHeaderBlock {
+        options: vec![SectionHeaderOption::Comment("12345678".into())],
+        ..Default::default()
+    };
+    let mut writer = PcapNgWriter::with_section_header(Vec::new(), section).await.unwrap();
+
+    let interface = InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0);
+    writer.write_block(&interface.clone().into_block()).await.unwrap();
+    let interface = InterfaceDescriptionBlock { options: vec![InterfaceDescriptionOption::IfName("eth1".into())], ..interface };
+    writer.write_block(&interface.into_block()).await.unwrap();
+
+    let packet = EnhancedPacketBlock {
+        interface_id: 1,
+        timestamp: Duration::from_secs(1),
+        original_len: 1000,
+        data: Cow::Owned(vec![0; 1000]),
+        options: vec![EnhancedPacketOption::Comment("a".into()), EnhancedPacketOption::Flags(0), EnhancedPacketOption::DropCount(0)],
+    };
+    writer.write_block(&packet.into_block()).await.unwrap();
+
+    let record = |names: &[&'static str]| {
+        Record::Ipv4(Ipv4Record {
+            ip_addr: Cow::Owned(vec![127, 0, 0, 1]),
+            names: names.iter().map(|&name| name.into()).collect(),
+        })
+    };
+    let nrb = NameResolutionBlock {
+        records: vec![record(&["localhost", "local"]), record(&["a", "b", "c"])],
+        options: vec![],
+    };
+    writer.write_block(&nrb.into_block()).await.unwrap();
+
+    writer
+        .write_block(&SectionHeaderBlock { endianness: Endianness::Little, ..Default::default() }.into_block())
+        .await
+        .unwrap();
+    writer.write_block(&InterfaceDescriptionBlock::new(DataLink::RAW, 0).into_block()).await.unwrap();
+
+    writer.into_inner()
+}
+
+/// Parses all the blocks of the capture, returns the number of blocks parsed
+fn parse(data: &[u8], limits: ParseLimits) -> Result<usize, PcapError> {
+    let (mut src, mut parser) = PcapNgParser::with_limits(data, limits)?;
+    let mut blocks = 1;
+    while !src.is_empty() {
+        let (rem, _) = parser.next_block(src)?;
+        src = rem;
+        blocks += 1;
+    }
+
+    Ok(blocks)
+}
+
+#[tokio::test]
+async fn parse_limits() {
+    let data = capture().await;
+
+    let limits = ParseLimits::default()
+        .with_max_block_len(1064)
+        .with_max_options(3)
+        .with_max_nrb_records(2)
+        .with_max_nrb_names(5)
+        .with_max_interfaces(2)
+        .with_max_string_len(9)
+        .with_max_sections(2);
+    assert_eq!(parse(&data, limits).unwrap(), 7);
+    assert_eq!(parse(&data, ParseLimits::untrusted()).unwrap(), 7);
+
+    let exceeded = [
+        (limits.with_max_block_len(1060), Limit::BlockLen, 1060),
+        (limits.with_max_options(2), Limit::Options, 2),
+        (limits.with_max_nrb_records(1), Limit::NrbRecords, 1),
+        (limits.with_max_nrb_names(4), Limit::NrbNames, 4),
+        (limits.with_max_interfaces(1), Limit::Interfaces, 1),
+        (limits.with_max_string_len(7), Limit::StringLen, 7),
+        (limits.with_max_sections(1), Limit::Sections, 1),
+    ];
+
+    for (limits, limit, max) in exceeded {
+        match parse(&data, limits) {
+            Err(PcapError::LimitExceeded(l, m)) => assert_eq!((l, m), (limit, max)),
+            res => panic!("{limit:?} not exceeded: {res:?}"),
+        }
+    }
+
+    // The block length is checked before the block is buffered
+    let limits = ParseLimits::default().with_max_block_len(1060);
+    let (src, mut parser) = PcapNgParser::with_limits(&data, limits).unwrap();
+    let (src, _) = parser.next_block(src).unwrap();
+    let (src, _) = parser.next_block(src).unwrap();
+    assert!(matches!(parser.next_block(&src[..12]), Err(PcapError::LimitExceeded(Limit::BlockLen, 1060))));
+    assert!(matches!(
+        PcapNgParser::with_limits(&data[..12], limits.with_max_block_len(16)),
+        Err(PcapError::LimitExceeded(Limit::BlockLen, 16))
+    ));
+
+    // The interface exceeding the limit isn't added
+    let (src, mut parser) = PcapNgParser::with_limits(&data, limits.with_max_interfaces(1)).unwrap();
+    let (src, _) = parser.next_raw_block(src).unwrap();
+    assert!(matches!(parser.next_raw_block(src), Err(PcapError::LimitExceeded(Limit::Interfaces, 1))));
+    assert_eq!(parser.interfaces().len(), 1);
+}
+
+#[tokio::test]
+async fn reader_limits() {
+    let data = capture().await;
+
+    let config = ReaderConfig::default().with_limits(ParseLimits::default().with_max_sections(1));
+    let mut reader = PcapNgReader::with_config(&data[..], config).await.unwrap();
+    let mut blocks = 1;
+    let err = loop {
+        match reader.next_block().await.unwrap() {
+            Ok(_) => blocks += 1,
+            Err(e) => break e,
+        }
+    };
+    assert_eq!(blocks, 5);
+    assert!(matches!(err, PcapError::LimitExceeded(Limit::Sections, 1)));
+    assert_eq!(reader.interfaces().len(), 2);
+
+    let config = ReaderConfig::default().with_limits(ParseLimits::default().with_max_string_len(4));
+    assert!(matches!(PcapNgReader::with_config(&data[..], config).await, Err(PcapError::LimitExceeded(Limit::StringLen, 4))));
+}
@@ -0,0 +1,132 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use glob::glob;
+use tokio::fs::File;
+
+use pcap_file_tokio::pcapng::blocks::enhanced_packet::EnhancedPacketBlock;
+use pcap_file_tokio::pcapng::blocks::interface_description::{InterfaceDescriptionBlock, InterfaceDescriptionOption};
+use pcap_file_tokio::pcapng::{lint, Diagnostic, PcapNgBlock, PcapNgWriter, Severity, Violation};
+use pcap_file_tokio::DataLink;
+
+/// Big endian block with the given initial length, also used as trailer length
+fn block(type_: u32, initial_len: u32, body: &[u8]) -> Vec<u8> {
+    let mut block = type_.to_be_bytes().to_vec();
+    block.extend_from_slice(&initial_len.to_be_bytes());
+    block.extend_from_slice(body);
+    block.extend_from_slice(&initial_len.to_be_bytes());
+    block
+}
+
+/// Big endian Section Header Block with the given major version
+fn section(major_version: u8) -> Vec<u8> {
+    block(0x0A0D0D0A, 28, &[0x1A, 0x2B, 0x3C, 0x4D, 0, major_version, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF])
+}
+
+/// Big endian Enhanced Packet Block on the given interface, with a timestamp in microseconds
+fn packet(interface_id: u8, timestamp: u32, data: &[u8], options: &[u8]) -> Vec<u8> {
+    let mut body = vec![0, 0, 0, interface_id, 0, 0, 0, 0];
+    body.extend_from_slice(&timestamp.to_be_bytes());
+    body.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    body.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    body.extend_from_slice(data);
+    body.extend_from_slice(options);
+
+    block(6, body.len() as u32 + 12, &body)
+}
+
+#[tokio::test]
+async fn violations() {
+    let mut data = section(1);
+
+    // Interface with a snaplen of 4 and a duplicate if_tsresol, at offset 28
+    let interface = [0, 1, 0, 0, 0, 0, 0, 4, 0, 9, 0, 1, 6, 0, 0, 0, 0, 9, 0, 1, 6, 0, 0, 0, 0, 0, 0, 0];
+    data.extend(block(1, 40, &interface));
+
+    // Packet on an undefined interface, at offset 68
+    data.extend(packet(1, 0, &[1, 2, 3, 4], &[]));
+    // Packet larger than the snaplen, at offset 104
+    data.extend(packet(0, 2_000_000, &[1, 2, 3, 4, 5, 6, 7, 8], &[]));
+    // Earlier packet with a flags option of 2 bytes and without end of options, at offset 144
+    data.extend(packet(0, 1_000_000, &[1, 2, 3, 4], &[0, 2, 0, 2, 1, 2, 0, 0, 0, 1, 0, 1, b'a', 0, 0, 0]));
+
+    // Unaligned block, at offset 196
+    data.extend(block(0x0BAD, 14, &[1, 2]));
+
+    // Section of an unknown version, whose packet on an undefined interface isn't checked, at offset 210
+    data.extend(section(2));
+    data.extend(packet(5, 0, &[1, 2, 3, 4], &[]));
+
+    // The interfaces of the previous sections are dropped, at offset 274
+    data.extend(section(1));
+    data.extend(block(5, 24, &[0; 12]));
+
+    // Trailer length mismatch, at offset 326
+    let mut mismatch = block(0x0BAD, 12, &[]);
+    mismatch[8..].copy_from_slice(&16_u32.to_be_bytes());
+    data.extend(mismatch);
+
+    // Truncated block, at offset 338
+    data.extend(&block(0x0BAD, 12, &[])[..8]);
+
+    let diagnostic = |offset, block_index, violation: Violation| {
+        Diagnostic { offset, block_index, severity: violation.severity(), violation }
+    };
+    let expected = vec![
+        diagnostic(52, 1, Violation::DuplicateOption(9)),
+        diagnostic(76, 2, Violation::UndefinedInterface(1)),
+        diagnostic(124, 3, Violation::CapturedLenExceedsSnaplen { captured_len: 8, snaplen: 4 }),
+        diagnostic(156, 4, Violation::NonMonotonicTimestamp(0)),
+        diagnostic(176, 4, Violation::OptionLength { code: 2, length: 2 }),
+        diagnostic(192, 4, Violation::MissingEndOfOptions),
+        diagnostic(200, 5, Violation::UnalignedBlockLength(14)),
+        diagnostic(222, 6, Violation::UnknownMajorVersion(2)),
+        diagnostic(310, 9, Violation::UndefinedInterface(0)),
+        diagnostic(334, 10, Violation::TrailerLengthMismatch { initial_len: 12, trailer_len: 16 }),
+        diagnostic(338, 11, Violation::TruncatedBlock),
+    ];
+
+    let diagnostics = lint(&data[..]).await.unwrap();
+    assert_eq!(diagnostics, expected);
+    assert_eq!(diagnostics[5].severity, Severity::Warning);
+    assert_eq!(diagnostics[0].to_string(), "block 1 at offset 0x34: error: duplicate option 9");
+
+    // A capture which doesn't start with a section
+    let diagnostics = lint(&block(1, 20, &[0; 8])[..]).await.unwrap();
+    assert_eq!(diagnostics, vec![diagnostic(0, 0, Violation::MissingSectionHeader)]);
+}
+
+#[tokio::test]
+async fn conforming() {
+    let mut writer = PcapNgWriter::new(Vec::new()).await.unwrap();
+    let interface = InterfaceDescriptionBlock {
+        options: vec![InterfaceDescriptionOption::IfName("eth0".into()), InterfaceDescriptionOption::IfTsResol(9)],
+        ..InterfaceDescriptionBlock::new(DataLink::ETHERNET, 1500)
+    };
+    writer.write_block(&interface.into_block()).await.unwrap();
+
+    for secs in [1, 2, 2] {
+        let packet = EnhancedPacketBlock {
+            interface_id: 0,
+            timestamp: Duration::from_secs(secs),
+            original_len: 4,
+            data: Cow::Borrowed(&[1, 2, 3, 4]),
+            options: vec![],
+        };
+        writer.write_block(&packet.into_block()).await.unwrap();
+    }
+
+    assert_eq!(lint(&writer.into_inner()[..]).await.unwrap(), vec![]);
+}
+
+#[tokio::test]
+async fn corpus() {
+    for entry in glob("tests/pcapng/**/**/*.pcapng").expect("Failed to read glob pattern") {
+        let entry = entry.unwrap();
+
+        let file = File::open(&entry).await.unwrap();
+        let diagnostics = lint(file).await.unwrap();
+        let errors: Vec<_> = diagnostics.iter().filter(|diagnostic| diagnostic.severity == Severity::Error).collect();
+        assert!(errors.is_empty(), "Errors on file {entry:?}: {errors:?}");
+    }
+}
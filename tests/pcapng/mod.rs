@@ -1,3 +1,13 @@
+mod append;
+mod buffer;
+mod interfaces;
+mod lifecycle;
+mod limits;
+mod lint;
+mod modes;
+mod sections;
+mod statistics;
+
 use tokio::{fs::File, io::AsyncReadExt};
 
 use glob::glob;
@@ -29,7 +39,7 @@ async fn parser() {
         file.read_to_end(&mut data).await.unwrap();
 
         let mut src = &data[..];
-        let (rem, mut pcapng_parser) = PcapNgParser::new(src).await.unwrap();
+        let (rem, mut pcapng_parser) = PcapNgParser::new(src).unwrap();
         src = rem;
 
         let mut i = 0;
@@ -40,7 +50,6 @@ async fn parser() {
 
             let (rem, _) = pcapng_parser
                 .next_block(src)
-                .await
                 .unwrap_or_else(|_| panic!("Error on block {i} on file: {entry:?}"));
             src = rem;
 
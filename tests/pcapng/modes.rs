@@ -0,0 +1,124 @@
+use std::borrow::Cow;
+
+use pcap_file_tokio::pcapng::blocks::enhanced_packet::EnhancedPacketOption;
+use pcap_file_tokio::pcapng::blocks::interface_description::{InterfaceDescriptionBlock, InterfaceDescriptionOption};
+use pcap_file_tokio::pcapng::blocks::{RawBlock, ENHANCED_PACKET_BLOCK, INTERFACE_DESCRIPTION_BLOCK};
+use pcap_file_tokio::pcapng::{Block, PcapNgBlock, PcapNgParser, PcapNgReader, PcapNgWriter};
+use pcap_file_tokio::{Check, Checks, DataLink, Endianness, ParseLimits, ParseMode, ParseWarning, PcapError, ReaderConfig};
+
+/// Big endian block with the given body
+fn raw_block(type_: u32, body: Vec<u8>) -> RawBlock<'static> {
+    let len = body.len() as u32 + 12;
+    RawBlock { type_, initial_len: len, body: Cow::Owned(body), trailer_len: len }
+}
+
+/// Big endian Interface Description Block with an if_name option, followed by `end` as the end of its options
+fn raw_interface(name: &[u8; 4], end: &[u8]) -> RawBlock<'static> {
+    let mut body = vec![0, 1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 4];
+    body.extend_from_slice(name);
+    body.extend_from_slice(end);
+
+    raw_block(INTERFACE_DESCRIPTION_BLOCK, body)
+}
+
+/// Big endian Enhanced Packet Block of 4 bytes on the interface 0, with the given options
+fn raw_packet(options: &[u8]) -> RawBlock<'static> {
+    let mut body = vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 4, 1, 2, 3, 4];
+    body.extend_from_slice(options);
+
+    raw_block(ENHANCED_PACKET_BLOCK, body)
+}
+
+/// Capture whose blocks 2 to 5 each violate a check
+async fn capture() -> Vec<u8> {
+    let mut writer = PcapNgWriter::with_endianness(Vec::new(), Endianness::Big).await.unwrap();
+    writer.write_block(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0).into_block()).await.unwrap();
+
+    // Bytes after the end of options
+    writer.write_raw_block(&raw_interface(b"eth1", &[0, 0, 0, 0, 1, 2, 3, 4])).await.unwrap();
+
+    // Flags option of 2 bytes instead of 4, followed by a comment
+    writer.write_raw_block(&raw_packet(&[0, 2, 0, 2, 1, 2, 0, 0, 0, 1, 0, 1, b'a', 0, 0, 0, 0, 0, 0, 0])).await.unwrap();
+
+    // Comment which isn't valid UTF-8
+    writer.write_raw_block(&raw_packet(&[0, 1, 0, 2, b'a', 0xFF, 0, 0, 0, 0, 0, 0])).await.unwrap();
+
+    // Missing end of options
+    writer.write_raw_block(&raw_interface(b"eth2", &[])).await.unwrap();
+
+    writer.into_inner()
+}
+
+#[tokio::test]
+async fn default_mode() {
+    let data = capture().await;
+
+    let mut reader = PcapNgReader::new(&data[..]).await.unwrap();
+    reader.next_block().await.unwrap().unwrap();
+    reader.next_block().await.unwrap().unwrap();
+    assert!(matches!(reader.next_block().await, Some(Err(PcapError::InvalidField("EnhancedPacketOption: Flags length != 4")))));
+
+    let warning = ParseWarning { check: Check::TrailingBytes, index: 2, reason: "Option: bytes after the end of options" };
+    assert_eq!(reader.warnings(), &[warning]);
+}
+
+#[tokio::test]
+async fn strict_mode() {
+    let data = capture().await;
+
+    let mut reader = PcapNgReader::with_config(&data[..], ReaderConfig::default().with_mode(ParseMode::Strict)).await.unwrap();
+    reader.next_block().await.unwrap().unwrap();
+    assert!(matches!(reader.next_block().await, Some(Err(PcapError::InvalidField("Option: bytes after the end of options")))));
+    assert_eq!(reader.interfaces().len(), 1);
+    assert!(reader.warnings().is_empty());
+}
+
+#[tokio::test]
+async fn lenient_mode() {
+    let data = capture().await;
+
+    let mut reader = PcapNgReader::with_config(&data[..], ReaderConfig::default().with_mode(ParseMode::Lenient)).await.unwrap();
+    let mut blocks = vec![];
+    while let Some(block) = reader.next_block().await {
+        blocks.push(block.unwrap().into_owned());
+    }
+    assert_eq!(blocks.len(), 5);
+
+    let interface_options = |block: &Block<'static>| block.clone().into_interface_description().unwrap().options;
+    let packet_options = |block: &Block<'static>| block.clone().into_enhanced_packet().unwrap().options;
+
+    // The malformed options are dropped or repaired, the section header was read by the constructor
+    assert_eq!(interface_options(&blocks[1]), vec![InterfaceDescriptionOption::IfName("eth1".into())]);
+    assert_eq!(packet_options(&blocks[2]), vec![EnhancedPacketOption::Comment("a".into())]);
+    assert_eq!(packet_options(&blocks[3]), vec![EnhancedPacketOption::Comment("a\u{FFFD}".into())]);
+    assert_eq!(interface_options(&blocks[4]), vec![InterfaceDescriptionOption::IfName("eth2".into())]);
+    assert_eq!(reader.interfaces().len(), 3);
+
+    let warnings = reader.take_warnings();
+    let checks: Vec<_> = warnings.iter().map(|warning| (warning.check, warning.index)).collect();
+    assert_eq!(checks, [(Check::TrailingBytes, 2), (Check::OptionLength, 3), (Check::Utf8, 4), (Check::OptionLength, 5)]);
+    assert!(reader.warnings().is_empty());
+}
+
+#[tokio::test]
+async fn custom_mode() {
+    let data = capture().await;
+
+    // Only the UTF-8 strings are checked
+    let mode = ParseMode::Custom(Checks::none().with_check(Check::Utf8, true));
+    let (mut src, mut parser) = PcapNgParser::with_limits_and_mode(&data, ParseLimits::default(), mode).unwrap();
+    for _ in 0..3 {
+        let (rem, _) = parser.next_block(src).unwrap();
+        src = rem;
+    }
+    assert!(matches!(parser.next_block(src), Err(PcapError::Utf8Error(_))));
+
+    // The warnings of a block which failed are dropped, it will be parsed again
+    assert_eq!(parser.warnings().len(), 2);
+
+    // The raw blocks aren't checked, except the interfaces
+    let (src, _) = parser.next_raw_block(src).unwrap();
+    let (src, _) = parser.next_raw_block(src).unwrap();
+    assert!(src.is_empty());
+    assert_eq!(parser.warnings().last().map(|warning| (warning.check, warning.index)), Some((Check::OptionLength, 5)));
+}
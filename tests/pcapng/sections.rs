@@ -0,0 +1,193 @@
+use std::borrow::Cow;
+use std::io::Cursor;
+use std::time::Duration;
+
+use pcap_file_tokio::pcapng::blocks::enhanced_packet::EnhancedPacketBlock;
+use pcap_file_tokio::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use pcap_file_tokio::pcapng::blocks::section_header::SectionHeaderBlock;
+use pcap_file_tokio::pcapng::blocks::SECTION_HEADER_BLOCK;
+use pcap_file_tokio::pcapng::{Block, PcapNgBlock, PcapNgReader, PcapNgWriter};
+use pcap_file_tokio::{DataLink, Endianness};
+
+async fn write_sections<W>(writer: &mut PcapNgWriter<W>, endianness: Endianness)
+where
+    W: tokio::io::AsyncWrite + Unpin + Send,
+{
+    for (linktype, packets) in [(DataLink::ETHERNET, 3), (DataLink::RAW, 1), (DataLink::LINUX_SLL, 2)] {
+        if linktype != DataLink::ETHERNET {
+            writer.write_block(&SectionHeaderBlock { endianness, ..Default::default() }.into_block()).await.unwrap();
+        }
+
+        writer.write_block(&InterfaceDescriptionBlock::new(linktype, 0).into_block()).await.unwrap();
+        for i in 0..packets {
+            let packet = EnhancedPacketBlock { interface_id: 0, timestamp: Duration::ZERO, original_len: i, data: Cow::Owned(vec![0; i as usize]), options: vec![] };
+            writer.write_block(&packet.into_block()).await.unwrap();
+        }
+    }
+}
+
+#[tokio::test]
+async fn section_lengths() {
+    let mut writer = PcapNgWriter::with_endianness(Cursor::new(Vec::new()), Endianness::Little).await.unwrap();
+    writer.track_section_lengths().await.unwrap();
+    write_sections(&mut writer, Endianness::Big).await;
+    let pcapng = writer.finish().await.unwrap().0.into_inner();
+
+    // IDB 20 bytes, EPB 32 bytes + padded data
+    let mut reader = PcapNgReader::new(&pcapng[..]).await.unwrap();
+    assert_eq!(reader.section().section_length, 20 + 3 * 32 + 8);
+
+    // Skip from the start of a section and from the middle of a section
+    assert!(reader.skip_section().await.unwrap());
+    assert_eq!(reader.section().endianness, Endianness::Big);
+    assert_eq!(reader.section().section_length, 20 + 32);
+    reader.next_block().await.unwrap().unwrap();
+    assert!(reader.skip_section().await.unwrap());
+    assert_eq!(reader.section().section_length, 20 + 2 * 32 + 4);
+
+    let block = reader.next_block().await.unwrap().unwrap();
+    assert_eq!(block.into_interface_description().unwrap().linktype, DataLink::LINUX_SLL);
+    assert!(!reader.skip_section().await.unwrap());
+    assert!(reader.next_block().await.is_none());
+}
+
+#[tokio::test]
+async fn raw_section_lengths() {
+    let mut writer = PcapNgWriter::with_endianness(Vec::new(), Endianness::Little).await.unwrap();
+    write_sections(&mut writer, Endianness::Little).await;
+    let pcapng = writer.into_inner();
+
+    // The raw blocks are counted with their header and trailer
+    let mut reader = PcapNgReader::new(&pcapng[..]).await.unwrap();
+    let mut writer = PcapNgWriter::with_section_header(Cursor::new(Vec::new()), reader.section().clone()).await.unwrap();
+    writer.track_section_lengths().await.unwrap();
+    while let Some(block) = reader.next_raw_block().await {
+        let block = block.unwrap();
+        assert_eq!(writer.write_raw_block(&block).await.unwrap(), block.initial_len as usize);
+    }
+    assert_eq!(writer.summary().bytes, pcapng.len() as u64);
+    let pcapng = writer.finish().await.unwrap().0.into_inner();
+
+    let mut reader = PcapNgReader::new(&pcapng[..]).await.unwrap();
+    assert_eq!(reader.section().section_length, 20 + 3 * 32 + 8);
+    assert!(reader.skip_section().await.unwrap());
+    assert_eq!(reader.section().section_length, 20 + 32);
+    assert!(reader.skip_section().await.unwrap());
+    assert_eq!(reader.section().section_length, 20 + 2 * 32 + 4);
+}
+
+#[tokio::test]
+async fn skip_unknown_section_length() {
+    let mut writer = PcapNgWriter::new(Vec::new()).await.unwrap();
+    write_sections(&mut writer, Endianness::native()).await;
+    let pcapng = writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&pcapng[..]).await.unwrap();
+    assert_eq!(reader.section().section_length, -1);
+    assert!(reader.skip_section().await.unwrap());
+    assert!(reader.skip_section().await.unwrap());
+    assert_eq!(reader.next_block().await.unwrap().unwrap(), Block::InterfaceDescription(InterfaceDescriptionBlock::new(DataLink::LINUX_SLL, 0)));
+    assert!(!reader.skip_section().await.unwrap());
+}
+
+async fn mixed_endianness() -> Vec<u8> {
+    let mut writer = PcapNgWriter::with_endianness(Vec::new(), Endianness::Little).await.unwrap();
+    write_sections(&mut writer, Endianness::Big).await;
+    writer.start_section(SectionHeaderBlock { endianness: Endianness::Little, ..Default::default() }).await.unwrap();
+    writer.write_block(&InterfaceDescriptionBlock::new(DataLink::RAW, 0).into_block()).await.unwrap();
+    writer.into_inner()
+}
+
+async fn read_blocks(pcapng: &[u8]) -> Vec<Block<'static>> {
+    let mut reader = PcapNgReader::new(pcapng).await.unwrap();
+    let mut blocks = vec![reader.section().clone().into_block()];
+    while let Some(block) = reader.next_block().await {
+        blocks.push(block.unwrap().into_owned());
+    }
+    blocks
+}
+
+#[tokio::test]
+async fn section_endianness() {
+    let pcapng = mixed_endianness().await;
+    let blocks = read_blocks(&pcapng).await;
+    let endianness: Vec<_> = blocks
+        .iter()
+        .filter_map(|block| match block {
+            Block::SectionHeader(section) => Some(section.endianness),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(endianness, [Endianness::Little, Endianness::Big, Endianness::Big, Endianness::Little]);
+
+    // Raw blocks round-trip byte for byte, the section headers switch the endianness
+    let mut reader = PcapNgReader::new(&pcapng[..]).await.unwrap();
+    let mut writer = PcapNgWriter::with_section_header(Vec::new(), reader.section().clone()).await.unwrap();
+    while let Some(block) = reader.next_raw_block().await {
+        writer.write_raw_block(&block.unwrap()).await.unwrap();
+    }
+    assert_eq!(writer.summary().bytes, pcapng.len() as u64);
+    assert_eq!(writer.into_inner(), pcapng);
+
+    // Blocks from other sections are re-encoded with the endianness of the current section
+    let mut reader = PcapNgReader::new(&pcapng[..]).await.unwrap();
+    let mut writer = PcapNgWriter::with_endianness(Vec::new(), Endianness::Big).await.unwrap();
+    while let Some(block) = reader.next_raw_block().await {
+        let block = block.unwrap().into_owned();
+        if block.type_ != SECTION_HEADER_BLOCK {
+            writer.write_raw_block_from(&block, reader.section().endianness).await.unwrap();
+        }
+        else {
+            writer.start_section(SectionHeaderBlock { endianness: Endianness::Big, ..Default::default() }).await.unwrap();
+        }
+    }
+
+    let expected: Vec<_> = blocks
+        .into_iter()
+        .map(|block| match block {
+            Block::SectionHeader(section) => Block::SectionHeader(SectionHeaderBlock { endianness: Endianness::Big, ..section }),
+            block => block,
+        })
+        .collect();
+    assert_eq!(read_blocks(&writer.into_inner()).await, expected);
+}
+
+#[tokio::test]
+async fn section_endianness_corpus() {
+    let flip = |endianness| match endianness {
+        Endianness::Big => Endianness::Little,
+        Endianness::Little => Endianness::Big,
+    };
+
+    for path in ["tests/pcapng/big_endian/difficult/test202.pcapng", "tests/pcapng/little_endian/difficult/test202.pcapng"] {
+        let pcapng = tokio::fs::read(path).await.unwrap();
+        let blocks = read_blocks(&pcapng).await;
+
+        // Every section is re-encoded with the opposite endianness
+        let mut reader = PcapNgReader::new(&pcapng[..]).await.unwrap();
+        let section = reader.section().clone();
+        let section = SectionHeaderBlock { endianness: flip(section.endianness), ..section };
+        let mut writer = PcapNgWriter::with_section_header(Vec::new(), section).await.unwrap();
+        while let Some(block) = reader.next_raw_block().await {
+            let block = block.unwrap().into_owned();
+            if block.type_ != SECTION_HEADER_BLOCK {
+                writer.write_raw_block_from(&block, reader.section().endianness).await.unwrap();
+            }
+            else {
+                let section = reader.section().clone();
+                writer.start_section(SectionHeaderBlock { endianness: flip(section.endianness), ..section }).await.unwrap();
+            }
+        }
+
+        let expected: Vec<_> = blocks
+            .into_iter()
+            .map(|block| match block {
+                Block::SectionHeader(section) => {
+                    Block::SectionHeader(SectionHeaderBlock { endianness: flip(section.endianness), ..section })
+                },
+                block => block,
+            })
+            .collect();
+        assert_eq!(read_blocks(&writer.into_inner()).await, expected, "{path}");
+    }
+}
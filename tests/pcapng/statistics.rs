@@ -0,0 +1,96 @@
+use std::time::Duration;
+
+use pcap_file_tokio::info::{InterfaceStatistics, StatisticsAggregator};
+use pcap_file_tokio::pcapng::blocks::enhanced_packet::EnhancedPacketOption;
+use pcap_file_tokio::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use pcap_file_tokio::pcapng::blocks::interface_statistics::{InterfaceStatisticsBlock, InterfaceStatisticsOption};
+use pcap_file_tokio::pcapng::blocks::section_header::SectionHeaderBlock;
+use pcap_file_tokio::pcapng::{Block, InterfaceCounters, PcapNgReader, PcapNgWriter};
+use pcap_file_tokio::DataLink;
+
+use crate::common::{packet, packet_with_options};
+
+#[tokio::test]
+async fn writer_statistics() {
+    let mut writer = PcapNgWriter::new(Vec::new()).await.unwrap();
+    assert_eq!(writer.statistics(), None);
+    writer.track_statistics(true);
+
+    writer.write_block(&Block::InterfaceDescription(InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0))).await.unwrap();
+    writer.write_block(&Block::InterfaceDescription(InterfaceDescriptionBlock::new(DataLink::RAW, 0))).await.unwrap();
+    writer.write_block(&packet(0, 2_000_000, &[1, 2, 3, 4])).await.unwrap();
+    writer.write_block(&packet_with_options(0, 1_000_000, &[1, 2, 3, 4], vec![EnhancedPacketOption::DropCount(3)])).await.unwrap();
+    writer.write_block(&packet_with_options(0, 5_000_000, &[1, 2, 3, 4], vec![EnhancedPacketOption::DropCount(1)])).await.unwrap();
+
+    assert_eq!(writer.statistics().unwrap(), &[
+        InterfaceCounters { packets: 3, drops: 4, start: Some(1_000_000), end: Some(5_000_000) },
+        InterfaceCounters::default()
+    ]);
+
+    // The statistics of the first section are written before the second one
+    writer.write_block(&Block::SectionHeader(SectionHeaderBlock::default())).await.unwrap();
+    writer.write_block(&Block::InterfaceDescription(InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0))).await.unwrap();
+    writer.write_block(&packet(0, 7_000_000, &[1, 2, 3, 4])).await.unwrap();
+    let pcapng = writer.close().await.unwrap();
+
+    let mut reader = PcapNgReader::new(&pcapng[..]).await.unwrap();
+    let mut aggregator = StatisticsAggregator::default();
+    let mut statistics = vec![];
+    while let Some(block) = reader.next_block().await {
+        let block = block.unwrap();
+        aggregator.push(&block).unwrap();
+        if let Block::InterfaceStatistics(stats) = block {
+            statistics.push(stats.into_owned());
+        }
+    }
+
+    assert_eq!(statistics.len(), 3);
+    assert_eq!(statistics[0].interface_id, 0);
+    assert_eq!(statistics[0].options, vec![
+        InterfaceStatisticsOption::IsbStartTime(1_000_000),
+        InterfaceStatisticsOption::IsbEndTime(5_000_000),
+        InterfaceStatisticsOption::IsbIfRecv(7),
+        InterfaceStatisticsOption::IsbIfDrop(4),
+        InterfaceStatisticsOption::IsbUsrDeliv(3),
+    ]);
+    assert_eq!(statistics[1].interface_id, 1);
+    assert_eq!(statistics[1].options, vec![
+        InterfaceStatisticsOption::IsbIfRecv(0),
+        InterfaceStatisticsOption::IsbIfDrop(0),
+        InterfaceStatisticsOption::IsbUsrDeliv(0),
+    ]);
+    assert!(statistics[0].timestamp > 1_600_000_000_000_000);
+
+    let merged = aggregator.statistics();
+    assert_eq!(merged.len(), 3);
+    assert_eq!(merged[0].as_ref().unwrap().start_time, Some(Duration::from_secs(1)));
+    assert_eq!(merged[0].as_ref().unwrap().received, Some(7));
+    assert_eq!(merged[2].as_ref().unwrap().delivered, Some(1));
+}
+
+#[tokio::test]
+async fn aggregator_merge() {
+    let mut aggregator = StatisticsAggregator::default();
+    aggregator.push(&Block::InterfaceDescription(InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0))).unwrap();
+    aggregator.push(&Block::InterfaceDescription(InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0))).unwrap();
+
+    let stats = |options| Block::InterfaceStatistics(InterfaceStatisticsBlock { interface_id: 0, timestamp: 0, options });
+    aggregator
+        .push(&stats(vec![InterfaceStatisticsOption::IsbStartTime(3_000_000), InterfaceStatisticsOption::IsbIfRecv(5), InterfaceStatisticsOption::IsbOsDrop(1)]))
+        .unwrap();
+    aggregator
+        .push(&stats(vec![InterfaceStatisticsOption::IsbStartTime(2_000_000), InterfaceStatisticsOption::IsbEndTime(4_000_000), InterfaceStatisticsOption::IsbIfRecv(9)]))
+        .unwrap();
+
+    let expected = InterfaceStatistics {
+        start_time: Some(Duration::from_secs(2)),
+        end_time: Some(Duration::from_secs(4)),
+        received: Some(9),
+        os_dropped: Some(1),
+        ..Default::default()
+    };
+    assert_eq!(aggregator.statistics(), &[Some(expected), None]);
+
+    let unknown = Block::InterfaceStatistics(InterfaceStatisticsBlock { interface_id: 2, timestamp: 0, options: vec![] });
+    assert!(aggregator.push(&unknown).is_err());
+}
@@ -1,4 +1,15 @@
 #![allow(clippy::unreadable_literal)]
 
+#[cfg(feature = "background")]
+mod background;
+#[cfg(feature = "bytes")]
+mod bytes_reader;
+#[cfg(feature = "codec")]
+mod codec;
+mod common;
+mod convert;
+mod decoder;
+mod info;
 mod pcap;
 mod pcapng;
+mod transform;
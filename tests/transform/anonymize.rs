@@ -0,0 +1,179 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use pcap_file_tokio::pcap::PcapPacket;
+use pcap_file_tokio::pcapng::blocks::enhanced_packet::{EnhancedPacketBlock, EnhancedPacketOption};
+use pcap_file_tokio::pcapng::blocks::interface_description::{InterfaceDescriptionBlock, InterfaceDescriptionOption};
+use pcap_file_tokio::pcapng::blocks::name_resolution::{Ipv4Record, NameResolutionBlock, Record};
+use pcap_file_tokio::pcapng::blocks::section_header::{SectionHeaderBlock, SectionHeaderOption};
+use pcap_file_tokio::pcapng::{Block, PcapNgReader, PcapNgWriter};
+use pcap_file_tokio::transform::anonymize::{anonymize_pcapng, Anonymizer, CryptoPan};
+use pcap_file_tokio::DataLink;
+
+/// Key of the reference implementation of Crypto-PAn
+const KEY: [u8; 32] = [
+    21, 34, 23, 141, 51, 164, 207, 128, 19, 10, 91, 22, 73, 144, 125, 16, 216, 152, 143, 131, 121, 121, 101, 39, 98, 87, 76, 45, 42, 132, 34, 2,
+];
+
+fn checksum_sum(data: &[u8]) -> u32 {
+    let mut sum: u32 = data.chunks(2).map(|c| u16::from_be_bytes([c[0], *c.get(1).unwrap_or(&0)]) as u32).sum();
+    while sum > 0xFFFF {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    sum
+}
+
+/// Returns true if the transport checksum of the packet, using a pseudo header with the given addresses, is valid.
+fn transport_checksum_valid(addrs: &[u8], protocol: u8, transport: &[u8]) -> bool {
+    let mut pseudo = addrs.to_vec();
+    pseudo.extend_from_slice(&(transport.len() as u32).to_be_bytes());
+    pseudo.extend_from_slice(&[0, protocol]);
+    pseudo.extend_from_slice(transport);
+    checksum_sum(&pseudo) == 0xFFFF
+}
+
+fn ethernet_ipv4_udp() -> Vec<u8> {
+    let mut frame = vec![0x00, 0x1B, 0x21, 0x01, 0x02, 0x03, 0x3C, 0xFD, 0xFE, 0x04, 0x05, 0x06, 0x08, 0x00];
+    let mut ip = vec![0x45, 0, 0, 48, 0, 1, 0, 0, 64, 17, 0, 0, 192, 168, 1, 10, 10, 0, 0, 1];
+    let sum = checksum_sum(&ip);
+    ip[10..12].copy_from_slice(&(!(sum as u16)).to_be_bytes());
+
+    let mut udp = vec![0x04, 0xD2, 0x00, 0x35, 0, 28, 0, 0];
+    udp.extend(0..20);
+    let mut pseudo = ip[12..20].to_vec();
+    pseudo.extend_from_slice(&[0, 0, 0, 28, 0, 17]);
+    pseudo.extend_from_slice(&udp);
+    udp[6..8].copy_from_slice(&(!(checksum_sum(&pseudo) as u16)).to_be_bytes());
+
+    frame.extend(ip);
+    frame.extend(udp);
+    frame
+}
+
+#[test]
+fn cryptopan_reference_vectors() {
+    let cryptopan = CryptoPan::new(&KEY);
+
+    let vectors = [
+        ([128, 11, 68, 132], [135, 242, 180, 132]),
+        ([129, 118, 74, 4], [134, 136, 186, 123]),
+        ([130, 132, 252, 244], [133, 68, 164, 234]),
+        ([141, 223, 7, 43], [141, 167, 8, 160]),
+        ([141, 233, 145, 108], [141, 129, 237, 235]),
+        ([192, 102, 249, 13], [252, 138, 62, 131]),
+    ];
+    for (addr, anonymized) in vectors {
+        assert_eq!(cryptopan.anonymize_ipv4(addr), anonymized);
+    }
+
+    // Prefix preservation in IPv6
+    let a = cryptopan.anonymize_ipv6([0x20, 0x01, 0x0D, 0xB8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+    let b = cryptopan.anonymize_ipv6([0x20, 0x01, 0x0D, 0xB8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x80, 1]);
+    assert_eq!(a[..14], b[..14]);
+    assert_ne!(a[14] & 0x80, b[14] & 0x80);
+}
+
+#[test]
+fn anonymize_ethernet_ipv4_udp() {
+    let anonymizer = Anonymizer::new(&KEY);
+    let original = ethernet_ipv4_udp();
+
+    let mut packet = PcapPacket::new(Duration::ZERO, original.len() as u32, &original);
+    anonymizer.anonymize_pcap_packet(&mut packet, DataLink::ETHERNET);
+    let data = packet.data.into_owned();
+
+    // OUI preserved
+    assert_eq!(data[..3], original[..3]);
+    assert_ne!(data[3..6], original[3..6]);
+    assert_eq!(data[6..9], original[6..9]);
+
+    let ip = &data[14..];
+    assert_eq!(ip[12..16], anonymizer.cryptopan().anonymize_ipv4([192, 168, 1, 10]));
+    assert_eq!(ip[16..20], anonymizer.cryptopan().anonymize_ipv4([10, 0, 0, 1]));
+    assert_eq!(checksum_sum(&ip[..20]), 0xFFFF);
+    assert!(transport_checksum_valid(&ip[12..20], 17, &ip[20..]));
+
+    // The incremental update of a truncated packet gives the same checksum
+    let mut truncated = original[..40].to_vec();
+    anonymizer.anonymize_data(DataLink::ETHERNET, &mut truncated);
+    assert_eq!(truncated[..], data[..40]);
+}
+
+#[test]
+fn anonymize_ipv6_tcp() {
+    let anonymizer = Anonymizer::new(&KEY);
+
+    let mut ip = vec![0x60, 0, 0, 0, 0, 20, 6, 64];
+    ip.extend_from_slice(&[0x20, 0x01, 0x0D, 0xB8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+    ip.extend_from_slice(&[0x20, 0x01, 0x0D, 0xB8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2]);
+    ip.extend_from_slice(&[0x04, 0xD2, 0x00, 0x50, 0, 0, 0, 1, 0, 0, 0, 0, 0x50, 0x02, 0xFF, 0xFF, 0, 0, 0, 0]);
+
+    anonymizer.anonymize_data(DataLink::RAW, &mut ip);
+    assert_ne!(ip[8..24], [0x20, 0x01, 0x0D, 0xB8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+    assert!(transport_checksum_valid(&ip[8..40], 6, &ip[40..]));
+}
+
+#[tokio::test]
+async fn anonymize_pcapng_metadata() {
+    let anonymizer = Anonymizer::new(&KEY);
+    let frame = ethernet_ipv4_udp();
+
+    let section = SectionHeaderBlock {
+        options: vec![SectionHeaderOption::Hardware("Server 3000".into()), SectionHeaderOption::UserApplication("test".into())],
+        ..Default::default()
+    };
+
+    let mut interface = InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0);
+    interface.options = vec![
+        InterfaceDescriptionOption::IfName("eth0".into()),
+        InterfaceDescriptionOption::IfIpv4Addr(Cow::Borrowed(&[192, 168, 1, 10, 255, 255, 255, 0])),
+        InterfaceDescriptionOption::IfMacAddr(Cow::Borrowed(&[0x00, 0x1B, 0x21, 0x01, 0x02, 0x03])),
+        InterfaceDescriptionOption::IfOs("Linux 6.1".into()),
+    ];
+
+    let record = Ipv4Record { ip_addr: Cow::Borrowed(&[192, 168, 1, 10]), names: vec!["secret.example.com".into()] };
+    let nrb = NameResolutionBlock { records: vec![Record::Ipv4(record)], options: vec![] };
+
+    let packet = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::ZERO,
+        original_len: frame.len() as u32,
+        data: Cow::Borrowed(&frame),
+        options: vec![EnhancedPacketOption::Comment("from 192.168.1.10".into())],
+    };
+
+    let mut writer = PcapNgWriter::with_section_header(Vec::new(), section).await.unwrap();
+    writer.write_block(&Block::InterfaceDescription(interface)).await.unwrap();
+    writer.write_block(&Block::NameResolution(nrb)).await.unwrap();
+    writer.write_block(&Block::EnhancedPacket(packet)).await.unwrap();
+    let pcapng = writer.into_inner();
+
+    let out = anonymize_pcapng(&pcapng[..], Vec::new(), &anonymizer).await.unwrap();
+    let mut reader = PcapNgReader::new(&out[..]).await.unwrap();
+    assert_eq!(reader.section().options, vec![SectionHeaderOption::UserApplication("test".into())]);
+
+    let anonymized_ip = anonymizer.cryptopan().anonymize_ipv4([192, 168, 1, 10]);
+    let mut expected_addr = anonymized_ip.to_vec();
+    expected_addr.extend_from_slice(&[255, 255, 255, 0]);
+
+    let interface = reader.next_block().await.unwrap().unwrap().into_interface_description().unwrap();
+    assert_eq!(interface.options.len(), 3);
+    assert_eq!(interface.options[1], InterfaceDescriptionOption::IfIpv4Addr(expected_addr.into()));
+    match &interface.options[2] {
+        InterfaceDescriptionOption::IfMacAddr(mac) => assert_eq!(mac[..], anonymizer.anonymize_mac(frame[..6].try_into().unwrap())),
+        opt => panic!("Unexpected option {opt:?}"),
+    }
+
+    let nrb = reader.next_block().await.unwrap().unwrap().into_name_resolution().unwrap();
+    match &nrb.records[0] {
+        Record::Ipv4(record) => {
+            assert_eq!(record.ip_addr[..], anonymized_ip);
+            assert!(record.names[0].starts_with("host-"));
+        },
+        record => panic!("Unexpected record {record:?}"),
+    }
+
+    let packet = reader.next_block().await.unwrap().unwrap().into_enhanced_packet().unwrap();
+    assert!(packet.options.is_empty());
+    assert_eq!(packet.data[26..30], anonymized_ip);
+}
@@ -0,0 +1,209 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
+
+use pcap_file_tokio::pcap::{PcapHeader, PcapPacket, PcapReader, PcapWriter};
+use pcap_file_tokio::pcapng::blocks::enhanced_packet::EnhancedPacketOption;
+use pcap_file_tokio::pcapng::blocks::interface_description::{InterfaceDescriptionBlock, InterfaceDescriptionOption};
+use pcap_file_tokio::pcapng::blocks::interface_statistics::InterfaceStatisticsBlock;
+use pcap_file_tokio::pcapng::{Block, PcapNgReader, PcapNgWriter};
+use pcap_file_tokio::transform::decap::{
+    decap_pcap, decap_pcapng, decapsulate, tunnel_layers, Decapsulator, Tunnel, TunnelLayer, DEFAULT_DECAP_PEN,
+};
+use pcap_file_tokio::{DataLink, PcapError};
+
+use crate::common::{ipv4, packet, udp, ETHERNET_IPV4};
+
+fn inner_frame() -> Vec<u8> {
+    let mut frame = ETHERNET_IPV4.to_vec();
+    frame.extend(ipv4(17, [192, 168, 0, 1], [192, 168, 0, 2], &udp(53, &[0xAA; 12])));
+    frame
+}
+
+fn vxlan_frame(vni: u32) -> Vec<u8> {
+    let mut vxlan = vec![0x08, 0, 0, 0];
+    vxlan.extend_from_slice(&(vni << 8).to_be_bytes());
+    vxlan.extend(inner_frame());
+
+    let mut frame = ETHERNET_IPV4.to_vec();
+    frame.extend(ipv4(17, [10, 0, 0, 1], [10, 0, 0, 2], &udp(4789, &vxlan)));
+    frame
+}
+
+fn erspan_frame(session_id: u16) -> Vec<u8> {
+    // GRE with sequence number then ERSPAN type II
+    let mut gre = vec![0x10, 0, 0x88, 0xBE, 0, 0, 0, 1, 0x10, 0, 0, 0, 0, 0, 0, 0];
+    gre[10..12].copy_from_slice(&session_id.to_be_bytes());
+    gre.extend(inner_frame());
+
+    let mut frame = ETHERNET_IPV4.to_vec();
+    frame.extend(ipv4(47, [10, 0, 0, 3], [10, 0, 0, 4], &gre));
+    frame
+}
+
+#[test]
+fn decapsulate_tunnels() {
+    let inner = inner_frame();
+    assert_eq!(decapsulate(DataLink::ETHERNET, &inner), None);
+
+    let frame = vxlan_frame(42);
+    let decapsulation = decapsulate(DataLink::ETHERNET, &frame).unwrap();
+    assert_eq!(decapsulation.datalink, DataLink::ETHERNET);
+    assert_eq!(&frame[decapsulation.offset..], &inner[..]);
+    let layer = TunnelLayer { tunnel: Tunnel::Vxlan { vni: 42 }, endpoints: Some((Ipv4Addr::new(10, 0, 0, 1).into(), Ipv4Addr::new(10, 0, 0, 2).into())) };
+    assert_eq!(decapsulation.layers, vec![layer.clone()]);
+    assert_eq!(layer.to_string(), "VXLAN 10.0.0.1 > 10.0.0.2, VNI 42");
+
+    let frame = erspan_frame(7);
+    let decapsulation = decapsulate(DataLink::ETHERNET, &frame).unwrap();
+    assert_eq!(&frame[decapsulation.offset..], &inner[..]);
+    assert_eq!(decapsulation.layers[0].tunnel, Tunnel::Erspan { version: 2, session_id: 7 });
+
+    // GRE with key carrying IPv4, inside MPLS
+    let mut gre = vec![0x20, 0, 0x08, 0x00, 0, 0, 0, 9];
+    gre.extend_from_slice(&inner[14..]);
+    let mut frame = ETHERNET_IPV4.to_vec();
+    frame[12..14].copy_from_slice(&[0x88, 0x47]);
+    frame.extend_from_slice(&[0, 0x01, 0x01, 64]);
+    frame.extend(ipv4(47, [10, 0, 0, 5], [10, 0, 0, 6], &gre));
+
+    let decapsulation = decapsulate(DataLink::ETHERNET, &frame).unwrap();
+    assert_eq!(decapsulation.datalink, DataLink::RAW);
+    assert_eq!(&frame[decapsulation.offset..], &inner[14..]);
+    assert_eq!(decapsulation.layers[0].tunnel, Tunnel::Mpls { labels: vec![16] });
+    assert_eq!(decapsulation.layers[1].tunnel, Tunnel::Gre { key: Some(9) });
+    assert_eq!(decapsulation.layers[1].to_string(), "GRE 10.0.0.5 > 10.0.0.6, key 9");
+
+    // VXLAN inside ERSPAN
+    let vxlan = vxlan_frame(1);
+    let mut gre = vec![0x10, 0, 0x88, 0xBE, 0, 0, 0, 1, 0x10, 0, 0, 3, 0, 0, 0, 0];
+    gre.extend(vxlan);
+    let mut frame = ETHERNET_IPV4.to_vec();
+    frame.extend(ipv4(47, [10, 0, 0, 3], [10, 0, 0, 4], &gre));
+    let decapsulation = decapsulate(DataLink::ETHERNET, &frame).unwrap();
+    assert_eq!(decapsulation.layers.len(), 2);
+    assert_eq!(&frame[decapsulation.offset..], &inner[..]);
+}
+
+#[test]
+fn tunnel_layer_bytes() {
+    let ipv4 = Some((IpAddr::from([10, 0, 0, 1]), IpAddr::from([10, 0, 0, 2])));
+    let ipv6 = Some((IpAddr::from(Ipv6Addr::LOCALHOST), IpAddr::from(Ipv6Addr::UNSPECIFIED)));
+    let layers = [
+        TunnelLayer { tunnel: Tunnel::Gre { key: None }, endpoints: ipv4 },
+        TunnelLayer { tunnel: Tunnel::Gre { key: Some(7) }, endpoints: ipv6 },
+        TunnelLayer { tunnel: Tunnel::Erspan { version: 3, session_id: 1023 }, endpoints: ipv4 },
+        TunnelLayer { tunnel: Tunnel::Vxlan { vni: 42 }, endpoints: ipv6 },
+        TunnelLayer { tunnel: Tunnel::Geneve { vni: 0xFFFFFF }, endpoints: ipv4 },
+        TunnelLayer { tunnel: Tunnel::IpInIp, endpoints: ipv4 },
+        TunnelLayer { tunnel: Tunnel::Mpls { labels: vec![16, 17] }, endpoints: None },
+    ];
+
+    for layer in layers {
+        let bytes = layer.to_bytes();
+        assert_eq!(TunnelLayer::from_bytes(&bytes), Some(layer));
+        assert_eq!(TunnelLayer::from_bytes(&bytes[..bytes.len() - 1]), None);
+    }
+
+    assert_eq!(TunnelLayer::from_bytes(&[3, 0, 0, 0, 0, 42]), Some(TunnelLayer { tunnel: Tunnel::Vxlan { vni: 42 }, endpoints: None }));
+    assert_eq!(TunnelLayer::from_bytes(&[7, 0]), None);
+}
+
+fn gre_ip_frame() -> Vec<u8> {
+    let mut gre = vec![0, 0, 0x08, 0x00];
+    gre.extend_from_slice(&inner_frame()[14..]);
+    let mut frame = ETHERNET_IPV4.to_vec();
+    frame.extend(ipv4(47, [10, 0, 0, 5], [10, 0, 0, 6], &gre));
+    frame
+}
+
+#[tokio::test]
+async fn decap_pcapng_interfaces() {
+    let plain = inner_frame();
+    let vxlan = vxlan_frame(42);
+    let erspan = erspan_frame(7);
+    let gre = gre_ip_frame();
+
+    let interface0 = InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0);
+    let mut interface1 = InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0);
+    interface1.options.push(InterfaceDescriptionOption::IfTsResol(9));
+
+    let mut writer = PcapNgWriter::new(Vec::new()).await.unwrap();
+    writer.write_block(&Block::InterfaceDescription(interface0)).await.unwrap();
+    writer.write_block(&packet(0, 1_000_000_000, &vxlan)).await.unwrap();
+    writer.write_block(&Block::InterfaceDescription(interface1.clone())).await.unwrap();
+    writer.write_block(&packet(1, 1_000_000_000, &plain)).await.unwrap();
+    writer.write_block(&packet(0, 1_000_000_000, &erspan)).await.unwrap();
+    writer.write_block(&packet(1, 1_000_000_000, &gre)).await.unwrap();
+    writer.write_block(&packet(1, 1_000_000_000, &gre)).await.unwrap();
+    writer.write_block(&Block::InterfaceStatistics(InterfaceStatisticsBlock { interface_id: 1, timestamp: 0, options: vec![] })).await.unwrap();
+    let pcapng = writer.into_inner();
+
+    let out = decap_pcapng(&pcapng[..], Vec::new(), Decapsulator::default()).await.unwrap();
+
+    let mut reader = PcapNgReader::new(&out[..]).await.unwrap();
+    let mut blocks = vec![];
+    while let Some(block) = reader.next_block().await {
+        blocks.push(block.unwrap().into_owned());
+    }
+
+    // The Ethernet frames stay on their interface, the raw IP packets get a new interface created once
+    let interfaces = reader.interfaces();
+    assert_eq!(interfaces.len(), 3);
+    assert_eq!(interfaces[2].linktype, DataLink::RAW);
+    assert_eq!(interfaces[2].options, interface1.options);
+
+    let packets: Vec<_> = blocks.iter().filter_map(|block| block.clone().into_enhanced_packet()).collect();
+    let ids: Vec<_> = packets.iter().map(|packet| packet.interface_id).collect();
+    assert_eq!(ids, [0, 1, 0, 2, 2]);
+    assert!(packets[..3].iter().all(|packet| packet.data[..] == plain[..] && packet.original_len == plain.len() as u32));
+    assert_eq!(&packets[3].data[..], &plain[14..]);
+
+    // The layers are kept as custom options, then as comments
+    let vxlan = TunnelLayer { tunnel: Tunnel::Vxlan { vni: 42 }, endpoints: Some(([10, 0, 0, 1].into(), [10, 0, 0, 2].into())) };
+    assert_eq!(tunnel_layers(&Block::EnhancedPacket(packets[0].clone()), DEFAULT_DECAP_PEN), vec![vxlan.clone()]);
+    assert_eq!(packets[0].options[1], EnhancedPacketOption::Comment("VXLAN 10.0.0.1 > 10.0.0.2, VNI 42".into()));
+    assert_eq!(packets[1].options, vec![]);
+    assert_eq!(packets[2].options[1], EnhancedPacketOption::Comment("ERSPAN type II 10.0.0.3 > 10.0.0.4, session 7".into()));
+    assert_eq!(packets[2].options.len(), 2);
+
+    // Without comments, the layers are still kept as custom options
+    let out = decap_pcapng(&pcapng[..], Vec::new(), Decapsulator::default().with_comments(false).with_pen(Some(1))).await.unwrap();
+    let mut reader = PcapNgReader::new(&out[..]).await.unwrap();
+    reader.next_block().await.unwrap().unwrap();
+    let block = reader.next_block().await.unwrap().unwrap();
+    assert_eq!(tunnel_layers(&block, 1), vec![vxlan]);
+    assert!(tunnel_layers(&block, DEFAULT_DECAP_PEN).is_empty());
+    assert_eq!(block.into_enhanced_packet().unwrap().options.len(), 1);
+
+    let stats = blocks.last().unwrap().clone().into_interface_statistics().unwrap();
+    assert_eq!(stats.interface_id, 1);
+}
+
+#[tokio::test]
+async fn decap_pcap_link_types() {
+    let header = PcapHeader { datalink: DataLink::ETHERNET, ..Default::default() };
+
+    let mut writer = PcapWriter::with_header(Vec::new(), header).await.unwrap();
+    for frame in [vxlan_frame(1), erspan_frame(2)] {
+        writer.write_packet(&PcapPacket::new(Duration::ZERO, frame.len() as u32, &frame)).await.unwrap();
+    }
+    let pcap = writer.into_writer();
+
+    let out = decap_pcap(&pcap[..], Vec::new()).await.unwrap();
+    let mut reader = PcapReader::new(&out[..]).await.unwrap();
+    assert_eq!(reader.header().datalink, DataLink::ETHERNET);
+    while let Some(packet) = reader.next_packet().await {
+        assert_eq!(&packet.unwrap().data[..], &inner_frame()[..]);
+    }
+
+    // IP inside GRE gives a raw IP packet, which can't be mixed with the Ethernet frames
+    let frame = gre_ip_frame();
+    let mut writer = PcapWriter::with_header(Vec::new(), header).await.unwrap();
+    writer.write_packet(&PcapPacket::new(Duration::ZERO, frame.len() as u32, &frame)).await.unwrap();
+    let vxlan = vxlan_frame(1);
+    writer.write_packet(&PcapPacket::new(Duration::ZERO, vxlan.len() as u32, &vxlan)).await.unwrap();
+    let pcap = writer.into_writer();
+
+    let result = decap_pcap(&pcap[..], Vec::new()).await;
+    assert!(matches!(result, Err(PcapError::MixedLinkTypes(DataLink::RAW, DataLink::ETHERNET))));
+}
@@ -0,0 +1,80 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use pcap_file_tokio::pcap::{PcapPacket, PcapReader, PcapWriter};
+use pcap_file_tokio::pcapng::blocks::enhanced_packet::{EnhancedPacketBlock, EnhancedPacketOption};
+use pcap_file_tokio::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use pcap_file_tokio::pcapng::{Block, PcapNgReader, PcapNgWriter};
+use pcap_file_tokio::transform::dedup::{dedup_pcap, dedup_pcapng, DedupConfig, DedupStats, DedupTag, DedupWindow, Deduplicator};
+use pcap_file_tokio::DataLink;
+
+fn packet(timestamp_micros: u64, data: &[u8]) -> Block<'_> {
+    Block::EnhancedPacket(EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::from_nanos(timestamp_micros),
+        original_len: data.len() as u32,
+        data: Cow::Borrowed(data),
+        options: vec![],
+    })
+}
+
+#[test]
+fn dedup_packet_window() {
+    let config = DedupConfig { window: DedupWindow::Packets(2), ignored_ranges: vec![5..6, 1..2], tag: DedupTag::None };
+    let mut deduplicator = Deduplicator::new(config);
+
+    let datas: [&[u8]; 7] = [&[0, 1, 0], &[0, 2, 0], &[1, 0, 0], &[0, 1, 1], &[1, 9, 0], &[0, 0, 0], &[0, 0]];
+    let kept: Vec<bool> = datas.iter().map(|data| deduplicator.push_packet(PcapPacket::new(Duration::ZERO, 3, data))).collect();
+
+    // [0, 2, 0] only differs by the ignored byte, [0, 0, 0] is out of the window
+    assert_eq!(kept, vec![true, false, true, true, false, true, true]);
+    assert_eq!(deduplicator.stats(), DedupStats { packets: 7, duplicates: 2 });
+    assert_eq!(deduplicator.len(), 5);
+}
+
+#[tokio::test]
+async fn dedup_pcapng_tagged() {
+    let a = [1_u8; 20];
+    let b = [2_u8; 20];
+
+    let mut writer = PcapNgWriter::new(Vec::new()).await.unwrap();
+    writer.write_block(&Block::InterfaceDescription(InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0))).await.unwrap();
+    for (timestamp, data) in [(0, &a), (10, &a), (20, &b), (30, &a), (5_000, &a), (5_010, &b)] {
+        writer.write_block(&packet(timestamp, data)).await.unwrap();
+    }
+    let pcapng = writer.into_inner();
+
+    let config = DedupConfig { window: DedupWindow::Duration(Duration::from_millis(1)), tag: DedupTag::Comment, ..Default::default() };
+    let (out, stats) = dedup_pcapng(&pcapng[..], Vec::new(), config).await.unwrap();
+    assert_eq!(stats, DedupStats { packets: 6, duplicates: 2 });
+
+    let mut reader = PcapNgReader::new(&out[..]).await.unwrap();
+    let mut packets = vec![];
+    while let Some(block) = reader.next_block().await {
+        if let Block::EnhancedPacket(packet) = block.unwrap() {
+            let packet = packet.into_owned();
+            packets.push((packet.timestamp.as_nanos(), packet.options));
+        }
+    }
+
+    let comment = vec![EnhancedPacketOption::Comment("2 duplicates dropped".into())];
+    assert_eq!(packets, vec![(0, comment), (20, vec![]), (5_000, vec![]), (5_010, vec![])]);
+}
+
+#[tokio::test]
+async fn dedup_pcap_file() {
+    let data = [0xAA_u8; 10];
+
+    let mut writer = PcapWriter::new(Vec::new()).await.unwrap();
+    for secs in 0..4 {
+        writer.write_packet(&PcapPacket::new(Duration::from_secs(secs), 10, &data)).await.unwrap();
+    }
+    let pcap = writer.into_writer();
+
+    let (out, stats) = dedup_pcap(&pcap[..], Vec::new(), DedupConfig::default()).await.unwrap();
+    assert_eq!(stats, DedupStats { packets: 4, duplicates: 3 });
+
+    let mut reader = PcapReader::new(&out[..]).await.unwrap();
+    assert_eq!(reader.next_packet().await.unwrap().unwrap().timestamp, Duration::ZERO);
+    assert!(reader.next_packet().await.is_none());
+}
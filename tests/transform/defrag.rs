@@ -0,0 +1,145 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use pcap_file_tokio::pcap::PcapPacket;
+use pcap_file_tokio::pcapng::blocks::enhanced_packet::EnhancedPacketOption;
+use pcap_file_tokio::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use pcap_file_tokio::pcapng::{Block, PcapNgReader, PcapNgWriter};
+use pcap_file_tokio::transform::defrag::{DefragConfig, DefragOutcome, Defragmenter};
+use pcap_file_tokio::DataLink;
+
+fn checksum(data: &[u8]) -> u16 {
+    let mut sum: u32 = data.chunks(2).map(|c| u16::from_be_bytes([c[0], *c.get(1).unwrap_or(&0)]) as u32).sum();
+    while sum > 0xFFFF {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+fn ethernet(ethertype: u16) -> Vec<u8> {
+    let mut frame = vec![0x02, 0, 0, 0, 0, 1, 0x02, 0, 0, 0, 0, 2];
+    frame.extend_from_slice(&ethertype.to_be_bytes());
+    frame
+}
+
+fn ipv4(payload: &[u8], flags_offset: u16) -> Vec<u8> {
+    let mut frame = ethernet(0x0800);
+    let mut header = vec![0x45, 0, 0, 0, 0x12, 0x34, 0, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2];
+    header[2..4].copy_from_slice(&(20 + payload.len() as u16).to_be_bytes());
+    header[6..8].copy_from_slice(&flags_offset.to_be_bytes());
+    let csum = checksum(&header);
+    header[10..12].copy_from_slice(&csum.to_be_bytes());
+    frame.extend_from_slice(&header);
+    frame.extend_from_slice(payload);
+    frame
+}
+
+fn ipv6(payload: &[u8], next_header: u8) -> Vec<u8> {
+    let mut frame = ethernet(0x86DD);
+    frame.extend_from_slice(&[0x60, 0, 0, 0]);
+    frame.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+    frame.extend_from_slice(&[next_header, 64]);
+    frame.extend_from_slice(&[0x20, 0x01, 0x0D, 0xB8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+    frame.extend_from_slice(&[0x20, 0x01, 0x0D, 0xB8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2]);
+    frame.extend_from_slice(payload);
+    frame
+}
+
+fn ipv6_fragment(payload: &[u8], offset: u16, more: bool) -> Vec<u8> {
+    let mut ext = vec![17, 0];
+    ext.extend_from_slice(&(offset | more as u16).to_be_bytes());
+    ext.extend_from_slice(&0xCAFE_u32.to_be_bytes());
+    ext.extend_from_slice(payload);
+    ipv6(&ext, 44)
+}
+
+fn udp_datagram() -> Vec<u8> {
+    let mut udp = vec![0x30, 0x39, 0x00, 0x35, 0, 208, 0, 0];
+    udp.extend((0..200).map(|i| i as u8));
+    udp
+}
+
+#[test]
+fn ipv4_reassembly() {
+    let udp = udp_datagram();
+    let expected = ipv4(&udp, 0);
+    let first = ipv4(&udp[..112], 0x2000);
+    let second = ipv4(&udp[112..], 112 / 8);
+
+    let mut defrag = Defragmenter::default();
+    let unrelated = PcapPacket::new(Duration::from_secs(1), expected.len() as u32, &expected);
+    assert_eq!(defrag.push_pcap_packet(&unrelated, DataLink::ETHERNET), DefragOutcome::Passthrough);
+
+    // Out of order arrival
+    assert_eq!(defrag.push(0, Duration::from_secs(2), DataLink::ETHERNET, &second), DefragOutcome::Buffered);
+    let reassembled = match defrag.push(0, Duration::from_secs(3), DataLink::ETHERNET, &first) {
+        DefragOutcome::Reassembled(packet) => packet,
+        outcome => panic!("Unexpected outcome: {outcome:?}"),
+    };
+
+    assert_eq!(reassembled.data, expected);
+    assert_eq!(reassembled.timestamp, Duration::from_secs(2));
+    assert_eq!(reassembled.source_frames, vec![2, 3]);
+    assert_eq!(defrag.pending(), 0);
+    assert_eq!(defrag.buffered_bytes(), 0);
+    assert_eq!(defrag.stats().reassembled, 1);
+}
+
+#[test]
+fn ipv6_reassembly() {
+    let udp = udp_datagram();
+    let expected = ipv6(&udp, 17);
+
+    let mut defrag = Defragmenter::default();
+    assert_eq!(defrag.push(0, Duration::ZERO, DataLink::ETHERNET, &ipv6_fragment(&udp[..104], 0, true)), DefragOutcome::Buffered);
+    match defrag.push(0, Duration::ZERO, DataLink::ETHERNET, &ipv6_fragment(&udp[104..], 104, false)) {
+        DefragOutcome::Reassembled(packet) => assert_eq!(packet.data, expected),
+        outcome => panic!("Unexpected outcome: {outcome:?}"),
+    }
+}
+
+#[test]
+fn timeout_and_limits() {
+    let udp = udp_datagram();
+    let config = DefragConfig { timeout: Duration::from_secs(5), max_flows: 1, ..Default::default() };
+    let mut defrag = Defragmenter::new(config);
+
+    assert_eq!(defrag.push(0, Duration::from_secs(0), DataLink::ETHERNET, &ipv4(&udp[..112], 0x2000)), DefragOutcome::Buffered);
+    assert_eq!(defrag.push(1, Duration::from_secs(1), DataLink::ETHERNET, &ipv4(&udp[..112], 0x2000)), DefragOutcome::Buffered);
+    assert_eq!(defrag.stats().evicted, 1);
+
+    assert_eq!(defrag.push(1, Duration::from_secs(10), DataLink::ETHERNET, &ipv4(&udp[112..], 14)), DefragOutcome::Buffered);
+    assert_eq!(defrag.stats().timed_out, 1);
+
+    // Truncated fragment
+    let truncated = ipv4(&udp[..112], 0x2000);
+    assert_eq!(defrag.push(0, Duration::from_secs(10), DataLink::ETHERNET, &truncated[..60]), DefragOutcome::Passthrough);
+    assert_eq!(defrag.stats().malformed, 1);
+}
+
+#[tokio::test]
+async fn write_reassembled_pcapng() {
+    let udp = udp_datagram();
+    let interface = InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0);
+
+    let mut defrag = Defragmenter::default();
+    defrag.push(0, Duration::from_micros(1_500_000), DataLink::ETHERNET, &ipv4(&udp[..112], 0x2000));
+    let reassembled = match defrag.push(0, Duration::from_secs(2), DataLink::ETHERNET, &ipv4(&udp[112..], 14)) {
+        DefragOutcome::Reassembled(packet) => packet,
+        outcome => panic!("Unexpected outcome: {outcome:?}"),
+    };
+
+    let mut writer = PcapNgWriter::new(Vec::new()).await.unwrap();
+    writer.write_block(&Block::InterfaceDescription(interface.clone())).await.unwrap();
+    writer.write_block(&Block::EnhancedPacket(reassembled.to_enhanced_packet(&interface).unwrap())).await.unwrap();
+
+    let data = writer.into_inner();
+    let mut reader = PcapNgReader::new(&data[..]).await.unwrap();
+    reader.next_block().await.unwrap().unwrap();
+    let packet = reader.next_block().await.unwrap().unwrap().into_enhanced_packet().unwrap();
+
+    assert_eq!(packet.timestamp, Duration::from_nanos(1_500_000));
+    assert_eq!(packet.absolute_timestamp(&interface), Duration::from_micros(1_500_000));
+    assert_eq!(packet.data, Cow::Borrowed(&ipv4(&udp, 0)[..]));
+    assert_eq!(packet.options, vec![EnhancedPacketOption::Comment("Reassembled from frames 1, 2".into())]);
+}
@@ -0,0 +1,143 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use pcap_file_tokio::pcap::{PcapHeader, PcapPacket, PcapReader, PcapWriter};
+use pcap_file_tokio::pcapng::blocks::enhanced_packet::EnhancedPacketBlock;
+use pcap_file_tokio::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use pcap_file_tokio::pcapng::{Block, PcapNgReader, PcapNgWriter};
+use pcap_file_tokio::transform::linktype::{pop_vlan, push_vlan, rewrite_pcap, rewrite_pcapng, LinkTypeRewriter, VlanTag};
+use pcap_file_tokio::{DataLink, PcapError};
+
+const SRC: [u8; 6] = [0x3C, 0xFD, 0xFE, 0x04, 0x05, 0x06];
+const DST: [u8; 6] = [0x00, 0x1B, 0x21, 0x01, 0x02, 0x03];
+
+fn ip_packet() -> Vec<u8> {
+    let mut ip = vec![0x45, 0, 0, 28, 0, 1, 0, 0, 64, 17, 0, 0, 192, 168, 1, 10, 10, 0, 0, 1];
+    ip.extend_from_slice(&[0x04, 0xD2, 0x00, 0x35, 0, 8, 0, 0]);
+    ip
+}
+
+fn ethernet_frame() -> Vec<u8> {
+    let mut frame = DST.to_vec();
+    frame.extend_from_slice(&SRC);
+    frame.extend_from_slice(&[0x08, 0x00]);
+    frame.extend(ip_packet());
+    frame
+}
+
+fn sll_packet(packet_type: u16) -> Vec<u8> {
+    let mut sll = packet_type.to_be_bytes().to_vec();
+    sll.extend_from_slice(&[0x00, 0x01, 0x00, 0x06]);
+    sll.extend_from_slice(&SRC);
+    sll.extend_from_slice(&[0, 0, 0x08, 0x00]);
+    sll.extend(ip_packet());
+    sll
+}
+
+#[test]
+fn vlan_tags() {
+    let mut frame = ethernet_frame();
+    assert_eq!(pop_vlan(&mut frame), None);
+
+    assert!(push_vlan(&mut frame, VlanTag::dot1q(100).with_priority(5)));
+    assert!(push_vlan(&mut frame, VlanTag::dot1ad(200)));
+    assert_eq!(&frame[12..20], &[0x88, 0xA8, 0x00, 0xC8, 0x81, 0x00, 0xA0, 0x64]);
+
+    assert_eq!(pop_vlan(&mut frame), Some(VlanTag::dot1ad(200)));
+    let tag = pop_vlan(&mut frame).unwrap();
+    assert_eq!((tag.vid(), tag.priority()), (100, 5));
+    assert_eq!(frame, ethernet_frame());
+
+    let rewriter = LinkTypeRewriter::default().strip_vlans().push_vlan(VlanTag::dot1q(7));
+    let mut tagged = ethernet_frame();
+    push_vlan(&mut tagged, VlanTag::dot1q(1));
+    push_vlan(&mut tagged, VlanTag::dot1ad(2));
+    let mut expected = ethernet_frame();
+    push_vlan(&mut expected, VlanTag::dot1q(7));
+    assert_eq!(rewriter.rewrite_data(DataLink::ETHERNET, &tagged).unwrap(), expected);
+}
+
+#[test]
+fn link_type_conversions() {
+    let to_ethernet = LinkTypeRewriter::default().with_link_type(DataLink::ETHERNET).with_macs(SRC, DST);
+    let to_raw = LinkTypeRewriter::default().with_link_type(DataLink::RAW);
+
+    assert_eq!(to_ethernet.rewrite_data(DataLink::RAW, &ip_packet()).unwrap(), ethernet_frame());
+    assert_eq!(to_ethernet.rewrite_data(DataLink::LINUX_SLL, &sll_packet(4)).unwrap(), ethernet_frame());
+    assert_eq!(&to_ethernet.rewrite_data(DataLink::LINUX_SLL, &sll_packet(1)).unwrap()[..6], &[0xFF; 6]);
+
+    let mut sll2 = vec![0x08, 0x00, 0, 0, 0, 0, 0, 2, 0x00, 0x01, 0, 6];
+    sll2.extend_from_slice(&SRC);
+    sll2.extend_from_slice(&[0, 0]);
+    sll2.extend(ip_packet());
+    assert_eq!(to_ethernet.rewrite_data(DataLink::LINUX_SLL2, &sll2).unwrap(), ethernet_frame());
+
+    let mut null = vec![2, 0, 0, 0];
+    null.extend(ip_packet());
+    assert_eq!(to_ethernet.rewrite_data(DataLink::NULL, &null).unwrap(), ethernet_frame());
+
+    assert_eq!(to_raw.rewrite_data(DataLink::ETHERNET, &ethernet_frame()).unwrap(), ip_packet());
+
+    // Errors
+    assert!(matches!(
+        to_ethernet.output_link_type(DataLink::USB_LINUX),
+        Err(PcapError::UnsupportedLinkTypeConversion(DataLink::USB_LINUX, DataLink::ETHERNET))
+    ));
+    assert!(matches!(
+        to_raw.clone().push_vlan(VlanTag::dot1q(1)).output_link_type(DataLink::ETHERNET),
+        Err(PcapError::VlanUnsupported(DataLink::RAW))
+    ));
+
+    let mut arp = ethernet_frame();
+    arp[12..14].copy_from_slice(&[0x08, 0x06]);
+    assert!(matches!(
+        to_raw.rewrite_data(DataLink::ETHERNET, &arp),
+        Err(PcapError::InvalidPacketForLinkType(DataLink::ETHERNET, DataLink::RAW, _))
+    ));
+
+    let mut llc = sll_packet(0);
+    llc[14..16].copy_from_slice(&[0x00, 0x04]);
+    assert!(matches!(to_ethernet.rewrite_data(DataLink::LINUX_SLL, &llc), Err(PcapError::InvalidPacketForLinkType(..))));
+}
+
+#[tokio::test]
+async fn rewrite_files() {
+    let rewriter = LinkTypeRewriter::default().with_link_type(DataLink::ETHERNET).with_macs(SRC, DST).push_vlan(VlanTag::dot1q(10));
+    let ip = ip_packet();
+
+    let header = PcapHeader { datalink: DataLink::RAW, ..Default::default() };
+    let mut writer = PcapWriter::with_header(Vec::new(), header).await.unwrap();
+    writer.write_packet(&PcapPacket::new(Duration::ZERO, 100, &ip)).await.unwrap();
+    let pcap = writer.into_writer();
+
+    let out = rewrite_pcap(&pcap[..], Vec::new(), &rewriter).await.unwrap();
+    let mut reader = PcapReader::new(&out[..]).await.unwrap();
+    assert_eq!(reader.header().datalink, DataLink::ETHERNET);
+    assert_eq!(reader.header().snaplen, header.snaplen + 18);
+    let packet = reader.next_packet().await.unwrap().unwrap();
+    assert_eq!(packet.data.len(), ip.len() + 18);
+    assert_eq!(packet.orig_len, 118);
+
+    let mut writer = PcapNgWriter::new(Vec::new()).await.unwrap();
+    writer.write_block(&Block::InterfaceDescription(InterfaceDescriptionBlock::new(DataLink::LINUX_SLL, 0))).await.unwrap();
+    let sll = sll_packet(0);
+    let packet = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::ZERO,
+        original_len: sll.len() as u32,
+        data: Cow::Borrowed(&sll),
+        options: vec![],
+    };
+    writer.write_block(&Block::EnhancedPacket(packet)).await.unwrap();
+    let pcapng = writer.into_inner();
+
+    let out = rewrite_pcapng(&pcapng[..], Vec::new(), &rewriter).await.unwrap();
+    let mut reader = PcapNgReader::new(&out[..]).await.unwrap();
+    reader.next_block().await.unwrap().unwrap();
+    let packet = reader.next_block().await.unwrap().unwrap().into_enhanced_packet().unwrap().into_owned();
+    assert_eq!(reader.interfaces()[0].linktype, DataLink::ETHERNET);
+    assert_eq!(reader.interfaces()[0].snaplen, 0);
+    assert_eq!(packet.data.len(), sll.len() + 2);
+    assert_eq!(packet.original_len, sll.len() as u32 + 2);
+    assert_eq!(&packet.data[12..16], &[0x81, 0x00, 0x00, 0x0A]);
+}
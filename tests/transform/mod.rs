@@ -0,0 +1,9 @@
+#[cfg(feature = "anonymize")]
+mod anonymize;
+mod decap;
+mod dedup;
+mod defrag;
+mod linktype;
+mod reorder;
+mod timestamp;
+mod truncate;
@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+use pcap_file_tokio::pcap::PcapPacket;
+use pcap_file_tokio::pcapng::blocks::interface_description::{InterfaceDescriptionBlock, InterfaceDescriptionOption};
+use pcap_file_tokio::pcapng::blocks::section_header::SectionHeaderBlock;
+use pcap_file_tokio::pcapng::Block;
+use pcap_file_tokio::transform::reorder::{ReorderStats, ReorderWindow, Reorderer};
+use pcap_file_tokio::DataLink;
+
+use crate::common::packet;
+
+fn packet_timestamp(block: &Block, interfaces: &[InterfaceDescriptionBlock]) -> Option<Duration> {
+    let packet = block.clone().into_enhanced_packet()?;
+    Some(packet.absolute_timestamp(&interfaces[packet.interface_id as usize]))
+}
+
+#[test]
+fn reorder_pcap_packets() {
+    let mut reorderer = Reorderer::new(ReorderWindow::Packets(2));
+    let mut out = vec![];
+
+    for (i, secs) in [1, 3, 2, 5, 4, 0, 6, 6].into_iter().enumerate() {
+        // The orig_len identifies the packets
+        reorderer.push_packet(PcapPacket::new(Duration::from_secs(secs), i as u32, &[]));
+        while let Some(packet) = reorderer.pop() {
+            out.push(packet);
+        }
+    }
+    assert_eq!(reorderer.len(), 2);
+
+    while let Some(packet) = reorderer.flush() {
+        out.push(packet);
+    }
+
+    let secs: Vec<u64> = out.iter().map(|packet| packet.timestamp.as_secs()).collect();
+    assert_eq!(secs, vec![1, 2, 3, 0, 4, 5, 6, 6]);
+    assert_eq!(out[6].orig_len, 6);
+    assert_eq!(reorderer.stats(), ReorderStats { packets: 8, late: 1, max_lateness: Duration::from_secs(3), max_buffered: 3 });
+}
+
+#[test]
+fn reorder_pcapng_interfaces() {
+    let micro = InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0);
+    let mut nano = InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0);
+    nano.options.push(InterfaceDescriptionOption::IfTsResol(9));
+    let interfaces = [micro.clone(), nano.clone()];
+
+    let mut reorderer = Reorderer::new(ReorderWindow::Duration(Duration::from_millis(10)));
+    let mut out = vec![];
+
+    let blocks = [
+        Block::InterfaceDescription(micro),
+        packet(0, 5_000, &[]),
+        Block::InterfaceDescription(nano),
+        packet(1, 2_000_000, &[]),
+        packet(0, 1_000, &[]),
+        packet(1, 30_000_000, &[]),
+        packet(0, 4_000, &[]),
+    ];
+    for block in blocks {
+        reorderer.push_block(block, &interfaces).unwrap();
+        while let Some(block) = reorderer.pop() {
+            out.push(block);
+        }
+    }
+
+    // The 30ms packet releases the packets older than 20ms, the late 4ms packet is released right away
+    assert_eq!(out.len(), 6);
+    assert_eq!(reorderer.len(), 1);
+    assert_eq!(reorderer.stats().late, 1);
+
+    // A new section flushes the buffered packets
+    reorderer.push_block(Block::SectionHeader(SectionHeaderBlock::default()), &[]).unwrap();
+    assert!(reorderer.push_block(packet(0, 0, &[]), &[]).is_err());
+    while let Some(block) = reorderer.pop() {
+        out.push(block);
+    }
+    assert!(reorderer.is_empty());
+
+    assert!(matches!(out[0], Block::InterfaceDescription(_)));
+    assert!(matches!(out[1], Block::InterfaceDescription(_)));
+    assert!(matches!(out[7], Block::SectionHeader(_)));
+
+    let millis: Vec<u128> = out[2..7].iter().map(|block| packet_timestamp(block, &interfaces).unwrap().as_millis()).collect();
+    assert_eq!(millis, vec![1, 2, 5, 4, 30]);
+}
@@ -0,0 +1,95 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use pcap_file_tokio::pcap::PcapPacket;
+use pcap_file_tokio::pcapng::blocks::enhanced_packet::EnhancedPacketBlock;
+use pcap_file_tokio::pcapng::blocks::interface_description::{InterfaceDescriptionBlock, InterfaceDescriptionOption};
+use pcap_file_tokio::pcapng::blocks::interface_statistics::{InterfaceStatisticsBlock, InterfaceStatisticsOption};
+use pcap_file_tokio::pcapng::{Block, PcapNgReader, PcapNgWriter};
+use pcap_file_tokio::transform::timestamp::{rewrite_pcapng, PcapNgTimestampRewriter, TimestampTransform};
+use pcap_file_tokio::{DataLink, PcapError, TsResolution};
+
+#[test]
+fn transforms() {
+    let t = Duration::new(100, 500);
+
+    assert_eq!(TimestampTransform::shift(-1_000).apply(t).unwrap(), Duration::new(99, 999_999_500));
+    assert!(TimestampTransform::shift(-101_000_000_000).apply(t).is_err());
+    assert_eq!(TimestampTransform::anchor(t, Duration::ZERO).unwrap().apply(t).unwrap(), Duration::ZERO);
+
+    // 1s every 1000s, i.e. 1ms/s
+    let linear = TimestampTransform::anchors((Duration::from_secs(0), Duration::from_secs(10)), (Duration::from_secs(1000), Duration::from_secs(1011))).unwrap();
+    assert_eq!(linear, TimestampTransform::linear(Duration::ZERO, 1_000_000, 10_000_000_000));
+    assert_eq!(linear.apply(Duration::from_secs(500)).unwrap(), Duration::from_millis(510_500));
+    assert!(TimestampTransform::anchors((t, t), (t, Duration::ZERO)).is_err());
+
+    // An extreme drift overflows instead of panicking or wrapping
+    let overflow = |transform: TimestampTransform| {
+        let res = transform.apply(Duration::from_secs(1_000_000_000_000));
+        matches!(res, Err(PcapError::InvalidField("TimestampTransform: timestamp overflow")))
+    };
+    assert!(overflow(TimestampTransform::linear(Duration::ZERO, i64::MAX, 0)));
+    assert!(overflow(TimestampTransform::linear(Duration::ZERO, i64::MIN, 0)));
+
+    let mut packet = PcapPacket::new(Duration::new(1, 123_456_789), 0, &[]);
+    TimestampTransform::shift(1).apply_pcap_packet(&mut packet, TsResolution::MicroSecond).unwrap();
+    assert_eq!(packet.timestamp, Duration::new(1, 123_456_000));
+
+    let mut packet = PcapPacket::new(Duration::from_secs(u32::MAX as u64), 0, &[]);
+    assert!(TimestampTransform::shift(1_000_000_000).apply_pcap_packet(&mut packet, TsResolution::NanoSecond).is_err());
+    assert_eq!(packet.timestamp, Duration::from_secs(u32::MAX as u64));
+}
+
+#[tokio::test]
+async fn rewrite_pcapng_interfaces() {
+    let micro = InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0);
+    let mut nano = InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0);
+    nano.options.push(InterfaceDescriptionOption::IfTsResol(9));
+
+    let packet = |interface_id, units| {
+        Block::EnhancedPacket(EnhancedPacketBlock {
+            interface_id,
+            timestamp: Duration::from_nanos(units),
+            original_len: 0,
+            data: Cow::Borrowed(&[]),
+            options: vec![],
+        })
+    };
+
+    let mut writer = PcapNgWriter::new(Vec::new()).await.unwrap();
+    writer.write_block(&Block::InterfaceDescription(micro)).await.unwrap();
+    writer.write_block(&Block::InterfaceDescription(nano)).await.unwrap();
+    writer.write_block(&packet(0, 5_000_000)).await.unwrap();
+    writer.write_block(&packet(1, 5_000_000_000)).await.unwrap();
+    let stats = InterfaceStatisticsBlock { interface_id: 1, timestamp: 2_000_000_000, options: vec![InterfaceStatisticsOption::IsbStartTime(1_000_000_000)] };
+    writer.write_block(&Block::InterfaceStatistics(stats)).await.unwrap();
+    let pcapng = writer.into_inner();
+
+    let rewriter = PcapNgTimestampRewriter::new(TimestampTransform::shift(3_000_000_000)).with_interface(1, TimestampTransform::shift(-1_500));
+    let out = rewrite_pcapng(&pcapng[..], Vec::new(), rewriter).await.unwrap();
+
+    let mut reader = PcapNgReader::new(&out[..]).await.unwrap();
+    let mut blocks = vec![];
+    while let Some(block) = reader.next_block().await {
+        blocks.push(block.unwrap().into_owned());
+    }
+    let interfaces = reader.interfaces().to_vec();
+
+    // Whole seconds shift: if_tsoffset is rewritten, not the packet
+    assert_eq!(interfaces[0].ts_offset(), 3);
+    let packet0 = blocks[2].clone().into_enhanced_packet().unwrap();
+    assert_eq!(packet0.timestamp, Duration::from_nanos(5_000_000));
+    assert_eq!(packet0.absolute_timestamp(&interfaces[0]), Duration::from_secs(8));
+
+    assert_eq!(interfaces[1].ts_offset(), 0);
+    let packet1 = blocks[3].clone().into_enhanced_packet().unwrap();
+    assert_eq!(packet1.absolute_timestamp(&interfaces[1]), Duration::from_nanos(4_999_998_500));
+
+    let stats = blocks[4].clone().into_interface_statistics().unwrap();
+    assert_eq!(stats.timestamp, 1_999_998_500);
+    assert!(matches!(stats.options[0], InterfaceStatisticsOption::IsbStartTime(999_998_500)));
+
+    // Can't go before the EPOCH
+    let rewriter = PcapNgTimestampRewriter::new(TimestampTransform::shift(-6_000_000_000)).rewrite_ts_offset(false);
+    assert!(rewrite_pcapng(&pcapng[..], Vec::new(), rewriter).await.is_err());
+}
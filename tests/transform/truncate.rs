@@ -0,0 +1,173 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use pcap_file_tokio::pcap::{PcapHeader, PcapPacket, PcapReader, PcapWriter};
+use pcap_file_tokio::pcapng::blocks::enhanced_packet::{EnhancedPacketBlock, EnhancedPacketOption};
+use pcap_file_tokio::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use pcap_file_tokio::pcapng::{Block, PcapNgReader, PcapNgWriter};
+use pcap_file_tokio::transform::truncate::{headers_len, truncate_pcap, truncate_pcapng, TruncateTag, Truncator};
+use pcap_file_tokio::DataLink;
+
+use crate::common::{ipv4, udp, ETHERNET_IPV4};
+
+const ETHERNET_IPV6: [u8; 14] = [0x00, 0x1B, 0x21, 0x01, 0x02, 0x03, 0x3C, 0xFD, 0xFE, 0x04, 0x05, 0x06, 0x86, 0xDD];
+
+fn tcp(payload_len: u8) -> Vec<u8> {
+    // Data offset of 8 words: 12 bytes of options
+    let mut tcp = vec![0x04, 0xD2, 0x00, 0x50, 0, 0, 0, 1, 0, 0, 0, 0, 0x80, 0x18, 0xFF, 0xFF, 0, 0, 0, 0];
+    tcp.extend_from_slice(&[1; 12]);
+    tcp.extend(0..payload_len);
+    tcp
+}
+
+fn ethernet_ipv4_tcp() -> Vec<u8> {
+    let mut frame = ETHERNET_IPV4.to_vec();
+    frame.extend(ipv4(6, [192, 168, 1, 10], [10, 0, 0, 1], &tcp(100)));
+    frame
+}
+
+#[test]
+fn ipv4_tcp() {
+    let frame = ethernet_ipv4_tcp();
+    assert_eq!(headers_len(DataLink::ETHERNET, &frame), Some(14 + 20 + 32));
+    assert_eq!(headers_len(DataLink::ETHERNET, &frame[..40]), Some(40));
+    assert_eq!(headers_len(DataLink::IPV4, &frame[14..]), Some(20 + 32));
+
+    // Non-first fragment: no transport header
+    let mut fragment = frame.clone();
+    fragment[14 + 6] = 0x00;
+    fragment[14 + 7] = 0x10;
+    assert_eq!(headers_len(DataLink::ETHERNET, &fragment), Some(14 + 20));
+
+    // Unknown ethertype: link header only
+    let mut unknown = frame.clone();
+    unknown[12..14].copy_from_slice(&[0x88, 0xB5]);
+    assert_eq!(headers_len(DataLink::ETHERNET, &unknown), Some(14));
+
+    assert_eq!(headers_len(DataLink::USB_LINUX, &frame), None);
+}
+
+#[test]
+fn ipv6_extension_headers() {
+    let mut frame = ETHERNET_IPV6.to_vec();
+    let payload = udp(53, &[0; 40]);
+    // Hop-by-hop (8 bytes) then fragment (8 bytes) then UDP
+    let mut ip = vec![0x60, 0, 0, 0, 0, (16 + payload.len()) as u8, 0, 64];
+    ip.extend_from_slice(&[0xFE, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+    ip.extend_from_slice(&[0xFE, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2]);
+    ip.extend_from_slice(&[44, 0, 1, 4, 0, 0, 0, 0]);
+    ip.extend_from_slice(&[17, 0, 0, 1, 0, 0, 0, 42]);
+    ip.extend(payload);
+    frame.extend(ip);
+
+    assert_eq!(headers_len(DataLink::ETHERNET, &frame), Some(14 + 40 + 16 + 8));
+
+    // Non-first fragment: no transport header
+    frame[14 + 48 + 2..14 + 48 + 4].copy_from_slice(&[0x00, 0x08]);
+    assert_eq!(headers_len(DataLink::ETHERNET, &frame), Some(14 + 40 + 16));
+}
+
+#[test]
+fn tunnels() {
+    let inner = ethernet_ipv4_tcp();
+
+    // VXLAN
+    let mut vxlan = vec![0x08, 0, 0, 0, 0, 0, 42, 0];
+    vxlan.extend_from_slice(&inner);
+    let mut frame = ETHERNET_IPV4.to_vec();
+    frame.extend(ipv4(17, [192, 168, 1, 10], [10, 0, 0, 1], &udp(4789, &vxlan)));
+    assert_eq!(headers_len(DataLink::ETHERNET, &frame), Some(14 + 20 + 8 + 8 + 14 + 20 + 32));
+
+    // GRE with key carrying IPv4
+    let mut gre = vec![0x20, 0, 0x08, 0x00, 0, 0, 0, 7];
+    gre.extend_from_slice(&inner[14..]);
+    let mut frame = ETHERNET_IPV4.to_vec();
+    frame.extend(ipv4(47, [192, 168, 1, 10], [10, 0, 0, 1], &gre));
+    assert_eq!(headers_len(DataLink::ETHERNET, &frame), Some(14 + 20 + 8 + 20 + 32));
+
+    // GRE with sequence number carrying ERSPAN type II
+    let mut gre = vec![0x10, 0, 0x88, 0xBE, 0, 0, 0, 1, 0x10, 0, 0, 1, 0, 0, 0, 0];
+    gre.extend_from_slice(&inner);
+    let mut frame = ETHERNET_IPV4.to_vec();
+    frame.extend(ipv4(47, [192, 168, 1, 10], [10, 0, 0, 1], &gre));
+    assert_eq!(headers_len(DataLink::ETHERNET, &frame), Some(14 + 20 + 8 + 8 + 14 + 20 + 32));
+
+    // MPLS with two labels
+    let mut frame = ETHERNET_IPV4.to_vec();
+    frame[12..14].copy_from_slice(&[0x88, 0x47]);
+    frame.extend_from_slice(&[0, 0, 0x10, 64, 0, 0, 0x21, 64]);
+    frame.extend_from_slice(&inner[14..]);
+    assert_eq!(headers_len(DataLink::ETHERNET, &frame), Some(14 + 8 + 20 + 32));
+}
+
+#[test]
+fn truncate_packets() {
+    let frame = ethernet_ipv4_tcp();
+
+    let mut packet = PcapPacket::new(Duration::ZERO, frame.len() as u32, &frame);
+    assert_eq!(Truncator::default().truncate_pcap_packet(&mut packet, DataLink::ETHERNET), 100);
+    assert_eq!(packet.data.len(), 66);
+    assert_eq!(packet.orig_len, frame.len() as u32);
+
+    let mut packet = PcapPacket::new(Duration::ZERO, frame.len() as u32, &frame);
+    assert_eq!(Truncator::default().with_snaplen(40).truncate_pcap_packet(&mut packet, DataLink::ETHERNET), frame.len() - 40);
+    assert_eq!(&packet.data[..], &frame[..40]);
+
+    // The snaplen of the interface is respected
+    let interface = InterfaceDescriptionBlock::new(DataLink::ETHERNET, 50);
+    let mut packet = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::ZERO,
+        original_len: frame.len() as u32,
+        data: Cow::Borrowed(&frame),
+        options: vec![],
+    };
+    let truncator = Truncator::default().with_tag(TruncateTag::CustomOption { pen: 32473 });
+    assert_eq!(truncator.truncate_enhanced_packet(&mut packet, &interface), frame.len() - 50);
+    assert_eq!(packet.data.len(), 50);
+    assert!(matches!(&packet.options[..], [EnhancedPacketOption::CustomBinary(option)] if option.pen == 32473));
+
+    // Nothing removed: no tag
+    assert_eq!(truncator.truncate_enhanced_packet(&mut packet, &interface), 0);
+    assert_eq!(packet.options.len(), 1);
+}
+
+#[tokio::test]
+async fn truncate_files() {
+    let frame = ethernet_ipv4_tcp();
+
+    let header = PcapHeader { datalink: DataLink::ETHERNET, ..Default::default() };
+    let mut writer = PcapWriter::with_header(Vec::new(), header).await.unwrap();
+    writer.write_packet(&PcapPacket::new(Duration::from_secs(1), frame.len() as u32, &frame)).await.unwrap();
+    let pcap = writer.into_writer();
+
+    let out = truncate_pcap(&pcap[..], Vec::new(), &Truncator::default()).await.unwrap();
+    let mut reader = PcapReader::new(&out[..]).await.unwrap();
+    let packet = reader.next_packet().await.unwrap().unwrap();
+    assert_eq!(&packet.data[..], &frame[..66]);
+    assert_eq!(packet.orig_len, frame.len() as u32);
+
+    let mut writer = PcapNgWriter::new(Vec::new()).await.unwrap();
+    writer.write_block(&Block::InterfaceDescription(InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0))).await.unwrap();
+    let packet = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::ZERO,
+        original_len: frame.len() as u32,
+        data: Cow::Borrowed(&frame),
+        options: vec![],
+    };
+    writer.write_block(&Block::EnhancedPacket(packet)).await.unwrap();
+    let pcapng = writer.into_inner();
+
+    let out = truncate_pcapng(&pcapng[..], Vec::new(), &Truncator::default().with_tag(TruncateTag::Comment)).await.unwrap();
+    let mut reader = PcapNgReader::new(&out[..]).await.unwrap();
+    reader.next_block().await.unwrap().unwrap();
+    let packet = reader.next_block().await.unwrap().unwrap().into_owned();
+    let packet = match packet {
+        Block::EnhancedPacket(packet) => packet,
+        block => panic!("Unexpected block: {block:?}"),
+    };
+    assert_eq!(packet.data.len(), 66);
+    assert_eq!(packet.original_len, frame.len() as u32);
+    assert_eq!(packet.options, vec![EnhancedPacketOption::Comment(Cow::Borrowed("Payload truncated: 100 bytes removed"))]);
+}